@@ -1,23 +1,39 @@
 //! Benchmark for the fractal rendering pipeline. Runs `RenderingPipeline::render`
 //! end-to-end (compute_raw_field → populate_histograms → CDF rebuild →
 //! refresh_cache → colorize_collapse_unified) at the user's full sampling
-//! level on a representative Mandelbrot example.
+//! level on representative Mandelbrot and driven-damped-pendulum examples
+//! (the latter with and without the coarse pre-pass), two-stage
+//! anti-aliasing, tent / Lanczos downscale and downsampled Mandelbrot
+//! renders, plus the colorize pass on its own via `recolorize_only`. Also
+//! times the Newton's method inner iteration with and without smooth-count
+//! refinement steps, a parsed complex expression's value and slope, one
+//! magnetic-pendulum sample, and the Buddhabrot orbit accumulation.
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use egui::{Color32, ColorImage};
 use fractal_renderer::{
     core::{
-        image_utils::{Renderable, field_upsample_factor},
+        complex_expression::ComplexExpression,
+        field_iteration::FieldKernel,
+        image_utils::{DownscaleFilter, Renderable, field_upsample_factor},
         render_pipeline::RenderingPipeline,
     },
-    fractals::mandelbrot::MandelbrotParams,
+    fractals::{
+        buddhabrot::accumulate_orbit_density,
+        common::FractalParams,
+        driven_damped_pendulum::DrivenDampedPendulumParams,
+        mandelbrot::MandelbrotParams,
+        newtons_method::{RootsOfUnityParams, ToleranceMode, newton_rhapson_iteration_sequence},
+    },
 };
+use num::complex::Complex64;
+use serde::de::DeserializeOwned;
 
-fn run_pipeline_render_benchmark(c: &mut Criterion, path: &str) {
-    let mandelbrot_params: MandelbrotParams =
-        serde_json::from_str(&std::fs::read_to_string(path).expect("Unable to read param file"))
-            .unwrap();
+fn load_params<T: DeserializeOwned>(path: &str) -> T {
+    serde_json::from_str(&std::fs::read_to_string(path).expect("Unable to read param file"))
+        .unwrap()
+}
 
-    let renderer = mandelbrot_params;
+fn run_pipeline_render_benchmark<F: Renderable>(c: &mut Criterion, name: &str, renderer: F) {
     let resolution = renderer.image_specification().resolution;
     let n_max_plus_1 = field_upsample_factor(renderer.render_options().sampling_level);
     let bin_count = renderer.histogram_bin_count();
@@ -26,23 +42,166 @@ fn run_pipeline_render_benchmark(c: &mut Criterion, path: &str) {
     let sampling_level = renderer.render_options().sampling_level;
 
     let mut pipeline =
-        RenderingPipeline::new(renderer, n_max_plus_1, bin_count, hist_max, lut_count);
+        RenderingPipeline::new(renderer, n_max_plus_1, bin_count, hist_max, lut_count).unwrap();
     let mut color_image = ColorImage::filled(
         [resolution[0] as usize, resolution[1] as usize],
         Color32::BLACK,
     );
 
-    c.bench_function(path, |b| {
+    c.bench_function(name, |b| {
         b.iter(|| {
-            pipeline.render(&mut color_image, sampling_level);
+            pipeline.render(&mut color_image, sampling_level).unwrap();
             black_box(&color_image);
         });
     });
+
+    // Colorize pass alone: a walk over the whole field buffer with no kernel
+    // evaluations, so it is dominated by the memory layout of the field.
+    c.bench_function(&format!("{name} (recolorize only)"), |b| {
+        b.iter(|| {
+            pipeline
+                .recolorize_only(&mut color_image, sampling_level)
+                .unwrap();
+            black_box(&color_image);
+        });
+    });
+}
+
+fn run_newton_iteration_benchmark(c: &mut Criterion, refinement_count: u32) {
+    let system = RootsOfUnityParams {
+        n_roots: 5,
+        newton_step_size: 1.0.into(),
+    };
+    let points: Vec<Complex64> = (0..64)
+        .flat_map(|i| {
+            (0..64).map(move |j| Complex64::new(i as f64 / 16.0 - 2.0, j as f64 / 16.0 - 2.0))
+        })
+        .collect();
+
+    c.bench_function(
+        &format!("newton_iteration_refinement_{refinement_count}"),
+        |b| {
+            b.iter(|| {
+                for &z0 in &points {
+                    let _ = black_box(newton_rhapson_iteration_sequence(
+                        &system,
+                        z0,
+                        1e-8,
+                        ToleranceMode::Absolute,
+                        128,
+                        refinement_count,
+                        None,
+                    ));
+                }
+            });
+        },
+    );
+}
+
+fn run_complex_expression_benchmark(c: &mut Criterion) {
+    let expression = ComplexExpression::parse("z^3 - 2z + sin(z) / (z + 2)").unwrap();
+    let points: Vec<Complex64> = (0..64)
+        .flat_map(|i| {
+            (0..64).map(move |j| Complex64::new(i as f64 / 16.0 - 2.0, j as f64 / 16.0 - 2.0))
+        })
+        .collect();
+
+    c.bench_function("complex_expression_value_and_slope", |b| {
+        b.iter(|| {
+            for &z in &points {
+                black_box(expression.value_and_slope(z));
+            }
+        });
+    });
+}
+
+fn run_magnetic_pendulum_sample_benchmark(c: &mut Criterion) {
+    let path = "examples/render-magnetic-pendulum/params.json";
+    let FractalParams::MagneticPendulum(params) = load_params(path) else {
+        panic!("{path} should hold MagneticPendulum params");
+    };
+    let points: Vec<[f64; 2]> = (0..16)
+        .flat_map(|i| (0..16).map(move |j| [i as f64 / 4.0 - 2.0, j as f64 / 4.0 - 2.0]))
+        .collect();
+
+    c.bench_function("magnetic_pendulum_evaluate", |b| {
+        b.iter(|| {
+            for &point in &points {
+                black_box(params.evaluate(point));
+            }
+        });
+    });
+}
+
+fn run_buddhabrot_orbit_benchmark(c: &mut Criterion) {
+    let path = "examples/render-buddhabrot/params.json";
+    let FractalParams::Buddhabrot(mut params) = load_params(path) else {
+        panic!("{path} should hold Buddhabrot params");
+    };
+    params.sample_count = 1 << 18;
+    params.image_specification.resolution = [400, 300];
+
+    c.bench_function("buddhabrot_accumulate_orbit_density", |b| {
+        b.iter(|| black_box(accumulate_orbit_density(&params)));
+    });
 }
 
 fn benchmark(c: &mut Criterion) {
-    run_pipeline_render_benchmark(c, "benches/mandelbrot_ice_fracture.json");
-    run_pipeline_render_benchmark(c, "benches/mandelbrot_default.json");
+    for path in [
+        "benches/mandelbrot_ice_fracture.json",
+        "benches/mandelbrot_default.json",
+    ] {
+        run_pipeline_render_benchmark(c, path, load_params::<MandelbrotParams>(path));
+    }
+
+    // Two-stage anti-aliasing: the full subpixel grid only near edges.
+    let ice_fracture_path = "benches/mandelbrot_ice_fracture.json";
+    let mut edge_adaptive: MandelbrotParams = load_params(ice_fracture_path);
+    edge_adaptive.render_options.edge_threshold = Some(0.5);
+    run_pipeline_render_benchmark(
+        c,
+        &format!("{ice_fracture_path} (edge_threshold: 0.5)"),
+        edge_adaptive,
+    );
+
+    // Weighted downscale filters: the colorize pass reads neighboring
+    // pixels' subpixels and runs two separable filter passes.
+    for filter in [DownscaleFilter::Tent, DownscaleFilter::Lanczos] {
+        let mut filtered: MandelbrotParams = load_params(ice_fracture_path);
+        filtered.render_options.downscale_filter = filter;
+        run_pipeline_render_benchmark(
+            c,
+            &format!("{ice_fracture_path} (downscale_filter: {filter:?})"),
+            filtered,
+        );
+    }
+
+    // Downsampled render: one kernel evaluation per 4x4 block of pixels, so
+    // the block-fill in the colorize pass is a large share of the work.
+    let mandelbrot_path = "benches/mandelbrot_default.json";
+    let mut downsampled: MandelbrotParams = load_params(mandelbrot_path);
+    downsampled.render_options.sampling_level = -3;
+    run_pipeline_render_benchmark(
+        c,
+        &format!("{mandelbrot_path} (sampling_level: -3)"),
+        downsampled,
+    );
+
+    let ddp_path = "benches/driven_damped_pendulum_default.json";
+    let mut ddp_params: DrivenDampedPendulumParams = load_params(ddp_path);
+    for coarse_prepass in [false, true] {
+        ddp_params.coarse_prepass = coarse_prepass;
+        run_pipeline_render_benchmark(
+            c,
+            &format!("{ddp_path} (coarse_prepass: {coarse_prepass})"),
+            ddp_params.clone(),
+        );
+    }
+    run_newton_iteration_benchmark(c, 0);
+    run_newton_iteration_benchmark(c, 2);
+    run_complex_expression_benchmark(c);
+    run_magnetic_pendulum_sample_benchmark(c);
+    run_buddhabrot_orbit_benchmark(c);
 }
 
 criterion_group!(benches, benchmark);
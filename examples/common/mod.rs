@@ -4,10 +4,17 @@ use std::path::{Path, PathBuf};
 
 #[allow(dead_code)]
 use fractal_renderer::{
-    cli::{explore::explore_fractal, render::render_fractal},
-    core::file_io::FilePrefix,
+    cli::render::render_fractal,
+    core::{
+        file_io::FilePrefix, image_utils::DEFAULT_MAX_PIXEL_COUNT,
+        params_merge::load_params_with_base,
+    },
 };
 
+#[cfg(feature = "gui")]
+#[allow(dead_code)]
+use fractal_renderer::cli::explore::explore_fractal;
+
 #[allow(dead_code)]
 pub fn build_output_path(project: &str) -> std::path::PathBuf {
     let directory_path: PathBuf = ["out", project].iter().collect();
@@ -15,6 +22,7 @@ pub fn build_output_path(project: &str) -> std::path::PathBuf {
     directory_path
 }
 
+#[cfg(feature = "gui")]
 #[allow(dead_code)]
 pub fn explore_example_from_string(example_name: &str) {
     let params_path = example_params_path(example_name);
@@ -47,6 +55,7 @@ pub fn render_example_from_string(example_name: &str) {
             directory_path: build_output_path(example_name),
             file_base: String::from("result"),
         },
+        DEFAULT_MAX_PIXEL_COUNT,
     )
     .unwrap_or_else(|e| {
         panic!(
@@ -96,20 +105,23 @@ fn parse_params_json_or_panic<T>(example_name: &str, params_path: &Path, json_te
 where
     T: serde::de::DeserializeOwned,
 {
-    serde_json::from_str::<T>(json_text).unwrap_or_else(|e| {
-        let preview_len = json_text.len().min(200);
-        let preview = &json_text[..preview_len];
-
-        panic!(
-            "Unable to parse params.json for example.\n\
-             - example_name: {example_name}\n\
-             - path: {}\n\
-             - error: {e}\n\
-             - file length: {} bytes\n\
-             - first {preview_len} bytes preview:\n\
-             {preview}",
-            params_path.display(),
-            json_text.len(),
-        )
-    })
+    let directory = params_path.parent().unwrap_or(Path::new(""));
+    load_params_with_base(json_text, directory)
+        .and_then(|value| serde_json::from_value::<T>(value).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            let preview_len = json_text.len().min(200);
+            let preview = &json_text[..preview_len];
+
+            panic!(
+                "Unable to parse params.json for example.\n\
+                 - example_name: {example_name}\n\
+                 - path: {}\n\
+                 - error: {e}\n\
+                 - file length: {} bytes\n\
+                 - first {preview_len} bytes preview:\n\
+                 {preview}",
+                params_path.display(),
+                json_text.len(),
+            )
+        })
 }
@@ -0,0 +1,6 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+fn main() {
+    common::explore_example_from_string("explore-magnetic-pendulum")
+}
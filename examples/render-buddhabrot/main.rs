@@ -0,0 +1,10 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+/// Render the orbit density of the escaping points of the Mandelbrot set.
+/// ```sh
+/// cargo rex render-buddhabrot
+/// ```
+fn main() {
+    common::render_example_from_string("render-buddhabrot")
+}
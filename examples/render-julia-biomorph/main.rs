@@ -0,0 +1,10 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+/// Render a Julia set in Pickover's "biomorph" mode.
+/// ```sh
+/// cargo rex render-julia-biomorph
+/// ```
+fn main() {
+    common::render_example_from_string("render-julia-biomorph")
+}
@@ -0,0 +1,10 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+/// Render the basins of a pendulum swinging above three magnets.
+/// ```sh
+/// cargo rex render-magnetic-pendulum
+/// ```
+fn main() {
+    common::render_example_from_string("render-magnetic-pendulum")
+}
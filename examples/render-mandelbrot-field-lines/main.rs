@@ -0,0 +1,10 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+/// Render the Mandelbrot set with its equipotentials and external rays.
+/// ```sh
+/// cargo rex render-mandelbrot-field-lines
+/// ```
+fn main() {
+    common::render_example_from_string("render-mandelbrot-field-lines")
+}
@@ -0,0 +1,11 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+/// Render the Nebulabrot: three Buddhabrot channels with different iteration
+/// bands, mapped to red, green, and blue.
+/// ```sh
+/// cargo rex render-nebulabrot
+/// ```
+fn main() {
+    common::render_example_from_string("render-nebulabrot")
+}
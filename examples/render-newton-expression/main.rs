@@ -0,0 +1,11 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+/// Render the fractal basin of attractions of `z^3 - 2z + 2`, given as an
+/// expression string in the params file.
+/// ```sh
+/// cargo rex render-newton-expression
+/// ```
+fn main() {
+    common::render_example_from_string("render-newton-expression")
+}
@@ -1,4 +1,17 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::cli::diff::DiffMode;
+use crate::core::{
+    file_io::extract_base_name,
+    image_utils::{DEFAULT_MAX_PIXEL_COUNT, ImageSpecification},
+};
+use crate::fractals::common::FractalParams;
+
+/// `params_path` that reads the parameters from standard input.
+pub const STDIN_PARAMS_PATH: &str = "-";
+
+/// Output file base name used when the parameters come from stdin.
+const STDIN_BASE_NAME: &str = "stdin";
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
@@ -10,13 +23,502 @@ pub struct FractalRendererArgs {
 #[derive(Debug, Subcommand)]
 pub enum CommandsEnum {
     Render(ParameterFilePath),
+    #[cfg(feature = "gui")]
     Explore(ParameterFilePath),
+    /// Render a grid of progressively zoomed-in views into one image.
+    ContactSheet(ContactSheetArgs),
+    /// Plot the phase-space trajectory of one driven-damped pendulum initial
+    /// condition.
+    PhaseSpace(PhaseSpaceArgs),
+    /// Plot where the anti-aliasing samples of one pixel land, for each
+    /// sample pattern.
+    SamplePattern(SamplePatternArgs),
+    /// Render two params files with the same resolution and write an image
+    /// of the per-pixel difference between them.
+    Diff(DiffArgs),
+}
+
+/// Arguments of the `diff` command.
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// The first params file, along with the output and view options.
+    #[command(flatten)]
+    pub params: ParameterFilePath,
+
+    /// Path to the second JSON parameter file. The view overrides and the
+    /// quality preset apply to both files.
+    pub other_params_path: String,
+
+    /// How to draw the difference.
+    #[clap(long, value_enum, default_value_t = DiffMode::Absolute)]
+    pub mode: DiffMode,
+}
+
+/// Arguments of the `sample-pattern` command.
+#[derive(Debug, Args)]
+pub struct SamplePatternArgs {
+    /// The params file whose view holds the pixel being plotted.
+    #[command(flatten)]
+    pub params: ParameterFilePath,
+
+    /// Samples per pixel side, i.e. `sampling_level + 1`.
+    #[clap(long, default_value_t = 4)]
+    pub subpixel_count: u32,
+
+    /// Output pixel to plot: its column and row.
+    #[clap(long, num_args = 2, value_names = ["X", "Y"], default_values_t = [0, 0])]
+    pub pixel: Vec<u32>,
+}
+
+/// Arguments of the `phase-space` command.
+#[derive(Debug, Args)]
+pub struct PhaseSpaceArgs {
+    /// The driven-damped pendulum params file that sets the simulation.
+    #[command(flatten)]
+    pub params: ParameterFilePath,
+
+    /// Initial condition to simulate: the angle and the rate.
+    #[clap(long, num_args = 2, value_names = ["ANGLE", "RATE"], allow_hyphen_values = true, required = true)]
+    pub point: Vec<f64>,
 }
 
+/// Arguments of the `contact-sheet` command.
 #[derive(Debug, Args)]
+pub struct ContactSheetArgs {
+    /// The params file (and view overrides) of the first, widest tile.
+    #[command(flatten)]
+    pub params: ParameterFilePath,
+
+    /// Ratio between the view widths of consecutive tiles.
+    #[clap(long, default_value_t = 4.0)]
+    pub zoom_factor: f64,
+
+    /// Number of tiles, starting from the view in the params file.
+    #[clap(long, default_value_t = 6)]
+    pub count: u32,
+
+    /// Tiles per row. Defaults to a roughly square grid.
+    #[clap(long)]
+    pub columns: Option<u32>,
+}
+
+#[derive(Debug, Clone, Args)]
 pub struct ParameterFilePath {
+    /// Path to the JSON parameter file, or `-` to read it from stdin.
     pub params_path: String,
 
     #[clap(long, short)]
     pub date_time_out: bool,
+
+    /// Override the view (resolution, center, width) with a view string, as
+    /// printed by the `V` key in explore mode.
+    #[clap(long)]
+    pub view: Option<String>,
+
+    /// Override the horizontal coordinate of the view center.
+    #[clap(long, allow_hyphen_values = true)]
+    pub center_x: Option<f64>,
+
+    /// Override the vertical coordinate of the view center.
+    #[clap(long, allow_hyphen_values = true)]
+    pub center_y: Option<f64>,
+
+    /// Override the width of the view, in the fractal's coordinates.
+    #[clap(long)]
+    pub width: Option<f64>,
+
+    /// Render only a sub-region of the image, given in pixels: the column
+    /// and row of its top-left pixel, then its width and height. Its pixels
+    /// line up exactly with those of the full image, so the output can be
+    /// pasted over the matching region of a finished render. Colors are
+    /// normalized over the crop alone.
+    #[clap(long, num_args = 4, value_names = ["LEFT", "TOP", "WIDTH", "HEIGHT"])]
+    pub crop: Option<Vec<u32>>,
+
+    /// Refuse to render images with more pixels (width x height) than this.
+    /// Guards against a typo in the resolution exhausting memory; raise it to
+    /// render very large images on purpose.
+    #[clap(long, default_value_t = DEFAULT_MAX_PIXEL_COUNT)]
+    pub max_pixel_count: u64,
+
+    /// Override the anti-aliasing level and iteration limits of the params
+    /// file with a named quality level.
+    #[clap(long, value_enum)]
+    pub quality_preset: Option<QualityPreset>,
+}
+
+/// Named quality levels for `--quality-preset`. Each one replaces the
+/// `sampling_level` of the loaded parameters and, for the quadratic maps and
+/// the driven-damped pendulum, their iteration limits. `draft` matches the
+/// `*-quickly` examples and `print` the `*-high-fidelity` ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QualityPreset {
+    /// Fast preview: 2x2 anti-aliasing, like the `*-quickly` examples.
+    Draft,
+    /// 3x3 anti-aliasing, enough for most images viewed on screen.
+    Standard,
+    /// 5x5 anti-aliasing with higher iteration limits.
+    High,
+    /// 7x7 anti-aliasing and the highest iteration limits, like the
+    /// `*-high-fidelity` examples.
+    Print,
+}
+
+impl QualityPreset {
+    fn sampling_level(self) -> i32 {
+        match self {
+            QualityPreset::Draft => 1,
+            QualityPreset::Standard => 2,
+            QualityPreset::High => 4,
+            QualityPreset::Print => 6,
+        }
+    }
+
+    /// `max_iter_count` for the Mandelbrot and Julia sets.
+    fn max_iter_count(self) -> u32 {
+        match self {
+            QualityPreset::Draft => 1024,
+            QualityPreset::Standard => 2048,
+            QualityPreset::High => 4096,
+            QualityPreset::Print => 8192,
+        }
+    }
+
+    /// `(n_max_period, n_steps_per_period, periodic_state_error_tolerance)`
+    /// for the driven-damped pendulum.
+    fn ddp_simulation(self) -> (u32, u32, f64) {
+        match self {
+            QualityPreset::Draft => (50, 12, 0.05),
+            QualityPreset::Standard => (100, 16, 0.01),
+            QualityPreset::High => (150, 24, 0.005),
+            QualityPreset::Print => (200, 32, 0.002),
+        }
+    }
+
+    /// Overwrite the quality settings of `params`. The Buddhabrot has no
+    /// anti-aliasing level, so it rejects a preset.
+    pub fn apply(self, params: &mut FractalParams) -> Result<(), String> {
+        let sampling_level = self.sampling_level();
+        match params {
+            FractalParams::Mandelbrot(inner) => {
+                inner.render_options.sampling_level = sampling_level;
+                inner.convergence_params.max_iter_count = self.max_iter_count();
+            }
+            FractalParams::Julia(inner) => {
+                inner.render_options.sampling_level = sampling_level;
+                inner.convergence_params.max_iter_count = self.max_iter_count();
+            }
+            FractalParams::DrivenDampedPendulum(inner) => {
+                inner.render_options.sampling_level = sampling_level;
+                (
+                    inner.n_max_period,
+                    inner.n_steps_per_period,
+                    inner.periodic_state_error_tolerance,
+                ) = self.ddp_simulation();
+            }
+            FractalParams::NewtonsMethod(inner) => {
+                inner.params.render_options.sampling_level = sampling_level;
+            }
+            FractalParams::MagneticPendulum(inner) => {
+                inner.render_options.sampling_level = sampling_level;
+            }
+            FractalParams::BarnsleyFern(inner) => {
+                inner.render_options.sampling_level = sampling_level;
+            }
+            FractalParams::Sierpinski(inner) => {
+                inner.render_options.sampling_level = sampling_level;
+            }
+            FractalParams::Buddhabrot(_) => {
+                return Err("the Buddhabrot does not support `--quality-preset`".to_owned());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ParameterFilePath {
+    /// The raw JSON parameters: the contents of `params_path`, or all of
+    /// stdin if it is `-`.
+    pub fn read_params_json(&self) -> std::io::Result<String> {
+        self.read_params_json_from(std::io::stdin().lock())
+    }
+
+    fn read_params_json_from(&self, mut stdin: impl std::io::Read) -> std::io::Result<String> {
+        if self.params_path == STDIN_PARAMS_PATH {
+            let mut json = String::new();
+            stdin.read_to_string(&mut json)?;
+            Ok(json)
+        } else {
+            std::fs::read_to_string(&self.params_path)
+        }
+    }
+
+    /// Directory that relative paths inside the parameters (e.g. a `base`
+    /// file) are resolved against: that of `params_path`, or the working
+    /// directory for stdin.
+    pub fn params_directory(&self) -> &std::path::Path {
+        if self.params_path == STDIN_PARAMS_PATH {
+            std::path::Path::new("")
+        } else {
+            std::path::Path::new(&self.params_path)
+                .parent()
+                .unwrap_or(std::path::Path::new(""))
+        }
+    }
+
+    /// Base name for the output files: the stem of the parameter file, or
+    /// `stdin` when the parameters are piped in.
+    pub fn output_base_name(&self) -> &str {
+        if self.params_path == STDIN_PARAMS_PATH {
+            STDIN_BASE_NAME
+        } else {
+            extract_base_name(&self.params_path)
+        }
+    }
+
+    /// True if any flag that overrides the view was passed.
+    pub fn has_view_override(&self) -> bool {
+        self.view.is_some()
+            || self.center_x.is_some()
+            || self.center_y.is_some()
+            || self.width.is_some()
+            || self.crop.is_some()
+    }
+
+    /// Apply the view overrides to `image_specification`. A `--view` string
+    /// replaces the resolution, center, and width first (the pixel aspect
+    /// ratio from the params file is kept); the individual center / width
+    /// flags are then applied on top of it, and `--crop` last of all.
+    pub fn override_view(
+        &self,
+        mut image_specification: ImageSpecification,
+    ) -> Result<ImageSpecification, String> {
+        if let Some(view) = &self.view {
+            image_specification = ImageSpecification {
+                pixel_aspect_ratio: image_specification.pixel_aspect_ratio,
+                min_width: image_specification.min_width,
+                max_width: image_specification.max_width,
+                ..ImageSpecification::from_view_string(view)?
+            };
+        }
+        if let Some(center_x) = self.center_x {
+            image_specification.center[0] = center_x;
+        }
+        if let Some(center_y) = self.center_y {
+            image_specification.center[1] = center_y;
+        }
+        if let Some(width) = self.width {
+            if !(width.is_finite() && width > 0.0) {
+                return Err(format!("`--width` must be positive, found {width}"));
+            }
+            image_specification.width = width;
+        }
+        if let Some(crop) = &self.crop {
+            image_specification =
+                image_specification.crop([crop[0], crop[1]], [crop[2], crop[3]])?;
+        }
+        Ok(image_specification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> ParameterFilePath {
+        parse_with_path("params.json", args)
+    }
+
+    fn parse_with_path(params_path: &str, args: &[&str]) -> ParameterFilePath {
+        let args = FractalRendererArgs::parse_from(
+            ["fractal-renderer", "render", params_path]
+                .iter()
+                .chain(args),
+        );
+        match args.command {
+            Some(CommandsEnum::Render(params)) => params,
+            _ => panic!("expected the render command"),
+        }
+    }
+
+    #[test]
+    fn dash_reads_params_from_stdin() {
+        let params = parse_with_path("-", &["--width", "2"]);
+        let json = params
+            .read_params_json_from(r#"{"piped": true}"#.as_bytes())
+            .unwrap();
+        assert_eq!(json, r#"{"piped": true}"#);
+        assert_eq!(params.output_base_name(), "stdin");
+        assert_eq!(params.params_directory(), std::path::Path::new(""));
+
+        let params = parse_with_path("examples/render-mandelbrot/params.json", &[]);
+        assert_eq!(params.output_base_name(), "params");
+        assert_eq!(
+            params.params_directory(),
+            std::path::Path::new("examples/render-mandelbrot")
+        );
+        let json = params.read_params_json_from("ignored".as_bytes()).unwrap();
+        assert!(json.contains("Mandelbrot"));
+    }
+
+    #[test]
+    fn center_and_width_flags_override_the_loaded_view() {
+        let loaded = ImageSpecification {
+            resolution: [40, 30],
+            center: [0.0, 0.0],
+            width: 4.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let params = parse(&["--center-x", "-0.75", "--width", "0.5"]);
+        assert!(params.has_view_override());
+        let view = params.override_view(loaded).unwrap();
+        assert_eq!(view.resolution, [40, 30]);
+        assert_eq!(view.center, [-0.75, 0.0]);
+        assert_eq!(view.width, 0.5);
+
+        assert!(!parse(&[]).has_view_override());
+        assert!(parse(&["--width", "0"]).override_view(loaded).is_err());
+    }
+
+    #[test]
+    fn max_pixel_count_defaults_to_the_library_limit() {
+        assert_eq!(parse(&[]).max_pixel_count, DEFAULT_MAX_PIXEL_COUNT);
+        let raised = parse(&["--max-pixel-count", "4800000000"]);
+        assert_eq!(raised.max_pixel_count, 4_800_000_000);
+    }
+
+    #[test]
+    fn individual_flags_apply_on_top_of_a_view_string() {
+        let shared = ImageSpecification {
+            resolution: [8, 6],
+            center: [1.0, 2.0],
+            width: 3.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let view_string = shared.to_view_string();
+        let params = parse(&["--view", &view_string, "--center-y", "-5"]);
+        let view = params.override_view(shared.upsample(2)).unwrap();
+        assert_eq!(view.resolution, [8, 6]);
+        assert_eq!(view.center, [1.0, -5.0]);
+        assert_eq!(view.width, 3.0);
+    }
+
+    fn load_example(name: &str) -> FractalParams {
+        let path = format!("examples/{name}/params.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn quality_presets_match_the_quick_and_high_fidelity_examples() {
+        let mut params = load_example("render-driven-damped-pendulum");
+        parse(&["--quality-preset", "draft"])
+            .quality_preset
+            .unwrap()
+            .apply(&mut params)
+            .unwrap();
+        let FractalParams::DrivenDampedPendulum(draft) = &params else {
+            panic!("expected the driven-damped pendulum");
+        };
+        let FractalParams::DrivenDampedPendulum(quickly) =
+            load_example("render-driven-damped-pendulum-quickly")
+        else {
+            panic!("expected the driven-damped pendulum");
+        };
+        assert_eq!(draft.n_max_period, quickly.n_max_period);
+        assert_eq!(draft.n_steps_per_period, quickly.n_steps_per_period);
+        assert_eq!(
+            draft.periodic_state_error_tolerance,
+            quickly.periodic_state_error_tolerance
+        );
+        assert_eq!(
+            draft.render_options.sampling_level,
+            quickly.render_options.sampling_level
+        );
+
+        QualityPreset::Print.apply(&mut params).unwrap();
+        let FractalParams::DrivenDampedPendulum(print) = &params else {
+            panic!("expected the driven-damped pendulum");
+        };
+        let FractalParams::DrivenDampedPendulum(high_fidelity) =
+            load_example("render-driven-damped-pendulum-high-fidelity")
+        else {
+            panic!("expected the driven-damped pendulum");
+        };
+        assert_eq!(print.n_max_period, high_fidelity.n_max_period);
+        assert_eq!(
+            print.render_options.sampling_level,
+            high_fidelity.render_options.sampling_level
+        );
+    }
+
+    #[test]
+    fn quality_preset_overrides_iteration_limits_and_rejects_the_buddhabrot() {
+        assert_eq!(parse(&[]).quality_preset, None);
+
+        let mut params = load_example("render-mandelbrot-default");
+        QualityPreset::High.apply(&mut params).unwrap();
+        let FractalParams::Mandelbrot(mandelbrot) = &params else {
+            panic!("expected the Mandelbrot set");
+        };
+        assert_eq!(mandelbrot.render_options.sampling_level, 4);
+        assert_eq!(mandelbrot.convergence_params.max_iter_count, 4096);
+
+        let mut params = load_example("render-barnsley-fern");
+        QualityPreset::Print.apply(&mut params).unwrap();
+        let FractalParams::BarnsleyFern(fern) = &params else {
+            panic!("expected the Barnsley fern");
+        };
+        assert_eq!(fern.render_options.sampling_level, 6);
+
+        let mut params = load_example("render-buddhabrot");
+        assert!(QualityPreset::Standard.apply(&mut params).is_err());
+    }
+
+    #[test]
+    fn each_quality_preset_sets_the_julia_sampling_level_and_iteration_limit() {
+        for (preset, sampling_level, max_iter_count) in [
+            (QualityPreset::Draft, 1, 1024),
+            (QualityPreset::Standard, 2, 2048),
+            (QualityPreset::High, 4, 4096),
+            (QualityPreset::Print, 6, 8192),
+        ] {
+            let mut params = load_example("render-julia-spiral");
+            preset.apply(&mut params).unwrap();
+            let FractalParams::Julia(julia) = &params else {
+                panic!("expected a Julia set");
+            };
+            assert_eq!(julia.render_options.sampling_level, sampling_level);
+            assert_eq!(julia.convergence_params.max_iter_count, max_iter_count);
+        }
+    }
+
+    #[test]
+    fn crop_flag_renders_a_pixel_aligned_sub_region() {
+        let loaded = ImageSpecification {
+            resolution: [40, 30],
+            center: [0.0, 0.0],
+            width: 4.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let params = parse(&["--crop", "10", "5", "8", "6"]);
+        assert!(params.has_view_override());
+        let view = params.override_view(loaded).unwrap();
+        assert_eq!(view, loaded.crop([10, 5], [8, 6]).unwrap());
+
+        // The crop is taken from the view after the other overrides.
+        let params = parse(&["--width", "2", "--crop", "0", "0", "40", "30"]);
+        let view = params.override_view(loaded).unwrap();
+        assert!((view.width - 2.0).abs() < 1e-12);
+        assert!(
+            parse(&["--crop", "35", "0", "8", "6"])
+                .override_view(loaded)
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,224 @@
+//! The `contact-sheet` command: render one view of a fractal at several
+//! zoom levels and tile the images into a single PNG, with the region shown
+//! by each next tile outlined on the one before it. Handy for showing the
+//! self-similarity of a fractal at a glance.
+
+use image::{Rgb, RgbImage};
+
+use crate::cli::render::render_fractal_to_image;
+use crate::core::{
+    file_io::FilePrefix,
+    image_utils::{ImageSpecification, check_pixel_count, write_image_to_file_or_panic},
+};
+use crate::fractals::common::FractalParams;
+
+/// Pixels of background between neighboring tiles, and around the sheet.
+const TILE_SPACING: u32 = 4;
+
+const SPACING_COLOR: Rgb<u8> = Rgb([32, 32, 32]);
+
+const OUTLINE_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// Layout of the sheet: `count` tiles, each `zoom_factor` times narrower
+/// than the one before, `columns` to a row.
+#[derive(Debug, Clone, Copy)]
+pub struct ContactSheetLayout {
+    /// Ratio between the view widths of consecutive tiles; above one.
+    pub zoom_factor: f64,
+    /// Number of tiles, at least one.
+    pub count: u32,
+    /// Tiles per row, between one and `count`.
+    pub columns: u32,
+}
+
+impl ContactSheetLayout {
+    /// `columns` defaults to a roughly square grid.
+    pub fn new(zoom_factor: f64, count: u32, columns: Option<u32>) -> Result<Self, String> {
+        if !(zoom_factor.is_finite() && zoom_factor > 1.0) {
+            return Err(format!(
+                "`--zoom-factor` must be greater than one, found {zoom_factor}"
+            ));
+        }
+        if count == 0 {
+            return Err("`--count` must be at least one".to_owned());
+        }
+        let columns = columns.unwrap_or_else(|| (count as f64).sqrt().ceil() as u32);
+        if columns == 0 {
+            return Err("`--columns` must be at least one".to_owned());
+        }
+        Ok(ContactSheetLayout {
+            zoom_factor,
+            count,
+            columns: columns.min(count),
+        })
+    }
+
+    /// Number of tile rows; the last one may be partly empty.
+    pub fn rows(&self) -> u32 {
+        self.count.div_ceil(self.columns)
+    }
+
+    /// Resolution of the composited sheet for tiles of `tile_resolution`.
+    pub fn sheet_resolution(&self, tile_resolution: [u32; 2]) -> [u32; 2] {
+        let grid = [self.columns, self.rows()];
+        [0, 1].map(|dim| grid[dim] * (tile_resolution[dim] + TILE_SPACING) + TILE_SPACING)
+    }
+
+    /// View of tile `index`: the base view, zoomed in about its center.
+    fn tile_specification(&self, base: &ImageSpecification, index: u32) -> ImageSpecification {
+        ImageSpecification {
+            width: base.width / self.zoom_factor.powi(index as i32),
+            ..*base
+        }
+    }
+}
+
+/// Render each tile of the sheet and composite them into one image. Sheets
+/// with more than `max_pixel_count` pixels are rejected before any tile is
+/// rendered.
+pub fn contact_sheet_image(
+    mut params: FractalParams,
+    layout: &ContactSheetLayout,
+    max_pixel_count: u64,
+) -> Result<RgbImage, String> {
+    check_pixel_count(
+        layout.sheet_resolution(params.resolution()),
+        max_pixel_count,
+    )
+    .map_err(|error| error.to_string())?;
+    let base = params
+        .image_specification()
+        .ok_or_else(|| "chaos-game fractals cannot be zoomed for a contact sheet".to_owned())?;
+    let tiles = (0..layout.count)
+        .map(|index| {
+            params.set_image_specification(layout.tile_specification(&base, index))?;
+            render_fractal_to_image(&params, max_pixel_count)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(composite_tiles(&tiles, layout))
+}
+
+/// Called by main: render the contact sheet and write it to a PNG file.
+pub fn render_contact_sheet(
+    params: FractalParams,
+    layout: &ContactSheetLayout,
+    file_prefix: FilePrefix,
+    max_pixel_count: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let sheet = contact_sheet_image(params, layout, max_pixel_count)?;
+    write_image_to_file_or_panic(file_prefix.full_path_with_suffix(".png"), |f| sheet.save(f));
+    Ok(())
+}
+
+/// Lay the tiles out row by row, outlining on each tile the region that the
+/// next tile zooms into.
+fn composite_tiles(tiles: &[RgbImage], layout: &ContactSheetLayout) -> RgbImage {
+    let (tile_width, tile_height) = tiles[0].dimensions();
+    let [sheet_width, sheet_height] = layout.sheet_resolution([tile_width, tile_height]);
+    let mut sheet = RgbImage::from_pixel(sheet_width, sheet_height, SPACING_COLOR);
+    for (index, tile) in tiles.iter().enumerate() {
+        let index = index as u32;
+        let x0 = TILE_SPACING + (index % layout.columns) * (tile_width + TILE_SPACING);
+        let y0 = TILE_SPACING + (index / layout.columns) * (tile_height + TILE_SPACING);
+        image::imageops::replace(&mut sheet, tile, x0 as i64, y0 as i64);
+        if index + 1 < layout.count {
+            let [half_width, half_height] =
+                [tile_width, tile_height].map(|n| (0.5 * n as f64 / layout.zoom_factor) as u32);
+            let [cx, cy] = [x0 + tile_width / 2, y0 + tile_height / 2];
+            draw_rectangle_outline(
+                &mut sheet,
+                [cx - half_width, cy - half_height],
+                [cx + half_width, cy + half_height],
+            );
+        }
+    }
+    sheet
+}
+
+/// One-pixel outline with inclusive corners `lower` and `upper`.
+fn draw_rectangle_outline(image: &mut RgbImage, lower: [u32; 2], upper: [u32; 2]) {
+    for x in lower[0]..=upper[0] {
+        image.put_pixel(x, lower[1], OUTLINE_COLOR);
+        image.put_pixel(x, upper[1], OUTLINE_COLOR);
+    }
+    for y in lower[1]..=upper[1] {
+        image.put_pixel(lower[0], y, OUTLINE_COLOR);
+        image.put_pixel(upper[0], y, OUTLINE_COLOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::image_utils::DEFAULT_MAX_PIXEL_COUNT;
+
+    #[test]
+    fn layout_defaults_to_a_square_grid_and_validates_its_inputs() {
+        let layout = ContactSheetLayout::new(2.0, 5, None).unwrap();
+        assert_eq!([layout.columns, layout.rows()], [3, 2]);
+        assert_eq!(layout.sheet_resolution([10, 8]), [46, 28]);
+
+        let layout = ContactSheetLayout::new(2.0, 2, Some(4)).unwrap();
+        assert_eq!([layout.columns, layout.rows()], [2, 1]);
+
+        assert!(ContactSheetLayout::new(1.0, 4, None).is_err());
+        assert!(ContactSheetLayout::new(2.0, 0, None).is_err());
+        assert!(ContactSheetLayout::new(2.0, 4, Some(0)).is_err());
+    }
+
+    #[test]
+    fn tiles_are_placed_in_rows_with_the_zoom_region_outlined() {
+        let layout = ContactSheetLayout::new(4.0, 3, Some(2)).unwrap();
+        let tiles: Vec<RgbImage> = (0..3)
+            .map(|i| RgbImage::from_pixel(16, 8, Rgb([10 * i as u8, 0, 0])))
+            .collect();
+        let sheet = composite_tiles(&tiles, &layout);
+        assert_eq!(sheet.dimensions(), (44, 28));
+
+        // Tile corners, and the spacing between them.
+        assert_eq!(*sheet.get_pixel(4, 4), Rgb([0, 0, 0]));
+        assert_eq!(*sheet.get_pixel(24, 4), Rgb([10, 0, 0]));
+        assert_eq!(*sheet.get_pixel(4, 16), Rgb([20, 0, 0]));
+        assert_eq!(*sheet.get_pixel(22, 4), SPACING_COLOR);
+
+        // The first tile outlines its central quarter (4 x 2 pixels)...
+        assert_eq!(*sheet.get_pixel(4 + 6, 4 + 3), OUTLINE_COLOR);
+        assert_eq!(*sheet.get_pixel(4 + 10, 4 + 5), OUTLINE_COLOR);
+        assert_eq!(*sheet.get_pixel(4 + 5, 4 + 3), Rgb([0, 0, 0]));
+        // ... and the last tile has nothing left to outline.
+        assert!((4..20).all(|x| (16..24).all(|y| *sheet.get_pixel(x, y) == Rgb([20, 0, 0]))));
+    }
+
+    #[test]
+    fn contact_sheet_renders_each_zoom_level() {
+        let params = || -> FractalParams {
+            serde_json::from_str(
+                r#"{
+                "DrivenDampedPendulum": {
+                    "image_specification": {
+                        "resolution": [12, 8],
+                        "center": [0, 0],
+                        "width": 14
+                    },
+                    "time_phase": 0,
+                    "n_max_period": 4,
+                    "n_steps_per_period": 8,
+                    "periodic_state_error_tolerance": 0.05,
+                    "render_options": { "sampling_level": 0 }
+                }
+            }"#,
+            )
+            .unwrap()
+        };
+        let layout = ContactSheetLayout::new(3.0, 4, None).unwrap();
+        let sheet = contact_sheet_image(params(), &layout, DEFAULT_MAX_PIXEL_COUNT).unwrap();
+        assert_eq!(sheet.dimensions(), (36, 28));
+        assert!(contact_sheet_image(params(), &layout, 36 * 28 - 1).is_err());
+
+        let fern: FractalParams = serde_json::from_str(
+            &std::fs::read_to_string("examples/render-barnsley-fern/params.json").unwrap(),
+        )
+        .unwrap();
+        assert!(contact_sheet_image(fern, &layout, DEFAULT_MAX_PIXEL_COUNT).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! The `diff` command: render two params files that share a resolution and
+//! write an image of how their pixels differ. Handy for seeing exactly what
+//! changing one parameter (e.g. `max_iter_count`) does to a render.
+
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+
+use crate::cli::render::render_fractal_to_image;
+use crate::core::{
+    color_map::{ColorMapKeyFrame, ColorMapper, KeyframeColorMap},
+    file_io::FilePrefix,
+    image_utils::write_image_to_file_or_panic,
+    interpolation::LinearInterpolator,
+};
+use crate::fractals::common::FractalParams;
+
+/// How the difference between the two renders is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DiffMode {
+    /// Per-channel `|second - first|`, black where the renders agree.
+    #[default]
+    Absolute,
+    /// Change in brightness from the first render to the second, through a
+    /// diverging color map: blue where it got darker, red where it got
+    /// brighter, white where it did not change.
+    Signed,
+}
+
+/// Diverging color map for `DiffMode::Signed`, centered on white.
+const SIGNED_DIFF_COLOR_MAP: [ColorMapKeyFrame; 3] = [
+    ColorMapKeyFrame {
+        query: 0.0,
+        rgb_raw: [40, 80, 220],
+    },
+    ColorMapKeyFrame {
+        query: 0.5,
+        rgb_raw: [255, 255, 255],
+    },
+    ColorMapKeyFrame {
+        query: 1.0,
+        rgb_raw: [220, 40, 40],
+    },
+];
+
+/// Perceived brightness of `pixel`, on `[0, 255]`.
+fn luma(pixel: &Rgb<u8>) -> f32 {
+    0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32
+}
+
+/// Pixel-by-pixel difference of two renders of the same size. Either mode
+/// stretches the largest difference in the image to the full color range,
+/// so that small changes stay visible.
+pub fn difference_image(
+    first: &RgbImage,
+    second: &RgbImage,
+    mode: DiffMode,
+) -> Result<RgbImage, String> {
+    if first.dimensions() != second.dimensions() {
+        return Err(format!(
+            "the renders differ in size: {:?} and {:?}",
+            first.dimensions(),
+            second.dimensions()
+        ));
+    }
+    let (width, height) = first.dimensions();
+    let pairs = || first.pixels().zip(second.pixels());
+    let image = match mode {
+        DiffMode::Absolute => {
+            let largest = pairs()
+                .flat_map(|(a, b)| (0..3).map(move |i| a[i].abs_diff(b[i])))
+                .max()
+                .unwrap_or(0)
+                .max(1) as f32;
+            let pixels = pairs()
+                .flat_map(|(a, b)| {
+                    (0..3)
+                        .map(move |i| (255.0 * a[i].abs_diff(b[i]) as f32 / largest).round() as u8)
+                })
+                .collect();
+            RgbImage::from_raw(width, height, pixels)
+        }
+        DiffMode::Signed => {
+            let color_map = KeyframeColorMap::new(&SIGNED_DIFF_COLOR_MAP, LinearInterpolator)
+                .map_err(|err| format!("the signed difference color map is invalid: {err}"))?;
+            let deltas: Vec<f32> = pairs().map(|(a, b)| luma(b) - luma(a)).collect();
+            let largest = deltas
+                .iter()
+                .fold(0.0f32, |largest, delta| largest.max(delta.abs()))
+                .max(f32::EPSILON);
+            let pixels = deltas
+                .iter()
+                .flat_map(|delta| color_map.compute_pixel(0.5 + 0.5 * delta / largest).0)
+                .collect();
+            RgbImage::from_raw(width, height, pixels)
+        }
+    };
+    image.ok_or_else(|| "the difference image does not have one RGB triple per pixel".to_owned())
+}
+
+/// Called by main: render both params files, report how much of the image
+/// changed, and write the difference image. Renders with more than
+/// `max_pixel_count` pixels are rejected.
+pub fn render_difference(
+    first: &FractalParams,
+    second: &FractalParams,
+    mode: DiffMode,
+    file_prefix: FilePrefix,
+    max_pixel_count: u64,
+) -> Result<PathBuf, String> {
+    if first.resolution() != second.resolution() {
+        return Err(format!(
+            "the params files must share a resolution, found {:?} and {:?}",
+            first.resolution(),
+            second.resolution()
+        ));
+    }
+    let [first_image, second_image] =
+        [first, second].map(|params| render_fractal_to_image(params, max_pixel_count));
+    let (first_image, second_image) = (first_image?, second_image?);
+    let changed = first_image
+        .pixels()
+        .zip(second_image.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    let total = first_image.pixels().len();
+    println!(
+        "INFO:  {changed} of {total} pixels differ ({:.2}%)",
+        100.0 * changed as f64 / total as f64
+    );
+    let image = difference_image(&first_image, &second_image, mode)?;
+    let path = file_prefix.full_path_with_suffix("_diff.png");
+    write_image_to_file_or_panic(path.clone(), |path| image.save(path));
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_renders_give_a_neutral_difference() {
+        let image = RgbImage::from_fn(4, 3, |x, y| Rgb([10 * x as u8, 20 * y as u8, 7]));
+        let absolute = difference_image(&image, &image, DiffMode::Absolute).unwrap();
+        assert!(absolute.pixels().all(|pixel| *pixel == Rgb([0, 0, 0])));
+        let signed = difference_image(&image, &image, DiffMode::Signed).unwrap();
+        assert!(signed.pixels().all(|pixel| *pixel == Rgb([255, 255, 255])));
+        assert!(difference_image(&image, &RgbImage::new(3, 4), DiffMode::Absolute).is_err());
+    }
+
+    #[test]
+    fn differences_are_stretched_to_the_full_range() {
+        let first = RgbImage::from_pixel(3, 1, Rgb([100, 100, 100]));
+        let mut second = first.clone();
+        second.put_pixel(0, 0, Rgb([120, 100, 100]));
+        second.put_pixel(1, 0, Rgb([90, 90, 90]));
+
+        let absolute = difference_image(&first, &second, DiffMode::Absolute).unwrap();
+        assert_eq!(*absolute.get_pixel(0, 0), Rgb([255, 0, 0]));
+        assert_eq!(*absolute.get_pixel(1, 0), Rgb([128, 128, 128]));
+        assert_eq!(*absolute.get_pixel(2, 0), Rgb([0, 0, 0]));
+
+        // The darkened pixel is the largest brightness change, so it takes
+        // the blue end of the map; the reddened one is only a little
+        // brighter.
+        let signed = difference_image(&first, &second, DiffMode::Signed).unwrap();
+        assert_eq!(
+            *signed.get_pixel(1, 0),
+            Rgb(SIGNED_DIFF_COLOR_MAP[0].rgb_raw)
+        );
+        let brighter = signed.get_pixel(0, 0);
+        assert!(
+            brighter[0] > brighter[2] && brighter[2] < 255,
+            "{brighter:?}"
+        );
+        assert_eq!(*signed.get_pixel(2, 0), Rgb([255, 255, 255]));
+    }
+}
@@ -1,9 +1,15 @@
 use std::any::type_name;
 
 use crate::{
-    core::{file_io::FilePrefix, interactive},
+    core::{
+        chaos_game::SampleAccumulatorRenderable, file_io::FilePrefix, image_utils::Renderable,
+        interactive,
+    },
     fractals::{
-        common::{FractalParams, ddp_snapshot_json, julia_snapshot_json, mandelbrot_snapshot_json},
+        common::{
+            FractalParams, barnsley_fern_snapshot_json, ddp_snapshot_json, julia_snapshot_json,
+            magnetic_pendulum_snapshot_json, mandelbrot_snapshot_json, sierpinski_snapshot_json,
+        },
         newtons_method,
     },
 };
@@ -53,6 +59,38 @@ pub fn explore_fractal(params: &FractalParams, mut file_prefix: FilePrefix) -> e
             newtons_method::explore_fractal(inner_params, file_prefix)
         }
 
+        FractalParams::MagneticPendulum(inner_params) => {
+            file_prefix.create_and_step_into_sub_directory("magnetic_pendulum");
+            interactive::explore(
+                file_prefix,
+                inner_params.image_specification,
+                (**inner_params).clone(),
+                magnetic_pendulum_snapshot_json,
+            )
+        }
+
+        FractalParams::BarnsleyFern(inner_params) => {
+            file_prefix.create_and_step_into_sub_directory("barnsley_fern");
+            let renderable = SampleAccumulatorRenderable::new((**inner_params).clone());
+            interactive::explore(
+                file_prefix,
+                *renderable.image_specification(),
+                renderable,
+                barnsley_fern_snapshot_json,
+            )
+        }
+
+        FractalParams::Sierpinski(inner_params) => {
+            file_prefix.create_and_step_into_sub_directory("sierpinski");
+            let renderable = SampleAccumulatorRenderable::new((**inner_params).clone());
+            interactive::explore(
+                file_prefix,
+                *renderable.image_specification(),
+                renderable,
+                sierpinski_snapshot_json,
+            )
+        }
+
         _ => {
             println!(
                 "ERROR: Parameter type `{}` does not yet implement the `RenderWindow` trait!  Aborting.",
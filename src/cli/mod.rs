@@ -1,3 +1,8 @@
 pub mod args;
+pub mod contact_sheet;
+pub mod diff;
+#[cfg(feature = "gui")]
 pub mod explore;
+pub mod phase_space;
 pub mod render;
+pub mod sample_pattern;
@@ -0,0 +1,41 @@
+//! The `phase-space` command: simulate the driven-damped pendulum from one
+//! initial condition and plot its trajectory, angle against rate. Shows why
+//! a single pixel of the basin image lands in the basin it does.
+
+use std::path::PathBuf;
+
+use crate::core::{
+    file_io::FilePrefix,
+    image_utils::write_image_to_file_or_panic,
+    phase_space_plot::{PHASE_SPACE_PLOT_RESOLUTION, phase_space_plot_image},
+};
+use crate::fractals::common::FractalParams;
+
+/// Plot the trajectory from `point`, an `[angle, rate]` initial condition,
+/// and report the basin it settles into. Only the driven-damped pendulum
+/// has a phase space to plot.
+pub fn plot_phase_space(
+    params: &FractalParams,
+    point: [f64; 2],
+    mut file_prefix: FilePrefix,
+) -> Result<PathBuf, String> {
+    let FractalParams::DrivenDampedPendulum(inner) = params else {
+        return Err("the `phase-space` command only supports the DrivenDampedPendulum".to_owned());
+    };
+    let (states, basin) = inner.simulate_trajectory(point);
+    match basin {
+        Some(basin) => println!(
+            "INFO:  [{}, {}] settles into basin {basin}",
+            point[0], point[1]
+        ),
+        None => println!(
+            "INFO:  [{}, {}] did not settle within n_max_period = {}",
+            point[0], point[1], inner.n_max_period
+        ),
+    }
+    file_prefix.create_and_step_into_sub_directory("driven_damped_pendulum");
+    let path = file_prefix.full_path_with_suffix("_phase_space.png");
+    let image = phase_space_plot_image(&states, PHASE_SPACE_PLOT_RESOLUTION);
+    write_image_to_file_or_panic(path.clone(), |path| image.save(path));
+    Ok(path)
+}
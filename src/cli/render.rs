@@ -1,33 +1,58 @@
-use crate::core::image_utils;
-use crate::fractals::newtons_method::render_newtons_method;
+use image::RgbImage;
+
+use crate::core::image_utils::{self, RenderError, check_pixel_count, render_to_image};
+use crate::fractals::newtons_method::{
+    CommonParams, ComplexFunctionWithSlope, NewtonsMethodRenderable, SystemType,
+    render_newtons_method,
+};
 use crate::fractals::{
     barnsley_fern::render_barnsley_fern,
-    common::{FractalParams, ddp_snapshot_json, julia_snapshot_json, mandelbrot_snapshot_json},
+    buddhabrot::render_buddhabrot,
+    common::{
+        FractalParams, ddp_snapshot_json, julia_snapshot_json, magnetic_pendulum_snapshot_json,
+        mandelbrot_snapshot_json,
+    },
     sierpinski::render_sierpinski,
 };
 
 use crate::core::file_io::FilePrefix;
 
+/// Render `params` to a PNG file, with its params JSON and diagnostics, under
+/// `file_prefix`. Images with more than `max_pixel_count` pixels are
+/// rejected before anything is allocated or written.
 pub fn render_fractal(
     params: &FractalParams,
     mut file_prefix: FilePrefix,
+    max_pixel_count: u64,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    check_pixel_count(params.resolution(), max_pixel_count)?;
     match params {
         FractalParams::Mandelbrot(inner_params) => {
             file_prefix.create_and_step_into_sub_directory("mandelbrot");
             image_utils::render(
                 (**inner_params).clone(),
                 file_prefix,
+                "Mandelbrot",
                 mandelbrot_snapshot_json,
             )
         }
         FractalParams::Julia(inner_params) => {
             file_prefix.create_and_step_into_sub_directory("julia");
-            image_utils::render((**inner_params).clone(), file_prefix, julia_snapshot_json)
+            image_utils::render(
+                (**inner_params).clone(),
+                file_prefix,
+                "Julia",
+                julia_snapshot_json,
+            )
         }
         FractalParams::DrivenDampedPendulum(inner_params) => {
             file_prefix.create_and_step_into_sub_directory("driven_damped_pendulum");
-            image_utils::render((**inner_params).clone(), file_prefix, ddp_snapshot_json)
+            image_utils::render(
+                (**inner_params).clone(),
+                file_prefix,
+                "DrivenDampedPendulum",
+                ddp_snapshot_json,
+            )
         }
         FractalParams::BarnsleyFern(inner_params) => {
             file_prefix.create_and_step_into_sub_directory("barnsley_fern");
@@ -41,5 +66,70 @@ pub fn render_fractal(
             file_prefix.create_and_step_into_sub_directory("newtons_method");
             render_newtons_method(inner_params, file_prefix)
         }
+        FractalParams::Buddhabrot(inner_params) => {
+            file_prefix.create_and_step_into_sub_directory("buddhabrot");
+            render_buddhabrot(inner_params, file_prefix)
+        }
+        FractalParams::MagneticPendulum(inner_params) => {
+            file_prefix.create_and_step_into_sub_directory("magnetic_pendulum");
+            image_utils::render(
+                (**inner_params).clone(),
+                file_prefix,
+                "MagneticPendulum",
+                magnetic_pendulum_snapshot_json,
+            )
+        }
     }
 }
+
+/// Render the pixel-based fractals straight to an in-memory image, without
+/// writing any files. The chaos games and the Buddhabrot accumulate their
+/// samples while writing to disk, so they are rejected, as are images with
+/// more than `max_pixel_count` pixels.
+pub fn render_fractal_to_image(
+    params: &FractalParams,
+    max_pixel_count: u64,
+) -> Result<RgbImage, String> {
+    let image = match params {
+        FractalParams::Mandelbrot(inner) => render_to_image((**inner).clone(), max_pixel_count),
+        FractalParams::Julia(inner) => render_to_image((**inner).clone(), max_pixel_count),
+        FractalParams::DrivenDampedPendulum(inner) => {
+            render_to_image((**inner).clone(), max_pixel_count)
+        }
+        FractalParams::MagneticPendulum(inner) => {
+            render_to_image((**inner).clone(), max_pixel_count)
+        }
+        FractalParams::NewtonsMethod(inner) => match &inner.system {
+            SystemType::RootsOfUnity(system) => {
+                render_newton_image(&inner.params, system.as_ref().clone(), max_pixel_count)
+            }
+            SystemType::CoshMinusOne(system) => {
+                render_newton_image(&inner.params, system.as_ref().clone(), max_pixel_count)
+            }
+            SystemType::Expression(system) => {
+                render_newton_image(&inner.params, system.as_ref().clone(), max_pixel_count)
+            }
+        },
+        FractalParams::BarnsleyFern(_) | FractalParams::Sierpinski(_) => {
+            return Err("chaos-game fractals can only be rendered to file".to_owned());
+        }
+        FractalParams::Buddhabrot(_) => {
+            return Err("the Buddhabrot can only be rendered to file".to_owned());
+        }
+    };
+    image.map_err(|error| error.to_string())
+}
+
+fn render_newton_image<F>(
+    params: &CommonParams,
+    system: F,
+    max_pixel_count: u64,
+) -> Result<RgbImage, RenderError>
+where
+    F: ComplexFunctionWithSlope + Sync + Send,
+{
+    render_to_image(
+        NewtonsMethodRenderable::new(params.clone(), system),
+        max_pixel_count,
+    )
+}
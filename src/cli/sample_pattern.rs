@@ -0,0 +1,55 @@
+//! The `sample-pattern` command: plot where the anti-aliasing samples of
+//! one output pixel land, for the regular subpixel grid, its jittered
+//! variant, and the proposed Poisson-disk pattern.
+
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+
+use crate::core::{
+    file_io::FilePrefix,
+    image_utils::{ImageSpecification, write_image_to_file_or_panic},
+    sample_pattern::{
+        SAMPLE_PATTERN_MAGNIFICATION, SamplePattern, centroid, sample_offsets, sample_pattern_image,
+    },
+};
+
+/// Plot the samples of output `pixel` of `image_specification`, taking
+/// `subpixel_count²` samples per pixel, once per pattern. Writes one image
+/// per pattern and returns their paths.
+pub fn plot_sample_patterns(
+    image_specification: &ImageSpecification,
+    subpixel_count: u32,
+    pixel: [u32; 2],
+    mut file_prefix: FilePrefix,
+) -> Result<Vec<PathBuf>, String> {
+    if subpixel_count == 0 {
+        return Err("the subpixel count must be at least 1".to_owned());
+    }
+    let resolution = image_specification.resolution;
+    if pixel[0] >= resolution[0] || pixel[1] >= resolution[1] {
+        return Err(format!(
+            "pixel {pixel:?} is outside the {}x{} image",
+            resolution[0], resolution[1]
+        ));
+    }
+    file_prefix.create_and_step_into_sub_directory("sample_pattern");
+    let mut paths = Vec::new();
+    for pattern in SamplePattern::value_variants() {
+        let offsets = sample_offsets(image_specification, subpixel_count, pixel, *pattern);
+        let Some(name) = pattern.to_possible_value() else {
+            continue;
+        };
+        let name = name.get_name();
+        let [x, y] = centroid(&offsets);
+        println!(
+            "INFO:  {name}: {} samples, centroid offset [{x:.4}, {y:.4}] pixels",
+            offsets.len()
+        );
+        let path = file_prefix.full_path_with_suffix(&format!("_{name}.png"));
+        let image = sample_pattern_image(&offsets, SAMPLE_PATTERN_MAGNIFICATION);
+        write_image_to_file_or_panic(path.clone(), |path| image.save(path));
+        paths.push(path);
+    }
+    Ok(paths)
+}
@@ -3,37 +3,146 @@
  * in which a discrete sequence of points is sampled, and rendering those
  * points will converge to some fractal.
  */
+use std::fmt::Debug;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
 use image::Pixel;
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::core::{
+    color_map::{ColorMapKeyFrame, ColorPalette, ScalarNormalization},
+    field_iteration::FieldKernel,
     file_io::FilePrefix,
     histogram::Histogram,
-    image_utils::{ImageSpecification, SubpixelGridMask, UpsampledPixelMapper},
+    image_utils::{
+        ImageSpecification, PixelMapper, RenderOptions, Renderable, SpeedOptimizer,
+        SubpixelGridMask, UpsampledPixelMapper,
+    },
 };
 
 use super::{image_utils::write_image_to_file_or_panic, stopwatch::Stopwatch};
 
+/// Random number generator for the seeded fractals (the chaos games and
+/// the Buddhabrot). Pinned to ChaCha12, the algorithm behind `rand`'s
+/// `StdRng` today, instead of `StdRng` itself, whose algorithm may change in
+/// any `rand` release: a saved `rng_seed` keeps producing the same image
+/// across dependency updates.
+pub type SeededRng = rand_chacha::ChaCha12Rng;
+
 pub struct ColoredPoint {
     pub point: [f64; 2],
     pub color: image::Rgb<u8>,
 }
 
-/**
- * Renders a fractal defined by randomly generated sequence of points from a carefully crafted distribution.
- * The user sets up the distribution, and this function samples from the distribution and handles all of the
- * file generation and diagnostics.
- */
-pub fn chaos_game_render<D>(
+/// A fractal drawn by the chaos game, described well enough to re-run the
+/// game for any view. Used by [`SampleAccumulatorRenderable`] to explore the
+/// chaos-game fractals interactively.
+pub trait ChaosGame: SpeedOptimizer + Serialize + Debug + Clone + Sync + Send {
+    /// View that fits the whole attractor.
+    fn image_specification(&self) -> ImageSpecification;
+
+    /// Only `sampling_level` applies.
+    fn render_options(&self) -> &RenderOptions;
+
+    fn sample_count(&self) -> u32;
+
+    fn background_color_rgb(&self) -> [u8; 3];
+
+    /// Every color that the sample generator can produce.
+    fn point_colors_rgb(&self) -> Vec<[u8; 3]>;
+
+    /// Endless sequence of chaos-game samples.
+    type Samples: Iterator<Item = ColoredPoint> + Debug + Clone + Sync + Send;
+
+    /// A fresh sample sequence, seeded from the params, so that repeated
+    /// calls produce the same points.
+    fn samples(&self) -> Self::Samples;
+}
+
+/// Samples drawn between checks of the clock when sampling to a time budget.
+const TIME_BUDGET_BATCH_SIZE: u64 = 1 << 16;
+
+/// Deserialize an optional `time_budget_seconds`, which must be positive and
+/// finite when given. Use with `#[serde(default)]`.
+pub fn deserialize_time_budget<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let budget = Option::<f64>::deserialize(deserializer)?;
+    match budget {
+        Some(seconds) if !(seconds.is_finite() && seconds > 0.0) => Err(serde::de::Error::custom(
+            format!("time_budget_seconds ({seconds}) must be positive and finite"),
+        )),
+        _ => Ok(budget),
+    }
+}
+
+/// Pass up to `sample_count` samples to `plot`, in order. With a
+/// `time_budget`, the clock is checked after each batch of
+/// `TIME_BUDGET_BATCH_SIZE` samples and sampling stops once the budget is
+/// spent. Returns the number of samples drawn.
+fn draw_samples_within_budget<I>(
+    samples: I,
+    sample_count: u32,
+    time_budget: Option<Duration>,
+    mut plot: impl FnMut(ColoredPoint),
+) -> u64
+where
+    I: Iterator<Item = ColoredPoint>,
+{
+    let start = Instant::now();
+    let mut drawn_count = 0;
+    for colored_point in samples.take(sample_count as usize) {
+        plot(colored_point);
+        drawn_count += 1;
+        if drawn_count % TIME_BUDGET_BATCH_SIZE == 0
+            && time_budget.is_some_and(|budget| start.elapsed() >= budget)
+        {
+            break;
+        }
+    }
+    drawn_count
+}
+
+/// Render a fractal defined by a randomly generated sequence of points from
+/// a carefully crafted distribution. The caller sets up the distribution and
+/// passes its points as `samples` (see `ChaosGame::samples`); this function
+/// draws up to `sample_count` of them and handles all of the file generation
+/// and diagnostics.
+///
+/// Sampling is a single sequential chain: each pixel keeps the color of the
+/// last point that landed on it, so the loop has no shared density buffer to
+/// split across threads. (Orbit-density rendering, as in the Buddhabrot,
+/// accumulates into per-worker buffers that are summed at the end.)
+///
+/// With a `time_budget_seconds`, sampling stops early once the budget is
+/// spent, so `sample_count` becomes an upper limit. The number of samples
+/// actually drawn is written to the diagnostics.
+pub fn chaos_game_render<I>(
     background_color: image::Rgb<u8>,
-    distribution_generator: &mut D,
+    samples: I,
     sample_count: u32,
-    subpixel_antialiasing: u32,
+    time_budget_seconds: Option<f64>,
+    render_options: &RenderOptions,
     image_specification: &ImageSpecification,
     file_prefix: FilePrefix,
 ) -> Result<(), Box<dyn std::error::Error>>
 where
-    D: FnMut() -> ColoredPoint,
+    I: Iterator<Item = ColoredPoint>,
 {
+    // Block-fill has no meaning for point sampling, so negative levels
+    // simply drop the anti-aliasing.
+    let subpixel_antialiasing = render_options.sampling_level.max(0) as u32 + 1;
+    if subpixel_antialiasing > SubpixelGridMask::MAX_COUNT_PER_SIDE {
+        return Err(format!(
+            "sampling_level = {} exceeds the maximum of {}",
+            render_options.sampling_level,
+            SubpixelGridMask::MAX_COUNT_PER_SIDE - 1
+        )
+        .into());
+    }
+
     let mut stopwatch = Stopwatch::new("Chaos Game Stopwatch".to_owned());
 
     // Create a new ImgBuf to store the render in memory (and eventually write it to a file).
@@ -57,16 +166,18 @@ where
 
     stopwatch.record_split("setup".to_owned());
 
-    for _ in 0..sample_count {
-        let colored_point = distribution_generator();
-        let index = pixel_mapper.inverse_map(&colored_point.point);
-        let [x, y] = index.pixel;
+    let time_budget = time_budget_seconds.map(Duration::from_secs_f64);
+    let drawn_sample_count =
+        draw_samples_within_budget(samples, sample_count, time_budget, |colored_point| {
+            let index = pixel_mapper.inverse_map(&colored_point.point);
+            let [x, y] = index.pixel;
 
-        if let Some(pixel) = imgbuf.get_pixel_mut_checked(x, y) {
-            *pixel = colored_point.color;
-            subpixel_mask[(x as usize, y as usize)].insert(subpixel_antialiasing, index.subpixel)
-        }
-    }
+            if let Some(pixel) = imgbuf.get_pixel_mut_checked(x, y) {
+                *pixel = colored_point.color;
+                subpixel_mask[(x as usize, y as usize)]
+                    .insert(subpixel_antialiasing, index.subpixel)
+            }
+        });
 
     stopwatch.record_split("sampling".to_owned());
 
@@ -101,7 +212,313 @@ where
 
     let mut diagnostics_file = file_prefix.create_file_with_suffix("_diagnostics.txt");
     stopwatch.display(&mut diagnostics_file)?;
+    writeln!(
+        diagnostics_file,
+        "Samples drawn: {drawn_sample_count} of sample_count = {sample_count}"
+    )?;
     histogram.display(&mut diagnostics_file)?;
 
     Ok(())
 }
+
+/// Real-space lattice of the cells that chaos-game samples are binned into:
+/// one cell per field sample, for the pipeline's subpixel grid at the
+/// current sampling level.
+#[derive(Debug, Clone)]
+struct SampleGrid {
+    resolution: [u32; 2],
+    origin: [f64; 2],
+    step: [f64; 2],
+}
+
+impl SampleGrid {
+    fn new(image_specification: &ImageSpecification, sampling_level: i32) -> SampleGrid {
+        // Block-fill samples one pixel per block, so it shares the baseline grid.
+        let subpixel_count = sampling_level.max(0) as u32 + 1;
+        let upsampled = image_specification.upsample(subpixel_count);
+        let mapper = PixelMapper::new(&upsampled);
+        let maps = [&mapper.width, &mapper.height];
+        SampleGrid {
+            resolution: upsampled.resolution,
+            origin: maps.map(|map| map.map(0)),
+            step: maps.map(|map| map.map(1) - map.map(0)),
+        }
+    }
+
+    fn cell_count(&self) -> usize {
+        (self.resolution[0] * self.resolution[1]) as usize
+    }
+
+    /// Index of the cell whose field sample lies nearest to `point`, or
+    /// `None` if off-screen. Rounding (rather than truncation) keeps the
+    /// field's own sample points inside their cells despite round-off.
+    fn cell_index(&self, point: &[f64; 2]) -> Option<usize> {
+        let [i, j] = [0, 1].map(|dim| ((point[dim] - self.origin[dim]) / self.step[dim]).round());
+        let in_grid = |index: f64, dim: usize| (0.0..self.resolution[dim] as f64).contains(&index);
+        (in_grid(i, 0) && in_grid(j, 1))
+            .then(|| (j as usize) * (self.resolution[0] as usize) + (i as usize))
+    }
+}
+
+/// Key of the view and quality that the cached samples were drawn for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AccumulationKey {
+    image_specification: ImageSpecification,
+    sampling_level: i32,
+}
+
+/// Idle refinement stops once a whole batch of samples lights up fewer new
+/// cells than this fraction of the cells already lit.
+const REFINEMENT_CUTOFF_FRACTION: f64 = 1e-3;
+
+/// Adapts a [`ChaosGame`] to the per-pixel `Renderable` pipeline, so that it
+/// can be panned and zoomed in the explorer. The game is re-run whenever the
+/// view or the sampling level changes, binning each sample into the field
+/// cell nearest to it; `evaluate` then reads back the color of the last
+/// sample in each cell. Cells that no sample reached take the background
+/// color, so the pipeline's subpixel averaging provides the anti-aliasing.
+///
+/// Each point color gets its own flat color map, so the palette editor can
+/// recolor the render. The `SpeedOptimizer` scales the sample count down
+/// while the view is moving. While the view holds still, raising the sample
+/// count continues the same sequence rather than starting over, and
+/// `refine` keeps adding batches of samples to sharpen the image.
+#[derive(Debug, Clone)]
+pub struct SampleAccumulatorRenderable<G: ChaosGame> {
+    params: G,
+    image_specification: ImageSpecification,
+    point_colors_rgb: Vec<[u8; 3]>,
+    color: ColorPalette,
+    grid: SampleGrid,
+    /// Color-map index of the last sample binned into each cell.
+    cells: Vec<Option<u32>>,
+    accumulated_for: Option<AccumulationKey>,
+    /// Continues the sequence that filled `cells`.
+    samples: G::Samples,
+    drawn_sample_count: u64,
+}
+
+impl<G: ChaosGame> SampleAccumulatorRenderable<G> {
+    /// Starts from the view that fits the whole attractor.
+    pub fn new(params: G) -> Self {
+        let image_specification = params.image_specification();
+        let point_colors_rgb = params.point_colors_rgb();
+        let color = ColorPalette {
+            background_color: params.background_color_rgb(),
+            color_maps: point_colors_rgb
+                .iter()
+                .map(|&rgb_raw| {
+                    [0.0, 1.0]
+                        .map(|query| ColorMapKeyFrame { query, rgb_raw })
+                        .to_vec()
+                })
+                .collect(),
+        };
+        let grid = SampleGrid::new(&image_specification, params.render_options().sampling_level);
+        let samples = params.samples();
+        let mut renderable = SampleAccumulatorRenderable {
+            params,
+            image_specification,
+            point_colors_rgb,
+            color,
+            grid,
+            cells: Vec::new(),
+            accumulated_for: None,
+            samples,
+            drawn_sample_count: 0,
+        };
+        renderable.accumulate_samples();
+        renderable
+    }
+
+    /// Bring the cells up to the current sample count: start the game over
+    /// if the view or sampling level changed, otherwise draw only the
+    /// samples that are still missing.
+    fn accumulate_samples(&mut self) {
+        let key = AccumulationKey {
+            image_specification: self.image_specification,
+            sampling_level: self.params.render_options().sampling_level,
+        };
+        if self.accumulated_for != Some(key) {
+            self.grid = SampleGrid::new(&self.image_specification, key.sampling_level);
+            self.cells.clear();
+            self.cells.resize(self.grid.cell_count(), None);
+            self.samples = self.params.samples();
+            self.drawn_sample_count = 0;
+            self.accumulated_for = Some(key);
+        }
+        let missing = (self.params.sample_count() as u64).saturating_sub(self.drawn_sample_count);
+        self.draw_samples(missing);
+    }
+
+    /// Bin the next `count` samples into the cells. Returns the number of
+    /// cells that they lit for the first time.
+    fn draw_samples(&mut self, count: u64) -> usize {
+        let mut newly_lit = 0;
+        for colored_point in self.samples.by_ref().take(count as usize) {
+            if let Some(index) = self.grid.cell_index(&colored_point.point) {
+                newly_lit += self.cells[index].is_none() as usize;
+                self.cells[index] = self
+                    .point_colors_rgb
+                    .iter()
+                    .position(|&rgb| rgb == colored_point.color.0)
+                    .map(|color_index| color_index as u32);
+            }
+        }
+        self.drawn_sample_count += count;
+        newly_lit
+    }
+}
+
+impl<G: ChaosGame> FieldKernel for SampleAccumulatorRenderable<G> {
+    fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)> {
+        let index = self.grid.cell_index(&point)?;
+        self.cells[index].map(|color_index| (1.0, color_index))
+    }
+}
+
+impl<G: ChaosGame> SpeedOptimizer for SampleAccumulatorRenderable<G> {
+    type ReferenceCache = G::ReferenceCache;
+
+    fn reference_cache(&self) -> Self::ReferenceCache {
+        self.params.reference_cache()
+    }
+
+    fn set_speed_optimization_level(&mut self, level: f64, cache: &Self::ReferenceCache) {
+        self.params.set_speed_optimization_level(level, cache);
+        self.accumulate_samples();
+    }
+}
+
+impl<G: ChaosGame> Renderable for SampleAccumulatorRenderable<G> {
+    type Params = G;
+
+    fn image_specification(&self) -> &ImageSpecification {
+        &self.image_specification
+    }
+
+    fn render_options(&self) -> &RenderOptions {
+        self.params.render_options()
+    }
+
+    fn set_image_specification(&mut self, image_specification: ImageSpecification) {
+        self.image_specification = image_specification;
+        self.accumulate_samples();
+    }
+
+    /// Draw another batch of `sample_count` samples into the unchanged view.
+    /// Stops once a batch barely adds to the image.
+    fn refine(&mut self) -> bool {
+        let lit_count = self.cells.iter().filter(|cell| cell.is_some()).count();
+        let newly_lit = self.draw_samples(self.params.sample_count() as u64);
+        newly_lit > 0 && newly_lit as f64 >= REFINEMENT_CUTOFF_FRACTION * lit_count as f64
+    }
+
+    fn write_diagnostics<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let hit_count = self.cells.iter().filter(|cell| cell.is_some()).count();
+        writeln!(
+            writer,
+            "chaos game: {} samples, {} of {} cells hit",
+            self.drawn_sample_count,
+            hit_count,
+            self.cells.len()
+        )
+    }
+
+    /// The palette edits are not written back: the chaos-game params carry
+    /// neither the view nor a palette, so a snapshot reloads the full
+    /// attractor in its original colors.
+    fn params(&self) -> &Self::Params {
+        &self.params
+    }
+
+    fn histogram_bin_count(&self) -> usize {
+        1
+    }
+
+    fn histogram_max_value(&self) -> f32 {
+        1.0
+    }
+
+    fn lookup_table_count(&self) -> usize {
+        2
+    }
+
+    /// Every cell evaluates to `1.0`, so the flat color maps need no
+    /// histogram equalization.
+    fn scalar_normalization(&self) -> ScalarNormalization {
+        ScalarNormalization::Linear
+    }
+
+    fn color_palette(&self) -> &ColorPalette {
+        &self.color
+    }
+
+    fn color_palette_mut(&mut self) -> &mut ColorPalette {
+        &mut self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{RngCore, SeedableRng};
+
+    use super::*;
+
+    /// Pins the generator's output for a seed, so a dependency update that
+    /// changes the sequence (and with it every seeded render) fails here.
+    #[test]
+    fn seeded_rng_sequence_is_pinned() {
+        let mut rng = SeededRng::seed_from_u64(12345);
+        let values: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(
+            values,
+            vec![
+                5_643_454_701_289_460_054,
+                16_290_607_951_801_111_627,
+                10_082_841_213_727_481_447
+            ]
+        );
+        // Still the same sequence as `rand`'s `StdRng`, so pinning it did
+        // not change any existing render.
+        let mut std_rng = rand::rngs::StdRng::seed_from_u64(12345);
+        assert_eq!(
+            values,
+            (0..3).map(|_| std_rng.next_u64()).collect::<Vec<_>>()
+        );
+    }
+
+    /// Every sample is drawn without a budget; a budget that is already
+    /// spent stops sampling after the first batch.
+    #[test]
+    fn time_budget_stops_sampling_after_the_current_batch() {
+        let samples = || {
+            std::iter::repeat_with(|| ColoredPoint {
+                point: [0.0, 0.0],
+                color: image::Rgb([1, 2, 3]),
+            })
+        };
+        let sample_count = 3 * TIME_BUDGET_BATCH_SIZE as u32 + 5;
+        let mut plotted = 0;
+        let drawn = draw_samples_within_budget(samples(), sample_count, None, |_| plotted += 1);
+        assert_eq!(drawn, sample_count as u64);
+        assert_eq!(plotted, sample_count as u64);
+
+        let drawn =
+            draw_samples_within_budget(samples(), sample_count, Some(Duration::ZERO), |_| {});
+        assert_eq!(drawn, TIME_BUDGET_BATCH_SIZE);
+        let generous = Some(Duration::from_secs(3600));
+        assert_eq!(
+            draw_samples_within_budget(samples(), sample_count, generous, |_| {}),
+            sample_count as u64
+        );
+
+        let parse = |json: &str| -> Result<Option<f64>, serde_json::Error> {
+            deserialize_time_budget(&mut serde_json::Deserializer::from_str(json))
+        };
+        assert_eq!(parse("2.5").unwrap(), Some(2.5));
+        assert_eq!(parse("null").unwrap(), None);
+        assert!(parse("0").is_err());
+        assert!(parse("-1.0").is_err());
+    }
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::core::histogram::{CumulativeDistributionFunction, Histogram};
 use crate::core::interpolation::{
     InterpolationKeyframe, Interpolator, KeyframeInterpolator, LinearInterpolator,
+    SoftStepInterpolator,
 };
 use crate::core::lookup_table::LookupTable;
 
@@ -24,6 +25,99 @@ pub struct ColorMapKeyFrame {
 /// DDP; one per root for Newton's method.
 pub type ColorMap = Vec<ColorMapKeyFrame>;
 
+/// Build a color map from `colors`, where `weights[i]` sets the share of the
+/// `[0, 1]` range taken by the transition from `colors[i]` to `colors[i + 1]`.
+/// Weights are relative and need not sum to one; equal weights give evenly
+/// spaced keyframes. The first query is exactly `0.0` and the last `1.0`.
+/// Fails unless there are at least two colors and one finite, positive
+/// weight per transition.
+pub fn with_weighted_spacing(
+    colors: &[[u8; 3]],
+    weights: &[f32],
+) -> Result<ColorMap, ColorMapError> {
+    if colors.len() < 2 {
+        return Err(ColorMapError::TooFewColors {
+            count: colors.len(),
+        });
+    }
+    if weights.len() != colors.len() - 1 {
+        return Err(ColorMapError::WeightCountMismatch {
+            color_count: colors.len(),
+            weight_count: weights.len(),
+        });
+    }
+    if let Some((index, &weight)) = weights
+        .iter()
+        .enumerate()
+        .find(|(_, weight)| !(weight.is_finite() && **weight > 0.0))
+    {
+        return Err(ColorMapError::InvalidWeight { index, weight });
+    }
+    let total: f32 = weights.iter().sum();
+    let mut cumulative = 0.0;
+    let mut color_map: ColorMap = colors
+        .iter()
+        .zip(std::iter::once(&0.0).chain(weights))
+        .map(|(&rgb_raw, &weight)| {
+            cumulative += weight;
+            ColorMapKeyFrame {
+                query: cumulative / total,
+                rgb_raw,
+            }
+        })
+        .collect();
+    // Rounding in the running sum must not leave the last keyframe short of 1.
+    if let Some(last) = color_map.last_mut() {
+        last.query = 1.0;
+    }
+    Ok(color_map)
+}
+
+/// Build a color map from keyframes whose queries are strictly increasing
+/// but on an arbitrary range (e.g. a palette imported with queries on
+/// `[0, 255]`), by rescaling the queries linearly so that the first lands on
+/// `0.0` and the last on `1.0`. A single keyframe becomes a constant map.
+/// Unlike the keyframes of a loaded `ColorPalette`, which must already span
+/// the unit interval exactly (see `validate_color_map`), only the order of
+/// the queries is checked here, so their exact values are not preserved.
+pub fn with_normalized_queries(keyframes: &[ColorMapKeyFrame]) -> Result<ColorMap, ColorMapError> {
+    let (Some(first), Some(last)) = (keyframes.first(), keyframes.last()) else {
+        return Err(ColorMapError::Empty);
+    };
+    if keyframes.len() == 1 {
+        return Ok([0.0, 1.0]
+            .map(|query| ColorMapKeyFrame {
+                query,
+                rgb_raw: first.rgb_raw,
+            })
+            .to_vec());
+    }
+    let offset = first.query;
+    let scale = 1.0 / (last.query - first.query);
+    let mut color_map: ColorMap = keyframes
+        .iter()
+        .map(|keyframe| ColorMapKeyFrame {
+            query: (keyframe.query - offset) * scale,
+            rgb_raw: keyframe.rgb_raw,
+        })
+        .collect();
+    // Rounding must not leave the end keyframes off the unit interval.
+    if let Some(keyframe) = color_map.first_mut() {
+        keyframe.query = 0.0;
+    }
+    if let Some(keyframe) = color_map.last_mut() {
+        keyframe.query = 1.0;
+    }
+    match validate_color_map(&color_map) {
+        // Report the caller's query, not the rescaled one.
+        Err(ColorMapError::NotIncreasing { index, .. }) => Err(ColorMapError::NotIncreasing {
+            index,
+            query: keyframes[index].query,
+        }),
+        result => result.map(|()| color_map),
+    }
+}
+
 /// Bundle of all the color data a fractal needs at render time:
 /// a background color (used when `FieldKernel::evaluate` returns `None`)
 /// plus one or more color maps that the gradient index from each populated
@@ -54,9 +148,172 @@ where
             "ColorPalette.color_maps must contain at least one color map",
         ));
     }
+    for (index, color_map) in color_maps.iter().enumerate() {
+        validate_color_map(color_map).map_err(|error| {
+            serde::de::Error::custom(format!("ColorPalette.color_maps[{index}]: {error}"))
+        })?;
+    }
     Ok(color_maps)
 }
 
+/// Why a list of keyframes is not a valid color map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMapError {
+    /// There are no keyframes.
+    Empty,
+    /// The first keyframe's query is not `0.0`.
+    FirstQueryNotZero { query: f32 },
+    /// The last keyframe's query is not `1.0`.
+    LastQueryNotOne { query: f32 },
+    /// Keyframe `index` does not come strictly after the one before it.
+    NotIncreasing { index: usize, query: f32 },
+    /// Fewer than two colors were given to space out.
+    TooFewColors { count: usize },
+    /// The number of weights is not one less than the number of colors.
+    WeightCountMismatch {
+        color_count: usize,
+        weight_count: usize,
+    },
+    /// Weight `index` is not finite and positive.
+    InvalidWeight { index: usize, weight: f32 },
+}
+
+impl std::fmt::Display for ColorMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ColorMapError::Empty => write!(f, "the color map has no keyframes"),
+            ColorMapError::FirstQueryNotZero { query } => write!(
+                f,
+                "keyframe 0 has query {query}, but the first keyframe must be at 0.0"
+            ),
+            ColorMapError::LastQueryNotOne { query } => write!(
+                f,
+                "the last keyframe has query {query}, but it must be at 1.0"
+            ),
+            ColorMapError::NotIncreasing { index, query } => write!(
+                f,
+                "keyframe {index} has query {query}, which does not come after keyframe {}",
+                index - 1
+            ),
+            ColorMapError::TooFewColors { count } => {
+                write!(f, "need at least two colors, got {count}")
+            }
+            ColorMapError::WeightCountMismatch {
+                color_count,
+                weight_count,
+            } => write!(
+                f,
+                "{color_count} colors need {} weights, one per transition, got {weight_count}",
+                color_count.saturating_sub(1)
+            ),
+            ColorMapError::InvalidWeight { index, weight } => {
+                write!(
+                    f,
+                    "weight {index} is {weight}, but must be finite and positive"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ColorMapError {}
+
+/// Check that `keyframes` span the unit interval with strictly increasing
+/// queries, as `KeyframeColorMap::new` requires. Palettes are checked when
+/// they are deserialized, so a bad params file is rejected at load time.
+/// Keyframes on another range can be rescaled with `with_normalized_queries`.
+pub fn validate_color_map(keyframes: &[ColorMapKeyFrame]) -> Result<(), ColorMapError> {
+    let queries: Vec<f32> = keyframes.iter().map(|keyframe| keyframe.query).collect();
+    validate_keyframe_queries(&queries)
+}
+
+/// The checks of `validate_color_map`, on the queries alone.
+fn validate_keyframe_queries(queries: &[f32]) -> Result<(), ColorMapError> {
+    let (Some(&first), Some(&last)) = (queries.first(), queries.last()) else {
+        return Err(ColorMapError::Empty);
+    };
+    if first != 0.0 {
+        return Err(ColorMapError::FirstQueryNotZero { query: first });
+    }
+    if let Some(index) = (1..queries.len()).find(|&i| {
+        // A NaN query is unordered, so it fails too.
+        queries[i].partial_cmp(&queries[i - 1]) != Some(std::cmp::Ordering::Greater)
+    }) {
+        return Err(ColorMapError::NotIncreasing {
+            index,
+            query: queries[index],
+        });
+    }
+    if last != 1.0 {
+        return Err(ColorMapError::LastQueryNotOne { query: last });
+    }
+    Ok(())
+}
+
+/// One keyframe of a single color channel's curve.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ChannelKeyFrame {
+    /// Location of this value within the curve; on `[0, 1]`.
+    pub query: f32,
+    /// Raw 8-bit channel value.
+    pub value: u8,
+}
+
+/// Three independent keyframe curves, one per color channel, as an
+/// alternative to a single RGB color map: each channel may have its own
+/// number and spacing of keyframes. Each curve must satisfy the same rules
+/// as a color map (see `validate_color_map`), checked at load time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChannelCurves {
+    /// Keyframes of the red channel.
+    #[serde(deserialize_with = "deserialize_channel_curve")]
+    pub red: Vec<ChannelKeyFrame>,
+    /// Keyframes of the green channel.
+    #[serde(deserialize_with = "deserialize_channel_curve")]
+    pub green: Vec<ChannelKeyFrame>,
+    /// Keyframes of the blue channel.
+    #[serde(deserialize_with = "deserialize_channel_curve")]
+    pub blue: Vec<ChannelKeyFrame>,
+}
+
+fn validate_channel_curve(curve: &[ChannelKeyFrame]) -> Result<(), ColorMapError> {
+    let queries: Vec<f32> = curve.iter().map(|keyframe| keyframe.query).collect();
+    validate_keyframe_queries(&queries)
+}
+
+fn deserialize_channel_curve<'de, D>(deserializer: D) -> Result<Vec<ChannelKeyFrame>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let curve: Vec<ChannelKeyFrame> = Vec::deserialize(deserializer)?;
+    validate_channel_curve(&curve)
+        .map_err(|error| serde::de::Error::custom(format!("channel curve: {error}")))?;
+    Ok(curve)
+}
+
+/// How a raw field value is mapped onto the `[0, 1]` color-map query
+/// domain before the lookup table is consulted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScalarNormalization {
+    /// Histogram equalization: the query is the value's percentile within
+    /// the current frame's distribution. Spreads the palette evenly over
+    /// whatever values are on screen, but shifts colors as the view moves.
+    #[default]
+    Histogram,
+    /// `value / max_value`, clamped to `[0, 1]`. Independent of the view,
+    /// so colors stay put while panning.
+    Linear,
+    /// `ln(1 + value) / ln(1 + max_value)`, clamped to `[0, 1]`. Like
+    /// `Linear`, but devotes more of the palette to small values.
+    Log,
+}
+
+/// Color-map index that a `FieldKernel` can emit to draw a cell in the
+/// overlay color (e.g. the Mandelbrot field lines) instead of through a color
+/// map. The cell value is still binned into the histograms as usual, so
+/// enabling an overlay does not shift the normalization of the other cells.
+pub const OVERLAY_COLOR_MAP_INDEX: u32 = u32::MAX;
+
 /// Allocation-once cache used by the colorize hot path. The pipeline
 /// owns one of these and refreshes it in place each frame.
 ///
@@ -81,16 +338,148 @@ pub struct ColorPaletteCache {
     lookup_tables: Vec<ColorMapLookUpTable>,
     /// `ColorPalette::background_color` pre-converted to `Color32`.
     background: Color32,
+    /// Color of cells emitted with `OVERLAY_COLOR_MAP_INDEX`. Falls back to
+    /// the background color when unset.
+    overlay: Option<Color32>,
+    /// Per-sector color multipliers; see [`Self::set_sector_count`]. Empty
+    /// when sector tinting is off.
+    sector_tints: Vec<[f32; 3]>,
+    /// Mapping from raw cell values to color-map queries.
+    normalization: ScalarNormalization,
+    /// Quantizes the normalized queries into bands; see
+    /// [`Self::set_band_count`]. `None` leaves them smooth.
+    bands: Option<KeyframeInterpolator<f32, f32, SoftStepInterpolator>>,
+    /// Dither the anti-aliased colors when quantizing them to 8 bits; see
+    /// [`Self::set_dither`].
+    dither: bool,
+    /// Replaces color map 0 with independent per-channel curves; see
+    /// [`Self::set_channel_curves`].
+    channel_curves: Option<ChannelCurves>,
+    /// Applied to every color map's output; see [`Self::set_color_effects`].
+    color_effects: Vec<ColorEffect>,
+    /// Largest raw value a cell can produce (the histogram's upper edge);
+    /// the scale for the `Linear` and `Log` normalizations.
+    max_value: f32,
+    /// When set, `refresh_after_compute_pass` leaves the CDFs untouched so
+    /// the histogram normalization stays fixed across frames.
+    cdfs_frozen: bool,
 }
 
 impl ColorPaletteCache {
     /// Read-only view of the per-color-map CDFs; the colorize hot path
-    /// reads them directly from `colorize_cell`. Mutation goes through
+    /// reads them from `colorize_cell`. Mutation goes through
     /// [`refresh_after_compute_pass`] only.
     pub fn cdfs(&self) -> &[CumulativeDistributionFunction] {
         &self.cdfs
     }
 
+    /// Stop rebuilding the CDFs on subsequent refreshes: the current CDFs
+    /// keep driving the histogram normalization until the cache is dropped.
+    /// Keyframe and background edits are still picked up.
+    pub fn freeze_cdfs(&mut self) {
+        self.cdfs_frozen = true;
+    }
+
+    /// Select how raw cell values are mapped onto the color-map queries.
+    /// Takes effect on the next colorize pass.
+    pub fn set_normalization(&mut self, normalization: ScalarNormalization) {
+        self.normalization = normalization;
+    }
+
+    /// Select the color of cells emitted with `OVERLAY_COLOR_MAP_INDEX`.
+    pub fn set_overlay_color(&mut self, overlay_color: Option<[u8; 3]>) {
+        self.overlay = overlay_color.map(|[r, g, b]| Color32::from_rgb(r, g, b));
+    }
+
+    /// Tint cells by sector. With `n` color maps, a cell emitted with
+    /// color-map index `(k + 1) * n + m` is drawn through color map `m` and
+    /// then multiplied by the hue of sector `k` (wrapping at `sector_count`),
+    /// with the hues spaced evenly around the color wheel. Indices below `n`
+    /// are left untinted. `None` turns the tint off, which leaves every index
+    /// wrapping onto the color maps as usual.
+    pub fn set_sector_count(&mut self, sector_count: Option<u32>) {
+        self.sector_tints = (0..sector_count.unwrap_or(0))
+            .map(|sector| sector_tint(sector, sector_count.unwrap_or(1)))
+            .collect();
+    }
+
+    /// Dither the output colors (see `RenderOptions::dither`): the collapse
+    /// step then looks up fractional colors with [`colorize_cell_smooth`]
+    /// and quantizes them with an ordered-dither threshold.
+    pub fn set_dither(&mut self, dither: bool) {
+        self.dither = dither;
+    }
+
+    /// True if the output colors are dithered; see `set_dither`.
+    pub fn dither(&self) -> bool {
+        self.dither
+    }
+
+    /// Drive color map 0 by independent red, green, and blue curves instead
+    /// of the palette's keyframes (see `ChannelwiseColorMap`). `None` goes
+    /// back to the palette. Applies immediately and on every refresh. Invalid
+    /// curves are rejected, leaving the previous ones in place.
+    pub fn set_channel_curves(
+        &mut self,
+        channel_curves: Option<ChannelCurves>,
+    ) -> Result<(), ColorMapError> {
+        if let (Some(curves), Some(lut)) = (&channel_curves, self.lookup_tables.first_mut()) {
+            reset_from_channel_curves(lut, curves, &self.color_effects)?;
+        }
+        self.channel_curves = channel_curves;
+        Ok(())
+    }
+
+    /// Apply `color_effects`, in order, to the output of every color map
+    /// (see `EffectColorMap`). Takes effect on the next refresh.
+    pub fn set_color_effects(&mut self, color_effects: Vec<ColorEffect>) {
+        self.color_effects = color_effects;
+    }
+
+    /// Split the color-map query domain into `band_count` equal bands, and
+    /// draw every cell in a band with the color at the start of the band:
+    /// the discrete "iteration band" look in place of smooth shading. The
+    /// last `band_softness` (in `[0, 1]`) of each band blends into the next,
+    /// so 0 gives hard bands and 1 the smooth coloring again. `None` (or zero
+    /// bands) keeps the coloring smooth. Takes effect on the next colorize
+    /// pass.
+    pub fn set_band_count(&mut self, band_count: Option<u32>, band_softness: f32) {
+        self.bands = band_count
+            .filter(|&band_count| band_count > 0)
+            .map(|band_count| {
+                let keyframes = (0..=band_count)
+                    .map(|band| {
+                        let query = band as f32 / band_count as f32;
+                        InterpolationKeyframe {
+                            query,
+                            value: query,
+                        }
+                    })
+                    .collect();
+                KeyframeInterpolator::new(
+                    keyframes,
+                    SoftStepInterpolator {
+                        softness: band_softness as f64,
+                    },
+                )
+            });
+    }
+
+    /// Map a raw cell value routed through color map `index` onto the
+    /// `[0, 1]` query domain of that color map's lookup table.
+    #[inline]
+    fn normalize(&self, index: usize, value: f32) -> f32 {
+        let query = match self.normalization {
+            ScalarNormalization::Histogram => self.cdfs()[index].percentile(value),
+            ScalarNormalization::Linear => (value / self.max_value).clamp(0.0, 1.0),
+            ScalarNormalization::Log => (value.ln_1p() / self.max_value.ln_1p()).clamp(0.0, 1.0),
+        };
+        match &self.bands {
+            Some(bands) => bands.evaluate(query),
+            None => query,
+        }
+    }
+
     /// Reset every histogram bin to zero. Call this between renders, before
     /// `field_iteration::populate_histograms` fills them again.
     pub fn reset_histograms(&mut self) {
@@ -103,15 +492,19 @@ impl ColorPaletteCache {
     ///
     /// 1. Per-color-map CDFs are rebuilt from `self.histograms` (which the
     ///    pipeline filled between the prior `reset_histograms` and this
-    ///    call).
+    ///    call), unless they were frozen by [`Self::freeze_cdfs`].
     /// 2. Per-color-map LUTs are refreshed from `palette`'s current
     ///    keyframes.
     /// 3. The cached `background` color is refreshed from
     ///    `palette.background_color`.
     ///
     /// One call updates all three so the colorize step can never see a
-    /// stale half-update.
-    pub fn refresh_after_compute_pass(&mut self, palette: &ColorPalette) {
+    /// stale half-update. If any of `palette`'s color maps is invalid, the
+    /// error is returned before anything is touched.
+    pub fn refresh_after_compute_pass(
+        &mut self,
+        palette: &ColorPalette,
+    ) -> Result<(), ColorMapError> {
         debug_assert_eq!(
             self.cdfs.len(),
             self.histograms.len(),
@@ -123,18 +516,27 @@ impl ColorPaletteCache {
             "ColorPaletteCache LUT count must match ColorPalette color_maps length; \
              color-map count is fixed for the session"
         );
-        for (cdf, histogram) in self.cdfs.iter_mut().zip(self.histograms.iter()) {
-            cdf.reset(histogram);
+        for keyframes in &palette.color_maps {
+            validate_color_map(keyframes)?;
+        }
+        if !self.cdfs_frozen {
+            for (cdf, histogram) in self.cdfs.iter_mut().zip(self.histograms.iter()) {
+                cdf.reset(histogram);
+            }
         }
         for (lut, keyframes) in self.lookup_tables.iter_mut().zip(palette.color_maps.iter()) {
-            let inner = KeyframeColorMap::new(keyframes, LinearInterpolator);
-            lut.reset([0.0, 1.0], &|q: f32| inner.compute_pixel(q));
+            let inner = KeyframeColorMap::new(keyframes, LinearInterpolator)?;
+            reset_with_effects(lut, inner, &self.color_effects);
+        }
+        if let (Some(curves), Some(lut)) = (&self.channel_curves, self.lookup_tables.first_mut()) {
+            reset_from_channel_curves(lut, curves, &self.color_effects)?;
         }
         self.background = Color32::from_rgb(
             palette.background_color[0],
             palette.background_color[1],
             palette.background_color[2],
         );
+        Ok(())
     }
 }
 
@@ -145,13 +547,14 @@ impl ColorPalette {
     /// `histogram_bin_count` and `histogram_max_value` so the pipeline can
     /// reuse them in place across frames; their contents are recomputed
     /// from each frame's per-color-map histogram via
-    /// [`ColorPaletteCache::refresh_after_compute_pass`].
+    /// [`ColorPaletteCache::refresh_after_compute_pass`]. Fails if any of
+    /// the color maps is invalid (see `validate_color_map`).
     pub fn create_cache(
         &self,
         histogram_bin_count: usize,
         histogram_max_value: f32,
         lookup_table_count: usize,
-    ) -> ColorPaletteCache {
+    ) -> Result<ColorPaletteCache, ColorMapError> {
         assert!(
             !self.color_maps.is_empty(),
             "ColorPalette.color_maps must contain at least one color map"
@@ -169,38 +572,98 @@ impl ColorPalette {
             .color_maps
             .iter()
             .map(|keyframes| {
-                let inner = KeyframeColorMap::new(keyframes, LinearInterpolator);
-                ColorMapLookUpTable::new(lookup_table_count, [0.0, 1.0], &|q: f32| {
-                    inner.compute_pixel(q)
-                })
+                let inner = KeyframeColorMap::new(keyframes, LinearInterpolator)?;
+                Ok(ColorMapLookUpTable::new(
+                    lookup_table_count,
+                    [0.0, 1.0],
+                    &|q: f32| inner.compute_pixel(q),
+                ))
             })
-            .collect();
+            .collect::<Result<_, ColorMapError>>()?;
         let background = Color32::from_rgb(
             self.background_color[0],
             self.background_color[1],
             self.background_color[2],
         );
-        ColorPaletteCache {
+        Ok(ColorPaletteCache {
             histograms,
             cdfs,
             lookup_tables,
             background,
-        }
+            overlay: None,
+            sector_tints: Vec::new(),
+            normalization: ScalarNormalization::default(),
+            bands: None,
+            dither: false,
+            channel_curves: None,
+            color_effects: Vec::new(),
+            max_value: histogram_max_value,
+            cdfs_frozen: false,
+        })
     }
 }
 
+fn reset_with_effects<M: ColorMapper>(
+    lut: &mut ColorMapLookUpTable,
+    color_map: M,
+    color_effects: &[ColorEffect],
+) {
+    let color_map = EffectColorMap::new(color_map, color_effects.to_vec());
+    lut.reset([0.0, 1.0], &|q: f32| color_map.compute_pixel(q));
+}
+
+fn reset_from_channel_curves(
+    lut: &mut ColorMapLookUpTable,
+    curves: &ChannelCurves,
+    color_effects: &[ColorEffect],
+) -> Result<(), ColorMapError> {
+    let inner = ChannelwiseColorMap::new(curves, LinearInterpolator)?;
+    reset_with_effects(lut, inner, color_effects);
+    Ok(())
+}
+
+/// Color multiplier for `sector` out of `sector_count`: a fully saturated
+/// hue, lifted toward white so that the tint shades the base color rather
+/// than replacing it.
+fn sector_tint(sector: u32, sector_count: u32) -> [f32; 3] {
+    const TINT_STRENGTH: f32 = 0.45;
+    let hue = 6.0 * (sector as f32) / (sector_count as f32);
+    // Piecewise-linear HSV-to-RGB at full saturation and value.
+    let channel = |offset: f32| {
+        let k = (offset + hue) % 6.0;
+        1.0 - (k.min(4.0 - k).clamp(0.0, 1.0))
+    };
+    [channel(5.0), channel(3.0), channel(1.0)].map(|c| 1.0 - TINT_STRENGTH * (1.0 - c))
+}
+
+/// Tint of the cells whose color-map index is `band` times the number of
+/// color maps (rounded down); see [`ColorPaletteCache::set_sector_count`].
+#[inline]
+fn sector_tint_of(cache: &ColorPaletteCache, band: usize) -> Option<[f32; 3]> {
+    let sector = band.checked_sub(1)?;
+    let sector_count = cache.sector_tints.len();
+    (sector_count > 0).then(|| cache.sector_tints[sector % sector_count])
+}
+
 /// Per-cell color lookup. Statically dispatched; called inside the
-/// anti-aliasing collapse loop. CDF percentile lookup happens here, in
-/// color space — the field stays raw end-to-end.
+/// anti-aliasing collapse loop. Normalization (CDF percentile lookup by
+/// default) happens here, in color space — the field stays raw end-to-end.
 #[inline]
 pub fn colorize_cell(cache: &ColorPaletteCache, cell: Option<(f32, u32)>) -> [u8; 3] {
     match cell {
+        Some((_, OVERLAY_COLOR_MAP_INDEX)) => {
+            let overlay = cache.overlay.unwrap_or(cache.background);
+            [overlay.r(), overlay.g(), overlay.b()]
+        }
         Some((value, color_map_index)) => {
-            let count = cache.lookup_tables.len();
-            let index = (color_map_index as usize) % count.max(1);
-            let percentile = cache.cdfs()[index].percentile(value);
-            let rgb: Rgb<u8> = cache.lookup_tables[index].compute_pixel(percentile);
-            [rgb[0], rgb[1], rgb[2]]
+            let count = cache.lookup_tables.len().max(1);
+            let index = (color_map_index as usize) % count;
+            let query = cache.normalize(index, value);
+            let rgb: Rgb<u8> = cache.lookup_tables[index].compute_pixel(query);
+            let Some(tint) = sector_tint_of(cache, color_map_index as usize / count) else {
+                return [rgb[0], rgb[1], rgb[2]];
+            };
+            [0, 1, 2].map(|i| (rgb[i] as f32 * tint[i]).round() as u8)
         }
         None => [
             cache.background.r(),
@@ -210,6 +673,26 @@ pub fn colorize_cell(cache: &ColorPaletteCache, cell: Option<(f32, u32)>) -> [u8
     }
 }
 
+/// Like [`colorize_cell`], but interpolates between the neighboring lookup
+/// table entries instead of taking the nearest one, so a smooth gradient
+/// keeps the fraction of a level between its 8-bit colors. Used to dither.
+#[inline]
+pub fn colorize_cell_smooth(cache: &ColorPaletteCache, cell: Option<(f32, u32)>) -> [f32; 3] {
+    match cell {
+        Some((value, color_map_index)) if color_map_index != OVERLAY_COLOR_MAP_INDEX => {
+            let count = cache.lookup_tables.len().max(1);
+            let index = (color_map_index as usize) % count;
+            let query = cache.normalize(index, value);
+            let rgb = cache.lookup_tables[index].compute_pixel_smooth(query);
+            let Some(tint) = sector_tint_of(cache, color_map_index as usize / count) else {
+                return rgb;
+            };
+            [0, 1, 2].map(|i| rgb[i] * tint[i])
+        }
+        _ => colorize_cell(cache, cell).map(f32::from),
+    }
+}
+
 /// Trait implemented by anything that maps a query in `[0, 1]` to an RGB
 /// color. Used by the editor preview.
 pub trait ColorMapper {
@@ -230,18 +713,11 @@ impl<F> KeyframeColorMap<F>
 where
     F: Interpolator<f32, Vector3<f32>>,
 {
-    /// Construct a keyframe-driven color map. Keyframes must be non-empty
-    /// and span the unit interval (first query 0.0, last query 1.0).
-    pub fn new(keyframes: &[ColorMapKeyFrame], interpolator: F) -> Self {
-        assert!(!keyframes.is_empty(), "keyframes must not be empty");
-        assert!(
-            keyframes.first().unwrap().query == 0.0,
-            "first keyframe query must be 0.0"
-        );
-        assert!(
-            keyframes.last().unwrap().query == 1.0,
-            "last keyframe query must be 1.0"
-        );
+    /// Construct a keyframe-driven color map. Keyframes must be non-empty,
+    /// strictly increasing, and span the unit interval (first query 0.0,
+    /// last query 1.0); see `validate_color_map`.
+    pub fn new(keyframes: &[ColorMapKeyFrame], interpolator: F) -> Result<Self, ColorMapError> {
+        validate_color_map(keyframes)?;
         let internal_keyframes: Vec<InterpolationKeyframe<f32, Vector3<f32>>> = keyframes
             .iter()
             .map(|kf| InterpolationKeyframe {
@@ -256,7 +732,7 @@ where
 
         let interpolator = KeyframeInterpolator::new(internal_keyframes, interpolator);
 
-        Self { interpolator }
+        Ok(Self { interpolator })
     }
 }
 
@@ -274,11 +750,123 @@ where
     }
 }
 
+/// Color map whose red, green, and blue channels each follow their own
+/// keyframe curve, interpolated independently.
+pub struct ChannelwiseColorMap<F>
+where
+    F: Interpolator<f32, f32>,
+{
+    channels: [KeyframeInterpolator<f32, f32, F>; 3],
+}
+
+impl<F> ChannelwiseColorMap<F>
+where
+    F: Interpolator<f32, f32> + Clone,
+{
+    /// Construct from three channel curves, each of which must pass the
+    /// same checks as a color map (see `validate_color_map`).
+    pub fn new(curves: &ChannelCurves, interpolator: F) -> Result<Self, ColorMapError> {
+        let channel = |curve: &[ChannelKeyFrame]| {
+            validate_channel_curve(curve)?;
+            let keyframes = curve
+                .iter()
+                .map(|keyframe| InterpolationKeyframe {
+                    query: keyframe.query,
+                    value: keyframe.value as f32,
+                })
+                .collect();
+            Ok(KeyframeInterpolator::new(keyframes, interpolator.clone()))
+        };
+        Ok(Self {
+            channels: [
+                channel(&curves.red)?,
+                channel(&curves.green)?,
+                channel(&curves.blue)?,
+            ],
+        })
+    }
+}
+
+impl<F> ColorMapper for ChannelwiseColorMap<F>
+where
+    F: Interpolator<f32, f32>,
+{
+    fn compute_pixel(&self, query: f32) -> image::Rgb<u8> {
+        image::Rgb(
+            self.channels
+                .each_ref()
+                .map(|channel| channel.evaluate(query).clamp(0.0, 255.0) as u8),
+        )
+    }
+}
+
+/// A cheap transform of the colors produced by a color map.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorEffect {
+    /// Replace each channel `c` with `255 - c`.
+    Invert,
+    /// Invert only the channels brighter than `threshold`.
+    Solarize { threshold: u8 },
+    /// Round each channel to the nearest of `levels` evenly spaced values,
+    /// black and full intensity included. Fewer than two levels act as two.
+    Posterize { levels: u8 },
+}
+
+impl ColorEffect {
+    /// Apply the effect to one 8-bit channel.
+    pub fn apply(self, channel: u8) -> u8 {
+        match self {
+            ColorEffect::Invert => 255 - channel,
+            ColorEffect::Solarize { threshold } if channel > threshold => 255 - channel,
+            ColorEffect::Solarize { .. } => channel,
+            ColorEffect::Posterize { levels } => {
+                let step = 255.0 / (levels.max(2) - 1) as f32;
+                ((channel as f32 / step).round() * step).round() as u8
+            }
+        }
+    }
+}
+
+/// Wraps any color map, passing its colors through a list of effects in
+/// order.
+pub struct EffectColorMap<M: ColorMapper> {
+    inner: M,
+    effects: Vec<ColorEffect>,
+}
+
+impl<M: ColorMapper> EffectColorMap<M> {
+    /// Wrap `inner`, applying `effects` to its colors in order.
+    pub fn new(inner: M, effects: Vec<ColorEffect>) -> Self {
+        Self { inner, effects }
+    }
+}
+
+impl<M: ColorMapper> ColorMapper for EffectColorMap<M> {
+    fn compute_pixel(&self, query: f32) -> image::Rgb<u8> {
+        let rgb = self.inner.compute_pixel(query);
+        image::Rgb(rgb.0.map(|channel| {
+            self.effects
+                .iter()
+                .fold(channel, |c, effect| effect.apply(c))
+        }))
+    }
+}
+
 /// Wrapper around a color map that precomputes a lookup table mapping from
 /// query to the resulting color. Makes evaluation much faster on the hot
 /// path.
+///
+/// Serializes as its query domain and the raw table entries, so a table for a
+/// fixed palette can be computed once, written to disk, and reloaded for every
+/// frame of a batch or animation without re-evaluating the color map.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(
+    try_from = "SerializedColorMapLookUpTable",
+    into = "SerializedColorMapLookUpTable"
+)]
 pub struct ColorMapLookUpTable {
-    /// The underlying lookup table, indexed over `[0, 1]`.
+    /// The underlying lookup table, indexed over the query domain passed to
+    /// `new` or `reset`. Queries outside of that domain are clamped to it.
     pub table: LookupTable<image::Rgb<u8>>,
 }
 
@@ -295,7 +883,9 @@ impl ColorMapLookUpTable {
         map
     }
 
-    /// Refresh the table in place, without allocating.
+    /// Refresh the table in place, without allocating. The query domain may
+    /// be any finite, increasing interval, e.g. the raw escape-count range
+    /// instead of the `[0, 1]` output of a CDF.
     pub fn reset<F>(&mut self, query_domain: [f32; 2], color_map: &F)
     where
         F: Fn(f32) -> image::Rgb<u8>,
@@ -304,6 +894,47 @@ impl ColorMapLookUpTable {
     }
 }
 
+/// On-disk form of a `ColorMapLookUpTable`.
+#[derive(Serialize, Deserialize)]
+struct SerializedColorMapLookUpTable {
+    query_domain: [f32; 2],
+    rgb_entries: Vec<[u8; 3]>,
+}
+
+impl TryFrom<SerializedColorMapLookUpTable> for ColorMapLookUpTable {
+    type Error = String;
+
+    fn try_from(serialized: SerializedColorMapLookUpTable) -> Result<Self, Self::Error> {
+        let entries = serialized.rgb_entries.into_iter().map(Rgb).collect();
+        Ok(ColorMapLookUpTable {
+            table: LookupTable::from_entries(serialized.query_domain, entries)?,
+        })
+    }
+}
+
+impl From<ColorMapLookUpTable> for SerializedColorMapLookUpTable {
+    fn from(lookup_table: ColorMapLookUpTable) -> Self {
+        SerializedColorMapLookUpTable {
+            query_domain: lookup_table.table.query_domain(),
+            rgb_entries: lookup_table
+                .table
+                .entries()
+                .iter()
+                .map(|rgb| rgb.0)
+                .collect(),
+        }
+    }
+}
+
+impl ColorMapLookUpTable {
+    /// The color at `query`, linearly interpolated between the two table
+    /// entries around it rather than rounded to one of them.
+    pub fn compute_pixel_smooth(&self, query: f32) -> [f32; 3] {
+        let (lower, upper, fraction) = self.table.lookup_bracket(query);
+        [0, 1, 2].map(|i| lower[i] as f32 + fraction * (upper[i] as f32 - lower[i] as f32))
+    }
+}
+
 impl ColorMapper for ColorMapLookUpTable {
     fn compute_pixel(&self, query: f32) -> image::Rgb<u8> {
         self.table.lookup(query)
@@ -389,6 +1020,158 @@ mod tests {
         assert_eq!(table.compute_pixel(2.0), Rgb([255, 0, 255]));
     }
 
+    #[test]
+    fn color_map_lookup_table_clamps_at_arbitrary_domain_edges() {
+        let simple_color_map = SimpleColorMap {
+            red: 255.0,
+            green: 255.0,
+            blue: 0.0,
+        };
+        // Escape-count domain: map `[16, 272]` onto the color map's `[0, 1]`.
+        let domain = [16.0, 272.0];
+        let table = ColorMapLookUpTable::new(257, domain, &|query: f32| {
+            simple_color_map.compute_pixel((query - domain[0]) / (domain[1] - domain[0]))
+        });
+
+        assert_eq!(table.compute_pixel(domain[0]), Rgb([0, 0, 0]));
+        assert_eq!(table.compute_pixel(domain[1]), Rgb([255, 255, 0]));
+        assert_eq!(table.compute_pixel(144.0), Rgb([128, 128, 0]));
+
+        assert_eq!(table.compute_pixel(domain[0] - 1e-3), Rgb([0, 0, 0]));
+        assert_eq!(table.compute_pixel(0.0), Rgb([0, 0, 0]));
+        assert_eq!(table.compute_pixel(domain[1] + 1e-3), Rgb([255, 255, 0]));
+        assert_eq!(table.compute_pixel(1e9), Rgb([255, 255, 0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "finite and increasing")]
+    fn color_map_lookup_table_rejects_empty_domain() {
+        ColorMapLookUpTable::new(8, [1.0, 1.0], &|_| Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn color_map_lookup_table_serde_round_trip() {
+        let original = ColorMapLookUpTable::new(33, [-2.0, 6.0], &|query: f32| {
+            let value = (32.0 * (query + 2.0)).round() as u8;
+            Rgb([value, 255 - value, 7])
+        });
+        let json = serde_json::to_string(&original).unwrap();
+        let parsed: ColorMapLookUpTable = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.table.query_domain(), [-2.0, 6.0]);
+        assert_eq!(parsed.table.entries(), original.table.entries());
+        for query in [-3.0, -2.0, -0.7, 0.0, 1.25, 5.9, 6.0, 8.0] {
+            assert_eq!(parsed.compute_pixel(query), original.compute_pixel(query));
+        }
+    }
+
+    #[test]
+    fn color_map_lookup_table_rejects_malformed_serialization() {
+        for json in [
+            r#"{ "query_domain": [0.0, 1.0], "rgb_entries": [] }"#,
+            r#"{ "query_domain": [1.0, 0.0], "rgb_entries": [[0, 0, 0]] }"#,
+        ] {
+            assert!(
+                serde_json::from_str::<ColorMapLookUpTable>(json).is_err(),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn weighted_spacing_places_keyframes_by_cumulative_weight() {
+        let colors = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        let color_map = with_weighted_spacing(&colors, &[1.0, 2.0, 5.0]).unwrap();
+        let queries: Vec<f32> = color_map.iter().map(|kf| kf.query).collect();
+        assert_eq!(queries, vec![0.0, 0.125, 0.375, 1.0]);
+        for (keyframe, rgb) in color_map.iter().zip(colors) {
+            assert_eq!(keyframe.rgb_raw, rgb);
+        }
+
+        // Many tiny weights still end exactly on the unit interval, and the
+        // result is accepted by the keyframe color map.
+        let colors = vec![[10, 20, 30]; 1001];
+        let color_map = with_weighted_spacing(&colors, &[0.1; 1000]).unwrap();
+        assert_eq!(color_map.first().unwrap().query, 0.0);
+        assert_eq!(color_map.last().unwrap().query, 1.0);
+        assert!(
+            color_map
+                .windows(2)
+                .all(|pair| pair[0].query < pair[1].query)
+        );
+        let mapper = KeyframeColorMap::new(&color_map, LinearInterpolator).unwrap();
+        assert_eq!(mapper.compute_pixel(0.5), Rgb([10, 20, 30]));
+    }
+
+    #[test]
+    fn weighted_spacing_rejects_bad_colors_and_weights() {
+        let colors = [[0, 0, 0], [1, 1, 1], [2, 2, 2]];
+        assert_eq!(
+            with_weighted_spacing(&colors, &[1.0, 0.0]).unwrap_err(),
+            ColorMapError::InvalidWeight {
+                index: 1,
+                weight: 0.0
+            }
+        );
+        assert_eq!(
+            with_weighted_spacing(&colors, &[1.0]).unwrap_err(),
+            ColorMapError::WeightCountMismatch {
+                color_count: 3,
+                weight_count: 1
+            }
+        );
+        assert_eq!(
+            with_weighted_spacing(&colors[..1], &[]).unwrap_err(),
+            ColorMapError::TooFewColors { count: 1 }
+        );
+    }
+
+    #[test]
+    fn normalized_queries_rescale_any_increasing_range_onto_the_unit_interval() {
+        let keyframe = |query, rgb_raw| ColorMapKeyFrame { query, rgb_raw };
+        let color_map = with_normalized_queries(&[
+            keyframe(-20.0, [0, 0, 0]),
+            keyframe(30.0, [100, 100, 100]),
+            keyframe(180.0, [255, 255, 255]),
+        ])
+        .unwrap();
+        let queries: Vec<f32> = color_map.iter().map(|kf| kf.query).collect();
+        assert_eq!(queries, vec![0.0, 0.25, 1.0]);
+        assert_eq!(color_map[1].rgb_raw, [100, 100, 100]);
+        assert!(validate_color_map(&color_map).is_ok());
+
+        // Keyframes already on the unit interval are unchanged.
+        let unit = make_red_to_blue_color_map();
+        let queries: Vec<f32> = with_normalized_queries(&unit)
+            .unwrap()
+            .iter()
+            .map(|kf| kf.query)
+            .collect();
+        assert_eq!(queries, vec![0.0, 1.0]);
+
+        let constant = with_normalized_queries(&[keyframe(7.0, [1, 2, 3])]).unwrap();
+        assert_eq!(constant.len(), 2);
+        assert!(validate_color_map(&constant).is_ok());
+
+        assert_eq!(
+            with_normalized_queries(&[]).err(),
+            Some(ColorMapError::Empty)
+        );
+        assert_eq!(
+            with_normalized_queries(&[
+                keyframe(0.0, [0, 0, 0]),
+                keyframe(5.0, [0, 0, 0]),
+                keyframe(5.0, [0, 0, 0]),
+                keyframe(9.0, [0, 0, 0]),
+            ])
+            .err(),
+            Some(ColorMapError::NotIncreasing {
+                index: 2,
+                query: 5.0
+            })
+        );
+    }
+
     #[test]
     fn color_palette_serde_round_trip() {
         let original = ColorPalette {
@@ -415,16 +1198,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn color_palette_reports_the_out_of_range_keyframe_at_deserialization() {
+        let parse_error = |color_maps: &str| {
+            let json =
+                format!(r#"{{ "background_color": [0, 0, 0], "color_maps": {color_maps} }}"#);
+            serde_json::from_str::<ColorPalette>(&json)
+                .unwrap_err()
+                .to_string()
+        };
+        let red = r#"{ "query": 0.0, "rgb_raw": [255, 0, 0] }"#;
+        let blue = r#"{ "query": 1.0, "rgb_raw": [0, 0, 255] }"#;
+
+        let error = parse_error(&format!(
+            r#"[[{red}, {blue}], [{{ "query": 0.2, "rgb_raw": [0, 0, 0] }}, {blue}]]"#
+        ));
+        assert!(
+            error.contains("color_maps[1]: keyframe 0 has query 0.2"),
+            "{error}"
+        );
+        let error = parse_error(&format!(
+            r#"[[{red}, {{ "query": 0.9, "rgb_raw": [0, 0, 0] }}]]"#
+        ));
+        assert!(
+            error.contains("color_maps[0]: the last keyframe has query 0.9"),
+            "{error}"
+        );
+        let error = parse_error(&format!(
+            r#"[[{red}, {{ "query": 0.6, "rgb_raw": [0, 0, 0] }}, {{ "query": 0.4, "rgb_raw": [0, 0, 0] }}, {blue}]]"#
+        ));
+        assert!(error.contains("keyframe 2 has query 0.4"), "{error}");
+        assert!(parse_error("[[]]").contains("no keyframes"));
+    }
+
+    #[test]
+    fn keyframe_color_map_rejects_keyframes_off_the_unit_interval() {
+        let mut keyframes = make_red_to_blue_color_map();
+        assert!(KeyframeColorMap::new(&keyframes, LinearInterpolator).is_ok());
+        keyframes.last_mut().unwrap().query = 1.5;
+        assert_eq!(
+            KeyframeColorMap::new(&keyframes, LinearInterpolator).err(),
+            Some(ColorMapError::LastQueryNotOne { query: 1.5 })
+        );
+    }
+
     #[test]
     fn colorize_cell_uses_background_color_for_none() {
         let palette = ColorPalette {
             background_color: [9, 9, 9],
             color_maps: vec![make_red_to_blue_color_map()],
         };
-        let cache = palette.create_cache(8, 1.0, 256);
+        let cache = palette.create_cache(8, 1.0, 256).unwrap();
         assert_eq!(colorize_cell(&cache, None), [9, 9, 9]);
     }
 
+    #[test]
+    fn colorize_cell_draws_overlay_index_in_overlay_color() {
+        let palette = ColorPalette {
+            background_color: [9, 9, 9],
+            color_maps: vec![make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(8, 1.0, 256).unwrap();
+        let overlay_cell = Some((0.5, OVERLAY_COLOR_MAP_INDEX));
+        assert_eq!(colorize_cell(&cache, overlay_cell), [9, 9, 9]);
+        cache.set_overlay_color(Some([1, 2, 3]));
+        assert_eq!(colorize_cell(&cache, overlay_cell), [1, 2, 3]);
+    }
+
+    #[test]
+    fn sector_tint_shades_each_sector_with_its_own_hue() {
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![vec![
+                ColorMapKeyFrame {
+                    query: 0.0,
+                    rgb_raw: [200, 200, 200],
+                },
+                ColorMapKeyFrame {
+                    query: 1.0,
+                    rgb_raw: [200, 200, 200],
+                },
+            ]],
+        };
+        let mut cache = palette.create_cache(4, 1.0, 256).unwrap();
+        prime_cdfs_to_unit_distribution(&mut cache, &palette);
+        assert_eq!(colorize_cell(&cache, Some((0.5, 1))), [200, 200, 200]);
+
+        cache.set_sector_count(Some(3));
+        // Indices below the color map count are not tinted.
+        assert_eq!(colorize_cell(&cache, Some((0.5, 0))), [200, 200, 200]);
+        // Red, green, and blue hues, lifted toward white.
+        assert_eq!(colorize_cell(&cache, Some((0.5, 1))), [200, 110, 110]);
+        assert_eq!(colorize_cell(&cache, Some((0.5, 2))), [110, 200, 110]);
+        assert_eq!(colorize_cell(&cache, Some((0.5, 3))), [110, 110, 200]);
+        assert_eq!(colorize_cell(&cache, Some((0.5, 4))), [200, 110, 110]);
+    }
+
     /// Populate each histogram in `cache` with a single mid-bucket sample
     /// so the subsequent `refresh_after_compute_pass` produces a CDF that
     /// maps value `0.0` → 0.0 (low keyframe) and any value in the rightmost
@@ -434,7 +1303,7 @@ mod tests {
         for histogram in &cache.histograms {
             histogram.insert(0.5);
         }
-        cache.refresh_after_compute_pass(palette);
+        cache.refresh_after_compute_pass(palette).unwrap();
     }
 
     #[test]
@@ -455,7 +1324,7 @@ mod tests {
                 ],
             ],
         };
-        let mut cache = palette.create_cache(4, 1.0, 256);
+        let mut cache = palette.create_cache(4, 1.0, 256).unwrap();
         prime_cdfs_to_unit_distribution(&mut cache, &palette);
 
         // Color map 0 maps low percentiles toward red, high toward blue.
@@ -471,13 +1340,121 @@ mod tests {
         assert_eq!(m1_high, [200, 0, 0]);
     }
 
+    /// `Linear` and `Log` normalization ignore the histogram entirely:
+    /// the query depends only on the value relative to `max_value`.
+    #[test]
+    fn colorize_cell_linear_and_log_normalization_ignore_histogram() {
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(4, 2.0, 256).unwrap();
+        prime_cdfs_to_unit_distribution(&mut cache, &palette);
+
+        cache.set_normalization(ScalarNormalization::Linear);
+        assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [255, 0, 0]);
+        assert_eq!(colorize_cell(&cache, Some((2.0, 0))), [0, 0, 255]);
+        assert_eq!(colorize_cell(&cache, Some((5.0, 0))), [0, 0, 255]);
+        let linear_mid = colorize_cell(&cache, Some((1.0, 0)));
+
+        cache.set_normalization(ScalarNormalization::Log);
+        assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [255, 0, 0]);
+        assert_eq!(colorize_cell(&cache, Some((2.0, 0))), [0, 0, 255]);
+        // ln(2) / ln(3) > 1/2: the log map is further toward blue.
+        let log_mid = colorize_cell(&cache, Some((1.0, 0)));
+        assert!(log_mid[2] > linear_mid[2]);
+    }
+
+    /// With bands, every value in a quarter of the query domain takes the
+    /// color at the start of that quarter.
+    #[test]
+    fn banded_cache_quantizes_queries_to_the_band_start() {
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(4, 1.0, 256).unwrap();
+        cache.set_normalization(ScalarNormalization::Linear);
+        let smooth_quarter = colorize_cell(&cache, Some((0.25, 0)));
+        let smooth_half = colorize_cell(&cache, Some((0.5, 0)));
+
+        cache.set_band_count(Some(4), 0.0);
+        assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [255, 0, 0]);
+        assert_eq!(colorize_cell(&cache, Some((0.2, 0))), [255, 0, 0]);
+        assert_eq!(colorize_cell(&cache, Some((0.25, 0))), smooth_quarter);
+        assert_eq!(colorize_cell(&cache, Some((0.4, 0))), smooth_quarter);
+        assert_eq!(colorize_cell(&cache, Some((0.6, 0))), smooth_half);
+        assert_eq!(colorize_cell(&cache, Some((1.0, 0))), [0, 0, 255]);
+
+        cache.set_band_count(None, 0.0);
+        assert_ne!(colorize_cell(&cache, Some((0.4, 0))), smooth_quarter);
+    }
+
+    /// Softness 0 keeps the hard bands, 1 gives back the smooth coloring,
+    /// and anything between blends only the end of each band.
+    #[test]
+    fn band_softness_spans_hard_bands_to_smooth_coloring() {
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(4, 1.0, 256).unwrap();
+        cache.set_normalization(ScalarNormalization::Linear);
+        let values: Vec<f32> = (0..=100).map(|i| i as f32 / 100.0).collect();
+        let colors = |cache: &ColorPaletteCache| -> Vec<[u8; 3]> {
+            values
+                .iter()
+                .map(|&value| colorize_cell(cache, Some((value, 0))))
+                .collect()
+        };
+        let smooth = colors(&cache);
+
+        cache.set_band_count(Some(5), 0.0);
+        let hard = colors(&cache);
+        assert_eq!(hard[0], hard[19]);
+        assert_ne!(hard[19], hard[20]);
+
+        cache.set_band_count(Some(5), 1.0);
+        assert_eq!(colors(&cache), smooth);
+
+        // With half of each band blended, the first half holds the band's
+        // color and the second half ramps toward the next band's.
+        cache.set_band_count(Some(5), 0.5);
+        let soft = colors(&cache);
+        assert_eq!(soft[5], hard[5]);
+        assert_eq!(soft[10], hard[10]);
+        assert!(soft[15] != hard[15] && soft[15] != hard[20]);
+        assert_eq!(soft[20], hard[20]);
+    }
+
+    /// Once frozen, the CDFs ignore new histogram data, so a value keeps
+    /// its color even though the distribution around it changed.
+    #[test]
+    fn frozen_cdfs_ignore_subsequent_histograms() {
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(4, 1.0, 256).unwrap();
+        cache.reset_histograms();
+        cache.histograms[0].insert(0.9);
+        cache.refresh_after_compute_pass(&palette).unwrap();
+        let before = colorize_cell(&cache, Some((0.5, 0)));
+
+        cache.freeze_cdfs();
+        cache.reset_histograms();
+        cache.histograms[0].insert(0.1);
+        cache.refresh_after_compute_pass(&palette).unwrap();
+        assert_eq!(colorize_cell(&cache, Some((0.5, 0))), before);
+    }
+
     #[test]
     fn colorize_cell_wraps_out_of_range_color_map_index() {
         let palette = ColorPalette {
             background_color: [0, 0, 0],
             color_maps: vec![make_red_to_blue_color_map()],
         };
-        let mut cache = palette.create_cache(4, 1.0, 256);
+        let mut cache = palette.create_cache(4, 1.0, 256).unwrap();
         prime_cdfs_to_unit_distribution(&mut cache, &palette);
 
         // color-map index 2 wraps to 0 via modulo.
@@ -491,13 +1468,13 @@ mod tests {
             background_color: [0, 0, 0],
             color_maps: vec![make_red_to_blue_color_map()],
         };
-        let mut cache = palette.create_cache(4, 1.0, 64);
+        let mut cache = palette.create_cache(4, 1.0, 64).unwrap();
         // Edit a keyframe and verify the next atomic refresh picks it up
         // alongside the freshly-binned CDFs.
         palette.color_maps[0][0].rgb_raw = [50, 60, 70];
         cache.reset_histograms();
         cache.histograms[0].insert(0.5);
-        cache.refresh_after_compute_pass(&palette);
+        cache.refresh_after_compute_pass(&palette).unwrap();
         assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [50, 60, 70]);
     }
 
@@ -507,9 +1484,143 @@ mod tests {
             background_color: [1, 2, 3],
             color_maps: vec![make_red_to_blue_color_map()],
         };
-        let mut cache = palette.create_cache(4, 1.0, 64);
+        let mut cache = palette.create_cache(4, 1.0, 64).unwrap();
         palette.background_color = [99, 100, 101];
-        cache.refresh_after_compute_pass(&palette);
+        cache.refresh_after_compute_pass(&palette).unwrap();
         assert_eq!(colorize_cell(&cache, None), [99, 100, 101]);
     }
+
+    #[test]
+    fn create_cache_rejects_an_invalid_color_map() {
+        let mut color_map = make_red_to_blue_color_map();
+        color_map.reverse();
+        let palette = ColorPalette {
+            background_color: [1, 2, 3],
+            color_maps: vec![color_map],
+        };
+        assert_eq!(
+            palette.create_cache(4, 1.0, 64).err(),
+            Some(ColorMapError::FirstQueryNotZero { query: 1.0 })
+        );
+    }
+
+    fn channel_curves() -> ChannelCurves {
+        let curve = |points: &[(f32, u8)]| {
+            points
+                .iter()
+                .map(|&(query, value)| ChannelKeyFrame { query, value })
+                .collect()
+        };
+        ChannelCurves {
+            red: curve(&[(0.0, 0), (1.0, 200)]),
+            green: curve(&[(0.0, 100), (0.5, 0), (1.0, 100)]),
+            blue: curve(&[(0.0, 40), (0.25, 40), (0.75, 240), (1.0, 240)]),
+        }
+    }
+
+    #[test]
+    fn channelwise_color_map_interpolates_each_channel_on_its_own_keyframes() {
+        let color_map = ChannelwiseColorMap::new(&channel_curves(), LinearInterpolator).unwrap();
+        assert_eq!(color_map.compute_pixel(0.0), Rgb([0, 100, 40]));
+        assert_eq!(color_map.compute_pixel(0.5), Rgb([100, 0, 140]));
+        assert_eq!(color_map.compute_pixel(1.0), Rgb([200, 100, 240]));
+
+        let mut bad_curves = channel_curves();
+        bad_curves.green[1].query = 1.5;
+        assert!(ChannelwiseColorMap::new(&bad_curves, LinearInterpolator).is_err());
+        let json = serde_json::to_string(&bad_curves).unwrap();
+        let error = serde_json::from_str::<ChannelCurves>(&json).unwrap_err();
+        assert!(error.to_string().contains("channel curve"), "{error}");
+    }
+
+    #[test]
+    fn channel_curves_replace_the_first_color_map_of_the_cache() {
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![make_red_to_blue_color_map(), make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(4, 1.0, 64).unwrap();
+        cache.set_channel_curves(Some(channel_curves())).unwrap();
+        cache.histograms[0].insert(0.5);
+        cache.histograms[1].insert(0.5);
+        cache.refresh_after_compute_pass(&palette).unwrap();
+        assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [0, 100, 40]);
+        assert_eq!(colorize_cell(&cache, Some((0.0, 1))), [255, 0, 0]);
+        cache.set_channel_curves(None).unwrap();
+        cache.refresh_after_compute_pass(&palette).unwrap();
+        assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [255, 0, 0]);
+    }
+
+    #[test]
+    fn set_channel_curves_rejects_invalid_curves() {
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(4, 1.0, 64).unwrap();
+        let mut curves = channel_curves();
+        curves.green.reverse();
+        assert_eq!(
+            cache.set_channel_curves(Some(curves)),
+            Err(ColorMapError::FirstQueryNotZero { query: 1.0 })
+        );
+        cache.refresh_after_compute_pass(&palette).unwrap();
+        assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [255, 0, 0]);
+    }
+
+    #[test]
+    fn color_effects_at_boundary_values() {
+        let apply = |effect: ColorEffect| [0, 1, 127, 128, 254, 255].map(|c| effect.apply(c));
+        assert_eq!(apply(ColorEffect::Invert), [255, 254, 128, 127, 1, 0]);
+        assert_eq!(
+            apply(ColorEffect::Solarize { threshold: 127 }),
+            [0, 1, 127, 127, 1, 0]
+        );
+        assert_eq!(
+            apply(ColorEffect::Solarize { threshold: 255 }),
+            [0, 1, 127, 128, 254, 255]
+        );
+        assert_eq!(
+            apply(ColorEffect::Posterize { levels: 2 }),
+            [0, 0, 0, 255, 255, 255]
+        );
+        assert_eq!(
+            apply(ColorEffect::Posterize { levels: 3 }),
+            [0, 0, 128, 128, 255, 255]
+        );
+        assert_eq!(
+            apply(ColorEffect::Posterize { levels: 0 }),
+            apply(ColorEffect::Posterize { levels: 2 })
+        );
+        let fine = apply(ColorEffect::Posterize { levels: 255 });
+        assert_eq!([fine[0], fine[5]], [0, 255]);
+        assert!(
+            fine.iter()
+                .zip([0, 1, 127, 128, 254, 255])
+                .all(|(&p, c)| p.abs_diff(c) <= 1)
+        );
+    }
+
+    #[test]
+    fn effect_color_map_applies_effects_in_order_and_reaches_the_cache() {
+        let inner = SimpleColorMap {
+            red: 200.0,
+            green: 100.0,
+            blue: 0.0,
+        };
+        let effects = vec![ColorEffect::Invert, ColorEffect::Posterize { levels: 2 }];
+        let color_map = EffectColorMap::new(inner, effects.clone());
+        assert_eq!(color_map.compute_pixel(1.0), Rgb([0, 255, 255]));
+
+        let palette = ColorPalette {
+            background_color: [0, 0, 0],
+            color_maps: vec![make_red_to_blue_color_map()],
+        };
+        let mut cache = palette.create_cache(4, 1.0, 64).unwrap();
+        cache.set_color_effects(vec![ColorEffect::Invert]);
+        cache.histograms[0].insert(0.5);
+        cache.refresh_after_compute_pass(&palette).unwrap();
+        assert_eq!(colorize_cell(&cache, Some((0.0, 0))), [0, 255, 255]);
+        assert_eq!(colorize_cell(&cache, None), [0, 0, 0]);
+    }
 }
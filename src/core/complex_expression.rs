@@ -0,0 +1,597 @@
+//! A small parser and evaluator for complex-valued expressions of one
+//! variable `z`, such as `"z^3 - 1"` or `"sin(z) - z"`.
+//!
+//! Evaluation uses forward-mode automatic differentiation: every node of the
+//! expression tree propagates both its value and its derivative with respect
+//! to `z`, so the derivative is exact (up to floating point) rather than a
+//! finite-difference approximation.
+//!
+//! Grammar, from lowest to highest precedence:
+//!
+//! ```text
+//! expr  := term (('+' | '-') term)*
+//! term  := unary (('*' | '/') unary | unary)*     // juxtaposition multiplies: "2z", "3(z+1)"
+//! unary := ('+' | '-') unary | power
+//! power := atom ('^' unary)?                     // right-associative, binds tighter than unary minus
+//! atom  := number | 'z' | 'i' | 'pi' | 'e' | function '(' expr ')' | '(' expr ')'
+//! ```
+//!
+//! Supported functions: `sin`, `cos`, `tan`, `sinh`, `cosh`, `tanh`, `exp`,
+//! `ln` (alias `log`), and `sqrt`, all using the principal branch.
+
+use num::complex::Complex64;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{E, PI};
+
+/// A parsed expression `f(z)`. It serializes as its source string, so a
+/// malformed expression in a parameter file is reported when the file is
+/// loaded rather than partway through a render.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct ComplexExpression {
+    source: String,
+    root: Node,
+}
+
+impl ComplexExpression {
+    /// Parse `source` using the grammar in the module docs. The error names
+    /// the offending token and its byte position.
+    pub fn parse(source: &str) -> Result<ComplexExpression, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            index: 0,
+            source_len: source.len(),
+        };
+        let root = parser.expr()?;
+        if let Some((token, position)) = parser.tokens.get(parser.index) {
+            return Err(format!(
+                "unexpected {token:?} at position {position} in `{source}`"
+            ));
+        }
+        Ok(ComplexExpression {
+            source: source.to_owned(),
+            root,
+        })
+    }
+
+    /// The expression text this was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate `f(z)` and its derivative `f'(z)`, returned as `(value, slope)`.
+    pub fn value_and_slope(&self, z: Complex64) -> (Complex64, Complex64) {
+        let dual = self.root.eval(z);
+        (dual.value, dual.slope)
+    }
+}
+
+impl TryFrom<String> for ComplexExpression {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        ComplexExpression::parse(&source)
+    }
+}
+
+impl From<ComplexExpression> for String {
+    fn from(expression: ComplexExpression) -> Self {
+        expression.source
+    }
+}
+
+/// A value together with its derivative with respect to `z`.
+#[derive(Clone, Copy)]
+struct Dual {
+    value: Complex64,
+    slope: Complex64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Ln,
+    Sqrt,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "exp" => Function::Exp,
+            "ln" | "log" => Function::Ln,
+            "sqrt" => Function::Sqrt,
+            _ => return None,
+        })
+    }
+
+    /// Returns `(g(a), g'(a))`; the chain rule is applied by the caller.
+    fn value_and_derivative(self, a: Complex64) -> (Complex64, Complex64) {
+        match self {
+            Function::Sin => (a.sin(), a.cos()),
+            Function::Cos => (a.cos(), -a.sin()),
+            Function::Tan => (a.tan(), a.cos().powi(-2)),
+            Function::Sinh => (a.sinh(), a.cosh()),
+            Function::Cosh => (a.cosh(), a.sinh()),
+            Function::Tanh => (a.tanh(), a.cosh().powi(-2)),
+            Function::Exp => {
+                let value = a.exp();
+                (value, value)
+            }
+            Function::Ln => (a.ln(), a.inv()),
+            Function::Sqrt => {
+                let value = a.sqrt();
+                (value, (2.0 * value).inv())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Constant(Complex64),
+    Z,
+    Negate(Box<Node>),
+    Add(Box<Node>, Box<Node>),
+    Subtract(Box<Node>, Box<Node>),
+    Multiply(Box<Node>, Box<Node>),
+    Divide(Box<Node>, Box<Node>),
+    Power(Box<Node>, Box<Node>),
+    Call(Function, Box<Node>),
+}
+
+impl Node {
+    fn eval(&self, z: Complex64) -> Dual {
+        match self {
+            Node::Constant(value) => Dual {
+                value: *value,
+                slope: Complex64::new(0.0, 0.0),
+            },
+            Node::Z => Dual {
+                value: z,
+                slope: Complex64::new(1.0, 0.0),
+            },
+            Node::Negate(a) => {
+                let a = a.eval(z);
+                Dual {
+                    value: -a.value,
+                    slope: -a.slope,
+                }
+            }
+            Node::Add(a, b) => {
+                let (a, b) = (a.eval(z), b.eval(z));
+                Dual {
+                    value: a.value + b.value,
+                    slope: a.slope + b.slope,
+                }
+            }
+            Node::Subtract(a, b) => {
+                let (a, b) = (a.eval(z), b.eval(z));
+                Dual {
+                    value: a.value - b.value,
+                    slope: a.slope - b.slope,
+                }
+            }
+            Node::Multiply(a, b) => {
+                let (a, b) = (a.eval(z), b.eval(z));
+                Dual {
+                    value: a.value * b.value,
+                    slope: a.slope * b.value + a.value * b.slope,
+                }
+            }
+            Node::Divide(a, b) => {
+                let (a, b) = (a.eval(z), b.eval(z));
+                let value = a.value / b.value;
+                Dual {
+                    value,
+                    slope: (a.slope - value * b.slope) / b.value,
+                }
+            }
+            Node::Power(base, exponent) => {
+                let a = base.eval(z);
+                match exponent.as_ref() {
+                    Node::Constant(c) => constant_power(a, *c),
+                    _ => {
+                        // a^b = exp(b ln a)  =>  (a^b)' = a^b (b' ln a + b a' / a)
+                        let b = exponent.eval(z);
+                        let ln_a = a.value.ln();
+                        let value = (b.value * ln_a).exp();
+                        Dual {
+                            value,
+                            slope: value * (b.slope * ln_a + b.value * a.slope / a.value),
+                        }
+                    }
+                }
+            }
+            Node::Call(function, a) => {
+                let a = a.eval(z);
+                let (value, derivative) = function.value_and_derivative(a.value);
+                Dual {
+                    value,
+                    slope: derivative * a.slope,
+                }
+            }
+        }
+    }
+}
+
+/// `a^c` for a constant exponent. Integer exponents use repeated
+/// multiplication, which is both faster and exact at `a = 0`, where the
+/// `exp(c ln a)` form would produce NaN.
+fn constant_power(a: Dual, c: Complex64) -> Dual {
+    if c.im == 0.0 && c.re.fract() == 0.0 && c.re.abs() <= i32::MAX as f64 {
+        let n = c.re as i32;
+        if n == 0 {
+            return Dual {
+                value: Complex64::new(1.0, 0.0),
+                slope: Complex64::new(0.0, 0.0),
+            };
+        }
+        let a_pow_n_minus_1 = a.value.powi(n - 1);
+        Dual {
+            value: a_pow_n_minus_1 * a.value,
+            slope: a_pow_n_minus_1 * a.slope * n as f64,
+        }
+    } else {
+        let a_pow_c_minus_1 = a.value.powc(c - 1.0);
+        Dual {
+            value: a_pow_c_minus_1 * a.value,
+            slope: a_pow_c_minus_1 * a.slope * c,
+        }
+    }
+}
+
+/// Build a binary node, folding it to a constant when both operands are
+/// constant. Folding lets `z^(1/2)` and `z^-2` take the constant-exponent path.
+fn binary(
+    make: fn(Box<Node>, Box<Node>) -> Node,
+    a: Node,
+    b: Node,
+    fold: fn(Complex64, Complex64) -> Complex64,
+) -> Node {
+    match (&a, &b) {
+        (Node::Constant(x), Node::Constant(y)) => Node::Constant(fold(*x, *y)),
+        _ => make(Box::new(a), Box::new(b)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    LeftParen,
+    RightParen,
+}
+
+/// Split the source into tokens, each paired with its byte offset for error messages.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        let start = index;
+        let byte = bytes[index];
+        let token = match byte {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                index += 1;
+                continue;
+            }
+            b'+' => Token::Plus,
+            b'-' => Token::Minus,
+            b'*' => Token::Star,
+            b'/' => Token::Slash,
+            b'^' => Token::Caret,
+            b'(' => Token::LeftParen,
+            b')' => Token::RightParen,
+            b'0'..=b'9' | b'.' => {
+                while index < bytes.len() && (bytes[index].is_ascii_digit() || bytes[index] == b'.')
+                {
+                    index += 1;
+                }
+                // Scientific notation, e.g. `1e-6`. A bare `e` after a number
+                // is left alone so that `2e` still reads as `2 * e`.
+                if index < bytes.len() && matches!(bytes[index], b'e' | b'E') {
+                    let mut exponent = index + 1;
+                    if exponent < bytes.len() && matches!(bytes[exponent], b'+' | b'-') {
+                        exponent += 1;
+                    }
+                    if exponent < bytes.len() && bytes[exponent].is_ascii_digit() {
+                        index = exponent;
+                        while index < bytes.len() && bytes[index].is_ascii_digit() {
+                            index += 1;
+                        }
+                    }
+                }
+                let text = &source[start..index];
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number `{text}` at position {start}"))?;
+                tokens.push((Token::Number(value), start));
+                continue;
+            }
+            b'a'..=b'z' | b'A'..=b'Z' => {
+                while index < bytes.len() && bytes[index].is_ascii_alphabetic() {
+                    index += 1;
+                }
+                tokens.push((Token::Identifier(source[start..index].to_owned()), start));
+                continue;
+            }
+            _ => {
+                let character = source[start..].chars().next().unwrap_or_default();
+                return Err(format!(
+                    "unexpected character `{character}` at position {start}"
+                ));
+            }
+        };
+        tokens.push((token, start));
+        index += 1;
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser over the token stream; one method per grammar rule.
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    index: usize,
+    source_len: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(self.source_len, |(_, position)| *position)
+    }
+
+    fn consume(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<Node, String> {
+        let mut node = self.term()?;
+        loop {
+            if self.consume(&Token::Plus) {
+                node = binary(Node::Add, node, self.term()?, |a, b| a + b);
+            } else if self.consume(&Token::Minus) {
+                node = binary(Node::Subtract, node, self.term()?, |a, b| a - b);
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            if self.consume(&Token::Star) {
+                node = binary(Node::Multiply, node, self.unary()?, |a, b| a * b);
+            } else if self.consume(&Token::Slash) {
+                node = binary(Node::Divide, node, self.unary()?, |a, b| a / b);
+            } else if matches!(
+                self.peek(),
+                Some(Token::Number(_) | Token::Identifier(_) | Token::LeftParen)
+            ) {
+                node = binary(Node::Multiply, node, self.unary()?, |a, b| a * b);
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.consume(&Token::Minus) {
+            return Ok(match self.unary()? {
+                Node::Constant(value) => Node::Constant(-value),
+                node => Node::Negate(Box::new(node)),
+            });
+        }
+        if self.consume(&Token::Plus) {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if self.consume(&Token::Caret) {
+            let exponent = self.unary()?;
+            return Ok(binary(Node::Power, base, exponent, |a, b| {
+                constant_power(
+                    Dual {
+                        value: a,
+                        slope: Complex64::new(0.0, 0.0),
+                    },
+                    b,
+                )
+                .value
+            }));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let position = self.position();
+        let Some((token, _)) = self.tokens.get(self.index) else {
+            return Err(format!(
+                "unexpected end of expression at position {position}"
+            ));
+        };
+        self.index += 1;
+        match token {
+            Token::Number(value) => Ok(Node::Constant(Complex64::new(*value, 0.0))),
+            Token::LeftParen => {
+                let node = self.expr()?;
+                self.expect_right_paren(position)?;
+                Ok(node)
+            }
+            Token::Identifier(name) => match name.as_str() {
+                "z" => Ok(Node::Z),
+                "i" => Ok(Node::Constant(Complex64::new(0.0, 1.0))),
+                "pi" => Ok(Node::Constant(Complex64::new(PI, 0.0))),
+                "e" => Ok(Node::Constant(Complex64::new(E, 0.0))),
+                _ => {
+                    let function = Function::from_name(name).ok_or_else(|| {
+                        format!("unknown identifier `{name}` at position {position}")
+                    })?;
+                    let open = self.position();
+                    if !self.consume(&Token::LeftParen) {
+                        return Err(format!(
+                            "expected `(` after function `{name}` at position {open}"
+                        ));
+                    }
+                    let argument = self.expr()?;
+                    self.expect_right_paren(open)?;
+                    Ok(match argument {
+                        Node::Constant(value) => {
+                            Node::Constant(function.value_and_derivative(value).0)
+                        }
+                        argument => Node::Call(function, Box::new(argument)),
+                    })
+                }
+            },
+            other => Err(format!("unexpected {other:?} at position {position}")),
+        }
+    }
+
+    fn expect_right_paren(&mut self, open: usize) -> Result<(), String> {
+        if self.consume(&Token::RightParen) {
+            Ok(())
+        } else {
+            Err(format!(
+                "missing `)` for the `(` at position {open}, found {} at position {}",
+                self.peek()
+                    .map_or("end of expression".to_owned(), |token| format!("{token:?}")),
+                self.position()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Complex64, expected: Complex64) {
+        assert!(
+            (actual - expected).norm() < 1e-12 * (1.0 + expected.norm()),
+            "{actual} != {expected}"
+        );
+    }
+
+    fn check(
+        source: &str,
+        f: impl Fn(Complex64) -> Complex64,
+        df: impl Fn(Complex64) -> Complex64,
+    ) {
+        let expression = ComplexExpression::parse(source).unwrap();
+        for z in [
+            Complex64::new(0.7, -0.3),
+            Complex64::new(-1.2, 0.9),
+            Complex64::new(0.1, 2.0),
+        ] {
+            let (value, slope) = expression.value_and_slope(z);
+            assert_close(value, f(z));
+            assert_close(slope, df(z));
+        }
+    }
+
+    #[test]
+    fn evaluates_values_and_exact_derivatives() {
+        let one = Complex64::new(1.0, 0.0);
+        check("z^3 - 1", |z| z.powi(3) - one, |z| 3.0 * z * z);
+        check("sin(z) - z", |z| z.sin() - z, |z| z.cos() - one);
+        check("z/(z+1)", |z| z / (z + one), |z| (z + one).powi(-2));
+        check("exp(z^2)", |z| (z * z).exp(), |z| 2.0 * z * (z * z).exp());
+        check(
+            "sqrt(z) + ln(z)",
+            |z| z.sqrt() + z.ln(),
+            |z| 0.5 / z.sqrt() + one / z,
+        );
+        check("z^z", |z| z.powc(z), |z| z.powc(z) * (z.ln() + one));
+        check(
+            "tan(z) * cosh(z)",
+            |z| z.tan() * z.cosh(),
+            |z| z.cosh() / z.cos().powi(2) + z.tan() * z.sinh(),
+        );
+        check("z^(1/2)", |z| z.sqrt(), |z| 0.5 / z.sqrt());
+    }
+
+    #[test]
+    fn follows_operator_precedence_and_constants() {
+        let z = Complex64::new(0.4, 0.8);
+        let eval = |source: &str| {
+            ComplexExpression::parse(source)
+                .unwrap()
+                .value_and_slope(z)
+                .0
+        };
+        assert_close(eval("1 + 2 * 3"), Complex64::new(7.0, 0.0));
+        assert_close(eval("-2^2"), Complex64::new(-4.0, 0.0));
+        assert_close(eval("2^3^2"), Complex64::new(512.0, 0.0));
+        assert_close(eval("2^-1"), Complex64::new(0.5, 0.0));
+        assert_close(eval("8 / 4 / 2"), Complex64::new(1.0, 0.0));
+        assert_close(eval("2z(z + 1)"), 2.0 * z * (z + 1.0));
+        assert_close(eval("3i + pi"), Complex64::new(PI, 3.0));
+        assert_close(eval("e^(i pi)"), Complex64::new(-1.0, 0.0));
+        assert_close(eval("1.5e-1 * 2e"), Complex64::new(0.3 * E, 0.0));
+    }
+
+    #[test]
+    fn integer_powers_are_exact_at_zero() {
+        let expression = ComplexExpression::parse("z^3 + z^1").unwrap();
+        let (value, slope) = expression.value_and_slope(Complex64::new(0.0, 0.0));
+        assert_eq!(value, Complex64::new(0.0, 0.0));
+        assert_eq!(slope, Complex64::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn reports_malformed_expressions() {
+        for (source, message) in [
+            ("", "unexpected end"),
+            ("z +", "unexpected end"),
+            ("(z + 1", "missing `)`"),
+            ("z + 1)", "unexpected RightParen"),
+            ("foo(z)", "unknown identifier `foo`"),
+            ("sin z", "expected `(`"),
+            ("z $ 2", "unexpected character `$`"),
+            ("1.2.3", "invalid number"),
+        ] {
+            let error = ComplexExpression::parse(source).unwrap_err();
+            assert!(error.contains(message), "`{source}`: {error}");
+        }
+    }
+
+    #[test]
+    fn serializes_as_its_source_string() {
+        let expression: ComplexExpression = serde_json::from_str(r#""z^3 - 1""#).unwrap();
+        assert_eq!(expression.source(), "z^3 - 1");
+        assert_eq!(serde_json::to_string(&expression).unwrap(), r#""z^3 - 1""#);
+        assert!(serde_json::from_str::<ComplexExpression>(r#""z^""#).is_err());
+    }
+}
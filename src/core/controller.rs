@@ -4,10 +4,33 @@ pub enum Target {
     Velocity { vel_ref: f64 },
 }
 
+/// How a `PointTracker` approaches a `Target::Position`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TrackingMode {
+    /// Move at `max_vel` until the target is reached, then stop abruptly.
+    #[default]
+    ConstantVelocity,
+    /// Second-order critically-damped approach with the given time constant
+    /// (seconds), so the motion eases in and out without overshoot. The speed
+    /// is still limited to `max_vel`.
+    CriticallyDamped { time_constant: f64 },
+}
+
+/// A critically-damped approach never quite arrives, so it snaps onto the
+/// target once both the remaining distance and the distance it would coast
+/// (`velocity * time_constant`) are below this fraction of
+/// `max_vel * time_constant`.
+const SETTLE_FRACTION: f64 = 1e-3;
+
 #[derive(Clone, Debug)]
 pub struct PointTracker {
     position: f64,
+    velocity: f64,
     target: Target,
+    mode: TrackingMode,
+    // Limit on the rate of change of velocity while tracking a
+    // `Target::Velocity`; `None` switches velocity instantly.
+    max_acceleration: Option<f64>,
     time: f64,
 }
 
@@ -15,11 +38,25 @@ impl PointTracker {
     pub fn new(time: f64, pos: f64) -> PointTracker {
         PointTracker {
             position: pos,
+            velocity: 0.0,
             target: Target::Velocity { vel_ref: 0.0 },
+            mode: TrackingMode::default(),
+            max_acceleration: None,
             time,
         }
     }
 
+    /// Ramp toward each `Target::Velocity` at no more than `max_acceleration`
+    /// (units per second squared), rather than switching to it instantly.
+    pub fn set_max_acceleration(&mut self, max_acceleration: Option<f64>) {
+        self.max_acceleration = max_acceleration;
+    }
+
+    /// Choose how later `Target::Position` targets are approached.
+    pub fn set_tracking_mode(&mut self, mode: TrackingMode) {
+        self.mode = mode;
+    }
+
     // Indicates the controller should drop an active velocity command
     // but keep tracking a position target until it is reached.
     pub fn set_idle_target(&mut self) {
@@ -39,6 +76,7 @@ impl PointTracker {
     /// Sets the position and clears any actively tracked target.
     pub fn set_position(&mut self, position: f64) {
         self.position = position;
+        self.velocity = 0.0;
         self.target = Target::Velocity { vel_ref: 0.0 };
     }
 
@@ -50,24 +88,152 @@ impl PointTracker {
     }
 
     fn update_position(&mut self, delta_time: f64) {
-        match self.target {
-            Target::Position { pos_ref, max_vel } => {
+        match (self.target.clone(), self.mode) {
+            (Target::Position { pos_ref, max_vel }, TrackingMode::ConstantVelocity) => {
                 let pos_err = pos_ref - self.position;
                 let max_pos_delta = (max_vel * delta_time).abs();
 
                 if pos_err.abs() < max_pos_delta {
                     // We reached the target!
                     self.position = pos_ref;
+                    self.velocity = 0.0;
                     self.target = Target::Velocity { vel_ref: 0.0 };
                 } else {
                     // Move toward the target at constant max velocity:
                     let pos_err_clamped = pos_err.clamp(-max_pos_delta, max_pos_delta);
                     self.position += pos_err_clamped;
+                    self.velocity = max_vel.abs().copysign(pos_err);
                 }
             }
-            Target::Velocity { vel_ref } => {
-                self.position += vel_ref * delta_time;
+            (
+                Target::Position { pos_ref, max_vel },
+                TrackingMode::CriticallyDamped { time_constant },
+            ) => {
+                self.update_critically_damped(pos_ref, max_vel.abs(), time_constant, delta_time);
+            }
+            (Target::Velocity { vel_ref }, _) => match self.max_acceleration {
+                Some(max_acceleration) => {
+                    let max_vel_delta = (max_acceleration * delta_time).abs();
+                    let next_velocity = self.velocity
+                        + (vel_ref - self.velocity).clamp(-max_vel_delta, max_vel_delta);
+                    // Trapezoidal rule: exact for constant acceleration.
+                    self.position += 0.5 * (self.velocity + next_velocity) * delta_time;
+                    self.velocity = next_velocity;
+                }
+                None => {
+                    self.position += vel_ref * delta_time;
+                    self.velocity = vel_ref;
+                }
+            },
+        }
+    }
+
+    /// Advance `x'' = -ω² (x - pos_ref) - 2ω x'` with `ω = 1 / time_constant`
+    /// using its closed-form solution, which is exact (and stable) for any
+    /// time step, then enforce the speed limit.
+    fn update_critically_damped(
+        &mut self,
+        pos_ref: f64,
+        max_speed: f64,
+        time_constant: f64,
+        delta_time: f64,
+    ) {
+        let omega = 1.0 / time_constant;
+        let decay = (-omega * delta_time).exp();
+        let pos_err = self.position - pos_ref;
+        let slope = self.velocity + omega * pos_err;
+        let next_pos_err = (pos_err + slope * delta_time) * decay;
+        let next_velocity = (self.velocity - omega * slope * delta_time) * decay;
+
+        let max_pos_delta = max_speed * delta_time;
+        self.position += (next_pos_err - pos_err).clamp(-max_pos_delta, max_pos_delta);
+        self.velocity = next_velocity.clamp(-max_speed, max_speed);
+
+        let remaining = (self.position - pos_ref).abs() + (self.velocity * time_constant).abs();
+        if remaining <= SETTLE_FRACTION * max_speed * time_constant {
+            self.position = pos_ref;
+            self.velocity = 0.0;
+            self.target = Target::Velocity { vel_ref: 0.0 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIME_STEP: f64 = 1.0 / 60.0;
+
+    /// Track a position target from rest and return the position at each
+    /// step, stopping once the tracker drops the target.
+    fn track_to_target(mode: TrackingMode, pos_ref: f64, max_vel: f64) -> Vec<f64> {
+        let mut tracker = PointTracker::new(0.0, 0.0);
+        tracker.set_tracking_mode(mode);
+        tracker.set_target(Target::Position { pos_ref, max_vel });
+        let mut positions = Vec::new();
+        for step in 1..1000 {
+            positions.push(tracker.update_and_return_pos(step as f64 * TIME_STEP));
+            if let Target::Velocity { .. } = tracker.target {
+                return positions;
             }
         }
+        panic!("tracker never reached the target");
+    }
+
+    #[test]
+    fn constant_velocity_ramps_linearly_and_stops_at_the_target() {
+        let positions = track_to_target(TrackingMode::default(), 1.0, 2.0);
+        assert_eq!(*positions.last().unwrap(), 1.0);
+        assert!((positions[0] - 2.0 * TIME_STEP).abs() < 1e-12);
+        assert!((positions[1] - 4.0 * TIME_STEP).abs() < 1e-12);
+    }
+
+    #[test]
+    fn critically_damped_eases_in_and_out_without_overshoot() {
+        let mode = TrackingMode::CriticallyDamped {
+            time_constant: 0.25,
+        };
+        let max_vel = 10.0;
+        let positions = track_to_target(mode, 1.0, max_vel);
+        assert_eq!(*positions.last().unwrap(), 1.0);
+
+        let steps: Vec<f64> = std::iter::once(positions[0])
+            .chain(positions.windows(2).map(|pair| pair[1] - pair[0]))
+            .collect();
+        let peak = steps
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap()
+            .0;
+        // Monotonic approach: speeds up to a single peak, then slows down.
+        assert!(peak > 0 && peak + 1 < steps.len(), "{peak}");
+        assert!(steps[..=peak].windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(
+            steps[peak..steps.len() - 1]
+                .windows(2)
+                .all(|pair| pair[0] >= pair[1])
+        );
+        assert!(positions.iter().all(|&p| p <= 1.0));
+        assert!(steps.iter().all(|&step| step <= max_vel * TIME_STEP));
+    }
+
+    #[test]
+    fn critically_damped_respects_the_speed_limit() {
+        let mode = TrackingMode::CriticallyDamped {
+            time_constant: 0.25,
+        };
+        let positions = track_to_target(mode, 10.0, 1.0);
+        assert_eq!(*positions.last().unwrap(), 10.0);
+        for pair in positions.windows(2) {
+            assert!(pair[1] - pair[0] <= 1.0 * TIME_STEP + 1e-12);
+        }
+        // Cruises at the limit for most of the 10 second trip, then takes a
+        // few time constants to settle.
+        let duration = positions.len() as f64 * TIME_STEP;
+        assert!(
+            duration > 10.0 && duration < 10.0 + 12.0 * 0.25,
+            "{duration}"
+        );
     }
 }
@@ -24,16 +24,23 @@
 //! read-only traversal (used by `populate_histograms`) consult the same
 //! planner, so the modular arithmetic lives in exactly one place and is
 //! covered by its own unit tests.
+//!
+//! The field itself is a [`FieldBuffer`]: one flat, row-major allocation
+//! with the same layout as the output image, so every pass walks it in
+//! memory order.
+
+use std::ops::{ControlFlow, Index, IndexMut};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use egui::{Color32, ColorImage};
-use rayon::iter::{
-    IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator,
-};
-use rayon::slice::ParallelSliceMut;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use rayon::slice::{ChunksExact, ChunksExactMut, ParallelSlice, ParallelSliceMut};
 
-use crate::core::color_map::{ColorPaletteCache, colorize_cell};
+use crate::core::color_map::{
+    ColorPaletteCache, OVERLAY_COLOR_MAP_INDEX, colorize_cell, colorize_cell_smooth,
+};
 use crate::core::histogram::Histogram;
-use crate::core::image_utils::{ImageSpecification, PixelMapper};
+use crate::core::image_utils::{DownscaleFilter, ImageSpecification, PixelMapper, RenderOptions};
 
 /// Domain-specific per-point evaluation. Each fractal implements exactly
 /// this much of the math; anti-aliasing / block-fill iteration lives in
@@ -44,8 +51,93 @@ pub trait FieldKernel: Sync + Send {
     /// `color_map_index` selects which color map (and which per-color-map
     /// histogram / CDF / LUT) the cell colorizes through.
     fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)>;
+
+    /// Opt-in adaptive refinement for expensive kernels with large uniform
+    /// regions. When `Some(stride)`, anti-aliased and baseline compute
+    /// passes first evaluate a coarse lattice of every `stride`-th sample,
+    /// then only evaluate the remaining samples whose nearby lattice points
+    /// disagree; the rest copy the shared lattice value. Features narrower
+    /// than `stride` samples that slip between agreeing lattice points are
+    /// missed. Block-fill passes always evaluate directly.
+    fn coarse_prepass_stride(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Raw field cells for one render, stored as a single row-major `Vec` with
+/// a row stride of `size[0]`. Indexed as `field[[x, y]]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldBuffer {
+    cells: Vec<Option<(f32, u32)>>,
+    size: [usize; 2],
+}
+
+impl FieldBuffer {
+    /// Allocate a `size[0] × size[1]` field with every cell unset.
+    pub fn new(size: [usize; 2]) -> Self {
+        FieldBuffer {
+            cells: vec![None; size[0] * size[1]],
+            size,
+        }
+    }
+
+    /// `[width, height]` in cells.
+    pub fn size(&self) -> [usize; 2] {
+        self.size
+    }
+
+    /// The cells of row `y`, in order of increasing `x`.
+    #[inline]
+    pub fn row(&self, y: usize) -> &[Option<(f32, u32)>] {
+        let start = y * self.size[0];
+        &self.cells[start..start + self.size[0]]
+    }
+
+    /// Every row, in order of increasing `y`, for parallel traversal.
+    fn par_rows(&self) -> ChunksExact<'_, Option<(f32, u32)>> {
+        self.cells.par_chunks_exact(self.size[0].max(1))
+    }
+
+    /// Every row, in order of increasing `y`, for parallel mutation.
+    fn par_rows_mut(&mut self) -> ChunksExactMut<'_, Option<(f32, u32)>> {
+        self.cells.par_chunks_exact_mut(self.size[0].max(1))
+    }
+
+    /// Consecutive groups of `rows_per_group` rows (one output pixel row
+    /// each, when `rows_per_group == n_max_plus_1`), for parallel mutation.
+    fn par_row_groups_mut(
+        &mut self,
+        rows_per_group: usize,
+    ) -> ChunksExactMut<'_, Option<(f32, u32)>> {
+        self.cells
+            .par_chunks_exact_mut((self.size[0] * rows_per_group).max(1))
+    }
 }
 
+impl Index<[usize; 2]> for FieldBuffer {
+    type Output = Option<(f32, u32)>;
+
+    #[inline]
+    fn index(&self, [x, y]: [usize; 2]) -> &Self::Output {
+        debug_assert!(x < self.size[0], "field x index {x} out of bounds");
+        &self.cells[y * self.size[0] + x]
+    }
+}
+
+impl IndexMut<[usize; 2]> for FieldBuffer {
+    #[inline]
+    fn index_mut(&mut self, [x, y]: [usize; 2]) -> &mut Self::Output {
+        debug_assert!(x < self.size[0], "field x index {x} out of bounds");
+        &mut self.cells[y * self.size[0] + x]
+    }
+}
+
+/// Progress / cancellation hook for the compute pass. Called with the
+/// fraction of field rows completed so far (in `(0, 1]`), possibly from
+/// several rayon workers at once. Returning `ControlFlow::Break(())` asks
+/// the traversal to stop; rows already in flight still finish.
+pub type ProgressCallback<'a> = dyn Fn(f32) -> ControlFlow<()> + Sync + 'a;
+
 /// Decomposes a field outer index back into the corresponding
 /// `(pixel_index, subpixel_index)` for a given sampling level, or `None`
 /// for positions the traversal should skip. Owns the modular arithmetic
@@ -145,50 +237,92 @@ impl SamplePlanner {
             }
         }
     }
+
+    /// The next outer index after the populated `outer_index` that the
+    /// traversal populates, i.e. its neighbor on the active sample grid.
+    /// May be past the end of the field.
+    #[inline]
+    pub fn next_populated(&self, outer_index: usize) -> usize {
+        match *self {
+            SamplePlanner::AntiAliasing {
+                n_max_plus_1,
+                subpixel_count,
+            } => {
+                let subpixel_index = outer_index % n_max_plus_1;
+                if subpixel_index + 1 < subpixel_count as usize {
+                    outer_index + 1
+                } else {
+                    outer_index - subpixel_index + n_max_plus_1
+                }
+            }
+            SamplePlanner::BlockFill {
+                n_max_plus_1,
+                block_size,
+            } => outer_index + n_max_plus_1 * block_size as usize,
+        }
+    }
 }
 
-/// Walk every populated cell of `field` in parallel by column. The closure
+/// Walk every populated cell of `field` in parallel by row. The closure
 /// receives a mutable reference to the cell plus its decomposed
 /// `(pixel_index, subpixel_index)` from the supplied planner.
 ///
-/// Used by [`compute_raw_field`]; the iteration shape is parallel columns,
-/// with rayon, then sequential row evaluation.
+/// Used by [`compute_raw_field`]; the iteration shape is parallel rows,
+/// with rayon, then sequential evaluation along the row. `progress` is
+/// invoked after each row with the fraction of rows visited; once it
+/// returns `Break`, rows that have not started yet are skipped and the walk
+/// returns `Break`.
 pub fn par_for_each_populated_cell_mut(
     planner: SamplePlanner,
-    field: &mut [Vec<Option<(f32, u32)>>],
+    field: &mut FieldBuffer,
+    progress: &ProgressCallback,
     visit: impl Fn(&mut Option<(f32, u32)>, [u32; 2], [u32; 2]) + Sync + Send,
-) {
-    field.par_iter_mut().enumerate().for_each(|(outer_x, col)| {
-        let Some((pixel_index_x, subpixel_index_x)) = planner.decompose(outer_x) else {
+) -> ControlFlow<()> {
+    let row_count = field.size()[1].max(1);
+    let completed_rows = AtomicUsize::new(0);
+    let cancelled = AtomicBool::new(false);
+    field.par_rows_mut().enumerate().for_each(|(outer_y, row)| {
+        if cancelled.load(Ordering::Relaxed) {
             return;
-        };
-        for (outer_y, cell) in col.iter_mut().enumerate() {
-            if let Some((pixel_index_y, subpixel_index_y)) = planner.decompose(outer_y) {
-                visit(
-                    cell,
-                    [pixel_index_x, pixel_index_y],
-                    [subpixel_index_x, subpixel_index_y],
-                );
+        }
+        if let Some((pixel_index_y, subpixel_index_y)) = planner.decompose(outer_y) {
+            for (outer_x, cell) in row.iter_mut().enumerate() {
+                if let Some((pixel_index_x, subpixel_index_x)) = planner.decompose(outer_x) {
+                    visit(
+                        cell,
+                        [pixel_index_x, pixel_index_y],
+                        [subpixel_index_x, subpixel_index_y],
+                    );
+                }
             }
         }
+        let completed = completed_rows.fetch_add(1, Ordering::Relaxed) + 1;
+        if progress(completed as f32 / row_count as f32).is_break() {
+            cancelled.store(true, Ordering::Relaxed);
+        }
     });
+    if cancelled.into_inner() {
+        ControlFlow::Break(())
+    } else {
+        ControlFlow::Continue(())
+    }
 }
 
 /// Read-only sibling of [`par_for_each_populated_cell_mut`]: walks every
-/// populated cell of `field` in parallel by column, passing the closure
-/// a shared reference to the cell plus the decomposed pixel/subpixel
+/// populated cell of `field` in parallel by row, passing the closure a
+/// shared reference to the cell plus the decomposed pixel/subpixel
 /// indices. Used by [`populate_histograms`].
 pub fn par_for_each_populated_cell(
     planner: SamplePlanner,
-    field: &[Vec<Option<(f32, u32)>>],
+    field: &FieldBuffer,
     visit: impl Fn(&Option<(f32, u32)>, [u32; 2], [u32; 2]) + Sync + Send,
 ) {
-    field.par_iter().enumerate().for_each(|(outer_x, col)| {
-        let Some((pixel_index_x, subpixel_index_x)) = planner.decompose(outer_x) else {
+    field.par_rows().enumerate().for_each(|(outer_y, row)| {
+        let Some((pixel_index_y, subpixel_index_y)) = planner.decompose(outer_y) else {
             return;
         };
-        for (outer_y, cell) in col.iter().enumerate() {
-            if let Some((pixel_index_y, subpixel_index_y)) = planner.decompose(outer_y) {
+        for (outer_x, cell) in row.iter().enumerate() {
+            if let Some((pixel_index_x, subpixel_index_x)) = planner.decompose(outer_x) {
                 visit(
                     cell,
                     [pixel_index_x, pixel_index_y],
@@ -216,28 +350,303 @@ pub fn par_for_each_populated_cell(
 ///
 /// Cells skipped by the planner are left untouched; the pipeline only
 /// reads the populated subset on subsequent passes.
+///
+/// `render_options` supplies the anti-aliasing refinements; its own
+/// `sampling_level` is only the cap the field was sized for, and the
+/// runtime `sampling_level` argument takes precedence:
+///
+/// - With an `edge_threshold` and anti-aliasing active, each pixel first
+///   samples its four corner subpixels; the rest of the subpixel grid is
+///   only evaluated where the corners disagree, and is interpolated from
+///   the corners elsewhere.
+/// - With `subpixel_jitter` and anti-aliasing active, each pixel's
+///   subpixel grid is shifted by [`subpixel_jitter`] of the pixel index.
 pub fn compute_raw_field<K: FieldKernel>(
     spec: &ImageSpecification,
     n_max_plus_1: usize,
     sampling_level: i32,
+    render_options: &RenderOptions,
     kernel: &K,
-    field: &mut [Vec<Option<(f32, u32)>>],
+    field: &mut FieldBuffer,
 ) {
+    // Without a cancelling callback the traversal always runs to completion.
+    let _ = compute_raw_field_with_progress(
+        spec,
+        n_max_plus_1,
+        sampling_level,
+        render_options,
+        kernel,
+        field,
+        &|_| ControlFlow::Continue(()),
+    );
+}
+
+/// Same as [`compute_raw_field`], but reports progress once per field
+/// row (the unit of parallel work) and stops early if `progress`
+/// returns `ControlFlow::Break`. Returns `Break` if the pass was
+/// cancelled, in which case the field is only partially refreshed.
+pub fn compute_raw_field_with_progress<K: FieldKernel>(
+    spec: &ImageSpecification,
+    n_max_plus_1: usize,
+    sampling_level: i32,
+    render_options: &RenderOptions,
+    kernel: &K,
+    field: &mut FieldBuffer,
+    progress: &ProgressCallback,
+) -> ControlFlow<()> {
     let planner = SamplePlanner::new(n_max_plus_1, sampling_level);
     let subpixel_count = planner.subpixel_count();
     let upsampled = PixelMapper::new(&spec.upsample(subpixel_count));
-    par_for_each_populated_cell_mut(planner, field, |cell, pixel_index, subpixel_index| {
-        let combined_x = pixel_index[0] * subpixel_count + subpixel_index[0];
-        let combined_y = pixel_index[1] * subpixel_count + subpixel_index[1];
-        let re = upsampled.width.map(combined_x);
-        let im = upsampled.height.map(combined_y);
-        *cell = kernel.evaluate([re, im]);
-    });
+    let jitter = render_options.subpixel_jitter && subpixel_count > 1;
+    let subpixel_step = [&upsampled.width, &upsampled.height].map(|map| map.map(1) - map.map(0));
+    let evaluate_at = |combined: [u32; 2]| {
+        let mut point = [
+            upsampled.width.map(combined[0]),
+            upsampled.height.map(combined[1]),
+        ];
+        if jitter {
+            let offset = subpixel_jitter(combined.map(|index| index / subpixel_count));
+            for ((coordinate, offset), step) in point.iter_mut().zip(offset).zip(subpixel_step) {
+                *coordinate += offset * step;
+            }
+        }
+        kernel.evaluate(point)
+    };
+    let coarse = match (kernel.coarse_prepass_stride(), planner) {
+        (Some(stride), SamplePlanner::AntiAliasing { .. }) if stride > 1 => {
+            Some(CoarseLattice::new(
+                [
+                    spec.resolution[0] * subpixel_count,
+                    spec.resolution[1] * subpixel_count,
+                ],
+                stride,
+                &evaluate_at,
+            ))
+        }
+        _ => None,
+    };
+    let sample = |combined: [u32; 2]| match coarse
+        .as_ref()
+        .and_then(|lattice| lattice.agreed_value(combined))
+    {
+        Some(value) => value,
+        None => evaluate_at(combined),
+    };
+    if let Some(edge_threshold) = render_options.edge_threshold
+        && subpixel_count > 1
+    {
+        return compute_edge_adaptive_field(
+            n_max_plus_1,
+            subpixel_count,
+            edge_threshold,
+            field,
+            progress,
+            sample,
+        );
+    }
+    par_for_each_populated_cell_mut(
+        planner,
+        field,
+        progress,
+        |cell, pixel_index, subpixel_index| {
+            *cell = sample([
+                pixel_index[0] * subpixel_count + subpixel_index[0],
+                pixel_index[1] * subpixel_count + subpixel_index[1],
+            ]);
+        },
+    )
+}
+
+/// Deterministic per-pixel shift of the subpixel grid for
+/// `RenderOptions::subpixel_jitter`, in units of the subpixel spacing,
+/// within `[-0.5, 0.5)` on each axis. A hash of the pixel index, so
+/// neighboring pixels sample at uncorrelated offsets but every render of
+/// the same view is identical.
+pub fn subpixel_jitter(pixel_index: [u32; 2]) -> [f64; 2] {
+    // SplitMix64 finalizer over the packed pixel index.
+    let mut hash = ((pixel_index[0] as u64) << 32) | pixel_index[1] as u64;
+    hash = hash.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    // One 32-bit half of the hash per axis.
+    [hash >> 32, hash & 0xffff_ffff].map(|bits| bits as f64 / (1u64 << 32) as f64 - 0.5)
+}
+
+/// Two-stage anti-aliasing pass, in parallel by output pixel row. Each
+/// pixel samples its four corner subpixels first. If [`corners_agree`],
+/// the remaining subpixels are bilinearly interpolated from the corners;
+/// otherwise the pixel straddles an edge and every subpixel is sampled.
+/// Progress is reported once per pixel row.
+fn compute_edge_adaptive_field(
+    n_max_plus_1: usize,
+    subpixel_count: u32,
+    edge_threshold: f32,
+    field: &mut FieldBuffer,
+    progress: &ProgressCallback,
+    sample: impl Fn([u32; 2]) -> Option<(f32, u32)> + Sync,
+) -> ControlFlow<()> {
+    let width = field.size()[0];
+    let pixel_width = width / n_max_plus_1;
+    let pixel_row_count = (field.size()[1] / n_max_plus_1).max(1);
+    let last = subpixel_count - 1;
+    let completed_rows = AtomicUsize::new(0);
+    let cancelled = AtomicBool::new(false);
+    field
+        .par_row_groups_mut(n_max_plus_1)
+        .enumerate()
+        .for_each(|(pixel_y, cells)| {
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            for pixel_x in 0..pixel_width {
+                let combined = |[subpixel_x, subpixel_y]: [u32; 2]| {
+                    [
+                        pixel_x as u32 * subpixel_count + subpixel_x,
+                        pixel_y as u32 * subpixel_count + subpixel_y,
+                    ]
+                };
+                let corners = [[0, 0], [last, 0], [0, last], [last, last]]
+                    .map(|subpixel| sample(combined(subpixel)));
+                let smooth = corners_agree(&corners, edge_threshold);
+                for subpixel_y in 0..subpixel_count {
+                    let row_start = subpixel_y as usize * width + pixel_x * n_max_plus_1;
+                    for subpixel_x in 0..subpixel_count {
+                        let weights = [subpixel_x, subpixel_y].map(|i| i as f32 / last as f32);
+                        let on_corner = (subpixel_x == 0 || subpixel_x == last)
+                            && (subpixel_y == 0 || subpixel_y == last);
+                        cells[row_start + subpixel_x as usize] = if smooth || on_corner {
+                            interpolate_corners(&corners, weights)
+                        } else {
+                            sample(combined([subpixel_x, subpixel_y]))
+                        };
+                    }
+                }
+            }
+            let completed = completed_rows.fetch_add(1, Ordering::Relaxed) + 1;
+            if progress(completed as f32 / pixel_row_count as f32).is_break() {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+        });
+    if cancelled.into_inner() {
+        ControlFlow::Break(())
+    } else {
+        ControlFlow::Continue(())
+    }
+}
+
+/// True if the four corner samples of a pixel describe a smooth patch:
+/// all unset, or all set on the same color map with values spanning at
+/// most `edge_threshold`.
+fn corners_agree(corners: &[Option<(f32, u32)>; 4], edge_threshold: f32) -> bool {
+    match corners {
+        [None, None, None, None] => true,
+        [Some((_, index)), ..] => {
+            let mut range = [f32::INFINITY, f32::NEG_INFINITY];
+            let same_map = corners.iter().all(|corner| match corner {
+                Some((value, other_index)) => {
+                    range = [range[0].min(*value), range[1].max(*value)];
+                    other_index == index
+                }
+                None => false,
+            });
+            same_map && range[1] - range[0] <= edge_threshold
+        }
+        _ => false,
+    }
+}
+
+/// Bilinear interpolation between the corner samples `[top-left,
+/// top-right, bottom-left, bottom-right]` at fractional position
+/// `[x, y] ∈ [0, 1]²`. Exact at the corners; between them it assumes the
+/// corners agree (see [`corners_agree`]), so they share one color map.
+fn interpolate_corners(corners: &[Option<(f32, u32)>; 4], [x, y]: [f32; 2]) -> Option<(f32, u32)> {
+    let weights = [(1.0 - x) * (1.0 - y), x * (1.0 - y), (1.0 - x) * y, x * y];
+    if let Some(corner) = weights.iter().position(|&weight| weight == 1.0) {
+        return corners[corner];
+    }
+    corners[0].map(|(_, index)| {
+        let value = corners
+            .iter()
+            .zip(weights)
+            .filter_map(|(corner, weight)| corner.map(|(value, _)| weight * value))
+            .sum();
+        (value, index)
+    })
+}
+
+/// Coarse pre-pass for [`FieldKernel::coarse_prepass_stride`]: kernel
+/// values at every `stride`-th sample along each axis of the combined
+/// (pixel × subpixel) sample grid, plus the last sample so every fine
+/// sample is enclosed by four lattice corners.
+struct CoarseLattice {
+    stride: u32,
+    /// Sample positions of the lattice lines along each axis.
+    positions: [Vec<u32>; 2],
+    /// `values[i][j]` is the kernel value at `(positions[0][i], positions[1][j])`.
+    values: Vec<Vec<Option<(f32, u32)>>>,
+}
+
+impl CoarseLattice {
+    fn new(
+        sample_counts: [u32; 2],
+        stride: u32,
+        evaluate_at: &(impl Fn([u32; 2]) -> Option<(f32, u32)> + Sync),
+    ) -> Self {
+        let positions = sample_counts.map(|count| {
+            let last = count.saturating_sub(1);
+            let mut positions: Vec<u32> = (0..=last).step_by(stride as usize).collect();
+            if positions.last() != Some(&last) {
+                positions.push(last);
+            }
+            positions
+        });
+        let values = positions[0]
+            .par_iter()
+            .map(|&x| positions[1].iter().map(|&y| evaluate_at([x, y])).collect())
+            .collect();
+        CoarseLattice {
+            stride,
+            positions,
+            values,
+        }
+    }
+
+    /// The value shared by the lattice points around `sample`, or `None` if
+    /// they disagree and the sample must be evaluated directly. The
+    /// neighborhood is the enclosing lattice cell plus a one-cell guard band
+    /// on every side, so thin features that cross a nearby lattice line
+    /// still force a direct evaluation. Samples that lie on the lattice
+    /// return their own coarse value.
+    fn agreed_value(&self, sample: [u32; 2]) -> Option<Option<(f32, u32)>> {
+        let [ix, iy] = [0, 1].map(|dim| {
+            let index = (sample[dim] / self.stride) as usize;
+            index.min(self.positions[dim].len() - 1)
+        });
+        if self.positions[0][ix] == sample[0] && self.positions[1][iy] == sample[1] {
+            return Some(self.values[ix][iy]);
+        }
+        let [x_range, y_range] = [(0, ix), (1, iy)].map(|(dim, index)| {
+            index.saturating_sub(1)..=(index + 2).min(self.positions[dim].len() - 1)
+        });
+        let corner = self.values[ix][iy];
+        self.values[x_range]
+            .iter()
+            .all(|column| column[y_range.clone()].iter().all(|&other| other == corner))
+            .then_some(corner)
+    }
 }
 
 /// Walk every populated cell of `field` and insert each
 /// `Some((value, color_map_index))` into
-/// `histograms[color_map_index % histograms.len()]`.
+/// `histograms[color_map_index % histograms.len()]`. Cells routed to
+/// `OVERLAY_COLOR_MAP_INDEX` are painted in a fixed color and are skipped.
+///
+/// At positive `sampling_level` this bins every anti-aliasing subpixel that
+/// `colorize_collapse_unified` later averages (jittered or interpolated
+/// from the corners alike), so the histogram already matches the rendered
+/// distribution at high-contrast boundaries without a separate sampling
+/// pass.
 ///
 /// Callers reset the histograms first (typically via
 /// `ColorPaletteCache::reset_histograms`); this function only accumulates,
@@ -248,7 +657,7 @@ pub fn compute_raw_field<K: FieldKernel>(
 pub fn populate_histograms(
     n_max_plus_1: usize,
     sampling_level: i32,
-    field: &[Vec<Option<(f32, u32)>>],
+    field: &FieldBuffer,
     histograms: &mut [Histogram],
 ) {
     let histogram_count = histograms.len();
@@ -256,55 +665,284 @@ pub fn populate_histograms(
     let histograms_ref: &[Histogram] = histograms;
     let planner = SamplePlanner::new(n_max_plus_1, sampling_level);
     par_for_each_populated_cell(planner, field, |cell, _pixel_index, _subpixel_index| {
-        if let Some((value, color_map_index)) = cell {
+        if let Some((value, color_map_index)) = cell
+            && *color_map_index != OVERLAY_COLOR_MAP_INDEX
+        {
             let index = (*color_map_index as usize) % histogram_count;
             histograms_ref[index].insert(*value);
         }
     });
 }
 
+/// Draw isolines of the integer part of the field values: every populated
+/// cell whose value has a different integer part than its right or lower
+/// neighbor on the active sample grid, with both routed through the same
+/// color map, is cleared to `None`. The lines are one sample thick and are
+/// colorized in the background color. Cells are compared across color maps
+/// only by their index, so basin boundaries and the overlay cells do not
+/// produce lines.
+///
+/// `on_isoline` is scratch space for the line mask, reused across frames.
+pub fn mark_isolines(
+    n_max_plus_1: usize,
+    sampling_level: i32,
+    field: &mut FieldBuffer,
+    on_isoline: &mut Vec<bool>,
+) {
+    let planner = SamplePlanner::new(n_max_plus_1, sampling_level);
+    let [width, height] = field.size();
+    on_isoline.clear();
+    on_isoline.resize(width * height, false);
+    let crosses_isoline =
+        |cell: &Option<(f32, u32)>, neighbor: &Option<(f32, u32)>| match (cell, neighbor) {
+            (Some((value, index)), Some((neighbor_value, neighbor_index))) => {
+                index == neighbor_index && value.floor() != neighbor_value.floor()
+            }
+            _ => false,
+        };
+
+    let field_ref: &FieldBuffer = field;
+    on_isoline
+        .par_chunks_exact_mut(width.max(1))
+        .enumerate()
+        .for_each(|(y, mask_row)| {
+            if planner.decompose(y).is_none() {
+                return;
+            }
+            let next_y = planner.next_populated(y);
+            for (x, on_isoline) in mask_row.iter_mut().enumerate() {
+                if planner.decompose(x).is_none() {
+                    continue;
+                }
+                let cell = &field_ref[[x, y]];
+                let next_x = planner.next_populated(x);
+                *on_isoline = (next_x < width && crosses_isoline(cell, &field_ref[[next_x, y]]))
+                    || (next_y < height && crosses_isoline(cell, &field_ref[[x, next_y]]));
+            }
+        });
+
+    field
+        .par_rows_mut()
+        .zip(on_isoline.par_chunks_exact(width.max(1)))
+        .for_each(|(row, mask_row)| {
+            for (cell, &on_isoline) in row.iter_mut().zip(mask_row) {
+                if on_isoline {
+                    *cell = None;
+                }
+            }
+        });
+}
+
+/// Fraction of the cells populated at `sampling_level` that hold a value
+/// rather than `None`; `0.0` if no cells are populated.
+pub fn valued_cell_fraction(n_max_plus_1: usize, sampling_level: i32, field: &FieldBuffer) -> f64 {
+    let planner = SamplePlanner::new(n_max_plus_1, sampling_level);
+    let populated = AtomicUsize::new(0);
+    let valued = AtomicUsize::new(0);
+    par_for_each_populated_cell(planner, field, |cell, _, _| {
+        populated.fetch_add(1, Ordering::Relaxed);
+        if cell.is_some() {
+            valued.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let populated = populated.into_inner();
+    if populated == 0 {
+        0.0
+    } else {
+        valued.into_inner() as f64 / populated as f64
+    }
+}
+
+/// How the cells populated by one render are spread over the color maps.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CellCounts {
+    /// Cells routed to each color map, indexed modulo the number of maps
+    /// as in [`populate_histograms`].
+    pub by_color_map: Vec<u32>,
+    /// Cells routed to `OVERLAY_COLOR_MAP_INDEX`.
+    pub overlay: u32,
+    /// Cells that hold `None`.
+    pub empty: u32,
+}
+
+impl CellCounts {
+    fn new(color_map_count: usize) -> Self {
+        CellCounts {
+            by_color_map: vec![0; color_map_count],
+            ..CellCounts::default()
+        }
+    }
+
+    fn add(mut self, cell: &Option<(f32, u32)>) -> Self {
+        match *cell {
+            None => self.empty += 1,
+            Some((_, OVERLAY_COLOR_MAP_INDEX)) => self.overlay += 1,
+            Some((_, index)) => {
+                let count = self.by_color_map.len();
+                self.by_color_map[index as usize % count] += 1;
+            }
+        }
+        self
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for (count, other_count) in self.by_color_map.iter_mut().zip(other.by_color_map) {
+            *count += other_count;
+        }
+        self.overlay += other.overlay;
+        self.empty += other.empty;
+        self
+    }
+
+    /// Every counted cell.
+    pub fn total(&self) -> u32 {
+        self.by_color_map.iter().sum::<u32>() + self.overlay + self.empty
+    }
+}
+
+/// Count the cells populated at `sampling_level` by color map, overlay, and
+/// `None`. `color_map_count` must be positive.
+pub fn count_cells(
+    n_max_plus_1: usize,
+    sampling_level: i32,
+    field: &FieldBuffer,
+    color_map_count: usize,
+) -> CellCounts {
+    assert!(color_map_count > 0, "color_map_count must be positive");
+    let planner = SamplePlanner::new(n_max_plus_1, sampling_level);
+    field
+        .par_rows()
+        .enumerate()
+        .filter(|(outer_y, _)| planner.decompose(*outer_y).is_some())
+        .fold(
+            || CellCounts::new(color_map_count),
+            |counts, (_, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(outer_x, _)| planner.decompose(*outer_x).is_some())
+                    .fold(counts, |counts, (_, cell)| counts.add(cell))
+            },
+        )
+        .reduce(|| CellCounts::new(color_map_count), CellCounts::merge)
+}
+
+/// Per-channel accumulator for the box-filter average: the sum of one
+/// `u8` channel over every subpixel of an output pixel. Wide enough that
+/// no sampling level the planner can build overflows it.
+type SubpixelSum = u64;
+
+/// 4×4 Bayer matrix: the order in which the pixels of each 4×4 tile round
+/// up, for ordered dithering.
+const BAYER_MATRIX: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Quantize a fractional color to 8 bits, rounding each channel up when its
+/// fraction exceeds the pixel's ordered-dither threshold. Over each 4×4 tile
+/// the thresholds are spread evenly over `(0, 1)`, so a flat fractional
+/// color averages back to itself.
+#[inline]
+fn quantize_dithered(rgb: [f32; 3], [pixel_x, pixel_y]: [usize; 2]) -> [u8; 3] {
+    let threshold = (BAYER_MATRIX[pixel_y % 4][pixel_x % 4] as f32 + 0.5) / 16.0;
+    rgb.map(|channel| (channel + threshold).floor().clamp(0.0, 255.0) as u8)
+}
+
 /// Walk the row-major output `egui::ColorImage`, collapsing field cells
 /// into output pixels via the unified `ColorPaletteCache`.
 ///
 /// - **Positive `sampling_level = subpixel_count - 1`**: each output pixel
 ///   `(px, py)` averages the `subpixel_count²` cells at
-///   `field[px·n_max_plus_1 + i][py·n_max_plus_1 + j]` for
+///   `field[[px·n_max_plus_1 + i, py·n_max_plus_1 + j]]` for
 ///   `i, j ∈ 0..subpixel_count`.
 /// - **`sampling_level == 0`**: one cell per output pixel (the top-left
 ///   of each block).
 /// - **Negative `sampling_level = -(block_size - 1)`**: block-fill
-///   (nearest-neighbor). Every `block_size²` output-pixel block reads one
-///   field cell.
+///   (nearest-neighbor). Every `block_size²` output-pixel block reads and
+///   colorizes one field cell.
+///
+/// The positive case averages with `downscale_filter`. The box filter is
+/// the plain average above; the others go through
+/// `colorize_collapse_filtered`, which keeps its intermediate rows in
+/// `scratch` so the buffer can be reused across frames.
+///
+/// When the cache dithers (`ColorPaletteCache::set_dither`), the
+/// non-negative levels average fractional colors from
+/// `colorize_cell_smooth` and quantize them with an ordered-dither
+/// threshold per output pixel instead of truncating or rounding.
 ///
 /// CDF percentile lookup happens inside `colorize_cell`; the field stays
 /// raw end-to-end. Per-pixel allocations: zero.
 pub fn colorize_collapse_unified(
     cache: &ColorPaletteCache,
-    field: &[Vec<Option<(f32, u32)>>],
+    field: &FieldBuffer,
     n_max_plus_1: usize,
     sampling_level: i32,
+    downscale_filter: DownscaleFilter,
+    scratch: &mut Vec<[f32; 3]>,
     out: &mut ColorImage,
 ) {
     let output_width = out.size[0];
 
-    if sampling_level >= 0 {
+    if sampling_level > 0 && downscale_filter != DownscaleFilter::Box {
+        colorize_collapse_filtered(
+            cache,
+            field,
+            n_max_plus_1,
+            sampling_level as usize + 1,
+            downscale_filter,
+            scratch,
+            out,
+        );
+    } else if sampling_level >= 0 && cache.dither() {
         let subpixel_count = sampling_level as usize + 1;
-        let cells_per_pixel = (subpixel_count * subpixel_count) as u32;
+        let cells_per_pixel = (subpixel_count * subpixel_count) as f32;
         out.pixels
             .par_chunks_exact_mut(output_width)
             .enumerate()
             .for_each(|(pixel_index_y, row)| {
                 for (pixel_index_x, pixel) in row.iter_mut().enumerate() {
-                    let mut sum = [0u32; 3];
-                    for subpixel_index_x in 0..subpixel_count {
-                        let cell_x = pixel_index_x * n_max_plus_1 + subpixel_index_x;
-                        let col = &field[cell_x];
-                        for subpixel_index_y in 0..subpixel_count {
-                            let cell_y = pixel_index_y * n_max_plus_1 + subpixel_index_y;
-                            let rgb = colorize_cell(cache, col[cell_y]);
-                            sum[0] += rgb[0] as u32;
-                            sum[1] += rgb[1] as u32;
-                            sum[2] += rgb[2] as u32;
+                    let mut sum = [0.0f32; 3];
+                    for subpixel_index_y in 0..subpixel_count {
+                        let cell_y = pixel_index_y * n_max_plus_1 + subpixel_index_y;
+                        let field_row = field.row(cell_y);
+                        for subpixel_index_x in 0..subpixel_count {
+                            let cell_x = pixel_index_x * n_max_plus_1 + subpixel_index_x;
+                            let rgb = colorize_cell_smooth(cache, field_row[cell_x]);
+                            for (channel, value) in sum.iter_mut().zip(rgb) {
+                                *channel += value;
+                            }
+                        }
+                    }
+                    let rgb = quantize_dithered(
+                        sum.map(|channel| channel / cells_per_pixel),
+                        [pixel_index_x, pixel_index_y],
+                    );
+                    *pixel = Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
+                }
+            });
+    } else if sampling_level >= 0 {
+        let subpixel_count = sampling_level as usize + 1;
+        // `usize` is at most 64 bits wide, so this cast is lossless.
+        let cells_per_pixel = (subpixel_count * subpixel_count) as SubpixelSum;
+        debug_assert!(
+            cells_per_pixel
+                .checked_mul(SubpixelSum::from(u8::MAX))
+                .is_some(),
+            "{cells_per_pixel} subpixels per pixel can overflow the channel sums"
+        );
+        out.pixels
+            .par_chunks_exact_mut(output_width)
+            .enumerate()
+            .for_each(|(pixel_index_y, row)| {
+                for (pixel_index_x, pixel) in row.iter_mut().enumerate() {
+                    let mut sum: [SubpixelSum; 3] = [0; 3];
+                    for subpixel_index_y in 0..subpixel_count {
+                        let cell_y = pixel_index_y * n_max_plus_1 + subpixel_index_y;
+                        let field_row = field.row(cell_y);
+                        for subpixel_index_x in 0..subpixel_count {
+                            let cell_x = pixel_index_x * n_max_plus_1 + subpixel_index_x;
+                            let rgb = colorize_cell(cache, field_row[cell_x]);
+                            for (channel, value) in sum.iter_mut().zip(rgb) {
+                                *channel += SubpixelSum::from(value);
+                            }
                         }
                     }
                     *pixel = Color32::from_rgb(
@@ -315,27 +953,147 @@ pub fn colorize_collapse_unified(
                 }
             });
     } else {
+        // Each parallel work item is one row of blocks: colorize the first
+        // pixel row, one cell per block, then copy it down the block.
         let block_size = (-sampling_level) as usize + 1;
         out.pixels
-            .par_chunks_exact_mut(output_width)
+            .par_chunks_mut(output_width * block_size)
             .enumerate()
-            .for_each(|(pixel_index_y, row)| {
-                let block_y = pixel_index_y / block_size;
+            .for_each(|(block_y, block_rows)| {
                 let cell_y = block_y * block_size * n_max_plus_1;
-                for (pixel_index_x, pixel) in row.iter_mut().enumerate() {
-                    let block_x = pixel_index_x / block_size;
+                let (first_row, other_rows) = block_rows.split_at_mut(output_width);
+                for (block_x, pixels) in first_row.chunks_mut(block_size).enumerate() {
                     let cell_x = block_x * block_size * n_max_plus_1;
-                    let rgb = colorize_cell(cache, field[cell_x][cell_y]);
-                    *pixel = Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
+                    let rgb = colorize_cell(cache, field[[cell_x, cell_y]]);
+                    pixels.fill(Color32::from_rgb(rgb[0], rgb[1], rgb[2]));
+                }
+                for row in other_rows.chunks_exact_mut(output_width) {
+                    row.copy_from_slice(first_row);
                 }
             });
     }
 }
 
+/// Separable weighted collapse of the anti-aliasing subpixel grid. Each
+/// subpixel is weighted by `filter` at its distance from the output pixel
+/// center, measured in output pixels, so tent and Lanczos filters also draw
+/// on the neighboring pixels' subpixels. The first pass colorizes each
+/// active field row once and filters it horizontally into `scratch` (one
+/// `[f32; 3]` per output column per subpixel row); the second filters those
+/// columns vertically. Taps that fall outside the image are dropped and the
+/// remaining weights renormalized.
+fn colorize_collapse_filtered(
+    cache: &ColorPaletteCache,
+    field: &FieldBuffer,
+    n_max_plus_1: usize,
+    subpixel_count: usize,
+    filter: DownscaleFilter,
+    scratch: &mut Vec<[f32; 3]>,
+    out: &mut ColorImage,
+) {
+    let [width, height] = out.size;
+    // Offsets of the contributing samples from the first subpixel of a
+    // pixel, along one axis, with their weights. The same for every pixel.
+    let reach = (filter.radius() * subpixel_count as f32).ceil() as isize;
+    let center = 0.5 * (subpixel_count - 1) as f32;
+    let taps: Vec<(isize, f32)> = (-reach..subpixel_count as isize + reach)
+        .map(|offset| {
+            let distance = (offset as f32 - center) / subpixel_count as f32;
+            (offset, filter.weight(distance))
+        })
+        .filter(|&(_, weight)| weight != 0.0)
+        .collect();
+    let taps = &taps[..];
+    // Field cell of a sample along one axis of the combined
+    // `pixel · subpixel_count + subpixel` grid.
+    let cell_index =
+        |sample: usize| (sample / subpixel_count) * n_max_plus_1 + sample % subpixel_count;
+    let colorize = |cell: Option<(f32, u32)>| {
+        if cache.dither() {
+            colorize_cell_smooth(cache, cell)
+        } else {
+            colorize_cell(cache, cell).map(f32::from)
+        }
+    };
+
+    let samples_per_row = width * subpixel_count;
+    scratch.clear();
+    scratch.resize(width * height * subpixel_count, [0.0; 3]);
+    scratch
+        .par_chunks_exact_mut(width)
+        .enumerate()
+        .for_each_init(
+            || Vec::with_capacity(samples_per_row),
+            |row_colors, (sample_y, filtered_row)| {
+                let field_row = field.row(cell_index(sample_y));
+                row_colors.clear();
+                row_colors.extend(
+                    (0..samples_per_row).map(|sample_x| colorize(field_row[cell_index(sample_x)])),
+                );
+                for (pixel_x, filtered) in filtered_row.iter_mut().enumerate() {
+                    *filtered =
+                        apply_taps(taps, subpixel_count, pixel_x, samples_per_row, |sample_x| {
+                            row_colors[sample_x]
+                        });
+                }
+            },
+        );
+    let scratch = &*scratch;
+    out.pixels
+        .par_chunks_exact_mut(width)
+        .enumerate()
+        .for_each(|(pixel_y, row)| {
+            for (pixel_x, pixel) in row.iter_mut().enumerate() {
+                let filtered = apply_taps(
+                    taps,
+                    subpixel_count,
+                    pixel_y,
+                    height * subpixel_count,
+                    |sample_y| scratch[sample_y * width + pixel_x],
+                );
+                let rgb = if cache.dither() {
+                    quantize_dithered(filtered, [pixel_x, pixel_y])
+                } else {
+                    filtered.map(|channel| channel.round().clamp(0.0, 255.0) as u8)
+                };
+                *pixel = Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
+            }
+        });
+}
+
+/// Weighted average of the samples around `pixel` along one axis, where
+/// `value_at` reads one of the `sample_count` samples on that axis. Taps
+/// outside the image are dropped and the remaining weights renormalized.
+#[inline]
+fn apply_taps(
+    taps: &[(isize, f32)],
+    subpixel_count: usize,
+    pixel: usize,
+    sample_count: usize,
+    value_at: impl Fn(usize) -> [f32; 3],
+) -> [f32; 3] {
+    let first = (pixel * subpixel_count) as isize;
+    let mut sum = [0.0f32; 3];
+    let mut total_weight = 0.0;
+    for &(offset, weight) in taps {
+        let Ok(sample) = usize::try_from(first + offset) else {
+            continue;
+        };
+        if sample >= sample_count {
+            continue;
+        }
+        for (channel, value) in sum.iter_mut().zip(value_at(sample)) {
+            *channel += weight * value;
+        }
+        total_weight += weight;
+    }
+    sum.map(|channel| channel / total_weight)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::color_map::{ColorMapKeyFrame, ColorPalette};
+    use crate::core::color_map::{ColorMapKeyFrame, ColorPalette, ScalarNormalization};
 
     /// Build a minimal `ColorPaletteCache` whose CDFs are pre-shaped so
     /// that percentile lookups land predictably on the color-map endpoints:
@@ -343,12 +1101,12 @@ mod tests {
     /// (low keyframe) and any value in the rightmost bin map to 1.0 (high
     /// keyframe).
     fn cache_with_unit_distribution(palette: &ColorPalette) -> ColorPaletteCache {
-        let mut cache = palette.create_cache(4, 1.0, 256);
+        let mut cache = palette.create_cache(4, 1.0, 256).unwrap();
         cache.reset_histograms();
         for histogram in &cache.histograms {
             histogram.insert(0.5);
         }
-        cache.refresh_after_compute_pass(palette);
+        cache.refresh_after_compute_pass(palette).unwrap();
         cache
     }
 
@@ -378,26 +1136,34 @@ mod tests {
         let palette = red_to_blue_palette();
         let cache = cache_with_unit_distribution(&palette);
 
-        let mut field: Vec<Vec<Option<(f32, u32)>>> = vec![vec![None; 4]; 4];
+        let mut field = FieldBuffer::new([4, 4]);
         // Top-left block (px=0, py=0) ← all Some((0.0, 0)) → red.
-        field[0][0] = Some((0.0, 0));
-        field[0][1] = Some((0.0, 0));
-        field[1][0] = Some((0.0, 0));
-        field[1][1] = Some((0.0, 0));
+        field[[0, 0]] = Some((0.0, 0));
+        field[[0, 1]] = Some((0.0, 0));
+        field[[1, 0]] = Some((0.0, 0));
+        field[[1, 1]] = Some((0.0, 0));
         // Top-right block (px=1, py=0) ← all None → background color.
         // Bottom-left block (px=0, py=1) ← 2 Some((0.0, 0)) + 2 None.
-        field[0][2] = Some((0.0, 0));
-        field[0][3] = None;
-        field[1][2] = Some((0.0, 0));
-        field[1][3] = None;
+        field[[0, 2]] = Some((0.0, 0));
+        field[[0, 3]] = None;
+        field[[1, 2]] = Some((0.0, 0));
+        field[[1, 3]] = None;
         // Bottom-right block (px=1, py=1) ← all Some((1.0, 0)) → blue.
-        field[2][2] = Some((1.0, 0));
-        field[2][3] = Some((1.0, 0));
-        field[3][2] = Some((1.0, 0));
-        field[3][3] = Some((1.0, 0));
+        field[[2, 2]] = Some((1.0, 0));
+        field[[2, 3]] = Some((1.0, 0));
+        field[[3, 2]] = Some((1.0, 0));
+        field[[3, 3]] = Some((1.0, 0));
 
         let mut out = ColorImage::filled([2, 2], Color32::BLACK);
-        colorize_collapse_unified(&cache, &field, 2, 1, &mut out);
+        colorize_collapse_unified(
+            &cache,
+            &field,
+            2,
+            1,
+            DownscaleFilter::Box,
+            &mut Vec::new(),
+            &mut out,
+        );
 
         let pixel_at = |px: usize, py: usize| out.pixels[py * 2 + px];
         assert_eq!(pixel_at(0, 0), Color32::from_rgb(255, 0, 0));
@@ -411,17 +1177,84 @@ mod tests {
 
     /// `subpixel_count = 1` (sampling_level = 0): one cell per output
     /// pixel, no averaging.
+    /// A flat color between two 8-bit levels is spread over each 4×4 tile
+    /// so that the tile averages back to it, instead of being truncated.
+    #[test]
+    fn colorize_collapse_unified_dithers_fractional_colors() {
+        let palette = red_to_blue_palette();
+        // Three entries: red channel 255, 127, 0 at queries 0, 0.5, 1.
+        let mut cache = palette.create_cache(4, 1.0, 3).unwrap();
+        cache.set_normalization(ScalarNormalization::Linear);
+        cache.refresh_after_compute_pass(&palette).unwrap();
+        // Query 0.1 sits a fifth of the way from 255 to 127: red 229.4.
+        let mut field = FieldBuffer::new([8, 8]);
+        for y in 0..8 {
+            for x in 0..8 {
+                field[[x, y]] = Some((0.1, 0));
+            }
+        }
+        let render = |cache: &ColorPaletteCache, filter| {
+            let mut out = ColorImage::filled([4, 4], Color32::BLACK);
+            colorize_collapse_unified(cache, &field, 2, 1, filter, &mut Vec::new(), &mut out);
+            out
+        };
+
+        let truncated = render(&cache, DownscaleFilter::Box);
+        assert!(truncated.pixels.iter().all(|p| p.r() == 255));
+
+        cache.set_dither(true);
+        for filter in [DownscaleFilter::Box, DownscaleFilter::Tent] {
+            let dithered = render(&cache, filter);
+            let reds: Vec<u8> = dithered.pixels.iter().map(|p| p.r()).collect();
+            assert!(reds.iter().all(|&r| r == 229 || r == 230), "{reds:?}");
+            let mean = reds.iter().map(|&r| r as f32).sum::<f32>() / 16.0;
+            assert!((mean - 229.4).abs() <= 1.0 / 16.0, "{mean}");
+        }
+
+        // Exact 8-bit colors (and the background) are left alone.
+        for y in 0..8 {
+            for x in 0..8 {
+                field[[x, y]] = Some((0.0, 0));
+            }
+        }
+        field[[0, 0]] = None;
+        let mut out = ColorImage::filled([8, 8], Color32::BLACK);
+        colorize_collapse_unified(
+            &cache,
+            &field,
+            1,
+            0,
+            DownscaleFilter::Box,
+            &mut Vec::new(),
+            &mut out,
+        );
+        assert_eq!(out.pixels[0], Color32::from_rgb(9, 9, 9));
+        assert!(
+            out.pixels[1..]
+                .iter()
+                .all(|&p| p == Color32::from_rgb(255, 0, 0))
+        );
+    }
+
     #[test]
     fn colorize_collapse_unified_no_aa_one_cell_per_pixel() {
         let palette = red_to_blue_palette();
         let cache = cache_with_unit_distribution(&palette);
 
-        let mut field: Vec<Vec<Option<(f32, u32)>>> = vec![vec![None; 2]; 2];
-        field[0][0] = Some((0.0, 0));
-        field[1][1] = Some((0.0, 0));
+        let mut field = FieldBuffer::new([2, 2]);
+        field[[0, 0]] = Some((0.0, 0));
+        field[[1, 1]] = Some((0.0, 0));
 
         let mut out = ColorImage::filled([2, 2], Color32::BLACK);
-        colorize_collapse_unified(&cache, &field, 1, 0, &mut out);
+        colorize_collapse_unified(
+            &cache,
+            &field,
+            1,
+            0,
+            DownscaleFilter::Box,
+            &mut Vec::new(),
+            &mut out,
+        );
 
         assert_eq!(out.pixels[0], Color32::from_rgb(255, 0, 0)); // (0,0)
         assert_eq!(out.pixels[1], Color32::from_rgb(9, 9, 9)); // (1,0) None
@@ -429,6 +1262,145 @@ mod tests {
         assert_eq!(out.pixels[3], Color32::from_rgb(255, 0, 0)); // (1,1)
     }
 
+    /// The block-row fill must match a plain per-pixel nearest-neighbor
+    /// lookup, including the partial blocks along the right and bottom
+    /// edges when the image size is not a multiple of the block size.
+    #[test]
+    fn colorize_collapse_unified_block_fill_matches_per_pixel_lookup() {
+        let palette = red_to_blue_palette();
+        let cache = cache_with_unit_distribution(&palette);
+        let [width, height] = [8, 7];
+        let block_size = 3;
+
+        let mut field = FieldBuffer::new([width, height]);
+        for y in (0..height).step_by(block_size) {
+            for x in (0..width).step_by(block_size) {
+                field[[x, y]] = match (x + 2 * y) % 3 {
+                    0 => None,
+                    1 => Some((0.0, 0)),
+                    _ => Some((1.0, 0)),
+                };
+            }
+        }
+
+        let mut out = ColorImage::filled([width, height], Color32::BLACK);
+        colorize_collapse_unified(
+            &cache,
+            &field,
+            1,
+            1 - block_size as i32,
+            DownscaleFilter::Box,
+            &mut Vec::new(),
+            &mut out,
+        );
+
+        for y in 0..height {
+            for x in 0..width {
+                let cell = field[[x / block_size * block_size, y / block_size * block_size]];
+                let rgb = colorize_cell(&cache, cell);
+                assert_eq!(
+                    out.pixels[y * width + x],
+                    Color32::from_rgb(rgb[0], rgb[1], rgb[2]),
+                    "({x},{y})"
+                );
+            }
+        }
+    }
+
+    /// The largest anti-aliasing grids must average saturated channels
+    /// without wrapping the per-channel sums.
+    #[test]
+    fn colorize_collapse_unified_averages_extreme_subpixel_counts() {
+        let palette = red_to_blue_palette();
+        let cache = cache_with_unit_distribution(&palette);
+        for subpixel_count in [13, 64] {
+            let mut field = FieldBuffer::new([2 * subpixel_count, subpixel_count]);
+            for y in 0..subpixel_count {
+                for x in 0..subpixel_count {
+                    field[[x, y]] = Some((1.0, 0));
+                    field[[subpixel_count + x, y]] = Some((0.0, 0));
+                }
+            }
+            let mut out = ColorImage::filled([2, 1], Color32::BLACK);
+            colorize_collapse_unified(
+                &cache,
+                &field,
+                subpixel_count,
+                subpixel_count as i32 - 1,
+                DownscaleFilter::Box,
+                &mut Vec::new(),
+                &mut out,
+            );
+            assert_eq!(out.pixels[0], Color32::from_rgb(0, 0, 255));
+            assert_eq!(out.pixels[1], Color32::from_rgb(255, 0, 0));
+        }
+    }
+
+    /// On a flat field every filter reproduces the box average; across a
+    /// vertical red/blue edge the tent and Lanczos filters pull the pixels
+    /// next to the edge toward their own side's color, the box filter
+    /// does not see past the pixel at all.
+    #[test]
+    fn colorize_collapse_unified_downscale_filters() {
+        let palette = red_to_blue_palette();
+        let cache = cache_with_unit_distribution(&palette);
+        let (n_max_plus_1, sampling_level) = (3, 2);
+        let [width, height] = [4, 2];
+        let render = |field: &FieldBuffer, filter| {
+            let mut out = ColorImage::filled([width, height], Color32::BLACK);
+            colorize_collapse_unified(
+                &cache,
+                field,
+                n_max_plus_1,
+                sampling_level,
+                filter,
+                &mut Vec::new(),
+                &mut out,
+            );
+            out.pixels
+        };
+        let filters = [
+            DownscaleFilter::Box,
+            DownscaleFilter::Tent,
+            DownscaleFilter::Lanczos,
+        ];
+
+        let size = [width * n_max_plus_1, height * n_max_plus_1];
+        let mut flat = FieldBuffer::new(size);
+        let mut edge = FieldBuffer::new(size);
+        for y in 0..size[1] {
+            for x in 0..size[0] {
+                flat[[x, y]] = Some((1.0, 0));
+                // Left half red, right half blue: pixels 0-1 red, 2-3 blue.
+                let value = if x < 2 * n_max_plus_1 { 0.0 } else { 1.0 };
+                edge[[x, y]] = Some((value, 0));
+            }
+        }
+        for filter in filters {
+            assert!(
+                render(&flat, filter)
+                    .iter()
+                    .all(|&pixel| pixel == Color32::from_rgb(0, 0, 255)),
+                "{filter:?}"
+            );
+        }
+
+        let [boxed, tent, lanczos] = filters.map(|filter| render(&edge, filter));
+        assert_eq!(boxed[1], Color32::from_rgb(255, 0, 0));
+        assert_eq!(boxed[2], Color32::from_rgb(0, 0, 255));
+        for pixels in [&tent, &lanczos] {
+            // Pixels touching the edge blend; the far columns stay pure.
+            assert!(pixels[1].b() > 0 && pixels[1].r() > pixels[1].b());
+            assert!(pixels[2].r() > 0 && pixels[2].b() > pixels[2].r());
+            assert_eq!(pixels[1].r(), pixels[2].b());
+            assert_eq!(pixels[0], Color32::from_rgb(255, 0, 0));
+            assert_eq!(pixels[3], Color32::from_rgb(0, 0, 255));
+        }
+        // The Lanczos window is narrower at the center, so the edge pixels
+        // keep more of their own color than under the tent.
+        assert!(lanczos[1].r() > tent[1].r());
+    }
+
     /// Block-fill (sampling_level = -1): each 2×2 output block reads one
     /// field cell.
     #[test]
@@ -436,14 +1408,22 @@ mod tests {
         let palette = red_to_blue_palette();
         let cache = cache_with_unit_distribution(&palette);
 
-        let mut field: Vec<Vec<Option<(f32, u32)>>> = vec![vec![None; 4]; 4];
-        field[0][0] = Some((0.0, 0));
-        field[2][0] = Some((1.0, 0));
-        field[0][2] = Some((1.0, 0));
-        field[2][2] = Some((0.0, 0));
+        let mut field = FieldBuffer::new([4, 4]);
+        field[[0, 0]] = Some((0.0, 0));
+        field[[2, 0]] = Some((1.0, 0));
+        field[[0, 2]] = Some((1.0, 0));
+        field[[2, 2]] = Some((0.0, 0));
 
         let mut out = ColorImage::filled([4, 4], Color32::BLACK);
-        colorize_collapse_unified(&cache, &field, 1, -1, &mut out);
+        colorize_collapse_unified(
+            &cache,
+            &field,
+            1,
+            -1,
+            DownscaleFilter::Box,
+            &mut Vec::new(),
+            &mut out,
+        );
 
         // Top-left 2×2 block: red.
         for py in 0..2 {
@@ -510,11 +1490,38 @@ mod tests {
             resolution: [width, height],
             center: [0.0, 0.0],
             width: span,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
         }
     }
 
-    fn allocate_field(outer: usize, inner: usize) -> Vec<Vec<Option<(f32, u32)>>> {
-        (0..outer).map(|_| vec![None; inner]).collect()
+    fn allocate_field(outer: usize, inner: usize) -> FieldBuffer {
+        FieldBuffer::new([outer, inner])
+    }
+
+    /// Every cell of `field` with its `[outer_x, outer_y]` index.
+    fn indexed_cells(
+        field: &FieldBuffer,
+    ) -> impl Iterator<Item = ([usize; 2], &Option<(f32, u32)>)> {
+        (0..field.size()[1]).flat_map(move |outer_y| {
+            field
+                .row(outer_y)
+                .iter()
+                .enumerate()
+                .map(move |(outer_x, cell)| ([outer_x, outer_y], cell))
+        })
+    }
+
+    #[test]
+    fn field_buffer_rows_are_contiguous_in_x() {
+        let mut field = FieldBuffer::new([3, 2]);
+        field[[2, 0]] = Some((1.0, 0));
+        field[[0, 1]] = Some((2.0, 0));
+        assert_eq!(field.size(), [3, 2]);
+        assert_eq!(field.row(0), &[None, None, Some((1.0, 0))]);
+        assert_eq!(field.row(1), &[Some((2.0, 0)), None, None]);
+        assert_eq!(indexed_cells(&field).count(), 6);
     }
 
     #[test]
@@ -628,23 +1635,28 @@ mod tests {
 
         // sampling_level = 1 → subpixel_count = 2; only the first 2×2
         // sub-grid of each 3×3 block should be populated.
-        compute_raw_field(&spec, n_max_plus_1, 1, &kernel, &mut field);
-
-        for (outer_x, col) in field.iter().enumerate() {
-            for (outer_y, cell) in col.iter().enumerate() {
-                let subpixel_x = outer_x % n_max_plus_1;
-                let subpixel_y = outer_y % n_max_plus_1;
-                if subpixel_x < 2 && subpixel_y < 2 {
-                    assert!(
-                        cell.is_some(),
-                        "cell ({outer_x},{outer_y}) within sub-grid should be populated"
-                    );
-                } else {
-                    assert!(
-                        cell.is_none(),
-                        "cell ({outer_x},{outer_y}) outside sub-grid should remain None"
-                    );
-                }
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            1,
+            &RenderOptions::default(),
+            &kernel,
+            &mut field,
+        );
+
+        for ([outer_x, outer_y], cell) in indexed_cells(&field) {
+            let subpixel_x = outer_x % n_max_plus_1;
+            let subpixel_y = outer_y % n_max_plus_1;
+            if subpixel_x < 2 && subpixel_y < 2 {
+                assert!(
+                    cell.is_some(),
+                    "cell ({outer_x},{outer_y}) within sub-grid should be populated"
+                );
+            } else {
+                assert!(
+                    cell.is_none(),
+                    "cell ({outer_x},{outer_y}) outside sub-grid should remain None"
+                );
             }
         }
     }
@@ -656,22 +1668,27 @@ mod tests {
         let mut field = allocate_field(6, 6);
         let kernel = EncodingKernel { color_map_index: 7 };
 
-        compute_raw_field(&spec, n_max_plus_1, 0, &kernel, &mut field);
-
-        for (outer_x, col) in field.iter().enumerate() {
-            for (outer_y, cell) in col.iter().enumerate() {
-                let is_top_left = outer_x % n_max_plus_1 == 0 && outer_y % n_max_plus_1 == 0;
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            0,
+            &RenderOptions::default(),
+            &kernel,
+            &mut field,
+        );
+
+        for ([outer_x, outer_y], cell) in indexed_cells(&field) {
+            let is_top_left = outer_x % n_max_plus_1 == 0 && outer_y % n_max_plus_1 == 0;
+            assert_eq!(
+                cell.is_some(),
+                is_top_left,
+                "cell ({outer_x},{outer_y}) populated state mismatch"
+            );
+            if let Some((_, color_map_index)) = cell {
                 assert_eq!(
-                    cell.is_some(),
-                    is_top_left,
-                    "cell ({outer_x},{outer_y}) populated state mismatch"
+                    *color_map_index, 7,
+                    "color-map index must come from the kernel"
                 );
-                if let Some((_, color_map_index)) = cell {
-                    assert_eq!(
-                        *color_map_index, 7,
-                        "color-map index must come from the kernel"
-                    );
-                }
             }
         }
     }
@@ -685,15 +1702,91 @@ mod tests {
 
         // sampling_level = -1 → block_size = 2; stride = 1 * 2 = 2.
         // Field cells (0,0), (2,0) populated iff outer_x % 2 == 0 and outer_y % 2 == 0.
-        compute_raw_field(&spec, n_max_plus_1, -1, &kernel, &mut field);
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            -1,
+            &RenderOptions::default(),
+            &kernel,
+            &mut field,
+        );
+
+        for ([outer_x, outer_y], cell) in indexed_cells(&field) {
+            let on_stride = outer_x % 2 == 0 && outer_y % 2 == 0;
+            assert_eq!(
+                cell.is_some(),
+                on_stride,
+                "({outer_x},{outer_y}) populated state should match stride logic"
+            );
+        }
+    }
 
-        for (outer_x, col) in field.iter().enumerate() {
-            for (outer_y, cell) in col.iter().enumerate() {
-                let on_stride = outer_x % 2 == 0 && outer_y % 2 == 0;
+    /// Block-fill with a block size that does not divide the resolution:
+    /// the partial blocks along the right and bottom edges are still
+    /// sampled at their top-left pixel, and every edge pixel holds that
+    /// sample, with no seam or unwritten column/row.
+    #[test]
+    fn block_fill_edges_hold_the_last_sample_when_the_stride_leaves_a_remainder() {
+        let [width, height] = [101usize, 97];
+        let block_size = 4;
+        let sampling_level = 1 - block_size as i32;
+        let n_max_plus_1 = 2; // Field sized for anti-aliasing, as in explore.
+        let spec = make_spec(width as u32, height as u32, 10.0);
+        let mut field = allocate_field(width * n_max_plus_1, height * n_max_plus_1);
+        let kernel = EncodingKernel { color_map_index: 0 };
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            sampling_level,
+            &RenderOptions::default(),
+            &kernel,
+            &mut field,
+        );
+
+        let block_origin_cell = |x: usize, y: usize| {
+            [x / block_size * block_size, y / block_size * block_size]
+                .map(|pixel| pixel * n_max_plus_1)
+        };
+        let mapper = PixelMapper::new(&spec);
+        for ([outer_x, outer_y], cell) in indexed_cells(&field) {
+            let stride = block_size * n_max_plus_1;
+            let on_stride = outer_x % stride == 0 && outer_y % stride == 0;
+            assert_eq!(cell.is_some(), on_stride, "({outer_x},{outer_y})");
+        }
+        // The last, partial blocks start at pixel 100 and row 96.
+        let (x, y) = mapper.map(&(100, 96));
+        assert_eq!(field[block_origin_cell(100, 96)], kernel.evaluate([x, y]));
+
+        // Give neighboring blocks different colors, then check that every
+        // output pixel shows the sample at the origin of its block.
+        let palette = red_to_blue_palette();
+        let cache = cache_with_unit_distribution(&palette);
+        for y in (0..height).step_by(block_size) {
+            for x in (0..width).step_by(block_size) {
+                field[block_origin_cell(x, y)] = match (x + 2 * y) / block_size % 3 {
+                    0 => None,
+                    1 => Some((0.0, 0)),
+                    _ => Some((1.0, 0)),
+                };
+            }
+        }
+        let mut out = ColorImage::filled([width, height], Color32::BLACK);
+        colorize_collapse_unified(
+            &cache,
+            &field,
+            n_max_plus_1,
+            sampling_level,
+            DownscaleFilter::Box,
+            &mut Vec::new(),
+            &mut out,
+        );
+        for y in 0..height {
+            for x in 0..width {
+                let rgb = colorize_cell(&cache, field[block_origin_cell(x, y)]);
                 assert_eq!(
-                    cell.is_some(),
-                    on_stride,
-                    "({outer_x},{outer_y}) populated state should match stride logic"
+                    out.pixels[y * width + x],
+                    Color32::from_rgb(rgb[0], rgb[1], rgb[2]),
+                    "({x},{y})"
                 );
             }
         }
@@ -713,6 +1806,220 @@ mod tests {
         }
     }
 
+    /// Reports one coordinate of the evaluation point as the cell value.
+    struct CoordinateKernel {
+        axis: usize,
+    }
+
+    impl FieldKernel for CoordinateKernel {
+        fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)> {
+            Some((point[self.axis] as f32, 0))
+        }
+    }
+
+    #[test]
+    fn subpixel_jitter_is_deterministic_bounded_and_uncorrelated() {
+        let offsets: Vec<[f64; 2]> = (0..32)
+            .flat_map(|x| (0..32).map(move |y| subpixel_jitter([x, y])))
+            .collect();
+        assert_eq!(subpixel_jitter([3, 7]), subpixel_jitter([3, 7]));
+        assert_ne!(subpixel_jitter([3, 7]), subpixel_jitter([7, 3]));
+        for axis in 0..2 {
+            assert!(
+                offsets
+                    .iter()
+                    .all(|offset| (-0.5..0.5).contains(&offset[axis]))
+            );
+            let mean = offsets.iter().map(|offset| offset[axis]).sum::<f64>() / 1024.0;
+            assert!(mean.abs() < 0.05, "axis {axis}: mean {mean}");
+        }
+    }
+
+    /// With jitter enabled, every subpixel of a pixel moves by that pixel's
+    /// `subpixel_jitter` offset (in subpixel steps); without anti-aliasing
+    /// the jitter is ignored.
+    #[test]
+    fn compute_raw_field_shifts_each_pixel_by_its_jitter() {
+        let spec = make_spec(4, 3, 4.0);
+        let n_max_plus_1 = 2;
+        let jittered = RenderOptions {
+            subpixel_jitter: true,
+            ..RenderOptions::default()
+        };
+        for axis in 0..2 {
+            let kernel = CoordinateKernel { axis };
+            let [plain, shifted] = [RenderOptions::default(), jittered].map(|render_options| {
+                let mut field = allocate_field(4 * n_max_plus_1, 3 * n_max_plus_1);
+                compute_raw_field(&spec, n_max_plus_1, 1, &render_options, &kernel, &mut field);
+                field
+            });
+            // Neighboring subpixels along `axis` within the first pixel.
+            let next = if axis == 0 { [1, 0] } else { [0, 1] };
+            let subpixel_step = plain[next].unwrap().0 - plain[[0, 0]].unwrap().0;
+            for (([x, y], cell), (_, shifted_cell)) in
+                indexed_cells(&plain).zip(indexed_cells(&shifted))
+            {
+                let pixel = [x / n_max_plus_1, y / n_max_plus_1].map(|index| index as u32);
+                let delta = shifted_cell.unwrap().0 - cell.unwrap().0;
+                let expected = subpixel_jitter(pixel)[axis] as f32 * subpixel_step;
+                assert!(
+                    (delta - expected).abs() < 1e-4,
+                    "axis {axis} ({x},{y}): {delta} vs {expected}"
+                );
+            }
+
+            let [plain, shifted] = [RenderOptions::default(), jittered].map(|render_options| {
+                let mut field = allocate_field(4, 3);
+                compute_raw_field(&spec, 1, 0, &render_options, &kernel, &mut field);
+                field
+            });
+            assert_eq!(plain, shifted);
+        }
+    }
+
+    /// Piecewise-constant kernel (a disk plus the `x > 0.6` half-plane)
+    /// that counts its evaluations, optionally requesting a coarse pre-pass.
+    struct RegionKernel {
+        stride: Option<u32>,
+        evaluations: AtomicUsize,
+    }
+
+    impl FieldKernel for RegionKernel {
+        fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)> {
+            self.evaluations.fetch_add(1, Ordering::Relaxed);
+            if point[0] > 0.6 {
+                Some((2.0, 1))
+            } else if point[0] * point[0] + point[1] * point[1] < 0.5 {
+                Some((1.0, 0))
+            } else {
+                None
+            }
+        }
+
+        fn coarse_prepass_stride(&self) -> Option<u32> {
+            self.stride
+        }
+    }
+
+    #[test]
+    fn coarse_prepass_matches_full_evaluation_with_fewer_kernel_calls() {
+        let spec = make_spec(96, 80, 3.0);
+        for sampling_level in [0, 1] {
+            let n_max_plus_1 = 2;
+            let outer = 96 * n_max_plus_1;
+            let inner = 80 * n_max_plus_1;
+            let full_kernel = RegionKernel {
+                stride: None,
+                evaluations: AtomicUsize::new(0),
+            };
+            let coarse_kernel = RegionKernel {
+                stride: Some(4),
+                evaluations: AtomicUsize::new(0),
+            };
+            let mut full = allocate_field(outer, inner);
+            let mut coarse = allocate_field(outer, inner);
+            compute_raw_field(
+                &spec,
+                n_max_plus_1,
+                sampling_level,
+                &RenderOptions::default(),
+                &full_kernel,
+                &mut full,
+            );
+            compute_raw_field(
+                &spec,
+                n_max_plus_1,
+                sampling_level,
+                &RenderOptions::default(),
+                &coarse_kernel,
+                &mut coarse,
+            );
+
+            assert_eq!(full, coarse, "sampling_level {sampling_level}");
+            let full_calls = full_kernel.evaluations.into_inner();
+            let coarse_calls = coarse_kernel.evaluations.into_inner();
+            assert!(
+                2 * coarse_calls < full_calls,
+                "{coarse_calls} vs {full_calls} at sampling_level {sampling_level}"
+            );
+        }
+    }
+
+    /// A linear ramp in `x`, or a vertical step between two color maps at
+    /// `x = 0.1` when `step` is set. Counts its evaluations.
+    struct RampKernel {
+        step: bool,
+        evaluations: AtomicUsize,
+    }
+
+    impl FieldKernel for RampKernel {
+        fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)> {
+            self.evaluations.fetch_add(1, Ordering::Relaxed);
+            if self.step {
+                Some((0.0, (point[0] > 0.1) as u32))
+            } else {
+                Some((point[0] as f32, 0))
+            }
+        }
+    }
+
+    /// Full-grid and edge-adaptive fields for the same kernel, plus the
+    /// number of kernel calls each took.
+    fn full_and_edge_adaptive_fields(
+        step: bool,
+        edge_threshold: f32,
+    ) -> ([FieldBuffer; 2], [usize; 2]) {
+        let spec = make_spec(12, 10, 3.0);
+        let (n_max_plus_1, sampling_level) = (4, 3);
+        let [full, adaptive] = [None, Some(edge_threshold)].map(|threshold| {
+            let kernel = RampKernel {
+                step,
+                evaluations: AtomicUsize::new(0),
+            };
+            let mut field = allocate_field(12 * n_max_plus_1, 10 * n_max_plus_1);
+            let render_options = RenderOptions {
+                edge_threshold: threshold,
+                ..RenderOptions::default()
+            };
+            compute_raw_field(
+                &spec,
+                n_max_plus_1,
+                sampling_level,
+                &render_options,
+                &kernel,
+                &mut field,
+            );
+            (field, kernel.evaluations.into_inner())
+        });
+        ([full.0, adaptive.0], [full.1, adaptive.1])
+    }
+
+    #[test]
+    fn edge_adaptive_aa_interpolates_smooth_pixels_from_their_corners() {
+        let ([full, adaptive], [full_calls, adaptive_calls]) =
+            full_and_edge_adaptive_fields(false, 1.0);
+        assert_eq!(full_calls, 12 * 10 * 16);
+        assert_eq!(adaptive_calls, 12 * 10 * 4);
+        for (([x, y], cell), (_, expected)) in indexed_cells(&adaptive).zip(indexed_cells(&full)) {
+            let (value, expected) = (cell.unwrap().0, expected.unwrap().0);
+            assert!(
+                (value - expected).abs() < 1e-5,
+                "({x},{y}): {value} vs {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn edge_adaptive_aa_samples_every_subpixel_across_edges() {
+        let ([full, adaptive], [full_calls, adaptive_calls]) =
+            full_and_edge_adaptive_fields(true, 0.0);
+        assert_eq!(full, adaptive);
+        assert!(
+            adaptive_calls < full_calls / 2,
+            "{adaptive_calls} vs {full_calls}"
+        );
+    }
+
     #[test]
     fn compute_raw_field_baseline_pixel_coords_match_base_pixel_mapper() {
         // 4×2 image; baseline sampling so each pixel is visited exactly
@@ -726,7 +2033,14 @@ mod tests {
             seen: std::sync::Mutex::new(Vec::new()),
         };
 
-        compute_raw_field(&spec, n_max_plus_1, 0, &kernel, &mut field);
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            0,
+            &RenderOptions::default(),
+            &kernel,
+            &mut field,
+        );
 
         let pixel_map = PixelMapper::new(&spec);
         let mut seen = kernel.seen.lock().unwrap();
@@ -749,7 +2063,14 @@ mod tests {
         let spec = make_spec(4, 1, 4.0);
         let n_max_plus_1 = 1;
         let mut field = allocate_field(4, 1);
-        compute_raw_field(&spec, n_max_plus_1, 0, &AlternatingKernel, &mut field);
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            0,
+            &RenderOptions::default(),
+            &AlternatingKernel,
+            &mut field,
+        );
 
         let mut histograms = vec![Histogram::new(4, 10.0), Histogram::new(4, 10.0)];
         for histogram in &mut histograms {
@@ -757,7 +2078,7 @@ mod tests {
         }
         populate_histograms(n_max_plus_1, 0, &field, &mut histograms);
 
-        // The traversal hits every column once (sampling_level=0). The
+        // The traversal hits every cell once (sampling_level=0). The
         // alternating kernel routes even-x → 0, odd-x → 1, so each
         // histogram should see two entries (4 px / 2 = 2).
         let total_0: u32 = (0..4).map(|i| histograms[0].bin_count(i)).sum();
@@ -766,18 +2087,60 @@ mod tests {
         assert_eq!(total_1, 2, "histogram[1] should see two odd-x cells");
     }
 
+    #[test]
+    fn valued_cell_fraction_counts_only_populated_cells() {
+        let mut field = allocate_field(4, 4);
+        assert_eq!(valued_cell_fraction(2, 1, &field), 0.0);
+        // Three of the four cells of pixel (0, 0) hold a value, plus one
+        // cell outside the active grid at sampling_level 0.
+        field[[0, 0]] = Some((1.0, 0));
+        field[[1, 0]] = Some((1.0, 0));
+        field[[0, 1]] = Some((1.0, 0));
+        field[[2, 2]] = Some((1.0, 0));
+        assert_eq!(valued_cell_fraction(2, 1, &field), 4.0 / 16.0);
+        assert_eq!(valued_cell_fraction(2, 0, &field), 2.0 / 4.0);
+    }
+
+    #[test]
+    fn count_cells_splits_by_color_map_overlay_and_none() {
+        let mut field = allocate_field(4, 2);
+        field[[0, 0]] = Some((1.0, 0));
+        field[[1, 0]] = Some((1.0, 3));
+        field[[2, 0]] = Some((1.0, OVERLAY_COLOR_MAP_INDEX));
+        // Outside the active grid at sampling_level 0.
+        field[[1, 1]] = Some((1.0, 1));
+        let counts = count_cells(2, 0, &field, 2);
+        assert_eq!(
+            counts,
+            CellCounts {
+                by_color_map: vec![1, 0],
+                overlay: 1,
+                empty: 0,
+            }
+        );
+        let counts = count_cells(2, 1, &field, 2);
+        assert_eq!(counts.by_color_map, vec![1, 2]);
+        assert_eq!(counts.empty, 4);
+        assert_eq!(counts.total(), 8);
+    }
+
     #[test]
     fn populate_histograms_counts_match_some_count() {
         let spec = make_spec(3, 3, 6.0);
         let n_max_plus_1 = 2; // field is 6×6
         let mut field = allocate_field(6, 6);
         let kernel = EncodingKernel { color_map_index: 0 };
-        compute_raw_field(&spec, n_max_plus_1, 1, &kernel, &mut field);
-
-        let some_count = field
-            .iter()
-            .flat_map(|c| c.iter())
-            .filter(|c| c.is_some())
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            1,
+            &RenderOptions::default(),
+            &kernel,
+            &mut field,
+        );
+
+        let some_count = indexed_cells(&field)
+            .filter(|(_, cell)| cell.is_some())
             .count();
         // subpixel_count=2, so each 2×2 block has 4 populated cells;
         // 3×3 = 9 blocks → 36.
@@ -798,7 +2161,14 @@ mod tests {
         let spec = make_spec(2, 2, 4.0);
         let n_max_plus_1 = 1;
         let mut field = allocate_field(2, 2);
-        compute_raw_field(&spec, n_max_plus_1, 0, &AlwaysNoneKernel, &mut field);
+        compute_raw_field(
+            &spec,
+            n_max_plus_1,
+            0,
+            &RenderOptions::default(),
+            &AlwaysNoneKernel,
+            &mut field,
+        );
 
         let mut histograms = vec![Histogram::new(4, 10.0)];
         for histogram in &mut histograms {
@@ -810,6 +2180,30 @@ mod tests {
         assert_eq!(total, 0, "no Some cells → empty histograms");
     }
 
+    /// `u32::MAX % 3 == 0`, so overlay cells would land in the first
+    /// histogram if they were binned.
+    #[test]
+    fn populate_histograms_skips_overlay_cells() {
+        let mut field = allocate_field(4, 1);
+        field[[0, 0]] = Some((0.5, 0));
+        field[[1, 0]] = Some((0.0, OVERLAY_COLOR_MAP_INDEX));
+        field[[2, 0]] = Some((0.0, OVERLAY_COLOR_MAP_INDEX));
+        field[[3, 0]] = Some((0.5, 1));
+
+        let mut histograms: Vec<Histogram> = (0..3).map(|_| Histogram::new(4, 1.0)).collect();
+        for histogram in &mut histograms {
+            histogram.reset();
+        }
+        populate_histograms(1, 0, &field, &mut histograms);
+
+        let totals: Vec<u32> = histograms
+            .iter()
+            .map(|histogram| (0..4).map(|i| histogram.bin_count(i)).sum())
+            .collect();
+        assert_eq!(totals, vec![1, 1, 0]);
+        assert_eq!(histograms[0].bin_count(0), 0);
+    }
+
     #[test]
     #[should_panic(expected = "histograms slice must not be empty")]
     fn populate_histograms_rejects_empty_slice() {
@@ -817,4 +2211,50 @@ mod tests {
         let mut histograms: Vec<Histogram> = vec![];
         populate_histograms(1, 0, &field, &mut histograms);
     }
+
+    /// Isolines are cut where the integer part of the value steps between
+    /// neighbors on the active sample grid, skipping the unpopulated cells
+    /// and never crossing color maps.
+    #[test]
+    fn mark_isolines_clears_cells_before_each_integer_step() {
+        // Three pixels of 2x2 subpixel slots, only the first slot per side
+        // active at sampling level 0. Values step from 1.x to 2.x between
+        // the first and second pixel columns.
+        let mut field = FieldBuffer::new([6, 2]);
+        for y in 0..2 {
+            for x in 0..6 {
+                field[[x, y]] = Some((if x < 2 { 1.2 } else { 2.7 }, 0));
+            }
+        }
+        // The unpopulated slot would otherwise form a line at x = 0.
+        field[[1, 0]] = Some((5.0, 0));
+        let mut scratch = Vec::new();
+
+        let mut baseline = field.clone();
+        mark_isolines(2, 0, &mut baseline, &mut scratch);
+        assert_eq!(baseline[[0, 0]], None);
+        assert_eq!(baseline[[2, 0]], Some((2.7, 0)));
+        assert_eq!(baseline[[4, 0]], Some((2.7, 0)));
+
+        // With anti-aliasing every slot is active: the line moves to the
+        // slot at x = 1, and x = 0 now only borders the same integer part.
+        field[[1, 0]] = Some((1.9, 0));
+        field[[1, 1]] = Some((1.9, 0));
+        let mut anti_aliased = field.clone();
+        mark_isolines(2, 1, &mut anti_aliased, &mut scratch);
+        assert_eq!(anti_aliased[[0, 0]], Some((1.2, 0)));
+        assert_eq!(anti_aliased[[1, 0]], None);
+        assert_eq!(anti_aliased[[1, 1]], None);
+        assert_eq!(anti_aliased[[2, 0]], Some((2.7, 0)));
+
+        // Neighbors in different color maps are a basin boundary, not an isoline.
+        for y in 0..2 {
+            for x in 2..6 {
+                field[[x, y]] = Some((2.7, 1));
+            }
+        }
+        let mut boundary = field.clone();
+        mark_isolines(2, 1, &mut boundary, &mut scratch);
+        assert_eq!(boundary, field);
+    }
 }
@@ -0,0 +1,238 @@
+//! Optional overlay of real-space gridlines, with coordinate labels, drawn
+//! onto the finished image. The gridlines sit at "nice" intervals (1, 2, or
+//! 5 times a power of ten), chosen so that about half a dozen span the view.
+
+use egui::{Color32, ColorImage};
+use serde::{Deserialize, Serialize};
+
+use crate::core::image_utils::{ImageSpecification, PixelMapper};
+
+/// Grid overlay settings, part of `RenderOptions`. Off by default.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct GridOverlay {
+    /// Draw the gridlines and their coordinate labels.
+    pub show_grid: bool,
+    /// Color of the gridlines and their labels. White by default.
+    pub grid_color: [u8; 3],
+}
+
+impl Default for GridOverlay {
+    fn default() -> Self {
+        Self {
+            show_grid: false,
+            grid_color: [255, 255, 255],
+        }
+    }
+}
+
+/// Roughly how many gridlines span the width of the view.
+const TARGET_GRIDLINE_COUNT: f64 = 6.0;
+
+/// Gap, in glyph pixels, between a gridline and its label.
+const LABEL_MARGIN: usize = 2;
+
+/// The smallest "nice" spacing (1, 2, or 5 times a power of ten) that puts
+/// at most about `TARGET_GRIDLINE_COUNT` gridlines across `span`.
+pub fn nice_grid_spacing(span: f64) -> f64 {
+    let raw = span.abs() / TARGET_GRIDLINE_COUNT;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let mantissa = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .find(|&mantissa| mantissa * magnitude >= raw)
+        .unwrap_or(10.0);
+    mantissa * magnitude
+}
+
+/// The multiples of `spacing` within the range of `map` over
+/// `0..pixel_count`, each paired with the index of the nearest pixel.
+fn gridline_pixels(pixel_count: u32, map: impl Fn(u32) -> f64, spacing: f64) -> Vec<(usize, f64)> {
+    let first = map(0);
+    let last = map(pixel_count - 1);
+    let pixels_per_unit = (pixel_count - 1) as f64 / (last - first);
+    let lowest = (first.min(last) / spacing).ceil() as i64;
+    let highest = (first.max(last) / spacing).floor() as i64;
+    (lowest..=highest)
+        .map(|multiple| {
+            let value = multiple as f64 * spacing;
+            let index = ((value - first) * pixels_per_unit).round() as usize;
+            (index.min(pixel_count as usize - 1), value)
+        })
+        .collect()
+}
+
+/// Format a gridline coordinate with just enough decimals for `spacing`.
+fn format_label(value: f64, spacing: f64) -> String {
+    let decimals = (-spacing.log10().floor()).max(0.0) as usize;
+    // Avoid labelling the origin "-0".
+    let value = if value.abs() < 0.5 * spacing {
+        0.0
+    } else {
+        value
+    };
+    format!("{value:.decimals$}")
+}
+
+/// 3x5 bitmaps for the characters of a coordinate label, one row per
+/// entry with the most significant of the low three bits on the left.
+fn glyph(character: char) -> Option<[u8; 5]> {
+    Some(match character {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => return None,
+    })
+}
+
+/// Draw `text` with its top-left corner at `[x, y]`, each glyph pixel
+/// `scale` image pixels square. Anything off the image is clipped.
+fn draw_label(
+    image: &mut ColorImage,
+    text: &str,
+    [x, y]: [usize; 2],
+    scale: usize,
+    color: Color32,
+) {
+    let [width, height] = image.size;
+    for (position, rows) in text.chars().filter_map(glyph).enumerate() {
+        let glyph_x = x + position * 4 * scale;
+        for (row_index, row) in rows.iter().enumerate() {
+            for column in 0..3 {
+                if row & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = glyph_x + column * scale + dx;
+                        let py = y + row_index * scale + dy;
+                        if px < width && py < height {
+                            image.pixels[py * width + px] = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draw the gridlines and their labels onto `image`, which must match the
+/// resolution of `image_specification`. Does nothing unless
+/// `grid.show_grid` is set.
+pub fn draw_grid_overlay(
+    grid: &GridOverlay,
+    image_specification: &ImageSpecification,
+    image: &mut ColorImage,
+) {
+    if !grid.show_grid {
+        return;
+    }
+    let [width, height] = image.size;
+    if width < 2 || height < 2 {
+        return;
+    }
+    let [r, g, b] = grid.grid_color;
+    let color = Color32::from_rgb(r, g, b);
+    let mapper = PixelMapper::new(image_specification);
+    let spacing = nice_grid_spacing(image_specification.width);
+    let scale = (width.max(height) / 800).max(1);
+    let margin = LABEL_MARGIN * scale;
+
+    let columns = gridline_pixels(
+        image_specification.resolution[0],
+        |i| mapper.width.map(i),
+        spacing,
+    );
+    let rows = gridline_pixels(
+        image_specification.resolution[1],
+        |i| mapper.height.map(i),
+        spacing,
+    );
+    for &(x, _) in &columns {
+        for y in 0..height {
+            image.pixels[y * width + x] = color;
+        }
+    }
+    for &(y, _) in &rows {
+        image.pixels[y * width..(y + 1) * width].fill(color);
+    }
+    for &(x, value) in &columns {
+        draw_label(
+            image,
+            &format_label(value, spacing),
+            [x + margin, margin],
+            scale,
+            color,
+        );
+    }
+    for &(y, value) in &rows {
+        draw_label(
+            image,
+            &format_label(value, spacing),
+            [margin, y + margin],
+            scale,
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_spacing_is_one_two_or_five_times_a_power_of_ten() {
+        assert_eq!(nice_grid_spacing(3.0), 0.5);
+        assert_eq!(nice_grid_spacing(10.0), 2.0);
+        assert_eq!(nice_grid_spacing(6.0), 1.0);
+        assert!((nice_grid_spacing(2.5e-6) / 5e-7 - 1.0).abs() < 1e-12);
+        assert_eq!(format_label(-1.5, 0.5), "-1.5");
+        assert_eq!(format_label(-1e-17, 0.5), "0.0");
+        assert_eq!(format_label(20.0, 5.0), "20");
+    }
+
+    #[test]
+    fn gridlines_land_on_the_pixels_nearest_nice_coordinates() {
+        let image_specification = ImageSpecification {
+            resolution: [41, 41],
+            center: [0.0, 0.0],
+            width: 4.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let background = Color32::from_rgb(1, 2, 3);
+        let mut image = ColorImage::filled([41, 41], background);
+        draw_grid_overlay(&GridOverlay::default(), &image_specification, &mut image);
+        assert!(image.pixels.iter().all(|&pixel| pixel == background));
+
+        let grid = GridOverlay {
+            show_grid: true,
+            grid_color: [200, 0, 0],
+        };
+        draw_grid_overlay(&grid, &image_specification, &mut image);
+        let line = Color32::from_rgb(200, 0, 0);
+        // Spacing 1.0 over [-2, 2] puts a gridline every 10 pixels, with the
+        // axes through the center row and column.
+        let row = &image.pixels[18 * 41..19 * 41];
+        assert_eq!(row[20], line);
+        assert_eq!(row[30], line);
+        assert_eq!(row[25], background);
+        assert!(image.pixels[20 * 41..21 * 41].iter().all(|&p| p == line));
+        assert_eq!(
+            gridline_pixels(41, |i| 2.0 - 0.1 * i as f64, 0.5)
+                .iter()
+                .map(|&(index, _)| index)
+                .collect::<Vec<_>>(),
+            [40, 35, 30, 25, 20, 15, 10, 5, 0]
+        );
+    }
+}
@@ -1,34 +1,339 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use egui::{Color32, ColorImage};
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+use std::ops::ControlFlow;
 use std::{
     io::{self, Write},
     path::PathBuf,
 };
 
-use crate::core::color_map::ColorPalette;
-use crate::core::field_iteration::FieldKernel;
+use crate::core::color_map::{
+    ChannelCurves, ColorEffect, ColorMapError, ColorPalette, ScalarNormalization,
+};
+use crate::core::field_iteration::{FieldBuffer, FieldKernel, ProgressCallback};
+use crate::core::grid_overlay::{GridOverlay, draw_grid_overlay};
 use crate::core::interpolation::Interpolator;
+use crate::core::post_process::PostProcess;
 use crate::core::render_pipeline::RenderingPipeline;
 
-use super::file_io::{FilePrefix, write_file_or_panic};
+use super::file_io::{FilePrefix, serialize_to_json_or_panic, write_file_or_panic};
 use super::stopwatch::Stopwatch;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+/// In a params file, the view may be given by `center` and `width` or by
+/// explicit real-space ranges: `x_range` replaces `center[0]` and `width`,
+/// and `y_range` replaces `center[1]` and `pixel_aspect_ratio` (see
+/// `ImageSpecification::from_ranges`). A range takes precedence over the
+/// fields it replaces.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "ImageSpecificationFile")]
 pub struct ImageSpecification {
+    /// Either `[width, height]` in pixels, or a pixel budget and an aspect
+    /// ratio: `{ "megapixels": 12.0, "aspect": [16, 9] }` or
+    /// `{ "long_edge": 3840, "aspect": [16, 9] }`. A megapixel budget takes an
+    /// optional `PixelRounding` (`"rounding": "Floor"`). Always written back
+    /// out as `[width, height]`.
     pub resolution: [u32; 2],
     pub center: [f64; 2],
+    /// Real-space width of the view. Must be positive and finite.
     pub width: f64,
+    /// Real-space height of one pixel divided by its real-space width.
+    /// Defaults to 1.0 (square pixels); other values produce anamorphic
+    /// output for display targets that stretch the image. Must be positive
+    /// and finite.
+    pub pixel_aspect_ratio: f64,
+    /// Smallest `width` that explore mode zooms in to. Rendering ignores
+    /// it. Explore also stops zooming in at the floating-point precision
+    /// floor, whether or not `min_width` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<f64>,
+    /// Largest `width` that explore mode zooms out to. Rendering ignores it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<f64>,
+}
+
+fn default_pixel_aspect_ratio() -> f64 {
+    1.0
+}
+
+/// `ImageSpecification` as written in a params file, before the ranges are
+/// resolved and the view is validated.
+#[derive(Deserialize)]
+struct ImageSpecificationFile {
+    #[serde(deserialize_with = "deserialize_resolution")]
+    resolution: [u32; 2],
+    center: Option<[f64; 2]>,
+    width: Option<f64>,
+    #[serde(default = "default_pixel_aspect_ratio")]
+    pixel_aspect_ratio: f64,
+    x_range: Option<[f64; 2]>,
+    y_range: Option<[f64; 2]>,
+    min_width: Option<f64>,
+    max_width: Option<f64>,
+}
+
+impl TryFrom<ImageSpecificationFile> for ImageSpecification {
+    type Error = String;
+
+    fn try_from(file: ImageSpecificationFile) -> Result<Self, Self::Error> {
+        let missing = |field: &str, range: &str| format!("missing `{field}` (or `{range}`)");
+        let [center_x, width] = match file.x_range {
+            Some(x_range) => range_center_and_span("x_range", x_range)?,
+            None => [
+                file.center.ok_or_else(|| missing("center", "x_range"))?[0],
+                file.width.ok_or_else(|| missing("width", "x_range"))?,
+            ],
+        };
+        require_positive_and_finite("width", width)?;
+        let [center_y, pixel_aspect_ratio] = match file.y_range {
+            Some(y_range) => {
+                let [center_y, height] = range_center_and_span("y_range", y_range)?;
+                let [columns, rows] = file.resolution.map(|count| count.max(1) as f64);
+                [center_y, height * columns / (width * rows)]
+            }
+            None => [
+                file.center.ok_or_else(|| missing("center", "y_range"))?[1],
+                file.pixel_aspect_ratio,
+            ],
+        };
+        require_positive_and_finite("pixel_aspect_ratio", pixel_aspect_ratio)?;
+        Ok(ImageSpecification {
+            resolution: file.resolution,
+            center: [center_x, center_y],
+            width,
+            pixel_aspect_ratio,
+            min_width: file.min_width,
+            max_width: file.max_width,
+        })
+    }
+}
+
+/// Reject a `value` that is not finite and positive, naming it `name` in the
+/// error.
+pub(crate) fn require_positive_and_finite(name: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{name} ({value}) must be positive and finite"))
+    }
+}
+
+/// Midpoint and length of a real-space range, in either order. Rejects
+/// ranges that are empty or not finite.
+fn range_center_and_span(name: &str, range: [f64; 2]) -> Result<[f64; 2], String> {
+    let span = (range[1] - range[0]).abs();
+    if !(span.is_finite() && span > 0.0) {
+        return Err(format!(
+            "{name} {range:?} must span a finite, non-zero interval"
+        ));
+    }
+    Ok([0.5 * (range[0] + range[1]), span])
+}
+
+/// The ways a resolution may be written in a params file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ResolutionSpecification {
+    Pixels([u32; 2]),
+    Megapixels {
+        megapixels: f64,
+        aspect: [u32; 2],
+        #[serde(default)]
+        rounding: PixelRounding,
+    },
+    LongEdge {
+        long_edge: u32,
+        aspect: [u32; 2],
+    },
+}
+
+impl ResolutionSpecification {
+    fn resolve(self) -> Result<[u32; 2], String> {
+        let check_aspect = |aspect: [u32; 2]| {
+            if aspect.contains(&0) {
+                Err(format!("aspect {aspect:?} must be non-zero"))
+            } else {
+                Ok(aspect)
+            }
+        };
+        match self {
+            ResolutionSpecification::Pixels(resolution) => Ok(resolution),
+            ResolutionSpecification::Megapixels {
+                megapixels,
+                aspect,
+                rounding,
+            } => {
+                let aspect = check_aspect(aspect)?;
+                if !(megapixels.is_finite() && megapixels > 0.0) {
+                    return Err(format!("megapixels ({megapixels}) must be positive"));
+                }
+                let total_pixel_count = (megapixels * 1.0e6).round().max(1.0) as u64;
+                Ok(scale_resolution_to_total_pixel_count(
+                    aspect,
+                    total_pixel_count,
+                    rounding,
+                ))
+            }
+            ResolutionSpecification::LongEdge { long_edge, aspect } => {
+                let aspect = check_aspect(aspect)?;
+                if long_edge == 0 {
+                    return Err("long_edge must be non-zero".to_owned());
+                }
+                let scale = long_edge as f64 / aspect[0].max(aspect[1]) as f64;
+                Ok(aspect.map(|edge| ((edge as f64 * scale).round() as u32).max(1)))
+            }
+        }
+    }
+}
+
+fn deserialize_resolution<'de, D>(deserializer: D) -> Result<[u32; 2], D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    ResolutionSpecification::deserialize(deserializer)
+        .map_err(|_| {
+            serde::de::Error::custom(
+                "resolution must be [width, height], { megapixels, aspect }, \
+                 or { long_edge, aspect }",
+            )
+        })?
+        .resolve()
+        .map_err(|error| serde::de::Error::custom(format!("resolution: {error}")))
+}
+
+/// How `scale_to_total_pixel_count` rounds each scaled edge to a whole
+/// number of pixels.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PixelRounding {
+    /// Never fewer pixels than the target.
+    #[default]
+    Ceil,
+    /// Closest to the target, on either side.
+    Round,
+    /// Never more pixels than the target, for a hard memory budget.
+    Floor,
+}
+
+impl PixelRounding {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            PixelRounding::Ceil => value.ceil(),
+            PixelRounding::Round => value.round(),
+            PixelRounding::Floor => value.floor(),
+        }
+    }
+}
+
+/// Scale `resolution`, keeping its aspect ratio, so that it holds about
+/// `total_pixel_count` pixels, rounding each edge with `rounding`. Edges
+/// are kept at least one pixel long.
+fn scale_resolution_to_total_pixel_count(
+    resolution: [u32; 2],
+    total_pixel_count: u64,
+    rounding: PixelRounding,
+) -> [u32; 2] {
+    let current_pixel_count = resolution[0] as f64 * resolution[1] as f64;
+    let scale = (total_pixel_count as f64 / current_pixel_count).sqrt();
+    resolution.map(|edge| (rounding.apply(edge as f64 * scale) as u32).max(1))
 }
 
 /**
  * Used to fully-specify both an image resolution and how it is anchored into the "real"
  * space in which the fractal (or other subject) lives. The height in "real" space is derived
- * from the aspect ratio of the image and the specified width.
+ * from the aspect ratio of the image, the specified width, and the pixel aspect ratio.
  */
 impl ImageSpecification {
     pub fn height(&self) -> f64 {
-        self.width * (self.resolution[1] as f64) / (self.resolution[0] as f64)
+        self.pixel_aspect_ratio * self.width * (self.resolution[1] as f64)
+            / (self.resolution[0] as f64)
+    }
+
+    /// Build a square-pixel view of `view_rectangle` at `resolution`. The
+    /// rectangle's center is kept; `fit` decides whether the whole rectangle
+    /// is shown or the image is filled by it.
+    pub fn from_view_rectangle(
+        view_rectangle: &ViewRectangle,
+        resolution: [u32; 2],
+        fit: ViewFit,
+    ) -> ImageSpecification {
+        let image_aspect_ratio = resolution[1] as f64 / resolution[0] as f64;
+        let [dims_width, dims_height] = view_rectangle.dimensions;
+        let width_to_fit_height = dims_height / image_aspect_ratio;
+        let width = match fit {
+            ViewFit::Contain => dims_width.max(width_to_fit_height),
+            ViewFit::Cover => dims_width.min(width_to_fit_height),
+        };
+        ImageSpecification {
+            resolution,
+            center: view_rectangle.center,
+            width,
+            pixel_aspect_ratio: default_pixel_aspect_ratio(),
+            min_width: None,
+            max_width: None,
+        }
+    }
+
+    /// Build the view that exactly covers `x_range` by `y_range` at
+    /// `resolution`, for plotting an asymmetric region such as real parts in
+    /// `[-2, 0.5]`. Each range may be given in either order; the image is
+    /// never mirrored. The pixel aspect ratio is whatever makes both ranges
+    /// fit, so pick a resolution with the ranges' aspect ratio to keep
+    /// square pixels.
+    pub fn from_ranges(
+        resolution: [u32; 2],
+        x_range: [f64; 2],
+        y_range: [f64; 2],
+    ) -> Result<ImageSpecification, String> {
+        if resolution.contains(&0) {
+            return Err(format!("resolution {resolution:?} must be non-zero"));
+        }
+        ImageSpecification::try_from(ImageSpecificationFile {
+            resolution,
+            center: None,
+            width: None,
+            pixel_aspect_ratio: default_pixel_aspect_ratio(),
+            x_range: Some(x_range),
+            y_range: Some(y_range),
+            min_width: None,
+            max_width: None,
+        })
+    }
+
+    /// The view of just the `size` pixels starting at pixel `corner` (the
+    /// top-left one) of this image. Every pixel of the crop sits exactly on
+    /// the matching pixel of the full image, so a re-render of the crop can
+    /// be pasted back over the original. The crop must fit inside the image
+    /// and be at least two pixels along each axis.
+    pub fn crop(&self, corner: [u32; 2], size: [u32; 2]) -> Result<ImageSpecification, String> {
+        for dim in 0..2 {
+            if size[dim] < 2 {
+                return Err(format!(
+                    "crop must be at least 2 x 2 pixels, found {} x {}",
+                    size[0], size[1]
+                ));
+            }
+            if corner[dim] as u64 + size[dim] as u64 > self.resolution[dim] as u64 {
+                return Err(format!(
+                    "crop of {} x {} pixels at [{}, {}] does not fit in the {} x {} image",
+                    size[0], size[1], corner[0], corner[1], self.resolution[0], self.resolution[1]
+                ));
+            }
+        }
+        let mapper = PixelMapper::new(self);
+        let axes = [&mapper.width, &mapper.height];
+        // Real-space coordinates of the first and last pixel of the crop.
+        let ends = [0, 1].map(|dim| {
+            [corner[dim], corner[dim] + size[dim] - 1].map(|index| axes[dim].map(index))
+        });
+        let center = ends.map(|[first, last]| 0.5 * (first + last));
+        let [width, height] = ends.map(|[first, last]| (last - first).abs());
+        Ok(ImageSpecification {
+            resolution: size,
+            center,
+            width,
+            pixel_aspect_ratio: height * size[0] as f64 / (width * size[1] as f64),
+            ..*self
+        })
     }
 
     /// Returns a new image specification object with the same center and
@@ -43,10 +348,89 @@ impl ImageSpecification {
             ],
             center: self.center,
             width: self.width,
+            pixel_aspect_ratio: self.pixel_aspect_ratio,
+            min_width: self.min_width,
+            max_width: self.max_width,
         }
     }
+
+    /// Returns a new image specification object with the same center and
+    /// width, but with the resolution scaled (keeping its aspect ratio) to
+    /// hold about `total_pixel_count` pixels. Each edge is rounded with
+    /// `rounding`: `Ceil` never falls short of the target and `Floor` never
+    /// exceeds it.
+    pub fn scale_to_total_pixel_count(
+        &self,
+        total_pixel_count: u64,
+        rounding: PixelRounding,
+    ) -> ImageSpecification {
+        ImageSpecification {
+            resolution: scale_resolution_to_total_pixel_count(
+                self.resolution,
+                total_pixel_count,
+                rounding,
+            ),
+            ..*self
+        }
+    }
+
+    /// Encode the view (resolution, center, width) as a compact, URL-safe
+    /// base64 string that can be shared and later passed to `--view`.
+    /// `from_view_string` recovers those three exactly; the pixel aspect
+    /// ratio and the width limits are not encoded.
+    pub fn to_view_string(self) -> String {
+        let mut bytes = Vec::with_capacity(VIEW_STRING_BYTE_COUNT);
+        bytes.extend_from_slice(&self.resolution[0].to_le_bytes());
+        bytes.extend_from_slice(&self.resolution[1].to_le_bytes());
+        bytes.extend_from_slice(&self.center[0].to_le_bytes());
+        bytes.extend_from_slice(&self.center[1].to_le_bytes());
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// Decode a string produced by `to_view_string`. Rejects malformed input
+    /// as well as views with a zero resolution or a non-positive width. The
+    /// view string does not carry the pixel aspect ratio, so the decoded view
+    /// has square pixels.
+    pub fn from_view_string(view: &str) -> Result<ImageSpecification, String> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(view.trim())
+            .map_err(|e| format!("invalid view string: {e}"))?;
+        let bytes: [u8; VIEW_STRING_BYTE_COUNT] = bytes.try_into().map_err(|b: Vec<u8>| {
+            format!(
+                "invalid view string: expected {VIEW_STRING_BYTE_COUNT} bytes, found {}",
+                b.len()
+            )
+        })?;
+        let u32_at =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let f64_at = |i: usize| {
+            let mut chunk = [0u8; 8];
+            chunk.copy_from_slice(&bytes[i..i + 8]);
+            f64::from_le_bytes(chunk)
+        };
+        let spec = ImageSpecification {
+            resolution: [u32_at(0), u32_at(4)],
+            center: [f64_at(8), f64_at(16)],
+            width: f64_at(24),
+            pixel_aspect_ratio: default_pixel_aspect_ratio(),
+            min_width: None,
+            max_width: None,
+        };
+        if spec.resolution.contains(&0) {
+            return Err("invalid view string: resolution must be non-zero".to_owned());
+        }
+        let finite = spec.width.is_finite() && spec.center.iter().all(|c| c.is_finite());
+        if !finite || spec.width <= 0.0 {
+            return Err("invalid view string: width must be positive and values finite".to_owned());
+        }
+        Ok(spec)
+    }
 }
 
+/// Two `u32` resolution entries, two `f64` center coordinates, one `f64` width.
+const VIEW_STRING_BYTE_COUNT: usize = 2 * 4 + 3 * 8;
+
 /**
  * Describes a rectangular region in space.
  */
@@ -84,6 +468,16 @@ impl ViewRectangle {
             dimensions: [range_x, range_y],
         }
     }
+
+    /// The point of the rectangle (including its boundary) nearest to `point`.
+    pub fn clamp(&self, point: [f64; 2]) -> [f64; 2] {
+        std::array::from_fn(|dim| {
+            let half_range = 0.5 * self.dimensions[dim];
+            point[dim]
+                .max(self.center[dim] - half_range)
+                .min(self.center[dim] + half_range)
+        })
+    }
 }
 
 /// Allows a set of parameters to be dynamically adjusted to hit a target frame rate.
@@ -171,10 +565,191 @@ where
 /// The JSON value is the **maximum** the pipeline ever runs at — the field
 /// buffer is sized to accommodate it. The adaptive regulator drives the
 /// runtime value passed to `RenderingPipeline::render`.
+///
+/// A params file may give `samples_per_pixel` instead of `sampling_level`;
+/// it is rounded to the nearest square grid (see
+/// `sampling_level_for_samples_per_pixel`) and saved back as
+/// `sampling_level`.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(try_from = "RenderOptionsFile")]
 pub struct RenderOptions {
     /// User-facing sampling level (see struct docs). `0` is baseline.
     pub sampling_level: i32,
+    /// Enables two-stage anti-aliasing: each pixel samples its four corner
+    /// subpixels first, and only takes the full subpixel grid if they land
+    /// on different color maps or their values differ by more than this.
+    /// Elsewhere the subpixels are interpolated from the corners. Unset
+    /// (the default) always samples the full grid.
+    pub edge_threshold: Option<f32>,
+    /// How anti-aliasing collapses the subpixel grid into output pixels.
+    pub downscale_filter: DownscaleFilter,
+    /// Shift each pixel's anti-aliasing subpixel grid by a deterministic
+    /// per-pixel offset (see `field_iteration::subpixel_jitter`), which
+    /// breaks up the moiré that a regular grid produces on fine periodic
+    /// detail. Renders stay reproducible.
+    pub subpixel_jitter: bool,
+    /// Ordered (Bayer) dithering of the output colors: the color map is
+    /// sampled between its 8-bit levels and the remainder is spread over
+    /// neighboring pixels, which breaks up the banding in smooth gradients.
+    /// Not applied to block-fill previews.
+    pub dither: bool,
+    /// Blur or bloom applied to the finished image; off by default.
+    pub post_process: PostProcess,
+    /// Real-space gridlines with coordinate labels, drawn onto rendered
+    /// files; off by default.
+    pub grid: GridOverlay,
+    /// Sampling level of the snapshots saved from explore mode, which may be
+    /// higher than `sampling_level` so that exploring stays responsive while
+    /// the saved image is crisp. The explorer's field buffer is sized for the
+    /// larger of the two. Unset saves at `sampling_level`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screenshot_sampling_level: Option<i32>,
+    /// Write a small `_thumb.png` (see `THUMBNAIL_LONG_EDGE`) next to each
+    /// image from the `render` command. On unless a params file turns it
+    /// off.
+    pub thumbnail: bool,
+}
+
+/// Matches a params file that gives only `sampling_level: 0`.
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            sampling_level: 0,
+            edge_threshold: None,
+            downscale_filter: DownscaleFilter::default(),
+            subpixel_jitter: false,
+            dither: false,
+            post_process: PostProcess::default(),
+            grid: GridOverlay::default(),
+            screenshot_sampling_level: None,
+            thumbnail: default_thumbnail(),
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Sampling level of an explore-mode snapshot.
+    pub fn screenshot_sampling_level(&self) -> i32 {
+        self.screenshot_sampling_level
+            .unwrap_or(self.sampling_level)
+    }
+}
+
+/// `RenderOptions` as written in a params file, where the sampling density
+/// is given by exactly one of `sampling_level` and `samples_per_pixel`.
+#[derive(Deserialize)]
+struct RenderOptionsFile {
+    sampling_level: Option<i32>,
+    samples_per_pixel: Option<u32>,
+    #[serde(default)]
+    edge_threshold: Option<f32>,
+    #[serde(default)]
+    downscale_filter: DownscaleFilter,
+    #[serde(default)]
+    subpixel_jitter: bool,
+    #[serde(default)]
+    dither: bool,
+    #[serde(default)]
+    post_process: PostProcess,
+    #[serde(default)]
+    grid: GridOverlay,
+    #[serde(default)]
+    screenshot_sampling_level: Option<i32>,
+    #[serde(default = "default_thumbnail")]
+    thumbnail: bool,
+}
+
+fn default_thumbnail() -> bool {
+    true
+}
+
+impl TryFrom<RenderOptionsFile> for RenderOptions {
+    type Error = String;
+
+    fn try_from(file: RenderOptionsFile) -> Result<Self, Self::Error> {
+        let sampling_level = match (file.sampling_level, file.samples_per_pixel) {
+            (Some(sampling_level), None) => sampling_level,
+            (None, Some(samples_per_pixel)) => {
+                sampling_level_for_samples_per_pixel(samples_per_pixel)?
+            }
+            (None, None) => return Err("missing `sampling_level` (or `samples_per_pixel`)".into()),
+            (Some(_), Some(_)) => {
+                return Err("set only one of `sampling_level` and `samples_per_pixel`".into());
+            }
+        };
+        Ok(RenderOptions {
+            sampling_level,
+            edge_threshold: file.edge_threshold,
+            downscale_filter: file.downscale_filter,
+            subpixel_jitter: file.subpixel_jitter,
+            dither: file.dither,
+            post_process: file.post_process,
+            grid: file.grid,
+            screenshot_sampling_level: file.screenshot_sampling_level,
+            thumbnail: file.thumbnail,
+        })
+    }
+}
+
+/// The anti-aliasing sampling level whose `(n+1)²` subpixel grid comes
+/// closest to `samples_per_pixel`, e.g. `16` gives `3` (a 4×4 grid) and
+/// `10` gives `2` (3×3, nine samples).
+pub fn sampling_level_for_samples_per_pixel(samples_per_pixel: u32) -> Result<i32, String> {
+    if samples_per_pixel == 0 {
+        return Err("samples_per_pixel must be at least 1".to_owned());
+    }
+    let grid_size = (samples_per_pixel as f64).sqrt().round() as i32;
+    Ok(grid_size.max(1) - 1)
+}
+
+/// Reconstruction filter used to collapse the anti-aliasing subpixel grid
+/// into output pixels. At `sampling_level == 0` every filter reduces to one
+/// sample per pixel.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DownscaleFilter {
+    /// Unweighted average of the pixel's own subpixels.
+    #[default]
+    Box,
+    /// Linear falloff over one output pixel from the pixel center, so
+    /// neighboring subpixels contribute a little and the center dominates.
+    Tent,
+    /// Two-lobe Lanczos window over two output pixels. Sharpest edges, at
+    /// the cost of slight ringing next to high-contrast boundaries.
+    Lanczos,
+}
+
+impl DownscaleFilter {
+    /// Filter weight for a sample `offset` output pixels from the center of
+    /// the pixel being computed. Zero beyond [`Self::radius`].
+    pub fn weight(&self, offset: f32) -> f32 {
+        let distance = offset.abs();
+        match self {
+            DownscaleFilter::Box if distance <= 0.5 => 1.0,
+            DownscaleFilter::Box => 0.0,
+            DownscaleFilter::Tent => (1.0 - distance).max(0.0),
+            DownscaleFilter::Lanczos if distance < 2.0 => {
+                let sinc = |x: f32| {
+                    if x == 0.0 {
+                        1.0
+                    } else {
+                        let pi_x = std::f32::consts::PI * x;
+                        pi_x.sin() / pi_x
+                    }
+                };
+                sinc(distance) * sinc(0.5 * distance)
+            }
+            DownscaleFilter::Lanczos => 0.0,
+        }
+    }
+
+    /// Half-width of the filter support, in output pixels.
+    pub fn radius(&self) -> f32 {
+        match self {
+            DownscaleFilter::Box => 0.5,
+            DownscaleFilter::Tent => 1.0,
+            DownscaleFilter::Lanczos => 2.0,
+        }
+    }
 }
 
 /// Most extreme block-fill the regulator pushes to under load
@@ -253,6 +828,106 @@ pub trait Renderable: Sync + Send + SpeedOptimizer + FieldKernel {
     /// allocated to this size at pipeline construction.
     fn lookup_table_count(&self) -> usize;
 
+    /// How raw field values map onto color-map queries. Read once at
+    /// pipeline construction; histogram equalization unless overridden.
+    fn scalar_normalization(&self) -> ScalarNormalization {
+        ScalarNormalization::Histogram
+    }
+
+    /// Color of the cells that `FieldKernel::evaluate` marks with
+    /// `OVERLAY_COLOR_MAP_INDEX`. Read once at pipeline construction.
+    fn overlay_color(&self) -> Option<[u8; 3]> {
+        None
+    }
+
+    /// Independent red, green, and blue curves that replace the first color
+    /// map; see `ColorPaletteCache::set_channel_curves`. Read once at
+    /// pipeline construction.
+    fn channel_curves(&self) -> Option<ChannelCurves> {
+        None
+    }
+
+    /// Effects applied to the output of every color map; see
+    /// `ColorPaletteCache::set_color_effects`. Read once at pipeline
+    /// construction.
+    fn color_effects(&self) -> Vec<ColorEffect> {
+        Vec::new()
+    }
+
+    /// Number of hue sectors used to tint the cells; see
+    /// `ColorPaletteCache::set_sector_count`. Read once at pipeline
+    /// construction.
+    fn sector_count(&self) -> Option<u32> {
+        None
+    }
+
+    /// Number of discrete color bands; see
+    /// `ColorPaletteCache::set_band_count`. Read once at pipeline
+    /// construction.
+    fn band_count(&self) -> Option<u32> {
+        None
+    }
+
+    /// Fraction of each color band blended into the next; see
+    /// `ColorPaletteCache::set_band_count`. Read once at pipeline
+    /// construction.
+    fn band_softness(&self) -> f32 {
+        0.0
+    }
+
+    /// Whether the histogram normalization (CDFs) computed from the first
+    /// frame rendered at full quality should be kept for all later frames,
+    /// rather than rebuilt per frame. Keeps colors stable while exploring.
+    fn hold_cdfs_fixed(&self) -> bool {
+        false
+    }
+
+    /// Draw isolines of the integer part of the field value (e.g. the
+    /// iteration count), in the background color, over the colorized field.
+    /// See `field_iteration::mark_isolines`.
+    fn show_isolines(&self) -> bool {
+        false
+    }
+
+    /// True if `FieldKernel::evaluate` returns `None` for exactly the
+    /// samples that never escape, as for the quadratic maps. The render
+    /// summary then reports the fraction of samples that escaped.
+    fn counts_escapes(&self) -> bool {
+        false
+    }
+
+    /// The phase-space trajectory (angle, rate) that starts from `point`,
+    /// for fractals whose pixels are initial conditions of a pendulum. The
+    /// explorer plots it for the point under the cursor.
+    fn phase_space_trajectory(&self, _point: [f64; 2]) -> Option<Vec<[f64; 2]>> {
+        None
+    }
+
+    /// Called by the pipeline before each compute pass. Fractals that tally
+    /// statistics while `FieldKernel::evaluate` runs reset them here, so
+    /// the tallies never span more than one frame.
+    fn begin_compute_pass(&mut self) {}
+
+    /// Tally statistics of the field that a compute pass just filled, for
+    /// `write_diagnostics`. Called by the pipeline once per compute pass,
+    /// after the histograms are populated, so the tallies always describe
+    /// the latest frame.
+    fn record_field_statistics(
+        &mut self,
+        _field: &FieldBuffer,
+        _n_max_plus_1: usize,
+        _sampling_level: i32,
+    ) {
+    }
+
+    /// Add detail to the current view without changing it, for the explorer
+    /// to call once the adaptive regulator has gone idle. Returns `false`
+    /// when there is nothing worth adding, which ends the refinement until
+    /// the next regulator-driven render.
+    fn refine(&mut self) -> bool {
+        false
+    }
+
     /// Reference to the unified `ColorPalette` driving colorization. Length
     /// of `color_maps` is fixed for the session and matches the color-map
     /// indices that `FieldKernel::evaluate` is allowed to emit.
@@ -268,41 +943,23 @@ pub trait Renderable: Sync + Send + SpeedOptimizer + FieldKernel {
 /// Drives the new `RenderingPipeline` at the user's full sampling level.
 ///
 /// `snapshot_json` wraps the renderable's inner params back into a reloadable,
-/// tagged `FractalParams` JSON string. It is supplied by the caller (the CLI
+/// tagged `FractalParams` JSON string, and `fractal_type` is that tag (it is
+/// recorded in the `_summary.json`). Both are supplied by the caller (the CLI
 /// dispatch knows the concrete variant) so this `core` function stays
 /// independent of the `fractals::FractalParams` enum.
 pub fn render<T: Renderable + 'static>(
     renderable: T,
     file_prefix: FilePrefix,
+    fractal_type: &str,
     snapshot_json: impl Fn(&T::Params) -> String,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut stopwatch = Stopwatch::new("Render Stopwatch".to_owned());
 
-    let spec = *renderable.image_specification();
-    let cached_sampling_level = renderable.render_options().sampling_level;
-    let n_max_plus_1 = field_upsample_factor(cached_sampling_level);
-    let histogram_bin_count = renderable.histogram_bin_count();
-    let histogram_max_value = renderable.histogram_max_value();
-    let lookup_table_count = renderable.lookup_table_count();
-
-    write_file_or_panic(
-        file_prefix.full_path_with_suffix(".json"),
-        &snapshot_json(renderable.params()),
-    );
+    let snapshot = snapshot_json(renderable.params());
+    write_file_or_panic(file_prefix.full_path_with_suffix(".json"), &snapshot);
     stopwatch.record_split("basic setup".to_owned());
 
-    let mut pipeline = RenderingPipeline::new(
-        renderable,
-        n_max_plus_1,
-        histogram_bin_count,
-        histogram_max_value,
-        lookup_table_count,
-    );
-    let mut color_image = ColorImage::filled(
-        [spec.resolution[0] as usize, spec.resolution[1] as usize],
-        Color32::BLACK,
-    );
-    pipeline.render(&mut color_image, cached_sampling_level);
+    let (pipeline, color_image) = render_color_image(renderable)?;
     stopwatch.record_split("render pipeline".to_owned());
 
     let imgbuf = color_image_to_rgb8(&color_image);
@@ -312,15 +969,257 @@ pub fn render<T: Renderable + 'static>(
     });
     stopwatch.record_split("write PNG".to_owned());
 
+    if pipeline.fractal().render_options().thumbnail {
+        let [width, height] = thumbnail_dimensions(imgbuf.dimensions().into(), THUMBNAIL_LONG_EDGE);
+        let thumbnail = image::imageops::thumbnail(&imgbuf, width, height);
+        write_image_to_file_or_panic(file_prefix.full_path_with_suffix("_thumb.png"), |f| {
+            thumbnail.save(f)
+        });
+        stopwatch.record_split("write thumbnail".to_owned());
+    }
+
     let mut diagnostics_file = file_prefix.create_file_with_suffix("_diagnostics.txt");
     stopwatch.display(&mut diagnostics_file)?;
+    write_build_info(&mut diagnostics_file)?;
     pipeline
         .fractal()
         .write_diagnostics(&mut diagnostics_file)?;
 
+    let fractal = pipeline.fractal();
+    let [width, height] = fractal.image_specification().resolution;
+    let sampling_level = fractal.render_options().sampling_level;
+    let summary = RenderSummary {
+        fractal_type: fractal_type.to_owned(),
+        resolution: [width, height],
+        total_pixels: width as u64 * height as u64,
+        sampling_level,
+        render_seconds: stopwatch.total_elapsed_seconds(),
+        escaped_fraction: fractal
+            .counts_escapes()
+            .then(|| pipeline.valued_sample_fraction(sampling_level)),
+    };
+    serialize_to_json_or_panic(file_prefix.full_path_with_suffix("_summary.json"), &summary);
+
+    Ok(())
+}
+
+/// Length, in pixels, of the longer edge of the thumbnails written by
+/// `render`.
+pub const THUMBNAIL_LONG_EDGE: u32 = 256;
+
+/// Resolution of a thumbnail of an image at `resolution`: the aspect ratio
+/// is kept and the longer edge shrunk to `long_edge`. Images that are
+/// already that small keep their size.
+pub fn thumbnail_dimensions(resolution: [u32; 2], long_edge: u32) -> [u32; 2] {
+    let scale = (long_edge as f64 / resolution[0].max(resolution[1]) as f64).min(1.0);
+    resolution.map(|edge| ((edge as f64 * scale).round() as u32).max(1))
+}
+
+/// SIMD instruction sets that the compiler was allowed to use in this build.
+/// The renderer has no hand-written SIMD, so these only reach it through
+/// auto-vectorization.
+const SIMD_TARGET_FEATURES: &[(&str, bool)] = &[
+    ("sse2", cfg!(target_feature = "sse2")),
+    ("sse4.1", cfg!(target_feature = "sse4.1")),
+    ("avx", cfg!(target_feature = "avx")),
+    ("avx2", cfg!(target_feature = "avx2")),
+    ("fma", cfg!(target_feature = "fma")),
+    ("neon", cfg!(target_feature = "neon")),
+    ("simd128", cfg!(target_feature = "simd128")),
+];
+
+/// Describe the build and the machine in the diagnostics file, to put
+/// render timings in context.
+fn write_build_info<W: Write>(writer: &mut W) -> io::Result<()> {
+    let simd_features: Vec<&str> = SIMD_TARGET_FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect();
+    writeln!(writer, "Build info:")?;
+    writeln!(writer, "  crate version: {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(
+        writer,
+        "  target: {}-{} ({})",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::FAMILY
+    )?;
+    writeln!(
+        writer,
+        "  profile: {}",
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+    )?;
+    writeln!(writer, "  render threads: {}", rayon::current_num_threads())?;
+    writeln!(
+        writer,
+        "  SIMD target features: {}",
+        if simd_features.is_empty() {
+            "none".to_owned()
+        } else {
+            simd_features.join(", ")
+        }
+    )?;
+    writeln!(writer)
+}
+
+/// Machine-readable companion to the `_diagnostics.txt` file, written by
+/// `render` as `_summary.json` for dashboards and run-to-run comparisons.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RenderSummary {
+    /// Tag of the `FractalParams` variant, e.g. `"Mandelbrot"`.
+    pub fractal_type: String,
+    /// Resolution of the rendered image, in pixels.
+    pub resolution: [u32; 2],
+    /// Number of pixels in the rendered image.
+    pub total_pixels: u64,
+    /// `RenderOptions::sampling_level` the image was rendered at.
+    pub sampling_level: i32,
+    /// Wall-clock time from setup through writing the PNG.
+    pub render_seconds: f64,
+    /// Fraction of the samples that escaped, for fractals where
+    /// `Renderable::counts_escapes` holds (the quadratic maps).
+    pub escaped_fraction: Option<f64>,
+}
+
+/// Render a fractal entirely in memory and return the resulting RGB image.
+/// Runs the same pipeline as `render` at the user's full sampling level, but
+/// writes nothing to disk — useful for embedding the renderer in other tools
+/// or for post-processing the pixels before saving them. Images with more
+/// than `max_pixel_count` pixels are rejected before anything is allocated.
+pub fn render_to_image<T: Renderable>(
+    renderable: T,
+    max_pixel_count: u64,
+) -> Result<image::RgbImage, RenderError> {
+    check_pixel_count(renderable.image_specification().resolution, max_pixel_count)?;
+    let (_, color_image) = render_color_image(renderable)?;
+    Ok(color_image_to_rgb8(&color_image))
+}
+
+/// Default limit on the number of pixels (width x height) of a render: 100
+/// megapixels, e.g. 12000 x 8000. Guards against a typo in the resolution
+/// exhausting memory.
+pub const DEFAULT_MAX_PIXEL_COUNT: u64 = 100_000_000;
+
+/// Reject a `resolution` with more pixels than `max_pixel_count`.
+pub fn check_pixel_count(resolution: [u32; 2], max_pixel_count: u64) -> Result<(), RenderError> {
+    if resolution[0] as u64 * resolution[1] as u64 > max_pixel_count {
+        return Err(RenderError::TooManyPixels {
+            resolution,
+            max_pixel_count,
+        });
+    }
     Ok(())
 }
 
+/// Why a render produced no image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderError {
+    /// A progress callback cancelled the render.
+    Cancelled,
+    /// One of the fractal's color maps or channel curves is invalid.
+    ColorMap(ColorMapError),
+    /// The image has more pixels than the limit; see `check_pixel_count`.
+    TooManyPixels {
+        resolution: [u32; 2],
+        max_pixel_count: u64,
+    },
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::Cancelled => write!(f, "render cancelled by progress callback"),
+            RenderError::ColorMap(error) => write!(f, "invalid color map: {error}"),
+            RenderError::TooManyPixels {
+                resolution: [width, height],
+                max_pixel_count,
+            } => write!(
+                f,
+                "resolution {width} x {height} is {} pixels, more than the limit of \
+                 {max_pixel_count}",
+                *width as u64 * *height as u64
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<ColorMapError> for RenderError {
+    fn from(error: ColorMapError) -> Self {
+        RenderError::ColorMap(error)
+    }
+}
+
+/// Like `render_to_image`, but calls `progress` with the fraction of the
+/// compute pass completed (once per field row, possibly from several
+/// threads at once). Returning `ControlFlow::Break(())` from the callback
+/// abandons the render and yields `Err(RenderError::Cancelled)`; the
+/// compute pass dominates render time, so the callback fires often enough
+/// for a GUI to stay responsive.
+pub fn render_to_image_with_progress<T: Renderable>(
+    renderable: T,
+    max_pixel_count: u64,
+    progress: &ProgressCallback,
+) -> Result<image::RgbImage, RenderError> {
+    check_pixel_count(renderable.image_specification().resolution, max_pixel_count)?;
+    let (mut pipeline, mut color_image, sampling_level) = allocate_pipeline(renderable)?;
+    match pipeline.render_with_progress(&mut color_image, sampling_level, progress)? {
+        ControlFlow::Continue(()) => Ok(color_image_to_rgb8(&color_image)),
+        ControlFlow::Break(()) => Err(RenderError::Cancelled),
+    }
+}
+
+/// Build a `RenderingPipeline` sized for the renderable's cached sampling
+/// level and run one full render into a freshly allocated `ColorImage`. The
+/// pipeline is returned alongside the image so that callers can still reach
+/// the fractal afterwards (e.g. to write diagnostics). The grid overlay, if
+/// enabled, is drawn onto the finished image.
+fn render_color_image<T: Renderable>(
+    renderable: T,
+) -> Result<(RenderingPipeline<T>, ColorImage), RenderError> {
+    let (mut pipeline, mut color_image, sampling_level) = allocate_pipeline(renderable)?;
+    pipeline.render(&mut color_image, sampling_level)?;
+    let fractal = pipeline.fractal();
+    draw_grid_overlay(
+        &fractal.render_options().grid,
+        fractal.image_specification(),
+        &mut color_image,
+    );
+    Ok((pipeline, color_image))
+}
+
+/// Allocate a pipeline and a matching output image for one full-quality
+/// render, returning them along with the renderable's cached sampling level.
+fn allocate_pipeline<T: Renderable>(
+    renderable: T,
+) -> Result<(RenderingPipeline<T>, ColorImage, i32), RenderError> {
+    let spec = *renderable.image_specification();
+    let cached_sampling_level = renderable.render_options().sampling_level;
+    let n_max_plus_1 = field_upsample_factor(cached_sampling_level);
+    let histogram_bin_count = renderable.histogram_bin_count();
+    let histogram_max_value = renderable.histogram_max_value();
+    let lookup_table_count = renderable.lookup_table_count();
+
+    let pipeline = RenderingPipeline::new(
+        renderable,
+        n_max_plus_1,
+        histogram_bin_count,
+        histogram_max_value,
+        lookup_table_count,
+    )?;
+    let color_image = ColorImage::filled(
+        [spec.resolution[0] as usize, spec.resolution[1] as usize],
+        Color32::BLACK,
+    );
+    Ok((pipeline, color_image, cached_sampling_level))
+}
+
 /// Compute the `n_max_plus_1` upsample factor for the field buffer based on
 /// the user's cached sampling level.
 ///
@@ -350,26 +1249,27 @@ pub struct FitImage {
 
 impl FitImage {
     pub fn image_specification(&self, view_rectangle: &ViewRectangle) -> ImageSpecification {
-        let pixel_height = self.resolution[1] as f64;
-        let pixel_width = self.resolution[0] as f64;
-        let dims_height = view_rectangle.dimensions[1];
-        let dims_width = view_rectangle.dimensions[0];
-
-        let aspect_ratio = pixel_height / pixel_width; // of the rendered image
-        let selected_width = if aspect_ratio > (dims_height / dims_width) {
-            dims_width
-        } else {
-            dims_height / aspect_ratio
-        };
-
-        ImageSpecification {
-            resolution: self.resolution,
-            center: view_rectangle.center,
-            width: self.padding_scale * selected_width,
-        }
+        let mut spec = ImageSpecification::from_view_rectangle(
+            view_rectangle,
+            self.resolution,
+            ViewFit::Contain,
+        );
+        spec.width *= self.padding_scale;
+        spec
     }
 }
 
+/// How `ImageSpecification::from_view_rectangle` reconciles a view rectangle
+/// with an image of a different aspect ratio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ViewFit {
+    /// Show the whole rectangle, adding margin along one axis (letterbox).
+    #[default]
+    Contain,
+    /// Fill the image with the rectangle, cropping it along one axis.
+    Cover,
+}
+
 #[derive(Clone, Debug)]
 /**
  * Used to map from image space into the "regular" domain used to generate the fractals.
@@ -484,14 +1384,18 @@ impl UpsampledPixelMapper {
     }
 }
 
-/// Bitmask for a square subpixel grid (max 8 per side). Used by `chaos_game`
-/// to track which subpixels of an output pixel were hit.
+/// Bitmask for a square subpixel grid (max [`SubpixelGridMask::MAX_COUNT_PER_SIDE`]
+/// per side). Used by `chaos_game` to track which subpixels of an output
+/// pixel were hit.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct SubpixelGridMask {
-    bitmask: u64,
+    bitmask: u128,
 }
 
 impl SubpixelGridMask {
+    /// Largest grid whose `count_per_side²` subpixels all fit in the mask.
+    pub const MAX_COUNT_PER_SIDE: u32 = 11;
+
     /// Empty mask.
     pub fn new() -> SubpixelGridMask {
         SubpixelGridMask { bitmask: 0 }
@@ -500,6 +1404,10 @@ impl SubpixelGridMask {
     /// Mark subpixel `coordinate` (in `[0, count_per_side)`²) as hit.
     pub fn insert(&mut self, count_per_side: u32, coordinate: [u32; 2]) {
         let [x, y] = coordinate;
+        assert!(
+            count_per_side <= Self::MAX_COUNT_PER_SIDE,
+            "{count_per_side} subpixels per side overflow the mask"
+        );
         assert!(x < count_per_side);
         assert!(y < count_per_side);
         let index = x * count_per_side + y;
@@ -540,6 +1448,284 @@ mod tests {
     use crate::core::interpolation::{ClampedLinearInterpolator, ClampedLogInterpolator};
     use approx::assert_relative_eq;
 
+    fn resolution_from_json(resolution: &str) -> Result<[u32; 2], serde_json::Error> {
+        let json = format!(r#"{{"resolution": {resolution}, "center": [0.0, 0.0], "width": 1.0}}"#);
+        serde_json::from_str::<ImageSpecification>(&json).map(|spec| spec.resolution)
+    }
+
+    #[test]
+    fn resolution_may_be_given_by_megapixels_or_long_edge() {
+        assert_eq!(resolution_from_json("[640, 480]").unwrap(), [640, 480]);
+        assert_eq!(
+            resolution_from_json(r#"{"long_edge": 3840, "aspect": [16, 9]}"#).unwrap(),
+            [3840, 2160]
+        );
+        assert_eq!(
+            resolution_from_json(r#"{"long_edge": 1000, "aspect": [2, 3]}"#).unwrap(),
+            [667, 1000]
+        );
+        let [width, height] =
+            resolution_from_json(r#"{"megapixels": 12.0, "aspect": [16, 9]}"#).unwrap();
+        let pixel_count = width as u64 * height as u64;
+        assert!(
+            (12_000_000..12_010_000).contains(&pixel_count),
+            "{width} x {height}"
+        );
+        assert!((width as f64 / height as f64 - 16.0 / 9.0).abs() < 1e-3);
+
+        for invalid in [
+            r#"{"megapixels": 0.0, "aspect": [16, 9]}"#,
+            r#"{"long_edge": 100, "aspect": [0, 9]}"#,
+            r#"{"long_edge": 100}"#,
+        ] {
+            assert!(resolution_from_json(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn scale_to_total_pixel_count_keeps_the_aspect_ratio() {
+        let spec = ImageSpecification {
+            resolution: [40, 30],
+            center: [1.0, 2.0],
+            width: 3.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let scaled = spec.scale_to_total_pixel_count(4800, PixelRounding::Ceil);
+        assert_eq!(scaled.resolution, [80, 60]);
+        assert_eq!(scaled.center, spec.center);
+        assert_eq!(scaled.width, spec.width);
+        let round_trip: ImageSpecification =
+            serde_json::from_str(&serde_json::to_string(&scaled).unwrap()).unwrap();
+        assert_eq!(round_trip, scaled);
+    }
+
+    #[test]
+    fn non_positive_view_width_is_rejected_at_load() {
+        let spec_json = |width: &str| {
+            format!(r#"{{"resolution": [8, 6], "center": [0.0, 0.0], "width": {width}}}"#)
+        };
+        assert!(serde_json::from_str::<ImageSpecification>(&spec_json("0.5")).is_ok());
+        for width in ["0.0", "-1.0"] {
+            let error = serde_json::from_str::<ImageSpecification>(&spec_json(width))
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("must be positive"), "{error}");
+        }
+    }
+
+    #[test]
+    fn non_positive_pixel_aspect_ratio_is_rejected_at_load() {
+        let spec_json = |ratio: &str| {
+            format!(
+                r#"{{"resolution": [8, 6], "center": [0.0, 0.0], "width": 1.0, "pixel_aspect_ratio": {ratio}}}"#
+            )
+        };
+        assert!(serde_json::from_str::<ImageSpecification>(&spec_json("2.0")).is_ok());
+        for ratio in ["0.0", "-1.0"] {
+            let error = serde_json::from_str::<ImageSpecification>(&spec_json(ratio))
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("pixel_aspect_ratio"), "{error}");
+        }
+    }
+
+    #[test]
+    fn samples_per_pixel_picks_the_nearest_square_grid() {
+        let levels: Vec<i32> = [1, 2, 4, 9, 10, 16, 20, 64]
+            .into_iter()
+            .map(|samples| sampling_level_for_samples_per_pixel(samples).unwrap())
+            .collect();
+        assert_eq!(levels, [0, 0, 1, 2, 2, 3, 3, 7]);
+        assert!(sampling_level_for_samples_per_pixel(0).is_err());
+
+        let options: RenderOptions =
+            serde_json::from_str(r#"{"samples_per_pixel": 16, "dither": true}"#).unwrap();
+        assert_eq!(options.sampling_level, 3);
+        assert!(options.dither);
+        let saved = serde_json::to_string(&options).unwrap();
+        assert!(saved.contains(r#""sampling_level":3"#), "{saved}");
+        for invalid in [
+            r#"{"dither": true}"#,
+            r#"{"sampling_level": 1, "samples_per_pixel": 4}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<RenderOptions>(invalid).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn thumbnails_keep_the_aspect_ratio_and_never_upscale() {
+        assert_eq!(thumbnail_dimensions([1920, 1080], 256), [256, 144]);
+        assert_eq!(thumbnail_dimensions([1000, 4000], 256), [64, 256]);
+        assert_eq!(thumbnail_dimensions([5000, 2], 256), [256, 1]);
+        assert_eq!(thumbnail_dimensions([200, 100], 256), [200, 100]);
+        let options: RenderOptions = serde_json::from_str(r#"{"sampling_level": 0}"#).unwrap();
+        assert!(options.thumbnail);
+        assert!(RenderOptions::default().thumbnail);
+    }
+
+    #[test]
+    fn screenshot_sampling_level_defaults_to_the_sampling_level() {
+        let options: RenderOptions = serde_json::from_str(r#"{"sampling_level": 1}"#).unwrap();
+        assert_eq!(options.screenshot_sampling_level(), 1);
+        assert!(
+            !serde_json::to_string(&options)
+                .unwrap()
+                .contains("screenshot")
+        );
+        let options: RenderOptions =
+            serde_json::from_str(r#"{"sampling_level": -1, "screenshot_sampling_level": 4}"#)
+                .unwrap();
+        assert_eq!(options.screenshot_sampling_level(), 4);
+    }
+
+    #[test]
+    fn view_rectangle_fits_by_letterbox_or_crop() {
+        let view_rectangle = ViewRectangle {
+            center: [1.0, -1.0],
+            dimensions: [4.0, 1.0],
+        };
+        // A square image: containing the wide rectangle adds margin above
+        // and below, covering crops its sides.
+        let contain =
+            ImageSpecification::from_view_rectangle(&view_rectangle, [100, 100], ViewFit::Contain);
+        assert_eq!(
+            (contain.center, contain.width, contain.height()),
+            ([1.0, -1.0], 4.0, 4.0)
+        );
+        let cover =
+            ImageSpecification::from_view_rectangle(&view_rectangle, [100, 100], ViewFit::Cover);
+        assert_eq!((cover.width, cover.height()), (1.0, 1.0));
+        // A matching aspect ratio fits exactly either way.
+        for fit in [ViewFit::Contain, ViewFit::Cover] {
+            let exact = ImageSpecification::from_view_rectangle(&view_rectangle, [400, 100], fit);
+            assert_eq!((exact.width, exact.height()), (4.0, 1.0));
+        }
+        let padded = FitImage {
+            resolution: [100, 100],
+            padding_scale: 1.5,
+        }
+        .image_specification(&view_rectangle);
+        assert_eq!(padded.width, 6.0);
+    }
+
+    #[test]
+    fn view_may_be_given_by_explicit_ranges() {
+        let spec = ImageSpecification::from_ranges([250, 100], [-2.0, 0.5], [1.0, 0.0]).unwrap();
+        assert_eq!(spec.center, [-0.75, 0.5]);
+        assert_eq!(spec.width, 2.5);
+        assert_relative_eq!(spec.height(), 1.0, epsilon = 1e-12);
+        let mapper = PixelMapper::new(&spec);
+        assert_relative_eq!(mapper.width.map(0), -2.0, epsilon = 1e-12);
+        assert_relative_eq!(mapper.width.map(249), 0.5, epsilon = 1e-12);
+        assert_relative_eq!(mapper.height.map(99), 0.0, epsilon = 1e-12);
+
+        for (x_range, y_range) in [([1.0, 1.0], [0.0, 1.0]), ([0.0, 1.0], [0.0, f64::NAN])] {
+            assert!(ImageSpecification::from_ranges([10, 10], x_range, y_range).is_err());
+        }
+
+        // In a params file, `x_range` takes precedence over `center[0]` and
+        // `width`, while `center[1]` still applies.
+        let spec: ImageSpecification = serde_json::from_str(
+            r#"{"resolution": [10, 10], "center": [9.0, 3.0], "width": 9.0, "x_range": [0.0, 4.0]}"#,
+        )
+        .unwrap();
+        assert_eq!(spec.center, [2.0, 3.0]);
+        assert_eq!(spec.width, 4.0);
+        let missing_center = serde_json::from_str::<ImageSpecification>(
+            r#"{"resolution": [10, 10], "x_range": [0.0, 4.0]}"#,
+        );
+        assert!(missing_center.unwrap_err().to_string().contains("center"));
+    }
+
+    #[test]
+    fn resolution_beyond_the_pixel_limit_is_rejected() {
+        assert!(check_pixel_count([1920, 1080], DEFAULT_MAX_PIXEL_COUNT).is_ok());
+        assert!(check_pixel_count([10_000, 10_000], DEFAULT_MAX_PIXEL_COUNT).is_ok());
+        let error = check_pixel_count([80_000, 60_000], DEFAULT_MAX_PIXEL_COUNT).unwrap_err();
+        assert!(error.to_string().contains("4800000000 pixels"), "{error}");
+        assert!(check_pixel_count([80_000, 60_000], 4_800_000_000).is_ok());
+    }
+
+    #[test]
+    fn pixel_rounding_brackets_the_target_pixel_count() {
+        let pixel_count = |resolution: [u32; 2]| resolution[0] as u64 * resolution[1] as u64;
+        for (aspect, target) in [([16, 9], 12_000_000), ([3, 2], 1000), ([7, 5], 12_345)] {
+            let scaled = |rounding| scale_resolution_to_total_pixel_count(aspect, target, rounding);
+            let [ceil, round, floor] = [
+                PixelRounding::Ceil,
+                PixelRounding::Round,
+                PixelRounding::Floor,
+            ]
+            .map(|rounding| pixel_count(scaled(rounding)));
+            assert!(floor <= target && target <= ceil, "{aspect:?} {target}");
+            assert!(floor <= round && round <= ceil, "{aspect:?} {target}");
+        }
+        let budget: Result<[u32; 2], _> =
+            resolution_from_json(r#"{"megapixels": 2.0, "aspect": [16, 9], "rounding": "Floor"}"#);
+        assert!(pixel_count(budget.unwrap()) <= 2_000_000);
+    }
+
+    #[test]
+    fn build_info_reports_version_threads_and_target() {
+        let mut info = Vec::new();
+        write_build_info(&mut info).unwrap();
+        let info = String::from_utf8(info).unwrap();
+        assert!(
+            info.contains(&format!("crate version: {}", env!("CARGO_PKG_VERSION"))),
+            "{info}"
+        );
+        assert!(
+            info.contains(&format!("render threads: {}", rayon::current_num_threads())),
+            "{info}"
+        );
+        assert!(info.contains(std::env::consts::ARCH), "{info}");
+        assert!(info.contains("SIMD target features: "), "{info}");
+    }
+
+    #[test]
+    fn subpixel_grid_mask_counts_every_subpixel_of_the_largest_grid() {
+        let count = SubpixelGridMask::MAX_COUNT_PER_SIDE;
+        let mut mask = SubpixelGridMask::new();
+        for x in 0..count {
+            for y in 0..count {
+                mask.insert(count, [x, y]);
+                // Re-inserting a subpixel must not change the count.
+                mask.insert(count, [x, y]);
+            }
+        }
+        assert_eq!(mask.count_ones(), count * count);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow the mask")]
+    fn subpixel_grid_mask_rejects_grids_too_large_to_store() {
+        let count = SubpixelGridMask::MAX_COUNT_PER_SIDE + 1;
+        SubpixelGridMask::new().insert(count, [count - 1, count - 1]);
+    }
+
+    #[test]
+    fn downscale_filter_weights_peak_at_center_and_vanish_past_radius() {
+        for filter in [
+            DownscaleFilter::Box,
+            DownscaleFilter::Tent,
+            DownscaleFilter::Lanczos,
+        ] {
+            assert_relative_eq!(filter.weight(0.0), 1.0);
+            assert_eq!(filter.weight(filter.radius() + 0.01), 0.0, "{filter:?}");
+            assert_eq!(filter.weight(0.3), filter.weight(-0.3), "{filter:?}");
+        }
+        assert_relative_eq!(DownscaleFilter::Tent.weight(0.25), 0.75);
+        // Lanczos has zeros at whole-pixel offsets and a negative lobe
+        // between them.
+        assert!(DownscaleFilter::Lanczos.weight(1.0).abs() < 1e-6);
+        assert!(DownscaleFilter::Lanczos.weight(1.5) < 0.0);
+    }
+
     #[test]
     fn test_view_port_from_vertices() {
         let vertices = vec![[1.0, 2.0], [3.0, 5.0], [-1.0, -2.0], [2.0, 3.0]];
@@ -550,6 +1736,44 @@ mod tests {
         assert_eq!(view_rectangle.dimensions, [4.0, 7.0]);
     }
 
+    #[test]
+    fn view_string_round_trips_exactly() {
+        let spec = ImageSpecification {
+            resolution: [1920, 1080],
+            center: [-0.743643887037151, 0.131825904205330],
+            width: 1.5e-9,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let encoded = spec.to_view_string();
+        assert!(
+            encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+        let decoded = ImageSpecification::from_view_string(&encoded).unwrap();
+        assert_eq!(decoded.resolution, spec.resolution);
+        assert_eq!(decoded.center, spec.center);
+        assert_eq!(decoded.width, spec.width);
+    }
+
+    #[test]
+    fn view_string_rejects_malformed_input() {
+        assert!(ImageSpecification::from_view_string("not base64!").is_err());
+        assert!(ImageSpecification::from_view_string("AAAA").is_err());
+
+        let zero_width = ImageSpecification {
+            resolution: [10, 10],
+            center: [0.0, 0.0],
+            width: 0.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        assert!(ImageSpecification::from_view_string(&zero_width.to_view_string()).is_err());
+    }
+
     #[test]
     #[should_panic(expected = "assertion failed")]
     fn test_view_port_empty_vertices() {
@@ -563,6 +1787,9 @@ mod tests {
             resolution: [5, 23],
             center: [2.6, 3.4],
             width: 8.5,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
         };
 
         // The `height` is defined S.T. that aspect ratio is identical in both the image and the regular space.
@@ -572,6 +1799,34 @@ mod tests {
         assert_eq!(aspect_ratio, pixel_aspect_ratio);
     }
 
+    #[test]
+    fn pixel_aspect_ratio_scales_the_real_space_aspect_ratio() {
+        let image_specification = ImageSpecification {
+            resolution: [40, 30],
+            center: [-1.0, 0.5],
+            width: 2.0,
+            pixel_aspect_ratio: 1.5,
+            min_width: None,
+            max_width: None,
+        };
+        let resolution_aspect =
+            (image_specification.resolution[1] as f64) / (image_specification.resolution[0] as f64);
+        let real_aspect = image_specification.height() / image_specification.width;
+        assert_relative_eq!(real_aspect, 1.5 * resolution_aspect, epsilon = 1e-12);
+
+        // The pixel mapper spans the stretched height, and subpixel upsampling
+        // keeps the same real-space extent.
+        let mapper = PixelMapper::new(&image_specification);
+        let mapped_height = mapper.height.map(0) - mapper.height.map(29);
+        assert_relative_eq!(mapped_height, image_specification.height(), epsilon = 1e-12);
+        let upsampled = image_specification.upsample(3);
+        assert_relative_eq!(
+            upsampled.height(),
+            image_specification.height(),
+            epsilon = 1e-12
+        );
+    }
+
     #[test]
     fn test_linear_pixel_map_domain_bounds_pos() {
         let n = 7;
@@ -678,4 +1933,33 @@ mod tests {
         assert_relative_eq!(up_lin, 0.5 * (cached + upper), epsilon = 1e-15);
         assert!(up_lin > up_log);
     }
+
+    #[test]
+    fn crop_pixels_land_on_the_full_image_pixels() {
+        let full = ImageSpecification {
+            resolution: [41, 31],
+            center: [-0.5, 0.25],
+            width: 4.0,
+            pixel_aspect_ratio: 1.5,
+            min_width: None,
+            max_width: None,
+        };
+        let crop = full.crop([10, 5], [8, 12]).unwrap();
+        assert_eq!(crop.resolution, [8, 12]);
+        let full_mapper = PixelMapper::new(&full);
+        let crop_mapper = PixelMapper::new(&crop);
+        for (x, y) in [(0, 0), (7, 11), (3, 6)] {
+            let (crop_x, crop_y) = crop_mapper.map(&(x, y));
+            let (full_x, full_y) = full_mapper.map(&(x + 10, y + 5));
+            assert_relative_eq!(crop_x, full_x, epsilon = 1e-12);
+            assert_relative_eq!(crop_y, full_y, epsilon = 1e-12);
+        }
+        let whole = full.crop([0, 0], [41, 31]).unwrap();
+        assert_relative_eq!(whole.width, full.width, epsilon = 1e-12);
+        assert_relative_eq!(whole.height(), full.height(), epsilon = 1e-12);
+
+        assert!(full.crop([40, 0], [2, 2]).is_err());
+        assert!(full.crop([0, 0], [1, 5]).is_err());
+        assert!(full.crop([0, u32::MAX], [2, 2]).is_err());
+    }
 }
@@ -10,6 +10,8 @@ use std::time::Duration;
 use egui::{self, Color32, ColorImage, Frame, Key, Pos2, Rect, Sense};
 
 use crate::core::{
+    color_map::ColorMapError,
+    controller::TrackingMode,
     eframe_support::wgpu_native_options,
     file_io::FilePrefix,
     image_utils::{ImageSpecification, PixelMapper, Renderable},
@@ -26,10 +28,16 @@ use crate::core::{
 const ZOOM_RATE: f64 = 0.4;
 /// "Fast" zoom rate, triggered by the A/D keys when W/S are idle.
 const FAST_ZOOM_RATE: f64 = 4.0 * ZOOM_RATE;
+/// Limit on the change in zoom rate, in natural-log-of-view-width per second
+/// squared. Ramps the fast zoom in and out over a quarter second.
+const MAX_ZOOM_ACCELERATION: f64 = 4.0 * FAST_ZOOM_RATE;
 /// Pan rate while arrow keys are held, in view-widths per second.
 const PAN_RATE: f64 = 0.2;
 /// Pan rate when servoing toward a click target.
 const FAST_PAN_RATE: f64 = 2.5 * PAN_RATE;
+/// Time constant, in seconds, of the critically-damped easing used when
+/// servoing toward a click target.
+const PAN_SMOOTHING_TIME_CONSTANT: f64 = 0.25;
 
 /// Minimum repaint period while the user is interacting or a render is in
 /// flight. 100 Hz is faster than any common vsync cap, so the actual cadence
@@ -90,15 +98,15 @@ fn keyboard_center_command(ctx: &egui::Context) -> CenterCommand {
     })
 }
 
-/// Convert a click in screen-space to a `CenterCommand` that recenters the
-/// view on the fractal coordinate under the cursor.
-fn click_to_center_command(
-    click_pos: Pos2,
+/// Real-space coordinate of the pixel under a screen-space position inside
+/// the preview image. Positions outside the image clamp to its edge.
+fn screen_to_point(
+    screen_pos: Pos2,
     image_rect: Rect,
     image_specification: &ImageSpecification,
-) -> CenterCommand {
-    let normalized_x = ((click_pos.x - image_rect.min.x) / image_rect.width()).clamp(0.0, 1.0);
-    let normalized_y = ((click_pos.y - image_rect.min.y) / image_rect.height()).clamp(0.0, 1.0);
+) -> [f64; 2] {
+    let normalized_x = ((screen_pos.x - image_rect.min.x) / image_rect.width()).clamp(0.0, 1.0);
+    let normalized_y = ((screen_pos.y - image_rect.min.y) / image_rect.height()).clamp(0.0, 1.0);
 
     let max_x = image_specification.resolution[0].saturating_sub(1);
     let max_y = image_specification.resolution[1].saturating_sub(1);
@@ -107,12 +115,32 @@ fn click_to_center_command(
         ((normalized_y * image_specification.resolution[1] as f32) as u32).min(max_y),
     );
     let (x, y) = PixelMapper::new(image_specification).map(&pixel);
+    [x, y]
+}
+
+/// Convert a click in screen-space to a `CenterCommand` that recenters the
+/// view on the fractal coordinate under the cursor.
+fn click_to_center_command(
+    click_pos: Pos2,
+    image_rect: Rect,
+    image_specification: &ImageSpecification,
+) -> CenterCommand {
     CenterCommand::Target(CenterTargetCommand {
-        view_center: [x, y],
+        view_center: screen_to_point(click_pos, image_rect, image_specification),
         pan_rate: FAST_PAN_RATE,
     })
 }
 
+/// Where the pointer is over the preview image this frame.
+struct PreviewPointer {
+    /// Rect the image occupies on screen.
+    rect: Rect,
+    /// Pointer position, if the pointer is over the image.
+    hover: Option<Pos2>,
+    /// Click position, if the image was clicked.
+    click: Option<Pos2>,
+}
+
 fn any_control_key_held(ctx: &egui::Context) -> bool {
     const KEYS: &[Key] = &[
         Key::W,
@@ -139,6 +167,8 @@ struct FractalApp<F: Renderable> {
     display_image: ColorImage,
     /// Selection state for the color-editor side panel.
     editor_state: EditorState,
+    /// Show the real-space coordinate under the cursor; toggled with `C`.
+    show_cursor_coordinates: bool,
 }
 
 impl<F: Renderable + Send + Sync + 'static> FractalApp<F> {
@@ -148,7 +178,7 @@ impl<F: Renderable + Send + Sync + 'static> FractalApp<F> {
         image_specification: ImageSpecification,
         renderer: F,
         serialize_snapshot: SnapshotSerializer<F>,
-    ) -> Self {
+    ) -> Result<Self, ColorMapError> {
         // Match the color editor's theme: black panel fill + no separator
         // stroke avoids sub-pixel gap artifacts between panels at fractional
         // DPI.
@@ -159,13 +189,18 @@ impl<F: Renderable + Send + Sync + 'static> FractalApp<F> {
 
         let stopwatch = Stopwatch::new("Fractal Explorer".to_string());
         let time = stopwatch.total_elapsed_seconds();
+        let mut view_control = ViewControl::new(time, image_specification);
+        view_control.set_pan_tracking_mode(TrackingMode::CriticallyDamped {
+            time_constant: PAN_SMOOTHING_TIME_CONSTANT,
+        });
+        view_control.set_max_zoom_acceleration(Some(MAX_ZOOM_ACCELERATION));
         let render_window = PixelGrid::new(
             time,
             file_prefix,
-            ViewControl::new(time, image_specification),
+            view_control,
             renderer,
             serialize_snapshot,
-        );
+        )?;
 
         let [res_w, res_h] = image_specification.resolution;
         let display_image = ColorImage::filled([res_w as usize, res_h as usize], Color32::BLACK);
@@ -175,19 +210,19 @@ impl<F: Renderable + Send + Sync + 'static> FractalApp<F> {
             egui::TextureOptions::LINEAR,
         );
 
-        Self {
+        Ok(Self {
             render_window,
             stopwatch,
             texture,
             display_image,
             editor_state: EditorState::default(),
-        }
+            show_cursor_coordinates: false,
+        })
     }
 
     /// Show the fractal preview, centered in the available space with its
-    /// aspect ratio preserved. Returns the click position (if any) along with
-    /// the rect the image occupies on screen.
-    fn show_preview(&self, ui: &mut egui::Ui) -> Option<(Pos2, Rect)> {
+    /// aspect ratio preserved. Returns where the pointer is over the image.
+    fn show_preview(&self, ui: &mut egui::Ui) -> PreviewPointer {
         let resolution = self.render_window.image_specification().resolution;
         let aspect = resolution[0] as f32 / resolution[1] as f32;
         let available = ui.available_size();
@@ -197,7 +232,6 @@ impl<F: Renderable + Send + Sync + 'static> FractalApp<F> {
             (available.x, available.x / aspect.max(f32::EPSILON))
         };
 
-        let mut click = None;
         ui.centered_and_justified(|ui| {
             let (rect, response) =
                 ui.allocate_exact_size(egui::vec2(display_w, display_h), Sense::click());
@@ -207,13 +241,16 @@ impl<F: Renderable + Send + Sync + 'static> FractalApp<F> {
                 Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
                 Color32::WHITE,
             );
-            if response.clicked()
-                && let Some(pos) = response.interact_pointer_pos()
-            {
-                click = Some((pos, rect));
+            PreviewPointer {
+                rect,
+                hover: response.hover_pos(),
+                click: response
+                    .clicked()
+                    .then(|| response.interact_pointer_pos())
+                    .flatten(),
             }
-        });
-        click
+        })
+        .inner
     }
 }
 
@@ -279,6 +316,20 @@ impl<F: Renderable + 'static> eframe::App for FractalApp<F> {
                 self.render_window.reset();
                 self.editor_state.selected_keyframe = None;
             }
+
+            // `V` copies the current view as a shareable string (accepted by
+            // `--view` on `render` / `explore`) and prints it to the terminal.
+            if ctx.input(|i| i.key_pressed(Key::V)) {
+                let view = self.render_window.image_specification().to_view_string();
+                println!("INFO:  View: {view}");
+                ctx.copy_text(view);
+            }
+
+            // `C` toggles the readout of the real-space coordinate under the
+            // cursor. `Ctrl+C` quits, above.
+            if ctx.input(|i| i.key_pressed(Key::C)) {
+                self.show_cursor_coordinates = !self.show_cursor_coordinates;
+            }
         }
 
         let mut palette_changed = false;
@@ -304,7 +355,7 @@ impl<F: Renderable + 'static> eframe::App for FractalApp<F> {
             ctx.request_repaint();
         }
 
-        let click = egui::CentralPanel::default()
+        let pointer = egui::CentralPanel::default()
             .frame(Frame::NONE.fill(Color32::BLACK))
             .show_inside(ui, |ui| self.show_preview(ui))
             .inner;
@@ -316,8 +367,29 @@ impl<F: Renderable + 'static> eframe::App for FractalApp<F> {
             (CenterCommand::Idle(), ZoomVelocityCommand::zero())
         } else {
             let image_specification = *self.render_window.image_specification();
-            let center_command = match click {
-                Some((pos, rect)) => click_to_center_command(pos, rect, &image_specification),
+            // `M` marks the spot under the cursor: recenter on it, like a
+            // click, and write a params file for it.
+            let mark = pointer
+                .hover
+                .filter(|_| ctx.input(|i| i.key_pressed(Key::M)));
+            if let Some(pos) = mark {
+                let center = screen_to_point(pos, pointer.rect, &image_specification);
+                let path = self.render_window.write_location(center);
+                println!("INFO:  Wrote location params to: {}", path.display());
+            }
+            // `T` plots the phase-space trajectory from the spot under the
+            // cursor, for the fractals that have one.
+            if let Some(pos) = pointer
+                .hover
+                .filter(|_| ctx.input(|i| i.key_pressed(Key::T)))
+            {
+                let point = screen_to_point(pos, pointer.rect, &image_specification);
+                if self.render_window.write_phase_space_plot(point).is_none() {
+                    println!("INFO:  This fractal has no phase-space trajectory to plot.");
+                }
+            }
+            let center_command = match pointer.click.or(mark) {
+                Some(pos) => click_to_center_command(pos, pointer.rect, &image_specification),
                 None => keyboard_center_command(&ctx),
             };
             (center_command, zoom_command_from_input(&ctx))
@@ -334,6 +406,16 @@ impl<F: Renderable + 'static> eframe::App for FractalApp<F> {
                 .set(self.display_image.clone(), egui::TextureOptions::LINEAR);
         }
 
+        if self.show_cursor_coordinates
+            && let Some(hover) = pointer.hover
+        {
+            let image_specification = self.render_window.image_specification();
+            let point = screen_to_point(hover, pointer.rect, image_specification);
+            let decimals =
+                coordinate_decimals(image_specification.width, image_specification.resolution[0]);
+            draw_cursor_coordinates(&ctx, point, decimals);
+        }
+
         // "Saving snapshot…" overlay while the gated render is in flight.
         if self.render_window.is_saving() {
             draw_saving_overlay(&ctx);
@@ -376,6 +458,37 @@ fn draw_saving_overlay(ctx: &egui::Context) {
         });
 }
 
+/// Number of decimal places that resolve one pixel-sized step at this
+/// zoom, so the readout neither hides nor invents precision.
+fn coordinate_decimals(view_width: f64, resolution_x: u32) -> usize {
+    let pixel_width = view_width / resolution_x.max(1) as f64;
+    (-pixel_width.log10().floor()).clamp(0.0, 17.0) as usize
+}
+
+/// Paint the real-space coordinate under the cursor in the top-left corner
+/// of the window.
+fn draw_cursor_coordinates(ctx: &egui::Context, point: [f64; 2], decimals: usize) {
+    egui::Area::new(egui::Id::new("cursor_coordinates"))
+        .anchor(egui::Align2::LEFT_TOP, egui::vec2(8.0, 8.0))
+        .order(egui::Order::Foreground)
+        .interactable(false)
+        .show(ctx, |ui| {
+            Frame::NONE
+                .fill(Color32::from_black_alpha(180))
+                .inner_margin(egui::Margin::same(6))
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "x: {:.*}\ny: {:.*}",
+                            decimals, point[0], decimals, point[1]
+                        ))
+                        .monospace()
+                        .color(Color32::WHITE),
+                    );
+                });
+        });
+}
+
 /// Open the interactive fractal explorer window.
 ///
 /// Controls:
@@ -384,6 +497,13 @@ fn draw_saving_overlay(ctx: &egui::Context) {
 /// - Left click: recenter the view on the clicked point.
 /// - `R`: reset to the initial view and color palette.
 /// - `Space`: save the current frame to disk (alongside its parameter JSON).
+/// - `V`: copy the current view string to the clipboard (and print it); pass
+///   it back with `--view` to reproduce the view.
+/// - `C`: toggle a readout of the real-space coordinate under the cursor.
+/// - `M`: recenter on the point under the cursor and write a params file for
+///   it at the current zoom, ready for the `render` command.
+/// - `T`: plot the phase-space trajectory from the point under the cursor
+///   (driven-damped pendulum only).
 /// - Click a keyframe in the editor to edit its color; `+` inserts, the drag
 ///   values set segment widths.
 /// - `Esc`: clear the keyframe selection. `Delete`: remove the selected
@@ -414,7 +534,37 @@ pub fn explore<F: Renderable + Send + Sync + 'static>(
                 image_specification,
                 renderer,
                 Box::new(serialize_snapshot),
-            )))
+            )?))
         }),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn screen_positions_map_to_the_real_coordinates_of_their_pixels() {
+        let image_specification = ImageSpecification {
+            resolution: [5, 3],
+            center: [1.0, -1.0],
+            width: 4.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let rect = Rect::from_min_max(Pos2::new(100.0, 50.0), Pos2::new(150.0, 80.0));
+        let point = |x, y| screen_to_point(Pos2::new(x, y), rect, &image_specification);
+        let assert_point = |actual: [f64; 2], expected: [f64; 2]| {
+            assert_relative_eq!(actual[0], expected[0], epsilon = 1e-12);
+            assert_relative_eq!(actual[1], expected[1], epsilon = 1e-12);
+        };
+        assert_point(point(100.0, 50.0), [-1.0, 0.2]);
+        assert_point(point(125.0, 65.0), [1.0, -1.0]);
+        // Outside the image clamps to the nearest edge pixel.
+        assert_point(point(500.0, 500.0), [3.0, -2.2]);
+        assert_eq!(coordinate_decimals(4.0, 400), 2);
+        assert_eq!(coordinate_decimals(1e-9, 1000), 12);
+    }
+}
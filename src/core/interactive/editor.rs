@@ -278,10 +278,9 @@ pub fn delete_keyframe(map: &mut ColorMap, index: usize) -> bool {
 /// fractional DPI, 1-logical-pixel strokes anti-alias across two physical
 /// pixels and leave visible gaps; adjacent rects avoid that artifact.
 fn paint_gradient_bar(painter: &egui::Painter, rect: egui::Rect, keyframes: &[ColorMapKeyFrame]) {
-    if keyframes.is_empty() {
+    let Ok(color_map) = KeyframeColorMap::new(keyframes, LinearInterpolator {}) else {
         return;
-    }
-    let color_map = KeyframeColorMap::new(keyframes, LinearInterpolator {});
+    };
     let column_count = (rect.width() as u32).max(2);
     let t_step = 1.0 / (column_count - 1) as f32;
     let step_w = rect.width() / column_count as f32;
@@ -204,6 +204,45 @@ where
     }
 }
 
+/// Step interpolation: holds `low` until `alpha` reaches 1, then `upp`.
+/// Turns a set of keyframes into a piecewise-constant staircase.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct StepInterpolator;
+
+impl<T, V> Interpolator<T, V> for StepInterpolator
+where
+    T: Float + Copy,
+    V: Copy + Add<Output = V> + Sub<Output = V> + Mul<T, Output = V>,
+{
+    fn interpolate(&self, alpha: T, low: V, upp: V) -> V {
+        if alpha < T::one() { low } else { upp }
+    }
+}
+
+/// Soft step interpolation: holds `low` for the first `1 - softness` of the
+/// interval, then ramps linearly up to `upp`. A `softness` of 0 is a
+/// [`StepInterpolator`] and 1 a [`ClampedLinearInterpolator`].
+#[derive(Default, Clone, Copy, Debug)]
+pub struct SoftStepInterpolator {
+    /// Fraction of each interval, in `[0, 1]`, spent blending.
+    pub softness: f64,
+}
+
+impl<T, V> Interpolator<T, V> for SoftStepInterpolator
+where
+    T: Float + Copy,
+    V: Copy + Add<Output = V> + Sub<Output = V> + Mul<T, Output = V>,
+{
+    fn interpolate(&self, alpha: T, low: V, upp: V) -> V {
+        let softness = T::from(self.softness.clamp(0.0, 1.0)).unwrap();
+        let ramp_start = T::one() - softness;
+        if alpha <= ramp_start {
+            return StepInterpolator.interpolate(alpha, low, upp);
+        }
+        ClampedLinearInterpolator.interpolate((alpha - ramp_start) / softness, low, upp)
+    }
+}
+
 //////////////////////////////////////////////////////////////////////////////
 #[cfg(test)]
 mod tests {
@@ -460,4 +499,44 @@ mod tests {
         let mut interp = make_test_scalar_interpolator();
         interp.set_keyframe_query(1, 100.0);
     }
+
+    #[test]
+    fn test_step_interpolator_holds_the_lower_keyframe() {
+        let keyframes = (0..=4)
+            .map(|k| InterpolationKeyframe {
+                query: k as f32,
+                value: 10.0 * k as f32,
+            })
+            .collect();
+        let interp = KeyframeInterpolator::new(keyframes, StepInterpolator);
+        assert_eq!(interp.evaluate(-1.0), 0.0);
+        assert_eq!(interp.evaluate(0.0), 0.0);
+        assert_eq!(interp.evaluate(0.99), 0.0);
+        assert_eq!(interp.evaluate(1.0), 10.0);
+        assert_eq!(interp.evaluate(3.5), 30.0);
+        assert_eq!(interp.evaluate(4.0), 40.0);
+        assert_eq!(interp.evaluate(9.0), 40.0);
+    }
+
+    #[test]
+    fn test_soft_step_interpolator_blends_the_end_of_each_step() {
+        let hard = SoftStepInterpolator { softness: 0.0 };
+        let soft = SoftStepInterpolator { softness: 0.25 };
+        let smooth = SoftStepInterpolator { softness: 1.0 };
+        for alpha in [0.0, 0.3, 0.74, 0.8, 0.9, 0.999, 1.0] {
+            assert_eq!(
+                hard.interpolate(alpha, 10.0, 20.0),
+                StepInterpolator.interpolate(alpha, 10.0, 20.0)
+            );
+            assert_relative_eq!(
+                smooth.interpolate(alpha, 10.0, 20.0),
+                10.0 + 10.0 * alpha,
+                epsilon = 1e-12
+            );
+        }
+        assert_eq!(soft.interpolate(0.5, 10.0, 20.0), 10.0);
+        assert_eq!(soft.interpolate(0.75, 10.0, 20.0), 10.0);
+        assert_relative_eq!(soft.interpolate(0.875, 10.0, 20.0), 15.0, epsilon = 1e-12);
+        assert_eq!(soft.interpolate(1.0, 10.0, 20.0), 20.0);
+    }
 }
@@ -1,9 +1,9 @@
 use iter_num_tools::lin_space;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct LookupTable<T: Clone> {
     table_entries: Vec<T>,
-    query_offset: f32,
+    query_domain: [f32; 2],
     query_to_index_scale: f32,
 }
 
@@ -17,19 +17,48 @@ impl<T: Clone> LookupTable<T> {
         let nominal_value = query_to_data(0.5 * (query_domain[0] + query_domain[1]));
         let mut lookup_table = LookupTable {
             table_entries: vec![nominal_value; entry_count],
-            query_offset: 0.0,
+            query_domain,
             query_to_index_scale: 1.0,
         };
         lookup_table.reset(query_domain, query_to_data);
         lookup_table
     }
 
+    /// Wraps a table that was computed elsewhere (e.g. loaded from disk), where
+    /// `table_entries` are evenly spaced over `query_domain`, endpoints included.
+    pub fn from_entries(query_domain: [f32; 2], table_entries: Vec<T>) -> Result<Self, String> {
+        validate_query_domain(query_domain)?;
+        if table_entries.is_empty() {
+            return Err("lookup table must have at least one entry".to_owned());
+        }
+        Ok(LookupTable {
+            query_to_index_scale: query_to_index_scale(query_domain, table_entries.len()),
+            table_entries,
+            query_domain,
+        })
+    }
+
+    /// Interval of queries covered by the table; queries outside it clamp to
+    /// the first or last entry.
+    pub fn query_domain(&self) -> [f32; 2] {
+        self.query_domain
+    }
+
+    /// Entries of the table, evenly spaced over `query_domain`.
+    pub fn entries(&self) -> &[T] {
+        &self.table_entries
+    }
+
     /// Updates the table in-place, without allocating. Overwrites all data in the existing table.
+    /// The domain may be any finite, increasing interval; the first and last entries are sampled
+    /// exactly at its endpoints.
     pub fn reset<F>(&mut self, query_domain: [f32; 2], query_to_data: F)
     where
         F: Fn(f32) -> T,
     {
-        assert!(query_domain[1] > query_domain[0]);
+        if let Err(message) = validate_query_domain(query_domain) {
+            panic!("{message}");
+        }
 
         let entry_count = self.table_entries.len();
         let queries = lin_space(query_domain[0]..=query_domain[1], entry_count);
@@ -38,16 +67,49 @@ impl<T: Clone> LookupTable<T> {
             self.table_entries[i] = query_to_data(query);
         }
 
-        self.query_offset = query_domain[0];
-        self.query_to_index_scale = (entry_count as f32) / (query_domain[1] - query_domain[0]);
+        self.query_domain = query_domain;
+        self.query_to_index_scale = query_to_index_scale(query_domain, entry_count);
     }
 
     /// @return the table entry corresponding to the query. Out-of-bound requests will be clamped to the domain of the table.
     pub fn lookup(&self, query: f32) -> T {
-        let index = (((query - self.query_offset) * self.query_to_index_scale) as i32)
+        let index = (((query - self.query_domain[0]) * self.query_to_index_scale) as i32)
             .clamp(0, self.table_entries.len() as i32 - 1);
         self.table_entries[index as usize].clone()
     }
+
+    /// @return the two table entries on either side of the query, and the fraction of the way
+    /// from the first to the second. Out-of-bound requests will be clamped to the domain of the
+    /// table.
+    pub fn lookup_bracket(&self, query: f32) -> (T, T, f32) {
+        let last = self.table_entries.len() - 1;
+        let position = ((query - self.query_domain[0])
+            / (self.query_domain[1] - self.query_domain[0])
+            * last as f32)
+            .max(0.0)
+            .min(last as f32);
+        let index = (position as usize).min(last.saturating_sub(1));
+        let upper = (index + 1).min(last);
+        (
+            self.table_entries[index].clone(),
+            self.table_entries[upper].clone(),
+            position - index as f32,
+        )
+    }
+}
+
+fn validate_query_domain(query_domain: [f32; 2]) -> Result<(), String> {
+    if query_domain.iter().all(|x| x.is_finite()) && query_domain[1] > query_domain[0] {
+        Ok(())
+    } else {
+        Err(format!(
+            "lookup table query domain must be finite and increasing, got {query_domain:?}"
+        ))
+    }
+}
+
+fn query_to_index_scale(query_domain: [f32; 2], entry_count: usize) -> f32 {
+    (entry_count as f32) / (query_domain[1] - query_domain[0])
 }
 
 #[cfg(test)]
@@ -91,4 +153,18 @@ mod tests {
         assert_eq!(lookup_table.lookup(-2.5), 10);
         assert_eq!(lookup_table.lookup(15.0), 16);
     }
+
+    #[test]
+    fn lookup_bracket_interpolates_between_entries_and_clamps() {
+        // Entries 0, 10, 20, 30 at queries 0, 1, 2, 3.
+        let lookup_table = LookupTable::new([0.0, 3.0], 4, |x: f32| (10.0 * x) as i32);
+        assert_eq!(lookup_table.lookup_bracket(0.0), (0, 10, 0.0));
+        assert_eq!(lookup_table.lookup_bracket(1.25), (10, 20, 0.25));
+        assert_eq!(lookup_table.lookup_bracket(3.0), (20, 30, 1.0));
+        assert_eq!(lookup_table.lookup_bracket(-1.0), (0, 10, 0.0));
+        assert_eq!(lookup_table.lookup_bracket(7.0), (20, 30, 1.0));
+
+        let single_entry = LookupTable::new([0.0, 1.0], 1, |_| 5);
+        assert_eq!(single_entry.lookup_bracket(0.7), (5, 5, 0.0));
+    }
 }
@@ -1,18 +1,31 @@
 pub mod chaos_game;
 pub mod color_map;
+pub mod complex_expression;
+#[cfg(feature = "gui")]
 pub mod controller;
 pub mod dynamical_systems;
+#[cfg(feature = "gui")]
 pub mod eframe_support;
 pub mod field_iteration;
 pub mod file_io;
+pub mod grid_overlay;
 pub mod histogram;
 pub mod image_utils;
+#[cfg(feature = "gui")]
 pub mod interactive;
 pub mod interpolation;
 pub mod lookup_table;
 pub mod ode_solvers;
+pub mod params_merge;
+pub mod phase_space_plot;
+pub mod post_process;
+pub mod relaxed_json;
 pub mod render_pipeline;
+#[cfg(feature = "gui")]
 pub mod render_quality_fsm;
+#[cfg(feature = "gui")]
 pub mod render_window;
+pub mod sample_pattern;
 pub mod stopwatch;
+#[cfg(feature = "gui")]
 pub mod view_control;
@@ -1,10 +1,18 @@
 //! Explicit ODE solvers
 
-use nalgebra::Vector2;
+use nalgebra::SVector;
 
-pub fn rk4_method_step<F>(dt: f64, t: f64, x: Vector2<f64>, dynamics: &F) -> Vector2<f64>
+/// One classic fourth-order Runge-Kutta step for a system with an
+/// `N`-dimensional state, e.g. `Vector2` for a pendulum (angle, rate) or
+/// `Vector4` for a planar point mass (position, velocity).
+pub fn rk4_method_step<const N: usize, F>(
+    dt: f64,
+    t: f64,
+    x: SVector<f64, N>,
+    dynamics: &F,
+) -> SVector<f64, N>
 where
-    F: Fn(f64, Vector2<f64>) -> Vector2<f64>,
+    F: Fn(f64, SVector<f64, N>) -> SVector<f64, N>,
 {
     let t_mid = t + 0.5 * dt;
     let t_next = t + dt;
@@ -17,15 +25,17 @@ where
     x + x_delta
 }
 
-pub fn rk4_simulate<F>(
+/// Integrate `dynamics` from `x0` at `t_begin` to `t_final` in `n_steps`
+/// equal fourth-order Runge-Kutta steps, and return the final state.
+pub fn rk4_simulate<const N: usize, F>(
     t_begin: f64,
     t_final: f64,
     n_steps: u32,
-    x0: Vector2<f64>,
+    x0: SVector<f64, N>,
     dynamics: &F,
-) -> Vector2<f64>
+) -> SVector<f64, N>
 where
-    F: Fn(f64, Vector2<f64>) -> Vector2<f64>,
+    F: Fn(f64, SVector<f64, N>) -> SVector<f64, N>,
 {
     let dt = (t_final - t_begin) / (n_steps as f64);
     let mut x = x0;
@@ -43,6 +53,7 @@ mod tests {
 
     use super::*;
     use approx::assert_relative_eq;
+    use nalgebra::Vector2;
 
     #[test]
     fn test_closed_loop_controller_analytic_soln() {
@@ -0,0 +1,156 @@
+//! Params files that extend a base file. A top-level `"base"` key names
+//! another params file, resolved relative to the directory of the file that
+//! names it; the base is loaded first and the rest of the file is merged on
+//! top before the whole is deserialized. Bases may themselves have bases.
+//!
+//! Merge semantics, applied at the JSON `Value` level:
+//! - objects are merged key by key, recursively;
+//! - anything else in the override, arrays included, replaces the base
+//!   value wholesale (so a `color_maps` override lists every map).
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::core::relaxed_json::from_relaxed_json_str;
+
+/// Top-level key naming the base params file.
+pub const BASE_KEY: &str = "base";
+
+/// Deep-merge `overrides` into `base`: the override wins wherever the two
+/// disagree, except that two objects are merged rather than replaced.
+pub fn merge_json(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Parse the params in `text` and, if they name a base file, merge them on
+/// top of it. Relative base paths are resolved against `directory`, the
+/// directory holding the file `text` came from.
+pub fn load_params_with_base(text: &str, directory: &Path) -> Result<Value, String> {
+    resolve_base(text, directory, &mut Vec::new())
+}
+
+fn resolve_base(text: &str, directory: &Path, chain: &mut Vec<PathBuf>) -> Result<Value, String> {
+    let mut value: Value = from_relaxed_json_str(text).map_err(|err| err.to_string())?;
+    let Some(base) = value.as_object_mut().and_then(|map| map.remove(BASE_KEY)) else {
+        return Ok(value);
+    };
+    let Value::String(base) = base else {
+        return Err(format!(
+            "`{BASE_KEY}` must be a path to a params file, not {base}"
+        ));
+    };
+    let base_path = directory.join(base);
+    let canonical = base_path.canonicalize().map_err(|err| {
+        format!(
+            "unable to read base params `{}`: {err}",
+            base_path.display()
+        )
+    })?;
+    if chain.contains(&canonical) {
+        return Err(format!(
+            "base params `{}` are their own base, directly or through other bases",
+            base_path.display()
+        ));
+    }
+    chain.push(canonical);
+    let base_text = std::fs::read_to_string(&base_path).map_err(|err| {
+        format!(
+            "unable to read base params `{}`: {err}",
+            base_path.display()
+        )
+    })?;
+    let base_directory = base_path.parent().unwrap_or(Path::new(""));
+    let mut merged = resolve_base(&base_text, base_directory, chain)
+        .map_err(|err| format!("in base params `{}`: {err}", base_path.display()))?;
+    merge_json(&mut merged, value);
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn overrides_win_objects_merge_and_arrays_are_replaced() {
+        let mut base = json!({
+            "Mandelbrot": {
+                "image_specification": {"resolution": [400, 300], "center": [0, 0], "width": 4},
+                "color_map": [{"query": 0}, {"query": 1}],
+                "max_iteration_count": 256
+            }
+        });
+        merge_json(
+            &mut base,
+            json!({
+                "Mandelbrot": {
+                    "image_specification": {"center": [-0.75, 0.1], "width": 0.5},
+                    "color_map": [{"query": 0.5}],
+                    "sampling_level": 2
+                }
+            }),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "Mandelbrot": {
+                    "image_specification": {
+                        "resolution": [400, 300],
+                        "center": [-0.75, 0.1],
+                        "width": 0.5
+                    },
+                    "color_map": [{"query": 0.5}],
+                    "max_iteration_count": 256,
+                    "sampling_level": 2
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn bases_chain_relative_to_the_file_that_names_them() {
+        let directory = std::env::temp_dir().join("fractal_renderer_params_merge");
+        let nested = directory.join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(
+            directory.join("root.json"),
+            r#"{"Julia": {"width": 4, "max_iteration_count": 100}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            nested.join("middle.json"),
+            r#"{"base": "../root.json", "Julia": {"width": 2}}"#,
+        )
+        .unwrap();
+        let merged = load_params_with_base(
+            r#"{"base": "nested/middle.json", "Julia": {"max_iteration_count": 50}}"#,
+            &directory,
+        )
+        .unwrap();
+        assert_eq!(
+            merged,
+            json!({"Julia": {"width": 2, "max_iteration_count": 50}})
+        );
+
+        std::fs::write(directory.join("loop.json"), r#"{"base": "loop.json"}"#).unwrap();
+        let err = load_params_with_base(r#"{"base": "loop.json"}"#, &directory).unwrap_err();
+        assert!(err.contains("their own base"), "{err}");
+        let err = load_params_with_base(r#"{"base": "missing.json"}"#, &directory).unwrap_err();
+        assert!(err.contains("missing.json"), "{err}");
+        let err = load_params_with_base(r#"{"base": 3}"#, &directory).unwrap_err();
+        assert!(err.contains("must be a path"), "{err}");
+    }
+}
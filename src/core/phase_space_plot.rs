@@ -0,0 +1,168 @@
+//! Plot of a single phase-space trajectory (angle vs. rate), for seeing why
+//! one initial condition settles where it does. A diagnostic rather than a
+//! fractal render: the axes are scaled to fit the trajectory, and the curve
+//! fades from blue at the initial condition to yellow where it ends.
+
+use image::{Rgb, RgbImage};
+
+/// Size of the written plot, in pixels.
+pub const PHASE_SPACE_PLOT_RESOLUTION: [u32; 2] = [800, 600];
+
+/// Blank space around the trajectory, as a fraction of its extent.
+const MARGIN_FRACTION: f64 = 0.05;
+
+const BACKGROUND_COLOR: Rgb<u8> = Rgb([16, 16, 16]);
+
+/// Color of the `angle = 0` and `rate = 0` axes.
+const AXIS_COLOR: Rgb<u8> = Rgb([80, 80, 80]);
+
+const START_COLOR: [f64; 3] = [40.0, 120.0, 255.0];
+
+const END_COLOR: [f64; 3] = [255.0, 220.0, 40.0];
+
+/// Half-width, in pixels, of the square that marks the initial condition.
+const START_MARKER_RADIUS: i64 = 3;
+
+/// Affine map from phase space onto the pixels of the plot, with the rate
+/// increasing upward.
+struct PlotMapping {
+    lower: [f64; 2],
+    pixels_per_unit: [f64; 2],
+    height: u32,
+}
+
+impl PlotMapping {
+    fn new(states: &[[f64; 2]], resolution: [u32; 2]) -> PlotMapping {
+        let mut lower = [f64::INFINITY; 2];
+        let mut upper = [f64::NEG_INFINITY; 2];
+        for state in states {
+            for dim in 0..2 {
+                lower[dim] = lower[dim].min(state[dim]);
+                upper[dim] = upper[dim].max(state[dim]);
+            }
+        }
+        let pixels_per_unit = [0, 1].map(|dim| {
+            // Keep a degenerate extent (e.g. a state at rest) from dividing by zero.
+            let span = (upper[dim] - lower[dim]).max(1e-9) * (1.0 + 2.0 * MARGIN_FRACTION);
+            lower[dim] -= MARGIN_FRACTION * span / (1.0 + 2.0 * MARGIN_FRACTION);
+            (resolution[dim] - 1) as f64 / span
+        });
+        PlotMapping {
+            lower,
+            pixels_per_unit,
+            height: resolution[1],
+        }
+    }
+
+    fn to_pixel(&self, state: [f64; 2]) -> [f64; 2] {
+        let x = (state[0] - self.lower[0]) * self.pixels_per_unit[0];
+        let y = (state[1] - self.lower[1]) * self.pixels_per_unit[1];
+        [x, (self.height - 1) as f64 - y]
+    }
+}
+
+fn put_pixel_clipped(image: &mut RgbImage, [x, y]: [i64; 2], color: Rgb<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// Draw a straight segment by sampling it at one-pixel steps.
+fn draw_segment(image: &mut RgbImage, from: [f64; 2], to: [f64; 2], color: Rgb<u8>) {
+    let steps = (to[0] - from[0])
+        .abs()
+        .max((to[1] - from[1]).abs())
+        .ceil()
+        .max(1.0);
+    for step in 0..=(steps as i64) {
+        let alpha = step as f64 / steps;
+        let point = [0, 1].map(|dim| (from[dim] + alpha * (to[dim] - from[dim])).round() as i64);
+        put_pixel_clipped(image, point, color);
+    }
+}
+
+fn blend_color(alpha: f64) -> Rgb<u8> {
+    Rgb([0, 1, 2].map(|i| (START_COLOR[i] + alpha * (END_COLOR[i] - START_COLOR[i])).round() as u8))
+}
+
+/// Plot the trajectory through `states`, each an `[angle, rate]` pair, in
+/// order. An empty trajectory gives a blank plot.
+pub fn phase_space_plot_image(states: &[[f64; 2]], resolution: [u32; 2]) -> RgbImage {
+    let mut image = RgbImage::from_pixel(resolution[0], resolution[1], BACKGROUND_COLOR);
+    let Some(&first) = states.first() else {
+        return image;
+    };
+    let mapping = PlotMapping::new(states, resolution);
+
+    let [origin_x, origin_y] = mapping.to_pixel([0.0, 0.0]);
+    let [width, height] = resolution.map(|n| n as f64 - 1.0);
+    if (0.0..=width).contains(&origin_x) {
+        draw_segment(&mut image, [origin_x, 0.0], [origin_x, height], AXIS_COLOR);
+    }
+    if (0.0..=height).contains(&origin_y) {
+        draw_segment(&mut image, [0.0, origin_y], [width, origin_y], AXIS_COLOR);
+    }
+
+    let segment_count = (states.len() - 1).max(1) as f64;
+    for (index, pair) in states.windows(2).enumerate() {
+        draw_segment(
+            &mut image,
+            mapping.to_pixel(pair[0]),
+            mapping.to_pixel(pair[1]),
+            blend_color(index as f64 / segment_count),
+        );
+    }
+
+    let [start_x, start_y] = mapping.to_pixel(first).map(|value| value.round() as i64);
+    for dy in -START_MARKER_RADIUS..=START_MARKER_RADIUS {
+        for dx in -START_MARKER_RADIUS..=START_MARKER_RADIUS {
+            put_pixel_clipped(&mut image, [start_x + dx, start_y + dy], blend_color(0.0));
+        }
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trajectory_fills_the_plot_and_fades_from_start_to_end() {
+        let states: Vec<[f64; 2]> = (0..=100)
+            .map(|i| {
+                let t = i as f64 * 0.1;
+                [4.0 + t.cos(), 2.0 * t.sin()]
+            })
+            .collect();
+        let image = phase_space_plot_image(&states, [200, 100]);
+        assert_eq!(image.dimensions(), (200, 100));
+
+        let mapping = PlotMapping::new(&states, [200, 100]);
+        let [x, y] = mapping.to_pixel(states[0]).map(f64::round);
+        assert_eq!(*image.get_pixel(x as u32, y as u32), blend_color(0.0));
+        let [x, y] = mapping.to_pixel(states[100]).map(f64::round);
+        assert_ne!(*image.get_pixel(x as u32, y as u32), BACKGROUND_COLOR);
+
+        // The margins keep the extremes just inside the image.
+        let corners = [[3.0, -2.0], [5.0, 2.0]].map(|state| mapping.to_pixel(state));
+        for [x, y] in corners {
+            assert!(
+                (5.0..15.0).contains(&x) || (185.0..195.0).contains(&x),
+                "{x}"
+            );
+            assert!((2.0..8.0).contains(&y) || (92.0..98.0).contains(&y), "{y}");
+        }
+        // The angle axis (`rate = 0`) is in view; the rate axis is off to the
+        // left.
+        let axis_row = mapping.to_pixel([4.0, 0.0])[1].round() as u32;
+        assert_eq!(*image.get_pixel(0, axis_row), AXIS_COLOR);
+    }
+
+    #[test]
+    fn degenerate_trajectories_still_plot() {
+        let blank = phase_space_plot_image(&[], [10, 10]);
+        assert!(blank.pixels().all(|&pixel| pixel == BACKGROUND_COLOR));
+        let at_rest = phase_space_plot_image(&[[1.0, 1.0]; 3], [10, 10]);
+        assert!(at_rest.pixels().any(|&pixel| pixel == blend_color(0.0)));
+    }
+}
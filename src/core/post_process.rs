@@ -0,0 +1,254 @@
+//! Optional post-processing of the colorized output image: a separable
+//! Gaussian blur, a bloom that blurs only the bright pixels and adds the
+//! glow back onto the sharp image, or an unsharp mask that subtracts the
+//! blur to crisp up edges. Runs after the colorize step of the render
+//! pipeline, so the preview and the saved image match.
+
+use egui::{Color32, ColorImage};
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Largest accepted `PostProcess::blur_radius`, in output pixels. The blur
+/// kernel reaches three radii each way, so this bounds its size.
+pub const MAX_BLUR_RADIUS: f32 = 256.0;
+
+/// Post-processing settings, part of `RenderOptions`. The default does
+/// nothing.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PostProcess {
+    /// Standard deviation of the Gaussian blur, in output pixels. `0` (the
+    /// default) turns post-processing off. Also the width of the edges that
+    /// the bloom and the unsharp mask act on. At most `MAX_BLUR_RADIUS`.
+    #[serde(default, deserialize_with = "deserialize_blur_radius")]
+    pub blur_radius: f32,
+    /// Turns the blur into a bloom: only pixels whose luminance, on
+    /// `[0, 1]`, is above this threshold are blurred, and the blurred glow is
+    /// added to the unblurred image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bloom_threshold: Option<f32>,
+    /// Turns the blur into an unsharp mask, unless a bloom is set: the
+    /// image is pushed away from its blurred copy by this multiple of their
+    /// difference, which sharpens the edges that anti-aliasing softens.
+    /// Around `0.5`-`1.5` is typical; `0` (the default) just blurs.
+    #[serde(default)]
+    pub sharpen_amount: f32,
+}
+
+/// Reject blur radii that are negative, non-finite, or above
+/// `MAX_BLUR_RADIUS` when the params file is loaded.
+fn deserialize_blur_radius<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let blur_radius = f32::deserialize(deserializer)?;
+    if !(0.0..=MAX_BLUR_RADIUS).contains(&blur_radius) {
+        return Err(serde::de::Error::custom(format!(
+            "blur_radius ({blur_radius}) must be between 0 and {MAX_BLUR_RADIUS}"
+        )));
+    }
+    Ok(blur_radius)
+}
+
+impl PostProcess {
+    /// True if `apply_post_process` changes the image, i.e. the blur radius
+    /// is positive.
+    pub fn is_enabled(&self) -> bool {
+        self.blur_radius > 0.0
+    }
+}
+
+/// Rec. 709 luminance of an 8-bit color, on `[0, 1]`.
+fn luminance(rgb: [f32; 3]) -> f32 {
+    (0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]) / 255.0
+}
+
+/// Gaussian weights for offsets `-reach..=reach`, left unnormalized:
+/// `apply_taps` divides by the weight of the taps it uses.
+fn gaussian_weights(sigma: f32) -> Vec<f32> {
+    let reach = (3.0 * sigma).ceil() as isize;
+    (-reach..=reach)
+        .map(|offset| (-0.5 * (offset as f32 / sigma).powi(2)).exp())
+        .collect()
+}
+
+/// Weighted average of `value_at(i)` over the taps centered on `center`,
+/// skipping taps outside `0..count` and renormalizing by the weight that
+/// remains, so the image edges are not darkened.
+fn apply_taps(
+    weights: &[f32],
+    center: usize,
+    count: usize,
+    value_at: impl Fn(usize) -> [f32; 3],
+) -> [f32; 3] {
+    let reach = (weights.len() / 2) as isize;
+    let mut sum = [0.0f32; 3];
+    let mut total_weight = 0.0;
+    for (tap, &weight) in weights.iter().enumerate() {
+        let Ok(index) = usize::try_from(center as isize + tap as isize - reach) else {
+            continue;
+        };
+        if index >= count {
+            continue;
+        }
+        for (channel, value) in sum.iter_mut().zip(value_at(index)) {
+            *channel += weight * value;
+        }
+        total_weight += weight;
+    }
+    sum.map(|channel| channel / total_weight)
+}
+
+/// Apply `post_process` to `image` in place. `scratch` holds the source and
+/// the horizontally blurred rows, and is reused across frames.
+pub fn apply_post_process(
+    post_process: &PostProcess,
+    image: &mut ColorImage,
+    scratch: &mut Vec<[f32; 3]>,
+) {
+    if !post_process.is_enabled() {
+        return;
+    }
+    let [width, height] = image.size;
+    let pixel_count = width * height;
+    if pixel_count == 0 {
+        return;
+    }
+    let weights = gaussian_weights(post_process.blur_radius);
+
+    scratch.clear();
+    scratch.extend(image.pixels.iter().map(|pixel| {
+        let rgb = [pixel.r(), pixel.g(), pixel.b()].map(f32::from);
+        match post_process.bloom_threshold {
+            Some(threshold) if luminance(rgb) <= threshold => [0.0; 3],
+            _ => rgb,
+        }
+    }));
+    scratch.resize(2 * pixel_count, [0.0; 3]);
+    let (source, horizontal) = scratch.split_at_mut(pixel_count);
+
+    let source = &*source;
+    horizontal
+        .par_chunks_exact_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let source_row = &source[y * width..(y + 1) * width];
+            for (x, blurred) in row.iter_mut().enumerate() {
+                *blurred = apply_taps(&weights, x, width, |i| source_row[i]);
+            }
+        });
+
+    let horizontal = &*horizontal;
+    image
+        .pixels
+        .par_chunks_exact_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let blurred = apply_taps(&weights, y, height, |i| horizontal[i * width + x]);
+                let sharp = [pixel.r(), pixel.g(), pixel.b()].map(f32::from);
+                let rgb = if post_process.bloom_threshold.is_some() {
+                    [0, 1, 2].map(|i| sharp[i] + blurred[i])
+                } else if post_process.sharpen_amount > 0.0 {
+                    let amount = post_process.sharpen_amount;
+                    [0, 1, 2].map(|i| sharp[i] + amount * (sharp[i] - blurred[i]))
+                } else {
+                    blurred
+                };
+                let [r, g, b] = rgb.map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+                *pixel = Color32::from_rgb(r, g, b);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_with_one_white_pixel() -> ColorImage {
+        let mut image = ColorImage::filled([9, 7], Color32::from_rgb(10, 20, 30));
+        image.pixels[3 * 9 + 4] = Color32::WHITE;
+        image
+    }
+
+    #[test]
+    fn out_of_range_blur_radii_are_rejected_at_load() {
+        let parse = |json: &str| serde_json::from_str::<PostProcess>(json);
+        assert_eq!(parse(r#"{"blur_radius": 2.5}"#).unwrap().blur_radius, 2.5);
+        assert_eq!(parse("{}").unwrap().blur_radius, 0.0);
+        for invalid in ["-1.0", "1e30", "257.0"] {
+            let error = parse(&format!(r#"{{"blur_radius": {invalid}}}"#)).unwrap_err();
+            assert!(error.to_string().contains("blur_radius"), "{error}");
+        }
+    }
+
+    #[test]
+    fn zero_blur_radius_is_a_no_op() {
+        let original = image_with_one_white_pixel();
+        for bloom_threshold in [None, Some(0.5)] {
+            let mut image = original.clone();
+            let post_process = PostProcess {
+                blur_radius: 0.0,
+                bloom_threshold,
+                sharpen_amount: 1.0,
+            };
+            apply_post_process(&post_process, &mut image, &mut Vec::new());
+            assert_eq!(image.pixels, original.pixels);
+        }
+    }
+
+    #[test]
+    fn blur_spreads_a_bright_pixel_and_keeps_flat_regions_flat() {
+        let flat = ColorImage::filled([9, 7], Color32::from_rgb(10, 20, 30));
+        let post_process = PostProcess {
+            blur_radius: 1.5,
+            ..PostProcess::default()
+        };
+        let mut scratch = Vec::new();
+        let mut blurred_flat = flat.clone();
+        apply_post_process(&post_process, &mut blurred_flat, &mut scratch);
+        assert_eq!(blurred_flat.pixels, flat.pixels);
+
+        let mut image = image_with_one_white_pixel();
+        apply_post_process(&post_process, &mut image, &mut scratch);
+        let center = image.pixels[3 * 9 + 4];
+        let neighbor = image.pixels[3 * 9 + 5];
+        let corner = image.pixels[0];
+        assert!(center.r() < 255 && center.r() > neighbor.r());
+        assert!(neighbor.r() > corner.r());
+        assert_eq!(corner, Color32::from_rgb(10, 20, 30));
+    }
+
+    #[test]
+    fn bloom_adds_a_glow_around_bright_pixels_only() {
+        let post_process = PostProcess {
+            blur_radius: 1.5,
+            bloom_threshold: Some(0.5),
+            ..PostProcess::default()
+        };
+        let mut image = image_with_one_white_pixel();
+        apply_post_process(&post_process, &mut image, &mut Vec::new());
+        // The bright pixel stays saturated, its neighbors glow, and the dark
+        // pixels far away are untouched.
+        assert_eq!(image.pixels[3 * 9 + 4], Color32::WHITE);
+        let neighbor = image.pixels[3 * 9 + 5];
+        assert!(neighbor.r() > 10 && neighbor.g() > 20 && neighbor.b() > 30);
+        assert_eq!(image.pixels[0], Color32::from_rgb(10, 20, 30));
+    }
+
+    #[test]
+    fn unsharp_mask_steepens_edges_and_keeps_flat_regions_flat() {
+        let post_process = PostProcess {
+            blur_radius: 1.0,
+            sharpen_amount: 1.0,
+            ..PostProcess::default()
+        };
+        // Left half dark gray, right half light gray.
+        let mut image = ColorImage::filled([8, 3], Color32::from_gray(60));
+        for row in image.pixels.chunks_exact_mut(8) {
+            row[4..].fill(Color32::from_gray(180));
+        }
+        apply_post_process(&post_process, &mut image, &mut Vec::new());
+        let row: Vec<u8> = image.pixels[8..16].iter().map(|p| p.r()).collect();
+        // Overshoot on both sides of the edge, untouched far from it.
+        assert!(row[3] < 60 && row[4] > 180, "{row:?}");
+        assert_eq!(row[0], 60);
+        assert_eq!(row[7], 180);
+    }
+}
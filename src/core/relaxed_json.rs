@@ -0,0 +1,122 @@
+//! Relaxed parsing for hand-edited parameter files: `//` and `/* */`
+//! comments and trailing commas are accepted on input. Everything written
+//! back out is still strict JSON.
+//!
+//! Rather than a separate parser, the relaxed syntax is blanked out with
+//! spaces before handing the text to `serde_json`, so the line and column
+//! numbers in its error messages still point into the original file.
+
+use serde::de::DeserializeOwned;
+
+/// Replace comments and trailing commas in `text` with spaces, leaving
+/// everything else (including newlines, and anything inside string
+/// literals) byte-for-byte in place. An unterminated block comment runs to
+/// the end of the text.
+pub fn strip_comments_and_trailing_commas(text: &str) -> String {
+    let bytes = text.as_bytes();
+    // Which bytes of `text` to replace with a space.
+    let mut blank = vec![false; bytes.len()];
+    // Index of the last comma seen outside a string, until something other
+    // than whitespace or a comment follows it.
+    let mut pending_comma: Option<usize> = None;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'"' => {
+                pending_comma = None;
+                index += 1;
+                while index < bytes.len() && bytes[index] != b'"' {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+                index += 1;
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'/') => {
+                while index < bytes.len() && bytes[index] != b'\n' {
+                    blank[index] = true;
+                    index += 1;
+                }
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                let end = text[index + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |offset| index + 2 + offset + 2);
+                for (flag, byte) in blank[index..end].iter_mut().zip(&bytes[index..end]) {
+                    *flag = *byte != b'\n';
+                }
+                index = end;
+            }
+            b',' => {
+                pending_comma = Some(index);
+                index += 1;
+            }
+            b'}' | b']' => {
+                if let Some(comma) = pending_comma.take() {
+                    blank[comma] = true;
+                }
+                index += 1;
+            }
+            byte => {
+                if !byte.is_ascii_whitespace() {
+                    pending_comma = None;
+                }
+                index += 1;
+            }
+        }
+    }
+    // A blanked character becomes one space per byte, so byte offsets (and
+    // hence serde_json's columns) are unchanged.
+    let mut stripped = String::with_capacity(text.len());
+    for (offset, character) in text.char_indices() {
+        if blank[offset] {
+            stripped.extend(std::iter::repeat_n(' ', character.len_utf8()));
+        } else {
+            stripped.push(character);
+        }
+    }
+    stripped
+}
+
+/// Deserialize `text`, allowing comments and trailing commas.
+pub fn from_relaxed_json_str<T: DeserializeOwned>(text: &str) -> serde_json::Result<T> {
+    serde_json::from_str(&strip_comments_and_trailing_commas(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_and_trailing_commas_are_blanked_in_place() {
+        let text =
+            "{\n  // the view\n  \"a\": [1, 2,], /* inline */ \"b\": \"//, not a comment,]\",\n}";
+        let stripped = strip_comments_and_trailing_commas(text);
+        assert_eq!(stripped.len(), text.len());
+        assert_eq!(stripped.lines().count(), text.lines().count());
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"a": [1, 2], "b": "//, not a comment,]"})
+        );
+
+        // Non-ASCII text inside a comment is blanked byte-for-byte.
+        let stripped = strip_comments_and_trailing_commas("[1] // café\n");
+        assert_eq!(stripped, format!("[1]{}\n", " ".repeat(" // café".len())));
+
+        // Escaped quotes do not end a string.
+        let value: serde_json::Value = from_relaxed_json_str(r#"["a \" // b", /* c */ ]"#).unwrap();
+        assert_eq!(value, serde_json::json!(["a \" // b"]));
+    }
+
+    #[test]
+    fn errors_still_point_into_the_original_text() {
+        let text = "{\n  /* comment */ \"a\": 1,\n  \"b\": nope\n}";
+        let error = from_relaxed_json_str::<serde_json::Value>(text).unwrap_err();
+        let strict_text = "{\n                \"a\": 1,\n  \"b\": nope\n}";
+        let strict_error = serde_json::from_str::<serde_json::Value>(strict_text).unwrap_err();
+        assert_eq!(error.to_string(), strict_error.to_string());
+        assert_eq!(error.line(), 3);
+        // A comma between two elements is not trailing.
+        assert!(from_relaxed_json_str::<serde_json::Value>("[1, , 2]").is_err());
+        assert!(from_relaxed_json_str::<serde_json::Value>("[1 /* open").is_err());
+    }
+}
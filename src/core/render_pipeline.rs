@@ -1,8 +1,9 @@
 //! Top-level orchestrator that owns all reusable buffers and drives the
 //! four-step render pipeline:
 //!
-//! - (a) `field_iteration::compute_raw_field` — fill the field with raw
-//!   `Option<(f32, u32)>` cells via the fractal's `FieldKernel::evaluate`.
+//! - (a) `field_iteration::compute_raw_field_with_progress` — fill the field
+//!   with raw `Option<(f32, u32)>` cells via the fractal's
+//!   `FieldKernel::evaluate`.
 //! - (b) `field_iteration::populate_histograms` — bin populated cells into the
 //!   per-color-map histograms (owned by the cache).
 //! - (c) `ColorPaletteCache::refresh_after_compute_pass` — atomically rebuild
@@ -10,20 +11,25 @@
 //!   the cache is never observed half-updated.
 //! - (d) `field_iteration::colorize_collapse_unified` — walk the output
 //!   `egui::ColorImage`, averaging `(n+1)²` subpixel `[u8; 3]` results into
-//!   each output pixel via `colorize_cell`.
+//!   each output pixel via `colorize_cell` (weighted by the render options'
+//!   downscale filter).
 //!
 //! All buffers are allocated once at construction (or `resize`); per-frame and
 //! per-pixel allocations are zero. Dispatch is fully monomorphized over
 //! `F: Renderable` — no `dyn`, no runtime variant matching on the hot path. The
 //! field stays raw end-to-end; CDF lookup happens inside `colorize_cell`.
 
+use std::ops::ControlFlow;
+
 use egui::ColorImage;
 
-use crate::core::color_map::ColorPaletteCache;
+use crate::core::color_map::{ColorMapError, ColorPaletteCache};
 use crate::core::field_iteration::{
-    colorize_collapse_unified, compute_raw_field, populate_histograms,
+    FieldBuffer, ProgressCallback, colorize_collapse_unified, compute_raw_field_with_progress,
+    mark_isolines, populate_histograms, valued_cell_fraction,
 };
 use crate::core::image_utils::Renderable;
+use crate::core::post_process::apply_post_process;
 
 /// Top-level orchestrator that owns all reusable buffers for one fractal
 /// instance and runs the four-step pipeline against them on every render.
@@ -32,7 +38,7 @@ pub struct RenderingPipeline<F: Renderable> {
     fractal: F,
     /// Subpixel field, sized at construction for `(n_max+1)·W × (n_max+1)·H`
     /// where `n_max+1` is derived from the user's JSON `sampling_level`.
-    field: Vec<Vec<Option<(f32, u32)>>>,
+    field: FieldBuffer,
     /// Allocation-once color cache (per-color-map histograms, CDFs, LUTs,
     /// and the pre-converted background `Color32`). The pipeline fills the
     /// histograms during (b), then `refresh_after_compute_pass` rebuilds
@@ -41,18 +47,31 @@ pub struct RenderingPipeline<F: Renderable> {
     /// Permanent upsample factor for the field. The runtime sampling level
     /// passed to `render` is at most `n_max_plus_1 - 1`.
     n_max_plus_1: usize,
+    /// Intermediate rows for the non-box downscale filters. Empty until one
+    /// is first used, then reused across frames.
+    downscale_scratch: Vec<[f32; 3]>,
+    /// Isoline mask, sized to the field. Empty until isolines are first
+    /// drawn, then reused across frames.
+    isoline_scratch: Vec<bool>,
+    /// Intermediate images for the post-processing blur. Empty until it is
+    /// first used, then reused across frames.
+    post_process_scratch: Vec<[f32; 3]>,
+    /// Whether the fractal is at the user's full quality rather than sped up
+    /// by the adaptive regulator; see `set_full_quality`.
+    full_quality: bool,
 }
 
 impl<F: Renderable> RenderingPipeline<F> {
     /// Construct a pipeline. Allocates all buffers based on the fractal's
     /// current image specification, render options, and color-map params.
+    /// Fails if any of the fractal's color maps or channel curves is invalid.
     pub fn new(
         fractal: F,
         n_max_plus_1: usize,
         histogram_bin_count: usize,
         histogram_max_value: f32,
         lookup_table_count: usize,
-    ) -> Self {
+    ) -> Result<Self, ColorMapError> {
         assert!(n_max_plus_1 >= 1, "n_max_plus_1 must be at least 1");
         let spec = fractal.image_specification();
         // The field buffer is sized as (n_max_plus_1·W) × (n_max_plus_1·H)
@@ -62,32 +81,63 @@ impl<F: Renderable> RenderingPipeline<F> {
         // stays untouched between frames.
         let outer_dim_x = (spec.resolution[0] as usize) * n_max_plus_1;
         let inner_dim_y = (spec.resolution[1] as usize) * n_max_plus_1;
-        let field = (0..outer_dim_x).map(|_| vec![None; inner_dim_y]).collect();
-        let color_cache = fractal.color_palette().create_cache(
+        let field = FieldBuffer::new([outer_dim_x, inner_dim_y]);
+        let mut color_cache = fractal.color_palette().create_cache(
             histogram_bin_count,
             histogram_max_value,
             lookup_table_count,
-        );
-        Self {
+        )?;
+        color_cache.set_normalization(fractal.scalar_normalization());
+        color_cache.set_overlay_color(fractal.overlay_color());
+        color_cache.set_sector_count(fractal.sector_count());
+        color_cache.set_band_count(fractal.band_count(), fractal.band_softness());
+        color_cache.set_dither(fractal.render_options().dither);
+        color_cache.set_color_effects(fractal.color_effects());
+        color_cache.set_channel_curves(fractal.channel_curves())?;
+        Ok(Self {
             fractal,
             field,
             color_cache,
             n_max_plus_1,
-        }
+            downscale_scratch: Vec::new(),
+            isoline_scratch: Vec::new(),
+            post_process_scratch: Vec::new(),
+            full_quality: true,
+        })
     }
 
     /// Run the full pipeline, writing one output pixel per cell of `out`.
     /// `sampling_level` is the runtime value driven by the adaptive
     /// regulator; positive values use the AA subpixel grid, negative
-    /// values trigger block-fill, and `0` is baseline.
-    pub fn render(&mut self, out: &mut ColorImage, sampling_level: i32) {
+    /// values trigger block-fill, and `0` is baseline. Fails, leaving `out`
+    /// untouched, if one of the fractal's color maps is invalid.
+    pub fn render(
+        &mut self,
+        out: &mut ColorImage,
+        sampling_level: i32,
+    ) -> Result<(), ColorMapError> {
+        // Without a cancelling callback the render always runs to completion.
+        self.render_with_progress(out, sampling_level, &|_| ControlFlow::Continue(()))
+            .map(|_| ())
+    }
+
+    /// Same as `render`, but reports the compute pass's progress through
+    /// `progress` and stops early if it returns `ControlFlow::Break`. A
+    /// cancelled render skips the colorize steps entirely, leaving `out`
+    /// untouched, and returns `Ok(Break)`.
+    pub fn render_with_progress(
+        &mut self,
+        out: &mut ColorImage,
+        sampling_level: i32,
+        progress: &ProgressCallback,
+    ) -> Result<ControlFlow<()>, ColorMapError> {
         debug_assert!(
             sampling_level < (self.n_max_plus_1 as i32),
             "runtime sampling_level cannot exceed the cap baked into the field buffer"
         );
         let spec = *self.fractal.image_specification();
         debug_assert_eq!(
-            self.field.len(),
+            self.field.size()[0],
             (spec.resolution[0] as usize) * self.n_max_plus_1,
             "field outer dim must match (n_max+1)·W"
         );
@@ -97,13 +147,41 @@ impl<F: Renderable> RenderingPipeline<F> {
         );
 
         // (a) Fill the field via the fractal's FieldKernel.
-        compute_raw_field(
+        self.fractal.begin_compute_pass();
+        if compute_raw_field_with_progress(
             &spec,
             self.n_max_plus_1,
             sampling_level,
+            self.fractal.render_options(),
             &self.fractal,
             &mut self.field,
-        );
+            progress,
+        )
+        .is_break()
+        {
+            return Ok(ControlFlow::Break(()));
+        }
+        self.colorize_after_compute_pass(out, sampling_level)?;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Steps (b) through (d) of the pipeline: histogram the freshly computed
+    /// field, rebuild the color cache, and colorize into `out`. Isolines, if
+    /// the fractal shows them, are cut into the field first, so they are
+    /// left out of the histograms and survive `recolorize_only`.
+    fn colorize_after_compute_pass(
+        &mut self,
+        out: &mut ColorImage,
+        sampling_level: i32,
+    ) -> Result<(), ColorMapError> {
+        if self.fractal.show_isolines() {
+            mark_isolines(
+                self.n_max_plus_1,
+                sampling_level,
+                &mut self.field,
+                &mut self.isoline_scratch,
+            );
+        }
 
         // (b) Bin populated cells into the cache's per-color-map histograms.
         self.color_cache.reset_histograms();
@@ -113,12 +191,21 @@ impl<F: Renderable> RenderingPipeline<F> {
             &self.field,
             &mut self.color_cache.histograms,
         );
+        self.fractal
+            .record_field_statistics(&self.field, self.n_max_plus_1, sampling_level);
 
         // (c) Atomically rebuild every downstream-visible cache field
         // (per-color-map CDFs, LUTs, background color) so the colorize
         // pass below can't observe a half-updated cache.
         self.color_cache
-            .refresh_after_compute_pass(self.fractal.color_palette());
+            .refresh_after_compute_pass(self.fractal.color_palette())?;
+        if self.fractal.hold_cdfs_fixed() && self.full_quality {
+            // The first full-quality pass's CDFs become the fixed
+            // normalization for every later frame (panning / zooming no
+            // longer shifts colors). Sped-up passes are skipped, since their
+            // lower iteration limits skew the histograms.
+            self.color_cache.freeze_cdfs();
+        }
 
         // (d) Walk the output image; CDF + LUT lookup per cell; AA-average.
         colorize_collapse_unified(
@@ -126,8 +213,18 @@ impl<F: Renderable> RenderingPipeline<F> {
             &self.field,
             self.n_max_plus_1,
             sampling_level,
+            self.fractal.render_options().downscale_filter,
+            &mut self.downscale_scratch,
+            out,
+        );
+
+        // (e) Optional blur / bloom of the finished image.
+        apply_post_process(
+            &self.fractal.render_options().post_process,
             out,
+            &mut self.post_process_scratch,
         );
+        Ok(())
     }
 
     /// Re-colorize the existing field after a keyframe edit, without
@@ -140,7 +237,12 @@ impl<F: Renderable> RenderingPipeline<F> {
     ///
     /// `sampling_level` must match the value the last `render` used, so the
     /// colorize pass walks the same populated sub-rectangle of the field.
-    pub fn recolorize_only(&mut self, out: &mut ColorImage, sampling_level: i32) {
+    /// Fails, leaving `out` untouched, if an edited color map is invalid.
+    pub fn recolorize_only(
+        &mut self,
+        out: &mut ColorImage,
+        sampling_level: i32,
+    ) -> Result<(), ColorMapError> {
         debug_assert!(
             sampling_level < (self.n_max_plus_1 as i32),
             "runtime sampling_level cannot exceed the cap baked into the field buffer"
@@ -150,7 +252,7 @@ impl<F: Renderable> RenderingPipeline<F> {
         // color-only pass must never be called against a stale buffer).
         let spec = *self.fractal.image_specification();
         debug_assert_eq!(
-            self.field.len(),
+            self.field.size()[0],
             (spec.resolution[0] as usize) * self.n_max_plus_1,
             "field outer dim must match (n_max+1)·W"
         );
@@ -161,7 +263,7 @@ impl<F: Renderable> RenderingPipeline<F> {
         // (c) Rebuild CDFs (identically, from the retained histograms), LUTs
         // (from the edited keyframes), and the background color.
         self.color_cache
-            .refresh_after_compute_pass(self.fractal.color_palette());
+            .refresh_after_compute_pass(self.fractal.color_palette())?;
 
         // (d) Walk the existing field; CDF + LUT lookup per cell; AA-average.
         colorize_collapse_unified(
@@ -169,8 +271,33 @@ impl<F: Renderable> RenderingPipeline<F> {
             &self.field,
             self.n_max_plus_1,
             sampling_level,
+            self.fractal.render_options().downscale_filter,
+            &mut self.downscale_scratch,
+            out,
+        );
+
+        // (e) Optional blur / bloom of the finished image.
+        apply_post_process(
+            &self.fractal.render_options().post_process,
             out,
+            &mut self.post_process_scratch,
         );
+        Ok(())
+    }
+
+    /// Tell the pipeline whether the fractal's params are currently at the
+    /// user's full quality, i.e. speed optimization level 0. CDFs held fixed
+    /// by `Renderable::hold_cdfs_fixed` are only frozen by a full-quality
+    /// pass, so the coarse first frames of explore mode do not set the
+    /// normalization. Defaults to `true`.
+    pub fn set_full_quality(&mut self, full_quality: bool) {
+        self.full_quality = full_quality;
+    }
+
+    /// Fraction of the samples of the last render at `sampling_level` that
+    /// produced a value rather than `None`.
+    pub fn valued_sample_fraction(&self, sampling_level: i32) -> f64 {
+        valued_cell_fraction(self.n_max_plus_1, sampling_level, &self.field)
     }
 
     /// Reference to the underlying fractal — used to read params for
@@ -194,7 +321,10 @@ mod tests {
 
     use crate::core::color_map::{ColorMap, ColorMapKeyFrame, ColorPalette};
     use crate::core::field_iteration::FieldKernel;
-    use crate::core::image_utils::{ImageSpecification, RenderOptions, Renderable, SpeedOptimizer};
+    use crate::core::image_utils::{
+        DEFAULT_MAX_PIXEL_COUNT, ImageSpecification, RenderError, RenderOptions, Renderable,
+        SpeedOptimizer, render_to_image, render_to_image_with_progress,
+    };
 
     use super::*;
 
@@ -205,6 +335,7 @@ mod tests {
         image_specification: ImageSpecification,
         render_options: RenderOptions,
         palette: ColorPalette,
+        hold_cdfs: bool,
     }
 
     impl FieldKernel for TestFractal {
@@ -257,6 +388,9 @@ mod tests {
         fn color_palette_mut(&mut self) -> &mut ColorPalette {
             &mut self.palette
         }
+        fn hold_cdfs_fixed(&self) -> bool {
+            self.hold_cdfs
+        }
     }
 
     fn red_to_blue() -> ColorMap {
@@ -278,14 +412,18 @@ mod tests {
                 resolution: [8, 6],
                 center: [0.0, 0.0],
                 width: 4.0,
+                pixel_aspect_ratio: 1.0,
+                min_width: None,
+                max_width: None,
             },
-            render_options: RenderOptions { sampling_level: 0 },
+            render_options: RenderOptions::default(),
             palette: ColorPalette {
                 background_color: [7, 8, 9],
                 color_maps: vec![red_to_blue()],
             },
+            hold_cdfs: false,
         };
-        RenderingPipeline::new(fractal, 1, 16, 2.0, 256)
+        RenderingPipeline::new(fractal, 1, 16, 2.0, 256).unwrap()
     }
 
     /// After a full `render`, `recolorize_only` with an unchanged palette must
@@ -297,8 +435,8 @@ mod tests {
         let mut rendered = ColorImage::filled([8, 6], Color32::BLACK);
         let mut recolorized = ColorImage::filled([8, 6], Color32::BLACK);
 
-        pipeline.render(&mut rendered, 0);
-        pipeline.recolorize_only(&mut recolorized, 0);
+        pipeline.render(&mut rendered, 0).unwrap();
+        pipeline.recolorize_only(&mut recolorized, 0).unwrap();
 
         assert_eq!(rendered.pixels, recolorized.pixels);
     }
@@ -310,16 +448,161 @@ mod tests {
     fn recolorize_only_picks_up_keyframe_edits() {
         let mut pipeline = test_pipeline();
         let mut rendered = ColorImage::filled([8, 6], Color32::BLACK);
-        pipeline.render(&mut rendered, 0);
+        pipeline.render(&mut rendered, 0).unwrap();
 
         pipeline.fractal_mut().color_palette_mut().color_maps[0][0].rgb_raw = [0, 255, 0];
 
         let mut recolorized = ColorImage::filled([8, 6], Color32::BLACK);
-        pipeline.recolorize_only(&mut recolorized, 0);
+        pipeline.recolorize_only(&mut recolorized, 0).unwrap();
         assert_ne!(rendered.pixels, recolorized.pixels);
 
         let mut fully_rerendered = ColorImage::filled([8, 6], Color32::BLACK);
-        pipeline.render(&mut fully_rerendered, 0);
+        pipeline.render(&mut fully_rerendered, 0).unwrap();
         assert_eq!(recolorized.pixels, fully_rerendered.pixels);
     }
+
+    /// An edit that leaves a color map invalid is reported, and the image
+    /// from the last good pass is left in place.
+    #[test]
+    fn recolorize_only_rejects_an_invalid_keyframe_edit() {
+        let mut pipeline = test_pipeline();
+        let mut image = ColorImage::filled([8, 6], Color32::BLACK);
+        pipeline.render(&mut image, 0).unwrap();
+        let rendered = image.clone();
+
+        pipeline.fractal_mut().color_palette_mut().color_maps[0][0].query = 0.5;
+
+        assert_eq!(
+            pipeline.recolorize_only(&mut image, 0),
+            Err(ColorMapError::FirstQueryNotZero { query: 0.5 })
+        );
+        assert_eq!(image.pixels, rendered.pixels);
+    }
+
+    /// `render_to_image` is an in-memory wrapper around the same pipeline,
+    /// so its RGB output must match a direct `render` pixel-for-pixel.
+    #[test]
+    fn render_to_image_matches_direct_pipeline_render() {
+        let mut pipeline = test_pipeline();
+        let mut rendered = ColorImage::filled([8, 6], Color32::BLACK);
+        pipeline.render(&mut rendered, 0).unwrap();
+
+        let fractal = TestFractal {
+            image_specification: *pipeline.fractal().image_specification(),
+            render_options: *pipeline.fractal().render_options(),
+            palette: pipeline.fractal().color_palette().clone(),
+            hold_cdfs: false,
+        };
+        let image = render_to_image(fractal, DEFAULT_MAX_PIXEL_COUNT).unwrap();
+
+        assert_eq!(image.dimensions(), (8, 6));
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let expected = rendered.pixels[(y as usize) * 8 + (x as usize)];
+            assert_eq!(pixel.0, [expected.r(), expected.g(), expected.b()]);
+        }
+    }
+
+    /// Uninterrupted progress reporting must end at 100% and produce the
+    /// same image as the plain `render_to_image` path.
+    #[test]
+    fn render_to_image_with_progress_reports_completion() {
+        let pipeline = test_pipeline();
+        let make_fractal = || TestFractal {
+            image_specification: *pipeline.fractal().image_specification(),
+            render_options: *pipeline.fractal().render_options(),
+            palette: pipeline.fractal().color_palette().clone(),
+            hold_cdfs: false,
+        };
+        let max_fraction = std::sync::Mutex::new(0.0f32);
+        let image =
+            render_to_image_with_progress(make_fractal(), DEFAULT_MAX_PIXEL_COUNT, &|fraction| {
+                let mut max_fraction = max_fraction.lock().unwrap();
+                *max_fraction = max_fraction.max(fraction);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(*max_fraction.lock().unwrap(), 1.0);
+        assert_eq!(
+            image,
+            render_to_image(make_fractal(), DEFAULT_MAX_PIXEL_COUNT).unwrap()
+        );
+    }
+
+    /// Held CDFs are frozen by the first full-quality pass, not by the sped-up
+    /// passes before it.
+    #[test]
+    fn held_cdfs_are_frozen_by_the_first_full_quality_pass() {
+        let mut pipeline = test_pipeline();
+        pipeline.fractal_mut().hold_cdfs = true;
+        let wide_view = *pipeline.fractal().image_specification();
+        let narrow_view = ImageSpecification {
+            center: [0.5, 0.0],
+            width: 1.0,
+            ..wide_view
+        };
+        let mut image = ColorImage::filled([8, 6], Color32::BLACK);
+        let mut render_at = |pipeline: &mut RenderingPipeline<TestFractal>, view| {
+            pipeline.fractal_mut().set_image_specification(view);
+            pipeline.render(&mut image, 0).unwrap();
+            pipeline.color_cache.cdfs()[0].percentile(0.5)
+        };
+
+        pipeline.set_full_quality(false);
+        let wide_percentile = render_at(&mut pipeline, wide_view);
+        let narrow_percentile = render_at(&mut pipeline, narrow_view);
+        assert_ne!(wide_percentile, narrow_percentile);
+
+        pipeline.set_full_quality(true);
+        assert_eq!(render_at(&mut pipeline, narrow_view), narrow_percentile);
+        assert_eq!(render_at(&mut pipeline, wide_view), narrow_percentile);
+    }
+
+    /// A callback that breaks on its first call cancels the render.
+    #[test]
+    fn render_to_image_with_progress_cancels_on_break() {
+        let pipeline = test_pipeline();
+        let fractal = TestFractal {
+            image_specification: *pipeline.fractal().image_specification(),
+            render_options: *pipeline.fractal().render_options(),
+            palette: pipeline.fractal().color_palette().clone(),
+            hold_cdfs: false,
+        };
+        let result = render_to_image_with_progress(fractal, DEFAULT_MAX_PIXEL_COUNT, &|_| {
+            ControlFlow::Break(())
+        });
+        assert_eq!(result, Err(RenderError::Cancelled));
+    }
+
+    /// Rayon splits the field into rows and reduces the histograms in
+    /// whatever order the threads finish, so this guards against any
+    /// order-dependent accumulation: a real fractal, with anti-aliasing and
+    /// histogram equalization, must render bit-identically on one thread
+    /// and on many.
+    #[test]
+    fn render_is_independent_of_the_thread_count() {
+        use crate::fractals::common::FractalParams;
+
+        let json = std::fs::read_to_string("examples/render-mandelbrot/params.json").unwrap();
+        let FractalParams::Mandelbrot(mut params) = serde_json::from_str(&json).unwrap() else {
+            panic!("expected Mandelbrot params");
+        };
+        params.image_specification.resolution = [96, 72];
+        params.render_options.sampling_level = 2;
+        params.convergence_params.max_iter_count = 256;
+
+        let render_with_threads = |num_threads: usize| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap()
+                .install(|| render_to_image((*params).clone(), DEFAULT_MAX_PIXEL_COUNT).unwrap())
+        };
+        let single_threaded = render_with_threads(1);
+        for num_threads in [2, 7, 16] {
+            assert!(
+                render_with_threads(num_threads) == single_threaded,
+                "render on {num_threads} threads differs from the single-threaded render"
+            );
+        }
+    }
 }
@@ -157,6 +157,11 @@ where
         self.mode == Mode::Idle
     }
 
+    /// Current state of the FSM.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
     /// @param previous_render_command: previous render command, if one has been set
     /// @param render_period: if the command has been completed, how long did it take?
     /// @param is_interactive:  is the user interacting with the fractal view port?
@@ -238,6 +243,18 @@ where
 
 ////////////////////////////////////////////////////////////////////////////////////////
 
+/// Snapshot of the internal state of an `AdaptiveOptimizationRegulator`, for
+/// debug overlays and data logging.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegulatorState {
+    /// State of the render quality FSM.
+    pub mode: Mode,
+    /// Command of the most recently started render, if any.
+    pub render_command: Option<f64>,
+    /// How long that render took, once it has finished.
+    pub render_period: Option<f64>,
+}
+
 /// The `AdaptiveOptimizationRegulator` is a simple class wrapping a finite state machine
 /// that is used to compute the "render quality" (0 = high quality but slow, 1 = low quality
 /// but fast), while exploring a fractal interactively with the user.
@@ -277,6 +294,15 @@ impl AdaptiveOptimizationRegulator {
         self.render_policy_fsm.is_idle()
     }
 
+    /// Snapshot of the regulator's state, for the debug overlay and logs.
+    pub fn state(&self) -> RegulatorState {
+        RegulatorState {
+            mode: self.render_policy_fsm.mode(),
+            render_command: self.render_command,
+            render_period: self.render_period,
+        }
+    }
+
     /// This method is called each time that the `explore` pipeline would like
     /// to render the fractal. It returns an optional value, which, if set,
     /// indicates that the fractal should be rendered, and the floating point
@@ -328,7 +354,7 @@ mod tests {
 
     use super::*;
     use approx::assert_relative_eq;
-    use more_asserts::{assert_ge, assert_le};
+    use more_asserts::{assert_ge, assert_gt, assert_le};
 
     use rand::Rng;
     use rand::SeedableRng;
@@ -616,4 +642,183 @@ mod tests {
             );
         }
     }
+
+    /// Policy that moves the command by a fixed step, so that FSM tests can
+    /// tell which policy produced a command and from which previous command.
+    #[derive(Clone, Debug)]
+    struct FixedStepPolicy {
+        step: f64,
+    }
+
+    impl RenderQualityPolicy for FixedStepPolicy {
+        fn evaluate(&mut self, previous_command: f64, _measured_period: f64) -> f64 {
+            previous_command + self.step
+        }
+    }
+
+    fn step_fsm(initial_command: f64) -> FiniteStateMachine<FixedStepPolicy, FixedStepPolicy> {
+        FiniteStateMachine::new(
+            initial_command,
+            FixedStepPolicy { step: 0.25 },
+            FixedStepPolicy { step: -0.5 },
+        )
+    }
+
+    #[test]
+    fn test_fsm_begin_rendering_sends_initial_command_and_picks_mode() {
+        let mut fsm = step_fsm(0.4);
+        assert_eq!(fsm.render_required(None, None, true), Some(0.4));
+        assert_eq!(fsm.mode, Mode::Interactive);
+
+        let mut fsm = step_fsm(0.4);
+        assert_eq!(fsm.render_required(None, None, false), Some(0.4));
+        assert_eq!(fsm.mode, Mode::Background);
+    }
+
+    #[test]
+    fn test_fsm_interactive_waits_for_period_then_steps_from_interactive_command() {
+        let mut fsm = step_fsm(0.0);
+        fsm.render_required(None, None, true);
+
+        // Render still in flight: nothing to do.
+        assert_eq!(fsm.render_required(Some(0.0), None, true), None);
+        assert_eq!(fsm.mode, Mode::Interactive);
+
+        // The interactive policy steps from its own last command, not from
+        // the `previous_render_command` argument.
+        assert_eq!(fsm.render_required(Some(0.9), Some(0.1), true), Some(0.25));
+        assert_eq!(fsm.render_required(Some(0.9), Some(0.1), true), Some(0.5));
+        assert_eq!(fsm.mode, Mode::Interactive);
+
+        // Saturates at the maximum command.
+        for _ in 0..4 {
+            fsm.render_required(Some(0.0), Some(0.1), true);
+        }
+        assert_eq!(fsm.render_required(Some(0.0), Some(0.1), true), Some(1.0));
+    }
+
+    #[test]
+    fn test_fsm_interactive_to_background_to_idle() {
+        let mut fsm = step_fsm(0.0);
+        fsm.render_required(None, None, true);
+        assert_eq!(fsm.render_required(Some(0.0), Some(0.1), true), Some(0.25));
+        assert_eq!(fsm.render_required(Some(0.25), Some(0.1), true), Some(0.5));
+
+        // Releasing the controls switches to the background policy on the
+        // next call, but this call still finishes the interactive update.
+        assert_eq!(fsm.render_required(Some(0.5), Some(0.1), false), Some(0.75));
+        assert_eq!(fsm.mode, Mode::Background);
+
+        // Background steps down from the last *completed* command, and goes
+        // idle once it reaches full quality.
+        assert_eq!(fsm.render_required(Some(0.75), None, false), None);
+        assert_eq!(
+            fsm.render_required(Some(0.75), Some(0.1), false),
+            Some(0.25)
+        );
+        assert_eq!(fsm.mode, Mode::Background);
+        assert_eq!(fsm.render_required(Some(0.25), Some(0.1), false), Some(0.0));
+        assert!(fsm.is_idle());
+
+        // Idle stays idle without interaction.
+        assert_eq!(fsm.render_required(Some(0.0), Some(0.1), false), None);
+        assert!(fsm.is_idle());
+    }
+
+    #[test]
+    #[should_panic(expected = "no matching command")]
+    fn test_fsm_background_requires_command_with_period() {
+        let mut fsm = step_fsm(0.0);
+        fsm.render_required(None, None, false);
+        fsm.render_required(None, Some(0.1), false);
+    }
+
+    #[test]
+    fn test_fsm_idle_resumes_at_previous_interactive_command() {
+        let mut fsm = step_fsm(0.0);
+        fsm.render_required(None, None, true);
+        fsm.render_required(Some(0.0), Some(0.1), true);
+        assert_eq!(fsm.render_required(Some(0.25), Some(0.1), false), Some(0.5));
+        fsm.render_required(Some(0.5), Some(0.1), false);
+        assert!(fsm.is_idle());
+
+        // Resuming interaction picks up where the interactive policy left
+        // off, rather than at the full-quality command that the background
+        // policy ramped down to.
+        assert_eq!(fsm.render_required(Some(0.0), Some(0.1), true), Some(0.5));
+        assert_eq!(fsm.mode, Mode::Interactive);
+        assert_eq!(fsm.render_required(Some(0.5), Some(0.1), true), Some(0.75));
+    }
+
+    #[test]
+    fn test_fsm_background_interrupted_by_interaction() {
+        let mut fsm = step_fsm(0.0);
+        fsm.render_required(None, None, false);
+        assert_eq!(fsm.mode, Mode::Background);
+
+        // The background update in progress completes, then the next call
+        // is handled by the interactive policy.
+        assert_eq!(fsm.render_required(Some(1.0), Some(0.1), true), Some(0.5));
+        assert_eq!(fsm.mode, Mode::Interactive);
+        assert_eq!(fsm.render_required(Some(0.5), Some(0.1), true), Some(0.25));
+    }
+
+    #[test]
+    fn test_fsm_reset_returns_to_begin_rendering() {
+        let mut fsm = step_fsm(0.1);
+        fsm.render_required(None, None, true);
+        fsm.render_required(Some(0.1), Some(0.1), true);
+        fsm.render_required(Some(0.35), Some(0.1), false);
+        fsm.reset();
+        assert_eq!(fsm.mode, Mode::BeginRendering);
+        assert_eq!(fsm.render_required(None, None, false), Some(0.1));
+    }
+
+    #[test]
+    fn test_regulator_double_finish_rendering_keeps_first_period() {
+        let mut regulator = AdaptiveOptimizationRegulator::new(0.025);
+        assert_eq!(regulator.render_required(true), Some(0.0));
+
+        regulator.begin_rendering(1.0, 0.0);
+        regulator.finish_rendering(1.5);
+        regulator.finish_rendering(3.0);
+        assert_eq!(regulator.render_period, Some(0.5));
+        assert_eq!(regulator.render_command, Some(0.0));
+
+        // A 20x-too-slow render pushes the interactive command up.
+        let command = regulator.render_required(true).unwrap();
+        assert_gt!(command, 0.5);
+
+        regulator.begin_rendering(4.0, command);
+        regulator.finish_rendering(4.01);
+        regulator.finish_rendering(10.0);
+        assert_relative_eq!(regulator.render_period.unwrap(), 0.01, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_regulator_state_tracks_mode_command_and_period() {
+        let mut regulator = AdaptiveOptimizationRegulator::new(0.025);
+        assert_eq!(
+            regulator.state(),
+            RegulatorState {
+                mode: Mode::BeginRendering,
+                render_command: None,
+                render_period: None,
+            }
+        );
+
+        let command = regulator.render_required(false).unwrap();
+        regulator.begin_rendering(1.0, command);
+        let state = regulator.state();
+        assert_eq!(state.mode, Mode::Background);
+        assert_eq!(state.render_command, Some(command));
+        assert_eq!(state.render_period, None);
+
+        regulator.finish_rendering(1.25);
+        assert_eq!(regulator.state().render_period, Some(0.25));
+
+        regulator.reset();
+        assert_eq!(regulator.state().mode, Mode::BeginRendering);
+        assert_eq!(regulator.state().render_command, None);
+    }
 }
@@ -5,8 +5,8 @@ use std::sync::{
 
 use egui::{Color32, ColorImage};
 
-use crate::core::color_map::ColorPalette;
-use crate::core::render_quality_fsm::AdaptiveOptimizationRegulator;
+use crate::core::color_map::{ColorMapError, ColorPalette};
+use crate::core::render_quality_fsm::{AdaptiveOptimizationRegulator, RegulatorState};
 
 use super::{
     file_io::{FilePrefix, date_time_string, write_file_or_panic},
@@ -14,6 +14,7 @@ use super::{
         ImageSpecification, Renderable, color_image_to_rgb8, field_upsample_factor,
         write_image_to_file_or_panic,
     },
+    phase_space_plot::{PHASE_SPACE_PLOT_RESOLUTION, phase_space_plot_image},
     render_pipeline::RenderingPipeline,
     view_control::{CenterCommand, CenterTargetCommand, ViewControl, ZoomVelocityCommand},
 };
@@ -134,6 +135,12 @@ pub struct PixelGrid<F: Renderable> {
     // Whether a render has ever been launched on this `PixelGrid`.
     has_started_rendering: bool,
 
+    // Whether idle refinement passes (`Renderable::refine`) should keep
+    // running on the current view. Set by each regulator-driven render and
+    // cleared by the background thread once the fractal has nothing left
+    // to add.
+    refinement_pending: Arc<AtomicBool>,
+
     // Serializes the current fractal params into a reloadable, tagged
     // `FractalParams` JSON string for the Space-as-save snapshot. Boxed so
     // `core` need not depend on the `fractals::FractalParams` enum; supplied
@@ -151,7 +158,8 @@ where
     F: Renderable + Send + Sync + 'static,
 {
     /// Construct a `PixelGrid` around the given fractal. Allocates the
-    /// pipeline's reusable buffers at the user's full sampling level.
+    /// pipeline's reusable buffers at the user's full sampling level. Fails
+    /// if any of the fractal's color maps is invalid.
     ///
     /// `serialize_snapshot` wraps the fractal's inner params back into a
     /// reloadable, tagged `FractalParams` JSON string for the Space-as-save
@@ -162,21 +170,26 @@ where
         view_control: ViewControl,
         renderer: F,
         serialize_snapshot: SnapshotSerializer<F>,
-    ) -> Self {
+    ) -> Result<Self, ColorMapError> {
         let resolution = view_control.image_specification().resolution;
         let center_command = CenterCommand::Target(CenterTargetCommand {
             view_center: view_control.image_specification().center,
             pan_rate: 0.0,
         });
 
-        let n_max_plus_1 = field_upsample_factor(renderer.render_options().sampling_level);
+        let render_options = renderer.render_options();
+        let n_max_plus_1 = field_upsample_factor(
+            render_options
+                .sampling_level
+                .max(render_options.screenshot_sampling_level()),
+        );
         let bin_count = renderer.histogram_bin_count();
         let hist_max = renderer.histogram_max_value();
         let lut_count = renderer.lookup_table_count();
         let speed_optimizer_cache = renderer.reference_cache();
         let initial_color_palette = renderer.color_palette().clone();
         let pipeline =
-            RenderingPipeline::new(renderer, n_max_plus_1, bin_count, hist_max, lut_count);
+            RenderingPipeline::new(renderer, n_max_plus_1, bin_count, hist_max, lut_count)?;
         let display_buffer = ColorImage::filled(
             [resolution[0] as usize, resolution[1] as usize],
             Color32::BLACK,
@@ -195,6 +208,7 @@ where
             initial_color_palette,
             last_sampling_level: Arc::new(AtomicI32::new(0)),
             has_started_rendering: false,
+            refinement_pending: Arc::new(AtomicBool::new(false)),
             serialize_snapshot,
             save_state: SaveState::Idle,
             adaptive_quality_regulator: AdaptiveOptimizationRegulator::new(
@@ -204,7 +218,7 @@ where
         pixel_grid
             .view_control
             .update(time, center_command, ZoomVelocityCommand::zero());
-        pixel_grid
+        Ok(pixel_grid)
     }
 
     /// Whether a background render is currently in flight.
@@ -218,9 +232,17 @@ where
     }
 
     /// Whether the regulator wants another render even without user input
-    /// (e.g. ramping quality back up after the user stopped panning).
+    /// (e.g. ramping quality back up after the user stopped panning), or an
+    /// idle refinement pass is still sharpening the image.
     pub fn adaptive_rendering_required(&self) -> bool {
         !self.adaptive_quality_regulator.is_idle()
+            || self.refinement_pending.load(Ordering::Acquire)
+    }
+
+    /// Current mode, last command, and measured render period of the
+    /// adaptive quality regulator, for a debug HUD or data logger.
+    pub fn regulator_state(&self) -> RegulatorState {
+        self.adaptive_quality_regulator.state()
     }
 
     /// Editor's source-of-truth color palette. The interactive app locks
@@ -256,6 +278,11 @@ where
     /// Serialize the current (full-quality, palette- and view-synced) fractal
     /// params to a timestamped reloadable JSON and write the on-screen buffer
     /// to a matching PNG. Called once the forced save render completes.
+    ///
+    /// The params are read back from the pipeline's fractal rather than from
+    /// `speed_optimizer_cache`, so they are the *effective* params after
+    /// `set_speed_optimization_level`: exactly what produced the PNG. The save
+    /// render forces level 0.0, so these are also the user's reference params.
     fn write_snapshot(&self) {
         let datetime = date_time_string();
         let json = {
@@ -275,9 +302,56 @@ where
         );
     }
 
+    /// Write a reloadable params JSON for the view centered on `center` at
+    /// the current width, with the user's full-quality params and the edited
+    /// palette, so the spot can be re-rendered with the `render` command.
+    /// Returns the path written. Leaves the pipeline's fractal at full
+    /// quality; the next render sets its own quality level and view anyway.
+    pub fn write_location(&self, center: [f64; 2]) -> std::path::PathBuf {
+        let image_specification = ImageSpecification {
+            center,
+            ..*self.image_specification()
+        };
+        let json = {
+            let mut pipeline = self.pipeline.lock().unwrap();
+            let fractal = pipeline.fractal_mut();
+            let previous_specification = *fractal.image_specification();
+            fractal.set_speed_optimization_level(0.0, &self.speed_optimizer_cache);
+            *fractal.color_palette_mut() = self.palette.lock().unwrap().clone();
+            fractal.set_image_specification(image_specification);
+            let json = (self.serialize_snapshot)(fractal.params());
+            fractal.set_image_specification(previous_specification);
+            json
+        };
+        let path = self
+            .file_prefix
+            .full_path_with_suffix(&format!("_location_{}.json", date_time_string()));
+        write_file_or_panic(path.clone(), &json);
+        path
+    }
+
+    /// Plot the phase-space trajectory that starts from `point`, simulated
+    /// with the user's full-quality params, if the fractal has one. Returns
+    /// the path written.
+    pub fn write_phase_space_plot(&self, point: [f64; 2]) -> Option<std::path::PathBuf> {
+        let states = {
+            let mut pipeline = self.pipeline.lock().unwrap();
+            let fractal = pipeline.fractal_mut();
+            fractal.set_speed_optimization_level(0.0, &self.speed_optimizer_cache);
+            fractal.phase_space_trajectory(point)?
+        };
+        let path = self
+            .file_prefix
+            .full_path_with_suffix(&format!("_phase_space_{}.png", date_time_string()));
+        let image = phase_space_plot_image(&states, PHASE_SPACE_PLOT_RESOLUTION);
+        write_image_to_file_or_panic(path.clone(), |path| image.save(path));
+        Some(path)
+    }
+
     /// Spawn a background render on the pipeline. The pipeline's mutex
-    /// serializes against the UI thread's parameter edits.
-    fn render(&mut self) {
+    /// serializes against the UI thread's parameter edits. A `screenshot`
+    /// render, for a save, samples at the screenshot sampling level.
+    fn render(&mut self, screenshot: bool) {
         let display_buffer = self.display_buffer.clone();
         let pipeline = self.pipeline.clone();
         let palette = self.palette.clone();
@@ -293,11 +367,54 @@ where
             pipeline_mut
                 .fractal_mut()
                 .set_image_specification(image_specification);
-            let sampling_level = pipeline_mut.fractal().render_options().sampling_level;
-            pipeline_mut.render(&mut color_image, sampling_level);
-            last_sampling_level.store(sampling_level, Ordering::Release);
+            let render_options = pipeline_mut.fractal().render_options();
+            let sampling_level = if screenshot {
+                render_options.screenshot_sampling_level()
+            } else {
+                render_options.sampling_level
+            };
+            match pipeline_mut.render(&mut color_image, sampling_level) {
+                Ok(()) => {
+                    last_sampling_level.store(sampling_level, Ordering::Release);
+                    redraw_required.store(true, Ordering::Release);
+                }
+                Err(error) => println!("ERROR:  Unable to render: {error}"),
+            }
+            render_task_is_busy.store(false, Ordering::Release);
+        });
+    }
+
+    /// Spawn a background refinement pass: let the fractal add detail to the
+    /// unchanged view, then re-render it at full quality. If the fractal has
+    /// nothing left to add, clear `refinement_pending` instead. The pipeline
+    /// is locked before the display buffer so that `draw` is not blocked
+    /// while the fractal refines.
+    fn refine(&mut self) {
+        let display_buffer = self.display_buffer.clone();
+        let pipeline = self.pipeline.clone();
+        let palette = self.palette.clone();
+        let render_task_is_busy = Arc::clone(&self.render_task_is_busy);
+        let redraw_required = self.redraw_required.clone();
+        let last_sampling_level = self.last_sampling_level.clone();
+        let refinement_pending = self.refinement_pending.clone();
+
+        std::thread::spawn(move || {
+            let mut pipeline_mut = pipeline.lock().unwrap();
+            if pipeline_mut.fractal_mut().refine() {
+                let mut color_image = display_buffer.lock().unwrap();
+                *pipeline_mut.fractal_mut().color_palette_mut() = palette.lock().unwrap().clone();
+                let sampling_level = pipeline_mut.fractal().render_options().sampling_level;
+                match pipeline_mut.render(&mut color_image, sampling_level) {
+                    Ok(()) => {
+                        last_sampling_level.store(sampling_level, Ordering::Release);
+                        redraw_required.store(true, Ordering::Release);
+                    }
+                    Err(error) => println!("ERROR:  Unable to render: {error}"),
+                }
+            } else {
+                refinement_pending.store(false, Ordering::Release);
+            }
             render_task_is_busy.store(false, Ordering::Release);
-            redraw_required.store(true, Ordering::Release);
         });
     }
 
@@ -317,9 +434,11 @@ where
             let mut color_image = display_buffer.lock().unwrap();
             let mut pipeline_mut = pipeline.lock().unwrap();
             *pipeline_mut.fractal_mut().color_palette_mut() = palette.lock().unwrap().clone();
-            pipeline_mut.recolorize_only(&mut color_image, sampling_level);
+            match pipeline_mut.recolorize_only(&mut color_image, sampling_level) {
+                Ok(()) => redraw_required.store(true, Ordering::Release),
+                Err(error) => println!("ERROR:  Unable to recolor: {error}"),
+            }
             render_task_is_busy.store(false, Ordering::Release);
-            redraw_required.store(true, Ordering::Release);
         });
     }
 }
@@ -394,13 +513,15 @@ where
                     // so a redundant recolorize doesn't fire after the save
                     // (mirrors the full-render path below).
                     self.color_dirty.store(false, Ordering::Release);
-                    self.pipeline
-                        .lock()
-                        .unwrap()
-                        .fractal_mut()
-                        .set_speed_optimization_level(0.0, &self.speed_optimizer_cache);
+                    {
+                        let mut pipeline = self.pipeline.lock().unwrap();
+                        pipeline
+                            .fractal_mut()
+                            .set_speed_optimization_level(0.0, &self.speed_optimizer_cache);
+                        pipeline.set_full_quality(true);
+                    }
                     self.has_started_rendering = true;
-                    self.render();
+                    self.render(true);
                     self.save_state = SaveState::Rendering;
                 }
                 return false;
@@ -428,16 +549,19 @@ where
             // If we need to render, poll the render background thread to see if it is available...
             if !self.render_task_is_busy.swap(true, Ordering::Acquire) {
                 // If we reach here, then the background thread is ready to render an image.
-                self.pipeline
-                    .lock()
-                    .unwrap()
-                    .fractal_mut()
-                    .set_speed_optimization_level(command, &self.speed_optimizer_cache);
+                {
+                    let mut pipeline = self.pipeline.lock().unwrap();
+                    pipeline
+                        .fractal_mut()
+                        .set_speed_optimization_level(command, &self.speed_optimizer_cache);
+                    pipeline.set_full_quality(command <= 0.0);
+                }
                 // Mark the start of the render operation so that we can collect accurate timing.
                 self.adaptive_quality_regulator
                     .begin_rendering(time, command);
                 self.has_started_rendering = true;
-                self.render();
+                self.refinement_pending.store(true, Ordering::Release);
+                self.render(false);
                 launched_full_render = true;
                 // The full render clones the current (possibly just-edited)
                 // palette into the fractal, so it already satisfies any
@@ -447,6 +571,19 @@ where
             }
         }
 
+        // Once the regulator has settled at full quality, keep refining the
+        // static view in the background for as long as the fractal reports
+        // progress (e.g. the chaos game drawing more samples).
+        if !launched_full_render
+            && !user_interaction
+            && self.adaptive_quality_regulator.is_idle()
+            && self.refinement_pending.load(Ordering::Acquire)
+            && !self.render_task_is_busy.swap(true, Ordering::Acquire)
+        {
+            self.refine();
+            launched_full_render = true;
+        }
+
         // Color-only re-render after a palette edit. A full view render takes
         // priority — it regenerates the field a recolorize would re-walk — so
         // only recolorize when none was launched this tick and the worker is
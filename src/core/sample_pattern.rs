@@ -0,0 +1,228 @@
+//! Diagnostic plot of where the anti-aliasing samples of one output pixel
+//! land, for checking sampling patterns by eye. Not part of any render.
+//!
+//! Offsets are measured from the center of the output pixel, in units of
+//! output pixels, with `y` increasing down the image. The plot shows the
+//! pixel's footprint (the square `[-0.5, 0.5]²`) magnified, with half a
+//! pixel of its neighbors around it, so samples that spill out of the
+//! footprint are visible too.
+
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+use rand::{Rng, SeedableRng};
+
+use crate::core::chaos_game::SeededRng;
+use crate::core::field_iteration::subpixel_jitter;
+use crate::core::image_utils::{ImageSpecification, PixelMapper};
+
+/// Side of the plot, in image pixels per output pixel.
+pub const SAMPLE_PATTERN_MAGNIFICATION: u32 = 256;
+
+/// Half-width of the plotted region, in output pixels.
+const PLOT_HALF_WIDTH: f64 = 1.0;
+
+const BACKGROUND_COLOR: Rgb<u8> = Rgb([16, 16, 16]);
+
+/// Outline of the pixel footprint and the crosshair at its center.
+const FOOTPRINT_COLOR: Rgb<u8> = Rgb([90, 90, 90]);
+
+const SAMPLE_COLOR: Rgb<u8> = Rgb([255, 200, 40]);
+
+/// Radius, in image pixels, of the dot drawn for each sample.
+const SAMPLE_DOT_RADIUS: i64 = 4;
+
+/// Poisson-disk dart throwing gives up after this many rejected candidates
+/// per requested sample.
+const POISSON_ATTEMPTS_PER_SAMPLE: usize = 64;
+
+/// Minimum spacing of the Poisson-disk samples, as a fraction of the
+/// spacing of the regular grid with the same sample count.
+const POISSON_SPACING_FRACTION: f64 = 0.75;
+
+/// Arrangement of the samples within a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SamplePattern {
+    /// The regular subpixel grid used by the render pipeline.
+    Grid,
+    /// The grid shifted by the per-pixel offset of
+    /// `RenderOptions::subpixel_jitter`.
+    Jittered,
+    /// Proposed: Poisson-disk samples, seeded by the pixel index. Not used
+    /// by any render.
+    Poisson,
+}
+
+/// Offsets, from the center of output pixel `pixel`, of its samples for
+/// `subpixel_count²` samples per pixel arranged as `pattern`.
+pub fn sample_offsets(
+    image_specification: &ImageSpecification,
+    subpixel_count: u32,
+    pixel: [u32; 2],
+    pattern: SamplePattern,
+) -> Vec<[f64; 2]> {
+    assert!(subpixel_count > 0);
+    if pattern == SamplePattern::Poisson {
+        return poisson_disk_offsets(subpixel_count, pixel);
+    }
+    let mapper = PixelMapper::new(image_specification);
+    let upsampled = PixelMapper::new(&image_specification.upsample(subpixel_count));
+    let maps = [
+        (&mapper.width, &upsampled.width),
+        (&mapper.height, &upsampled.height),
+    ];
+    // The render only jitters when it takes more than one sample.
+    let jitter = if pattern == SamplePattern::Jittered && subpixel_count > 1 {
+        subpixel_jitter(pixel)
+    } else {
+        [0.0; 2]
+    };
+    let mut offsets = Vec::with_capacity((subpixel_count * subpixel_count) as usize);
+    for sub_y in 0..subpixel_count {
+        for sub_x in 0..subpixel_count {
+            let subpixel = [sub_x, sub_y];
+            offsets.push([0, 1].map(|dim| {
+                let (map, upsampled_map) = maps[dim];
+                let pixel_step = map.map(1) - map.map(0);
+                let subpixel_step = upsampled_map.map(1) - upsampled_map.map(0);
+                let sample = upsampled_map.map(pixel[dim] * subpixel_count + subpixel[dim])
+                    + jitter[dim] * subpixel_step;
+                (sample - map.map(pixel[dim])) / pixel_step
+            }));
+        }
+    }
+    offsets
+}
+
+/// Dart throwing within the pixel footprint: up to `subpixel_count²`
+/// samples, no two closer than `POISSON_SPACING_FRACTION / subpixel_count`.
+fn poisson_disk_offsets(subpixel_count: u32, pixel: [u32; 2]) -> Vec<[f64; 2]> {
+    let target = (subpixel_count * subpixel_count) as usize;
+    let min_distance_squared = (POISSON_SPACING_FRACTION / subpixel_count as f64).powi(2);
+    let mut rng = SeededRng::seed_from_u64(((pixel[0] as u64) << 32) | pixel[1] as u64);
+    let mut offsets: Vec<[f64; 2]> = Vec::with_capacity(target);
+    for _ in 0..target * POISSON_ATTEMPTS_PER_SAMPLE {
+        if offsets.len() == target {
+            break;
+        }
+        let candidate = [rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5)];
+        if offsets.iter().all(|other: &[f64; 2]| {
+            (other[0] - candidate[0]).powi(2) + (other[1] - candidate[1]).powi(2)
+                >= min_distance_squared
+        }) {
+            offsets.push(candidate);
+        }
+    }
+    offsets
+}
+
+/// Mean of `offsets`: how far the pattern as a whole sits from the pixel
+/// center.
+pub fn centroid(offsets: &[[f64; 2]]) -> [f64; 2] {
+    let count = offsets.len().max(1) as f64;
+    [0, 1].map(|dim| offsets.iter().map(|offset| offset[dim]).sum::<f64>() / count)
+}
+
+/// Plot `offsets` as dots over the magnified pixel footprint.
+pub fn sample_pattern_image(offsets: &[[f64; 2]], magnification: u32) -> RgbImage {
+    let size = (2.0 * PLOT_HALF_WIDTH * magnification as f64).round() as u32;
+    let mut image = RgbImage::from_pixel(size, size, BACKGROUND_COLOR);
+    let to_image = |offset: f64| ((offset + PLOT_HALF_WIDTH) * magnification as f64).round() as i64;
+
+    let [low, high] = [to_image(-0.5), to_image(0.5)];
+    let center = to_image(0.0);
+    for along in low..=high {
+        for edge in [low, high] {
+            put_pixel_clipped(&mut image, [along, edge], FOOTPRINT_COLOR);
+            put_pixel_clipped(&mut image, [edge, along], FOOTPRINT_COLOR);
+        }
+    }
+    for along in (center - SAMPLE_DOT_RADIUS * 2)..=(center + SAMPLE_DOT_RADIUS * 2) {
+        put_pixel_clipped(&mut image, [along, center], FOOTPRINT_COLOR);
+        put_pixel_clipped(&mut image, [center, along], FOOTPRINT_COLOR);
+    }
+
+    for offset in offsets {
+        let [x, y] = offset.map(to_image);
+        for dy in -SAMPLE_DOT_RADIUS..=SAMPLE_DOT_RADIUS {
+            for dx in -SAMPLE_DOT_RADIUS..=SAMPLE_DOT_RADIUS {
+                if dx * dx + dy * dy <= SAMPLE_DOT_RADIUS * SAMPLE_DOT_RADIUS {
+                    put_pixel_clipped(&mut image, [x + dx, y + dy], SAMPLE_COLOR);
+                }
+            }
+        }
+    }
+    image
+}
+
+fn put_pixel_clipped(image: &mut RgbImage, [x, y]: [i64; 2], color: Rgb<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ImageSpecification {
+        ImageSpecification {
+            resolution: [64, 48],
+            center: [0.0, 0.0],
+            width: 4.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        }
+    }
+
+    #[test]
+    fn grid_and_jittered_patterns_match_the_render_pipeline() {
+        let spec = spec();
+        let grid = sample_offsets(&spec, 3, [10, 20], SamplePattern::Grid);
+        assert_eq!(grid.len(), 9);
+        // Row-major, with consecutive samples one subpixel step apart. The
+        // upsampled grid spans the same view with `3n - 1` steps where the
+        // output has `n - 1`, so a subpixel step is a little under a third
+        // of a pixel, and not quite the same on the two axes.
+        let step = [grid[1][0] - grid[0][0], grid[3][1] - grid[0][1]];
+        assert!((step[0] - 63.0 / 191.0).abs() < 1e-9, "{step:?}");
+        assert!((step[1] - 47.0 / 143.0).abs() < 1e-9, "{step:?}");
+
+        let jittered = sample_offsets(&spec, 3, [10, 20], SamplePattern::Jittered);
+        let jitter = subpixel_jitter([10, 20]);
+        for (plain, shifted) in grid.iter().zip(&jittered) {
+            for dim in 0..2 {
+                let expected = plain[dim] + jitter[dim] * step[dim];
+                assert!((shifted[dim] - expected).abs() < 1e-9);
+            }
+        }
+        // One sample per pixel lands on the pixel center at the origin.
+        let single = sample_offsets(&spec, 1, [0, 0], SamplePattern::Jittered);
+        assert_eq!(single.len(), 1);
+        assert!(single[0].iter().all(|offset| offset.abs() < 1e-9));
+    }
+
+    #[test]
+    fn poisson_samples_stay_in_the_footprint_and_apart() {
+        let offsets = sample_offsets(&spec(), 4, [3, 5], SamplePattern::Poisson);
+        assert!(offsets.len() > 8, "{}", offsets.len());
+        assert!(offsets.len() <= 16);
+        for (index, a) in offsets.iter().enumerate() {
+            assert!(a.iter().all(|offset| (-0.5..0.5).contains(offset)));
+            for b in &offsets[..index] {
+                let distance = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+                assert!(distance >= POISSON_SPACING_FRACTION / 4.0 - 1e-12);
+            }
+        }
+        assert_eq!(
+            offsets,
+            sample_offsets(&spec(), 4, [3, 5], SamplePattern::Poisson)
+        );
+
+        let image = sample_pattern_image(&offsets, 64);
+        assert_eq!(image.dimensions(), (128, 128));
+        let [x, y] = offsets[0].map(|offset| ((offset + 1.0) * 64.0).round() as u32);
+        assert_eq!(*image.get_pixel(x, y), SAMPLE_COLOR);
+        assert_eq!(*image.get_pixel(32, 40), FOOTPRINT_COLOR);
+    }
+}
@@ -1,8 +1,8 @@
 use nalgebra::Vector2;
 
 use super::{
-    controller::{PointTracker, Target},
-    image_utils::ImageSpecification,
+    controller::{PointTracker, Target, TrackingMode},
+    image_utils::{ImageSpecification, ViewRectangle},
 };
 
 #[derive(PartialEq, Debug)]
@@ -119,6 +119,30 @@ pub struct ViewControl {
     // Internal controllers:
     pub pan_control: [PointTracker; 2],
     pub zoom_control: PointTracker,
+
+    /// Region that the view center is kept within, e.g. to stop the user from
+    /// wandering off into empty space. `None` (the default) is unbounded.
+    pub pan_bounds: Option<ViewRectangle>,
+
+    // Set while the zoom is held at the precision floor, so the warning is
+    // printed once each time the floor is reached.
+    at_precision_floor: bool,
+}
+
+/// Pixels narrower than this many multiples of the f64 spacing near the view
+/// center (or near 1.0, as the iterated values are of that order) no longer
+/// resolve distinct points, and the image degrades into noise.
+const PRECISION_FLOOR_ULPS_PER_PIXEL: f64 = 64.0;
+
+/// Narrowest view width that `f64` arithmetic can still resolve at this
+/// center and resolution.
+pub fn precision_floor_width(image_specification: &ImageSpecification) -> f64 {
+    let [x, y] = image_specification.center;
+    let magnitude = x.abs().max(y.abs()).max(1.0);
+    magnitude
+        * f64::EPSILON
+        * PRECISION_FLOOR_ULPS_PER_PIXEL
+        * image_specification.resolution[0] as f64
 }
 
 impl ViewControl {
@@ -143,10 +167,29 @@ impl ViewControl {
                 PointTracker::new(time, image_specification.center[1]),
             ],
             zoom_control: PointTracker::new(time, image_specification.width.ln()),
+            pan_bounds: None,
+            at_precision_floor: false,
         }
     }
 
+    /// Sets how the view center approaches a `CenterCommand::Target`, e.g.
+    /// after a click-to-center.
+    pub fn set_pan_tracking_mode(&mut self, mode: TrackingMode) {
+        for ctrl in &mut self.pan_control {
+            ctrl.set_tracking_mode(mode);
+        }
+    }
+
+    /// Limits how quickly the zoom rate can change, in natural log of width
+    /// per second squared, so zooms ease in and out. `None` applies each
+    /// `ZoomVelocityCommand` instantly.
+    pub fn set_max_zoom_acceleration(&mut self, max_zoom_acceleration: Option<f64>) {
+        self.zoom_control
+            .set_max_acceleration(max_zoom_acceleration);
+    }
+
     pub fn reset(&mut self) {
+        self.at_precision_floor = false;
         self.image_specification = self.initial_image_specification;
         self.pan_control[0].set_position(self.image_specification.center[0]);
         self.pan_control[1].set_position(self.image_specification.center[1]);
@@ -196,7 +239,10 @@ impl ViewControl {
                     });
                 }
             }
-            CenterCommand::Target(center_target) => {
+            CenterCommand::Target(mut center_target) => {
+                if let Some(pan_bounds) = &self.pan_bounds {
+                    center_target.view_center = pan_bounds.clamp(center_target.view_center);
+                }
                 // Adjust the per-axis limits to enforce the max perceived speed:
                 let max_vel_vec = compute_directional_max_velocity(
                     Vector2::from(center_target.view_center) - Vector2::from(self.view_center()),
@@ -231,18 +277,243 @@ impl ViewControl {
             }
         };
 
-        monitored_assignment(
-            &mut self.image_specification.center[0],
-            self.pan_control[0].update_and_return_pos(time),
-        );
-        monitored_assignment(
-            &mut self.image_specification.center[1],
-            self.pan_control[1].update_and_return_pos(time),
-        );
-        monitored_assignment(
-            &mut self.image_specification.width,
-            self.zoom_control.update_and_return_pos(time).exp(),
-        );
+        let center = self
+            .pan_control
+            .each_mut()
+            .map(|ctrl| ctrl.update_and_return_pos(time));
+        let clamped_center = match &self.pan_bounds {
+            Some(pan_bounds) => pan_bounds.clamp(center),
+            None => center,
+        };
+        for (index, ctrl) in self.pan_control.iter_mut().enumerate() {
+            // A velocity command stops at the edge of the bounds.
+            if clamped_center[index] != center[index] {
+                ctrl.set_position(clamped_center[index]);
+            }
+            monitored_assignment(
+                &mut self.image_specification.center[index],
+                clamped_center[index],
+            );
+        }
+        let log_width = self.zoom_control.update_and_return_pos(time);
+        let log_width = self.clamp_log_width(log_width);
+        monitored_assignment(&mut self.image_specification.width, log_width.exp());
         view_was_modified
     }
+
+    /// Keep the zoom within `[min_width, max_width]` of the image
+    /// specification and above the precision floor, stopping the zoom
+    /// controller at the bound. Warns once on reaching the precision floor.
+    fn clamp_log_width(&mut self, log_width: f64) -> f64 {
+        let spec = &self.image_specification;
+        let precision_floor = precision_floor_width(spec);
+        let min_width = spec.min_width.unwrap_or(0.0).max(precision_floor);
+        let max_width = spec.max_width.unwrap_or(f64::INFINITY);
+        // Not `clamp`: it panics if a misconfigured `min_width > max_width`.
+        let clamped = log_width.max(min_width.ln()).min(max_width.ln());
+        if clamped != log_width {
+            self.zoom_control.set_position(clamped);
+        }
+
+        let at_precision_floor = log_width <= precision_floor.ln() && clamped == min_width.ln();
+        if at_precision_floor && !self.at_precision_floor {
+            println!(
+                "WARNING:  Reached the f64 precision floor at width {min_width:e}; \
+                 zooming in further would only produce noise."
+            );
+        }
+        self.at_precision_floor = at_precision_floor;
+        clamped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIME_STEP: f64 = 1.0 / 60.0;
+
+    fn zoom_in(zoom_rate: f64) -> ZoomVelocityCommand {
+        ZoomVelocityCommand {
+            zoom_direction: ScalarDirection::Neg(),
+            zoom_rate,
+        }
+    }
+
+    /// Hold a zoom command for `hold_steps`, then release it, and return the
+    /// log of the view width after each step.
+    fn log_width_trajectory(max_zoom_acceleration: Option<f64>, hold_steps: usize) -> Vec<f64> {
+        let image_specification = ImageSpecification {
+            resolution: [16, 16],
+            center: [0.0, 0.0],
+            width: 1.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let mut view_control = ViewControl::new(0.0, image_specification);
+        view_control.set_max_zoom_acceleration(max_zoom_acceleration);
+        (1..=2 * hold_steps)
+            .map(|step| {
+                let command = if step <= hold_steps {
+                    zoom_in(2.0)
+                } else {
+                    ZoomVelocityCommand::zero()
+                };
+                view_control.update(step as f64 * TIME_STEP, CenterCommand::Idle(), command);
+                view_control.image_specification().width.ln()
+            })
+            .collect()
+    }
+
+    fn zoom_rates(log_widths: &[f64]) -> Vec<f64> {
+        std::iter::once(0.0)
+            .chain(log_widths.iter().copied())
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]) / TIME_STEP)
+            .collect()
+    }
+
+    /// Zoom in (or out) at a high rate for ten seconds and return the
+    /// final view width.
+    fn zoom_with_limits(min_width: Option<f64>, max_width: Option<f64>, zoom_in: bool) -> f64 {
+        let image_specification = ImageSpecification {
+            resolution: [100, 100],
+            center: [-0.5, 0.0],
+            width: 1.0,
+            pixel_aspect_ratio: 1.0,
+            min_width,
+            max_width,
+        };
+        let mut view_control = ViewControl::new(0.0, image_specification);
+        for step in 1..=100 {
+            let command = ZoomVelocityCommand {
+                zoom_direction: if zoom_in {
+                    ScalarDirection::Neg()
+                } else {
+                    ScalarDirection::Pos()
+                },
+                zoom_rate: 10.0,
+            };
+            view_control.update(step as f64 * 0.1, CenterCommand::Idle(), command);
+        }
+        view_control.image_specification().width
+    }
+
+    #[test]
+    fn zoom_is_clamped_to_the_configured_width_bounds() {
+        let min_width = zoom_with_limits(Some(1e-3), Some(10.0), true);
+        assert!((min_width - 1e-3).abs() < 1e-15, "{min_width}");
+        let max_width = zoom_with_limits(Some(1e-3), Some(10.0), false);
+        assert!((max_width - 10.0).abs() < 1e-12, "{max_width}");
+        // Unbounded zoom out.
+        assert!(zoom_with_limits(None, None, false) > 1e40);
+    }
+
+    #[test]
+    fn pan_bounds_clamp_targets_and_velocity_commands() {
+        let image_specification = ImageSpecification {
+            resolution: [16, 16],
+            center: [0.0, 0.0],
+            width: 1.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let mut view_control = ViewControl::new(0.0, image_specification);
+        view_control.pan_bounds = Some(ViewRectangle {
+            center: [0.0, 0.0],
+            dimensions: [2.0, 4.0],
+        });
+
+        let target = CenterCommand::Target(CenterTargetCommand {
+            view_center: [5.0, -1.0],
+            pan_rate: 10.0,
+        });
+        view_control.update(0.0, target, ZoomVelocityCommand::zero());
+        for step in 1..=100 {
+            view_control.update(
+                step as f64 * 0.1,
+                CenterCommand::Idle(),
+                ZoomVelocityCommand::zero(),
+            );
+        }
+        assert_eq!(view_control.view_center(), [1.0, -1.0]);
+
+        let pan_down = || {
+            CenterCommand::Velocity(CenterVelocityCommand {
+                center_direction: [ScalarDirection::Zero(), ScalarDirection::Neg()],
+                pan_rate: 1.0,
+            })
+        };
+        for step in 101..=200 {
+            view_control.update(step as f64 * 0.1, pan_down(), ZoomVelocityCommand::zero());
+        }
+        assert_eq!(view_control.image_specification().center, [1.0, -2.0]);
+    }
+
+    #[test]
+    fn degenerate_width_is_lifted_to_the_precision_floor() {
+        let image_specification = ImageSpecification {
+            resolution: [100, 100],
+            center: [0.0, 0.0],
+            width: 0.0,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let mut view_control = ViewControl::new(0.0, image_specification);
+        view_control.update(
+            TIME_STEP,
+            CenterCommand::Idle(),
+            ZoomVelocityCommand::zero(),
+        );
+        let width = view_control.image_specification().width;
+        let floor = precision_floor_width(&image_specification);
+        assert!((width / floor - 1.0).abs() < 1e-9, "{width} vs {floor}");
+        assert!(view_control.at_precision_floor);
+    }
+
+    #[test]
+    fn zoom_stops_at_the_precision_floor() {
+        let width = zoom_with_limits(None, None, true);
+        let floor = 64.0 * 100.0 * f64::EPSILON;
+        assert!((width / floor - 1.0).abs() < 1e-9, "{width} vs {floor}");
+        // The floor wins over a smaller configured bound.
+        let width = zoom_with_limits(Some(1e-300), None, true);
+        assert!((width / floor - 1.0).abs() < 1e-9, "{width} vs {floor}");
+    }
+
+    #[test]
+    fn zoom_without_acceleration_limit_starts_and_stops_instantly() {
+        let rates = zoom_rates(&log_width_trajectory(None, 60));
+        assert!((rates[0] + 2.0).abs() < 1e-9);
+        assert!((rates[59] + 2.0).abs() < 1e-9);
+        assert_eq!(rates[60], 0.0);
+    }
+
+    #[test]
+    fn zoom_acceleration_limit_ramps_the_log_width_rate() {
+        let max_zoom_acceleration = 8.0;
+        let log_widths = log_width_trajectory(Some(max_zoom_acceleration), 60);
+        let rates = zoom_rates(&log_widths);
+
+        // The rate changes by at most the acceleration limit (with the
+        // trapezoidal average rate over each step, half of that on the step
+        // where the ramp starts or ends).
+        for pair in rates.windows(2) {
+            assert!((pair[1] - pair[0]).abs() <= max_zoom_acceleration * TIME_STEP + 1e-9);
+        }
+        // Ramps up over 0.25 s, cruises, then ramps back down to a stop.
+        assert!(rates[0] > -0.2);
+        assert!((rates[30] + 2.0).abs() < 1e-9);
+        assert!((rates[59] + 2.0).abs() < 1e-9);
+        assert!(rates[60] < -1.5);
+        assert_eq!(*rates.last().unwrap(), 0.0);
+
+        // Symmetric ramps cover the same total zoom as the unlimited
+        // trajectory: one second at the full rate.
+        assert!((log_widths.last().unwrap() + 2.0).abs() < 1e-9);
+    }
 }
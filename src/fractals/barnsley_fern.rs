@@ -1,8 +1,13 @@
-use crate::core::chaos_game::{ColoredPoint, chaos_game_render};
+use crate::core::chaos_game::{
+    ChaosGame, ColoredPoint, SeededRng, chaos_game_render, deserialize_time_budget,
+};
 use crate::core::file_io::{FilePrefix, serialize_to_json_or_panic};
-use crate::core::image_utils::{FitImage, ViewRectangle};
+use crate::core::image_utils::{
+    FitImage, ImageSpecification, RenderOptions, SpeedOptimizer, ViewRectangle,
+    scale_down_parameter_for_speed,
+};
+use crate::core::interpolation::ClampedLogInterpolator;
 use rand::distributions::{Distribution, Uniform};
-use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 
@@ -62,18 +67,72 @@ impl Coeffs {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BarnsleyFernParams {
     pub fit_image: FitImage,
+    /// Number of chaos-game samples to draw; an upper limit when a
+    /// `time_budget_seconds` is given.
     pub sample_count: u32,
+    /// Stop sampling once this much time has been spent on it, so the render
+    /// takes about as long on any machine. The number of samples actually
+    /// drawn is reported in the diagnostics. Only applies to the `render`
+    /// command. Unset by default.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_time_budget",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub time_budget_seconds: Option<f64>,
+    /// Seed of the random number generator, so a render is reproducible.
     pub rng_seed: u64,
-    pub subpixel_antialiasing: u32,
+    /// Only `sampling_level` applies: each pixel is split into
+    /// `(sampling_level + 1)²` subpixels for anti-aliasing. Defaults to
+    /// `sampling_level: 0`.
+    #[serde(default)]
+    pub render_options: RenderOptions,
+    /// Deprecated and ignored: replaced by `render_options.sampling_level`.
+    /// Older params files that set it still load and it is not written back
+    /// out; see `FractalParams::deprecation_warnings`.
+    #[serde(default, skip_serializing)]
+    pub subpixel_antialiasing: Option<u32>,
     pub background_color_rgb: [u8; 3],
     pub fern_color_rgb: [u8; 3],
     pub coeffs: Coeffs,
 }
 
+/// Reference cache used by `SpeedOptimizer` to interpolate runtime
+/// parameters back toward the user's specified values.
+pub struct ParamsReferenceCache {
+    /// User-specified `sample_count`.
+    pub sample_count: u32,
+    /// User-specified render options (including `sampling_level`).
+    pub render_options: RenderOptions,
+}
+
+impl SpeedOptimizer for BarnsleyFernParams {
+    type ReferenceCache = ParamsReferenceCache;
+
+    fn reference_cache(&self) -> Self::ReferenceCache {
+        ParamsReferenceCache {
+            sample_count: self.sample_count,
+            render_options: self.render_options,
+        }
+    }
+
+    fn set_speed_optimization_level(&mut self, level: f64, cache: &Self::ReferenceCache) {
+        self.sample_count = scale_down_parameter_for_speed(
+            1e5,
+            cache.sample_count as f64,
+            level,
+            ClampedLogInterpolator,
+        ) as u32;
+        self.render_options
+            .set_speed_optimization_level(level, &cache.render_options);
+    }
+}
+
 /**
  * Wrapper around `Coeffs`, used to precompute a few things before
  * running the sample generation.
  */
+#[derive(Debug, Clone)]
 pub struct SampleGenerator {
     distribution: Uniform<f64>,
     f2_threshold: f64,
@@ -115,6 +174,61 @@ impl SampleGenerator {
     }
 }
 
+impl ChaosGame for BarnsleyFernParams {
+    fn image_specification(&self) -> ImageSpecification {
+        self.fit_image
+            .image_specification(&self.coeffs.view_rectangle)
+    }
+
+    fn render_options(&self) -> &RenderOptions {
+        &self.render_options
+    }
+
+    fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    fn background_color_rgb(&self) -> [u8; 3] {
+        self.background_color_rgb
+    }
+
+    fn point_colors_rgb(&self) -> Vec<[u8; 3]> {
+        vec![self.fern_color_rgb]
+    }
+
+    type Samples = FernSamples;
+
+    fn samples(&self) -> FernSamples {
+        FernSamples {
+            rng: SeededRng::seed_from_u64(self.rng_seed),
+            sample_point: nalgebra::Vector2::<f64>::new(0.0, 0.0),
+            generator: SampleGenerator::new(&self.coeffs),
+            fern_color: image::Rgb(self.fern_color_rgb),
+        }
+    }
+}
+
+/// The "fern sample distribution": an endless chaos-game sequence.
+#[derive(Debug, Clone)]
+pub struct FernSamples {
+    rng: SeededRng,
+    sample_point: nalgebra::Vector2<f64>,
+    generator: SampleGenerator,
+    fern_color: image::Rgb<u8>,
+}
+
+impl Iterator for FernSamples {
+    type Item = ColoredPoint;
+
+    fn next(&mut self) -> Option<ColoredPoint> {
+        self.sample_point = self.generator.next(&mut self.rng, &self.sample_point);
+        Some(ColoredPoint {
+            point: self.sample_point.into(),
+            color: self.fern_color,
+        })
+    }
+}
+
 /**
  * Called by main, used to render the fractal using the above data structures.
  */
@@ -122,30 +236,15 @@ pub fn render_barnsley_fern(
     params: &BarnsleyFernParams,
     file_prefix: FilePrefix,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Set up the "fern sample distribution":
-    let mut sample_point = nalgebra::Vector2::<f64>::new(0.0, 0.0);
-    let mut rng = StdRng::seed_from_u64(params.rng_seed);
-    let generator = SampleGenerator::new(&params.coeffs);
-    let fern_color = image::Rgb(params.fern_color_rgb);
-
-    let mut distribution = || {
-        sample_point = generator.next(&mut rng, &sample_point);
-        ColoredPoint {
-            point: sample_point.into(),
-            color: fern_color,
-        }
-    };
-
     serialize_to_json_or_panic(file_prefix.full_path_with_suffix(".json"), &params);
 
     chaos_game_render(
         image::Rgb(params.background_color_rgb),
-        &mut distribution,
+        params.samples(),
         params.sample_count,
-        params.subpixel_antialiasing,
-        &params
-            .fit_image
-            .image_specification(&params.coeffs.view_rectangle),
+        params.time_budget_seconds,
+        &params.render_options,
+        &params.image_specification(),
         file_prefix,
     )
 }
@@ -0,0 +1,360 @@
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    chaos_game::SeededRng,
+    file_io::{FilePrefix, write_file_or_panic},
+    image_utils::{ImageSpecification, PixelMapper, write_image_to_file_or_panic},
+    stopwatch::Stopwatch,
+};
+use crate::fractals::{common::buddhabrot_snapshot_json, quadratic_map::QuadraticMapSequence};
+
+/// One color channel of the Buddhabrot. Only orbits whose escape count lands
+/// in `[min_iter_count, max_iter_count]` are accumulated into the channel.
+/// A single channel gives the classic Buddhabrot; three channels with
+/// different iteration bands (e.g. 5000 / 500 / 50 for red / green / blue)
+/// give the "Nebulabrot".
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BuddhabrotChannel {
+    /// Fewest iterations an orbit may take to escape and still count.
+    pub min_iter_count: u32,
+    /// Most iterations an orbit may take to escape and still count.
+    pub max_iter_count: u32,
+    /// Color added to the pixel at full density.
+    pub color_rgb: [u8; 3],
+}
+
+/**
+ * The Buddhabrot plots the density of the *orbits* of escaping points of the
+ * Mandelbrot iteration, rather than their escape counts. Points `c` are drawn
+ * at random from the square `[-2, 2] x [-2, 2]`; for each one that escapes,
+ * every point of its orbit that lands in the view is counted in the pixel it
+ * falls on.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BuddhabrotParams {
+    /// Image dimensions and viewport.
+    pub image_specification: ImageSpecification,
+    /// Number of random starting points `c`.
+    pub sample_count: u32,
+    /// Seed for the starting points; the same seed gives the same image.
+    pub rng_seed: u64,
+    /// An orbit has escaped once `|z|²` exceeds this.
+    pub escape_radius_squared: f64,
+    /// Iteration bands and colors, blended additively.
+    pub channels: Vec<BuddhabrotChannel>,
+    /// Color of pixels that no counted orbit passes through.
+    pub background_color_rgb: [u8; 3],
+    /// Each channel is drawn with intensity `(density / max_density)^gamma`.
+    /// Values below one brighten the faint outer orbits.
+    pub gamma: f64,
+}
+
+/// Samples per parallel work item. Each chunk gets its own RNG, seeded from
+/// `rng_seed` and the chunk index, so the result does not depend on how the
+/// chunks are scheduled across threads.
+const SAMPLES_PER_CHUNK: u32 = 1 << 14;
+
+/// Half-width of the square that the starting points are drawn from. Every
+/// point outside the disk of radius 2 escapes immediately.
+const SAMPLE_RADIUS: f64 = 2.0;
+
+/// Points in the main cardioid or the period-2 bulb never escape, so they can
+/// be skipped without iterating. This avoids running most of the
+/// non-escaping samples all the way to the iteration limit.
+fn in_main_cardioid_or_period_two_bulb(c: &[f64; 2]) -> bool {
+    let [x, y] = *c;
+    let y_sqr = y * y;
+    let x_shift = x - 0.25;
+    let q = x_shift * x_shift + y_sqr;
+    let in_cardioid = q * (q + x_shift) <= 0.25 * y_sqr;
+    let in_bulb = (x + 1.0) * (x + 1.0) + y_sqr <= 1.0 / 16.0;
+    in_cardioid || in_bulb
+}
+
+/// Maps orbit points into the row-major pixel buffer of the image.
+struct DensityMapper {
+    pixel_mapper: PixelMapper,
+    resolution: [u32; 2],
+    x_range: [f64; 2],
+    y_range: [f64; 2],
+}
+
+impl DensityMapper {
+    fn new(image_specification: &ImageSpecification) -> DensityMapper {
+        let half_width = 0.5 * image_specification.width;
+        let half_height = 0.5 * image_specification.height();
+        let [x, y] = image_specification.center;
+        DensityMapper {
+            pixel_mapper: PixelMapper::new(image_specification),
+            resolution: image_specification.resolution,
+            x_range: [x - half_width, x + half_width],
+            y_range: [y - half_height, y + half_height],
+        }
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.resolution[0] as usize * self.resolution[1] as usize
+    }
+
+    /// Buffer index of the pixel containing `point`, or `None` if off-screen.
+    /// The range check comes first: `inverse_map` saturates off-screen points
+    /// onto the edge pixels.
+    fn buffer_index(&self, point: &[f64; 2]) -> Option<usize> {
+        let in_view = (self.x_range[0]..=self.x_range[1]).contains(&point[0])
+            && (self.y_range[0]..=self.y_range[1]).contains(&point[1]);
+        if !in_view {
+            return None;
+        }
+        let [i, j] = self.pixel_mapper.inverse_map(point);
+        let i = i.min(self.resolution[0] - 1);
+        let j = j.min(self.resolution[1] - 1);
+        Some((j * self.resolution[0] + i) as usize)
+    }
+}
+
+/// Iterate `z -> z² + c` from `z0 = 0`, writing the buffer index of each
+/// on-screen point `z1 = c, z2, ...` of the orbit into `orbit`, while
+/// iterating so that escaping orbits do not need to be iterated twice. `z0`
+/// is the same for every orbit, so it is left out. Returns the escape
+/// count, or `None` if the orbit did not escape within `max_iter_count`.
+fn record_escaping_orbit(
+    c: &[f64; 2],
+    max_iter_count: u32,
+    escape_radius_squared: f64,
+    mapper: &DensityMapper,
+    orbit: &mut Vec<usize>,
+) -> Option<u32> {
+    orbit.clear();
+    let mut sequence = QuadraticMapSequence::new(&[0.0, 0.0], c);
+    // `new` has already taken the first step, to `z1 = c`.
+    while sequence.iter_count < max_iter_count && sequence.radius_squared() <= escape_radius_squared
+    {
+        orbit.extend(mapper.buffer_index(&[sequence.x, sequence.y]));
+        sequence.step();
+    }
+    (sequence.radius_squared() > escape_radius_squared).then_some(sequence.iter_count)
+}
+
+/// Accumulate the orbit density of each channel, as one row-major buffer of
+/// hit counts per channel. Counts saturate at `u32::MAX` rather than wrap.
+pub fn accumulate_orbit_density(params: &BuddhabrotParams) -> Vec<Vec<u32>> {
+    let mapper = DensityMapper::new(&params.image_specification);
+    let max_iter_count = params
+        .channels
+        .iter()
+        .map(|channel| channel.max_iter_count)
+        .max()
+        .unwrap_or(0);
+    let empty_buffers = || vec![vec![0u32; mapper.pixel_count()]; params.channels.len()];
+    let chunk_count = params.sample_count.div_ceil(SAMPLES_PER_CHUNK);
+
+    (0..chunk_count)
+        .into_par_iter()
+        .fold(empty_buffers, |mut buffers, chunk_index| {
+            let mut rng =
+                SeededRng::seed_from_u64(params.rng_seed.wrapping_add(chunk_index as u64));
+            let chunk_begin = chunk_index * SAMPLES_PER_CHUNK;
+            let chunk_size = SAMPLES_PER_CHUNK.min(params.sample_count - chunk_begin);
+            let mut orbit: Vec<usize> = Vec::new();
+            for _ in 0..chunk_size {
+                let c = [
+                    rng.gen_range(-SAMPLE_RADIUS..SAMPLE_RADIUS),
+                    rng.gen_range(-SAMPLE_RADIUS..SAMPLE_RADIUS),
+                ];
+                if in_main_cardioid_or_period_two_bulb(&c) {
+                    continue;
+                }
+
+                let Some(iter_count) = record_escaping_orbit(
+                    &c,
+                    max_iter_count,
+                    params.escape_radius_squared,
+                    &mapper,
+                    &mut orbit,
+                ) else {
+                    continue;
+                };
+
+                for (channel, buffer) in params.channels.iter().zip(buffers.iter_mut()) {
+                    if (channel.min_iter_count..=channel.max_iter_count).contains(&iter_count) {
+                        for &index in &orbit {
+                            buffer[index] = buffer[index].saturating_add(1);
+                        }
+                    }
+                }
+            }
+            buffers
+        })
+        .reduce(empty_buffers, |mut total, partial| {
+            for (total_buffer, partial_buffer) in total.iter_mut().zip(partial) {
+                for (t, p) in total_buffer.iter_mut().zip(partial_buffer) {
+                    *t = t.saturating_add(p);
+                }
+            }
+            total
+        })
+}
+
+/// Blend the per-channel densities over the background color.
+fn colorize_density(
+    params: &BuddhabrotParams,
+    densities: &[Vec<u32>],
+) -> image::ImageBuffer<image::Rgb<u8>, Vec<u8>> {
+    let [width, height] = params.image_specification.resolution;
+    let scales: Vec<f64> = densities
+        .iter()
+        .map(|density| 1.0 / (density.iter().copied().max().unwrap_or(0).max(1) as f64))
+        .collect();
+
+    image::ImageBuffer::from_fn(width, height, |x, y| {
+        let index = (y * width + x) as usize;
+        let mut rgb = params.background_color_rgb.map(|value| value as f64);
+        for ((channel, density), scale) in params.channels.iter().zip(densities).zip(&scales) {
+            let intensity = ((density[index] as f64) * scale).powf(params.gamma);
+            for (value, color) in rgb.iter_mut().zip(channel.color_rgb) {
+                *value += intensity * (color as f64);
+            }
+        }
+        image::Rgb(rgb.map(|value| value.round().min(255.0) as u8))
+    })
+}
+
+/// Render the Buddhabrot (or Nebulabrot) to a PNG, along with the params
+/// snapshot and diagnostics files.
+pub fn render_buddhabrot(
+    params: &BuddhabrotParams,
+    file_prefix: FilePrefix,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stopwatch = Stopwatch::new("Buddhabrot Stopwatch".to_owned());
+
+    write_file_or_panic(
+        file_prefix.full_path_with_suffix(".json"),
+        &buddhabrot_snapshot_json(params),
+    );
+    stopwatch.record_split("setup".to_owned());
+
+    let densities = accumulate_orbit_density(params);
+    stopwatch.record_split("accumulate orbits".to_owned());
+
+    let imgbuf = colorize_density(params, &densities);
+    stopwatch.record_split("colorize".to_owned());
+
+    write_image_to_file_or_panic(file_prefix.full_path_with_suffix(".png"), |f| {
+        imgbuf.save(f)
+    });
+    stopwatch.record_split("write PNG".to_owned());
+
+    let mut diagnostics_file = file_prefix.create_file_with_suffix("_diagnostics.txt");
+    stopwatch.display(&mut diagnostics_file)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nebulabrot_params(sample_count: u32) -> BuddhabrotParams {
+        serde_json::from_str(&format!(
+            r#"{{
+                "image_specification": {{
+                    "resolution": [24, 24],
+                    "center": [-0.5, 0.0],
+                    "width": 3.0
+                }},
+                "sample_count": {sample_count},
+                "rng_seed": 7,
+                "escape_radius_squared": 4.0,
+                "channels": [
+                    {{ "min_iter_count": 0, "max_iter_count": 200, "color_rgb": [255, 0, 0] }},
+                    {{ "min_iter_count": 20, "max_iter_count": 200, "color_rgb": [0, 255, 0] }},
+                    {{ "min_iter_count": 0, "max_iter_count": 10, "color_rgb": [0, 0, 255] }}
+                ],
+                "background_color_rgb": [0, 0, 0],
+                "gamma": 0.5
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn cardioid_and_bulb_test_matches_known_points() {
+        for inside in [
+            [0.0, 0.0],
+            [0.2, 0.0],
+            [-0.7, 0.2],
+            [-1.0, 0.0],
+            [-1.2, 0.1],
+        ] {
+            assert!(in_main_cardioid_or_period_two_bulb(&inside), "{inside:?}");
+        }
+        for outside in [
+            [0.3, 0.0],
+            [-0.8, 0.3],
+            [-1.3, 0.0],
+            [0.0, 1.0],
+            [-2.0, 0.0],
+        ] {
+            assert!(
+                !in_main_cardioid_or_period_two_bulb(&outside),
+                "{outside:?}"
+            );
+        }
+    }
+
+    /// The density must not depend on how rayon splits the work, and each
+    /// iteration band only collects the orbits that escape within it.
+    #[test]
+    fn orbit_density_is_deterministic_and_respects_iteration_bands() {
+        let params = nebulabrot_params(3 * SAMPLES_PER_CHUNK + 123);
+        let densities = accumulate_orbit_density(&params);
+        assert_eq!(densities, accumulate_orbit_density(&params));
+
+        let [all, slow, fast] = [&densities[0], &densities[1], &densities[2]];
+        assert!(fast.iter().sum::<u32>() > 0);
+        assert!(slow.iter().sum::<u32>() > 0);
+        for index in 0..all.len() {
+            // The two narrower bands do not overlap and both lie within the
+            // full band.
+            assert!(slow[index] + fast[index] <= all[index]);
+        }
+    }
+
+    /// The orbit starts at `z1 = c`: the shared `z0 = 0` would add a hit at
+    /// the origin for every escaping sample.
+    #[test]
+    fn escaping_orbit_is_recorded_from_c_onwards() {
+        let mut params = nebulabrot_params(0);
+        params.image_specification.center = [0.0, 0.0];
+        let mapper = DensityMapper::new(&params.image_specification);
+        let mut orbit = Vec::new();
+
+        // z1 = 1 + i, z2 = 1 + 3i escapes off-screen.
+        let iter_count = record_escaping_orbit(&[1.0, 1.0], 200, 4.0, &mapper, &mut orbit);
+        assert_eq!(iter_count, Some(2));
+        assert_eq!(orbit, vec![mapper.buffer_index(&[1.0, 1.0]).unwrap()]);
+
+        let origin = mapper.buffer_index(&[0.0, 0.0]).unwrap();
+        let c = [0.4, 0.3];
+        assert!(record_escaping_orbit(&c, 200, 4.0, &mapper, &mut orbit).is_some());
+        assert_eq!(orbit[0], mapper.buffer_index(&c).unwrap());
+        assert!(!orbit.contains(&origin));
+
+        // Points in the main cardioid never escape.
+        assert_eq!(
+            record_escaping_orbit(&[0.1, 0.0], 200, 4.0, &mapper, &mut orbit),
+            None
+        );
+    }
+
+    #[test]
+    fn off_screen_orbit_points_are_dropped() {
+        let mapper = DensityMapper::new(&nebulabrot_params(0).image_specification);
+        assert_eq!(mapper.buffer_index(&[-2.1, 0.0]), None);
+        assert_eq!(mapper.buffer_index(&[0.0, 1.6]), None);
+        assert_eq!(mapper.buffer_index(&[-2.0, 1.5]), Some(0));
+        assert_eq!(mapper.buffer_index(&[1.0, -1.5]), Some(24 * 24 - 1));
+    }
+}
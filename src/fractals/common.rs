@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
 
-use crate::core::file_io::to_pretty_json_or_panic;
+use crate::core::{file_io::to_pretty_json_or_panic, image_utils::ImageSpecification};
 
 use super::{
     barnsley_fern::BarnsleyFernParams,
+    buddhabrot::BuddhabrotParams,
     driven_damped_pendulum::DrivenDampedPendulumParams,
     julia::JuliaParams,
+    magnetic_pendulum::MagneticPendulumParams,
     mandelbrot::MandelbrotParams,
     newtons_method::{CommonParams, NewtonsMethodParams, SystemType},
     sierpinski::SierpinskiParams,
@@ -19,6 +21,88 @@ pub enum FractalParams {
     BarnsleyFern(Box<BarnsleyFernParams>),
     Sierpinski(Box<SierpinskiParams>),
     NewtonsMethod(Box<NewtonsMethodParams>),
+    MagneticPendulum(Box<MagneticPendulumParams>),
+    Buddhabrot(Box<BuddhabrotParams>),
+}
+
+impl FractalParams {
+    /// The view of the fractal, or `None` for the chaos-game fractals, which
+    /// fit their view to the attractor automatically.
+    pub fn image_specification(&self) -> Option<ImageSpecification> {
+        match self {
+            FractalParams::Mandelbrot(inner) => Some(inner.image_specification),
+            FractalParams::Julia(inner) => Some(inner.image_specification),
+            FractalParams::DrivenDampedPendulum(inner) => Some(inner.image_specification),
+            FractalParams::NewtonsMethod(inner) => Some(inner.params.image_specification),
+            FractalParams::MagneticPendulum(inner) => Some(inner.image_specification),
+            FractalParams::Buddhabrot(inner) => Some(inner.image_specification),
+            FractalParams::BarnsleyFern(_) | FractalParams::Sierpinski(_) => None,
+        }
+    }
+
+    /// Resolution of the rendered image, including for the chaos-game
+    /// fractals, which fit the rest of their view automatically.
+    pub fn resolution(&self) -> [u32; 2] {
+        match self {
+            FractalParams::Mandelbrot(inner) => inner.image_specification.resolution,
+            FractalParams::Julia(inner) => inner.image_specification.resolution,
+            FractalParams::DrivenDampedPendulum(inner) => inner.image_specification.resolution,
+            FractalParams::NewtonsMethod(inner) => inner.params.image_specification.resolution,
+            FractalParams::MagneticPendulum(inner) => inner.image_specification.resolution,
+            FractalParams::Buddhabrot(inner) => inner.image_specification.resolution,
+            FractalParams::BarnsleyFern(inner) => inner.fit_image.resolution,
+            FractalParams::Sierpinski(inner) => inner.fit_image.resolution,
+        }
+    }
+
+    /// Deprecated keys that were set in the loaded params file and are
+    /// ignored, for the caller to report. Deserialization itself stays quiet
+    /// so that library and wasm callers do not print to stdout.
+    pub fn deprecation_warnings(&self) -> Vec<&'static str> {
+        let subpixel_antialiasing = match self {
+            FractalParams::BarnsleyFern(inner) => inner.subpixel_antialiasing,
+            FractalParams::Sierpinski(inner) => inner.subpixel_antialiasing,
+            _ => None,
+        };
+        subpixel_antialiasing
+            .map(|_| {
+                "`subpixel_antialiasing` is deprecated and ignored; set \
+                 `render_options.sampling_level` instead."
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// Replace the view (resolution, center, width) of the fractal, e.g. from
+    /// the `--view` / `--center-x` / ... CLI overrides. The chaos-game
+    /// fractals fit their view to the attractor automatically, so they reject
+    /// an explicit override.
+    pub fn set_image_specification(
+        &mut self,
+        image_specification: ImageSpecification,
+    ) -> Result<(), String> {
+        match self {
+            FractalParams::Mandelbrot(inner) => inner.image_specification = image_specification,
+            FractalParams::Julia(inner) => inner.image_specification = image_specification,
+            FractalParams::DrivenDampedPendulum(inner) => {
+                inner.image_specification = image_specification
+            }
+            FractalParams::NewtonsMethod(inner) => {
+                inner.params.image_specification = image_specification
+            }
+            FractalParams::MagneticPendulum(inner) => {
+                inner.image_specification = image_specification
+            }
+            FractalParams::Buddhabrot(inner) => inner.image_specification = image_specification,
+            FractalParams::BarnsleyFern(_) | FractalParams::Sierpinski(_) => {
+                return Err(
+                    "chaos-game fractals fit their own view and do not accept a view override"
+                        .to_owned(),
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Serialize Mandelbrot params as a reloadable, pretty-printed tagged
@@ -42,6 +126,30 @@ pub fn ddp_snapshot_json(params: &DrivenDampedPendulumParams) -> String {
     )))
 }
 
+/// Serialize magnetic-pendulum params as a reloadable, pretty-printed tagged
+/// `FractalParams` snapshot.
+pub fn magnetic_pendulum_snapshot_json(params: &MagneticPendulumParams) -> String {
+    to_pretty_json_or_panic(&FractalParams::MagneticPendulum(Box::new(params.clone())))
+}
+
+/// Serialize Barnsley fern params as a reloadable, pretty-printed tagged
+/// `FractalParams` snapshot.
+pub fn barnsley_fern_snapshot_json(params: &BarnsleyFernParams) -> String {
+    to_pretty_json_or_panic(&FractalParams::BarnsleyFern(Box::new(params.clone())))
+}
+
+/// Serialize Sierpinski params as a reloadable, pretty-printed tagged
+/// `FractalParams` snapshot.
+pub fn sierpinski_snapshot_json(params: &SierpinskiParams) -> String {
+    to_pretty_json_or_panic(&FractalParams::Sierpinski(Box::new(params.clone())))
+}
+
+/// Serialize Buddhabrot params as a reloadable, pretty-printed tagged
+/// `FractalParams` snapshot.
+pub fn buddhabrot_snapshot_json(params: &BuddhabrotParams) -> String {
+    to_pretty_json_or_panic(&FractalParams::Buddhabrot(Box::new(params.clone())))
+}
+
 /// Serialize Newton's-method params as a reloadable, pretty-printed tagged
 /// `FractalParams` snapshot. The `system` must be supplied separately because
 /// it is not part of the renderer's `Renderable::Params` (`CommonParams`); the
@@ -145,12 +253,30 @@ mod tests {
         assert_round_trips(&ddp_snapshot_json(&inner), "DrivenDampedPendulum");
     }
 
+    #[test]
+    fn magnetic_pendulum_snapshot_json_round_trips() {
+        let json = r#"{"MagneticPendulum":{"image_specification":{"resolution":[10,10],"center":[0,0],"width":6.0},"magnets":[{"position":[1.0,0.0],"strength":1.0},{"position":[-1.0,0.0],"strength":0.5}],"height":0.25,"spring_constant":0.5,"friction":0.2,"time_step":0.02,"max_time":30.0,"capture_radius":0.1,"render_options":{"sampling_level":0},"color":{"background_color":[255,255,255],"color_maps":[[{"query":0.0,"rgb_raw":[240,80,60]},{"query":1.0,"rgb_raw":[40,8,6]}]]},"lookup_table_count":64,"histogram_bin_count":64}}"#;
+        let FractalParams::MagneticPendulum(inner) = serde_json::from_str(json).unwrap() else {
+            panic!("expected MagneticPendulum variant");
+        };
+        assert_round_trips(&magnetic_pendulum_snapshot_json(&inner), "MagneticPendulum");
+    }
+
+    #[test]
+    fn sierpinski_snapshot_json_round_trips() {
+        let json = r#"{"Sierpinski":{"fit_image":{"resolution":[10,10],"padding_scale":1.05},"sample_count":1000,"rng_seed":7,"render_options":{"sampling_level":1},"background_color_rgb":[0,0,0],"vertex_colors_rgb":[[255,0,0],[0,255,0],[0,0,255]]}}"#;
+        let FractalParams::Sierpinski(inner) = serde_json::from_str(json).unwrap() else {
+            panic!("expected Sierpinski variant");
+        };
+        assert_round_trips(&sierpinski_snapshot_json(&inner), "Sierpinski");
+    }
+
     /// The Newton snapshot must re-inject the `system`, which is not part of
     /// `CommonParams` (`Renderable::Params`). A missing system would fail to
     /// deserialize; this also asserts the system content is preserved exactly.
     #[test]
     fn newton_snapshot_json_round_trips_and_preserves_system() {
-        let json = r#"{"NewtonsMethod":{"params":{"image_specification":{"resolution":[10,10],"center":[0,0],"width":5.0},"max_iteration_count":250,"convergence_tolerance":1e-6,"render_options":{"sampling_level":2},"color":{"background_color":[255,255,255],"color_maps":[[{"query":0.0,"rgb_raw":[9,42,27]},{"query":1.0,"rgb_raw":[0,0,0]}]]},"lookup_table_count":512,"histogram_bin_count":512},"system":{"RootsOfUnity":{"n_roots":4,"newton_step_size":1.0}}}}"#;
+        let json = r#"{"NewtonsMethod":{"params":{"image_specification":{"resolution":[10,10],"center":[0,0],"width":5.0},"max_iteration_count":250,"convergence_tolerance":1e-6,"render_options":{"sampling_level":2},"color":{"background_color":[255,255,255],"color_maps":[[{"query":0.0,"rgb_raw":[9,42,27]},{"query":1.0,"rgb_raw":[0,0,0]}],[{"query":0.0,"rgb_raw":[9,42,27]},{"query":1.0,"rgb_raw":[0,0,0]}],[{"query":0.0,"rgb_raw":[9,42,27]},{"query":1.0,"rgb_raw":[0,0,0]}],[{"query":0.0,"rgb_raw":[9,42,27]},{"query":1.0,"rgb_raw":[0,0,0]}]]},"lookup_table_count":512,"histogram_bin_count":512},"system":{"RootsOfUnity":{"n_roots":4,"newton_step_size":1.0}}}}"#;
         let FractalParams::NewtonsMethod(inner) = serde_json::from_str(json).unwrap() else {
             panic!("expected NewtonsMethod variant");
         };
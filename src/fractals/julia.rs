@@ -2,7 +2,8 @@ use crate::core::image_utils::{ImageSpecification, RenderOptions};
 use serde::{Deserialize, Serialize};
 
 use super::quadratic_map::{
-    ColorMapParams, ConvergenceParams, QuadraticMapParams, QuadraticMapSequence,
+    ColorMapParams, ConvergenceParams, EscapeTracking, OrbitResult, PixelTerm, QuadraticMapParams,
+    QuadraticMapSequence,
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -47,11 +48,13 @@ impl QuadraticMapParams for JuliaParams {
         &mut self.render_options
     }
 
-    fn normalized_log_escape_count(&self, point: &[f64; 2]) -> Option<f32> {
-        QuadraticMapSequence::normalized_log_escape_count(
+    fn orbit_result(&self, point: &[f64; 2], tracking: EscapeTracking) -> OrbitResult {
+        QuadraticMapSequence::orbit_result(
             point,
             &self.constant_term,
             &self.convergence_params,
+            tracking,
+            PixelTerm::InitialPoint,
         )
     }
 }
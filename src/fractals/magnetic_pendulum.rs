@@ -0,0 +1,324 @@
+use nalgebra::{Vector2, Vector4};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::core::{
+    color_map::ColorPalette,
+    field_iteration::FieldKernel,
+    image_utils::{
+        ImageSpecification, RenderOptions, Renderable, SpeedOptimizer, require_positive_and_finite,
+        scale_up_parameter_for_speed,
+    },
+    interpolation::ClampedLogInterpolator,
+    ode_solvers::rk4_method_step,
+};
+
+/// A fixed magnet in the plane beneath the pendulum bob.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Magnet {
+    /// Location of the magnet in the plane, `[x, y]`.
+    pub position: [f64; 2],
+    /// Scales the attractive force; negative values repel.
+    pub strength: f64,
+}
+
+/// The classic magnetic pendulum: a bob swings above a plane of magnets,
+/// pulled back toward the origin by gravity and slowed by friction. Each
+/// pixel is an initial position (released at rest) and is colored with the
+/// color map of the magnet the bob comes to rest over, shaded by how long it
+/// takes to get there. Bobs that have not settled by `max_time` are drawn in
+/// the background color.
+///
+/// The bob is modeled as a point mass moving in the plane, which is the usual
+/// small-angle approximation:
+///
+/// ```text
+/// p'' = -spring_constant p - friction p'
+///       + Σ strength_i (m_i - p) / (|m_i - p|² + height²)^(3/2)
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MagneticPendulumParams {
+    /// Image dimensions and viewport, in initial positions of the bob.
+    pub image_specification: ImageSpecification,
+    /// The magnets; each one's basin gets its own color map.
+    pub magnets: Vec<Magnet>,
+    /// Distance between the plane of the bob and the plane of the magnets.
+    /// Keeps the magnetic force finite directly above each magnet.
+    pub height: f64,
+    /// Linear restoring force (gravity) toward the origin.
+    pub spring_constant: f64,
+    /// Linear damping on the velocity of the bob.
+    pub friction: f64,
+    /// Integration step for the fourth-order Runge-Kutta solver. Must be
+    /// positive and finite.
+    #[serde(deserialize_with = "deserialize_time_step")]
+    pub time_step: f64,
+    /// Simulation time after which an unsettled bob is given up on. Must be
+    /// positive and finite.
+    #[serde(deserialize_with = "deserialize_max_time")]
+    pub max_time: f64,
+    /// The bob has settled once its combined phase-space distance
+    /// `sqrt(|p - m|² + |p'|²)` to a magnet drops below this radius.
+    pub capture_radius: f64,
+    /// Rendering options (anti-aliasing, downsampling, etc.).
+    pub render_options: RenderOptions,
+    /// Color map `k` shades the basin of `magnets[k]` (wrapping if there are
+    /// fewer color maps than magnets); the background color marks bobs that
+    /// never settled.
+    pub color: ColorPalette,
+    /// Number of entries in each precomputed color lookup table.
+    pub lookup_table_count: usize,
+    /// Number of bins in each per-magnet histogram of settling times.
+    pub histogram_bin_count: usize,
+}
+
+fn deserialize_positive_and_finite<'de, D>(deserializer: D, name: &str) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = f64::deserialize(deserializer)?;
+    require_positive_and_finite(name, value).map_err(serde::de::Error::custom)?;
+    Ok(value)
+}
+
+fn deserialize_time_step<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserialize_positive_and_finite(deserializer, "time_step")
+}
+
+fn deserialize_max_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserialize_positive_and_finite(deserializer, "max_time")
+}
+
+impl MagneticPendulumParams {
+    /// Time derivative of the state `[x, y, vx, vy]`.
+    fn dynamics(&self, state: Vector4<f64>) -> Vector4<f64> {
+        let position = Vector2::new(state[0], state[1]);
+        let velocity = Vector2::new(state[2], state[3]);
+        let height_squared = self.height * self.height;
+        let magnetic_force: Vector2<f64> = self
+            .magnets
+            .iter()
+            .map(|magnet| {
+                let delta = Vector2::new(magnet.position[0], magnet.position[1]) - position;
+                let distance_squared = delta.norm_squared() + height_squared;
+                delta * (magnet.strength / (distance_squared * distance_squared.sqrt()))
+            })
+            .sum();
+        let acceleration =
+            magnetic_force - self.spring_constant * position - self.friction * velocity;
+        Vector4::new(velocity[0], velocity[1], acceleration[0], acceleration[1])
+    }
+
+    /// Index of the magnet the bob is resting over, if any.
+    fn captured_magnet(&self, state: &Vector4<f64>) -> Option<usize> {
+        let capture_radius_squared = self.capture_radius * self.capture_radius;
+        let speed_squared = state[2] * state[2] + state[3] * state[3];
+        self.magnets.iter().position(|magnet| {
+            let dx = state[0] - magnet.position[0];
+            let dy = state[1] - magnet.position[1];
+            dx * dx + dy * dy + speed_squared < capture_radius_squared
+        })
+    }
+
+    /// Release the bob at rest from `point` and simulate until it settles.
+    /// Returns the settling time and the index of the capturing magnet.
+    pub fn simulate(&self, point: [f64; 2]) -> Option<(f64, usize)> {
+        let dynamics = |_t: f64, x: Vector4<f64>| self.dynamics(x);
+        let n_steps = (self.max_time / self.time_step).ceil() as u32;
+        let mut state = Vector4::new(point[0], point[1], 0.0, 0.0);
+        for step in 0..n_steps {
+            if let Some(index) = self.captured_magnet(&state) {
+                return Some((step as f64 * self.time_step, index));
+            }
+            state = rk4_method_step(self.time_step, 0.0, state, &dynamics);
+        }
+        None
+    }
+}
+
+impl FieldKernel for MagneticPendulumParams {
+    fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)> {
+        let n_color_maps = self.color.color_maps.len();
+        self.simulate(point)
+            .map(|(time, index)| (time as f32, (index % n_color_maps.max(1)) as u32))
+    }
+}
+
+impl Renderable for MagneticPendulumParams {
+    type Params = MagneticPendulumParams;
+
+    fn image_specification(&self) -> &ImageSpecification {
+        &self.image_specification
+    }
+
+    fn render_options(&self) -> &RenderOptions {
+        &self.render_options
+    }
+
+    fn set_image_specification(&mut self, image_specification: ImageSpecification) {
+        self.image_specification = image_specification;
+    }
+
+    fn write_diagnostics<W: std::io::Write>(&self, _writer: &mut W) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn params(&self) -> &Self::Params {
+        self
+    }
+
+    fn color_palette(&self) -> &ColorPalette {
+        &self.color
+    }
+
+    fn color_palette_mut(&mut self) -> &mut ColorPalette {
+        &mut self.color
+    }
+
+    fn histogram_bin_count(&self) -> usize {
+        self.histogram_bin_count
+    }
+
+    fn histogram_max_value(&self) -> f32 {
+        self.max_time as f32
+    }
+
+    fn lookup_table_count(&self) -> usize {
+        self.lookup_table_count
+    }
+}
+
+/// The user's values of the parameters that `set_speed_optimization_level`
+/// coarsens, to scale from at every level.
+pub struct ParamsReferenceCache {
+    /// Integration step from the params file.
+    pub time_step: f64,
+    /// Capture radius from the params file.
+    pub capture_radius: f64,
+    /// Render options from the params file.
+    pub render_options: RenderOptions,
+}
+
+impl SpeedOptimizer for MagneticPendulumParams {
+    type ReferenceCache = ParamsReferenceCache;
+
+    fn reference_cache(&self) -> Self::ReferenceCache {
+        ParamsReferenceCache {
+            time_step: self.time_step,
+            capture_radius: self.capture_radius,
+            render_options: self.render_options,
+        }
+    }
+
+    fn set_speed_optimization_level(&mut self, level: f64, cache: &Self::ReferenceCache) {
+        self.time_step =
+            scale_up_parameter_for_speed(0.1, cache.time_step, level, ClampedLogInterpolator);
+
+        self.capture_radius =
+            scale_up_parameter_for_speed(0.3, cache.capture_radius, level, ClampedLogInterpolator);
+
+        self.render_options
+            .set_speed_optimization_level(level, &cache.render_options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn three_magnet_params() -> MagneticPendulumParams {
+        serde_json::from_str(
+            r#"{
+                "image_specification": {
+                    "resolution": [8, 8],
+                    "center": [0, 0],
+                    "width": 6
+                },
+                "magnets": [
+                    { "position": [1.0, 0.0], "strength": 1.0 },
+                    { "position": [-0.5, 0.8660254037844386], "strength": 1.0 },
+                    { "position": [-0.5, -0.8660254037844386], "strength": 1.0 }
+                ],
+                "height": 0.25,
+                "spring_constant": 0.5,
+                "friction": 0.2,
+                "time_step": 0.02,
+                "max_time": 60.0,
+                "capture_radius": 0.1,
+                "render_options": { "sampling_level": 0 },
+                "color": {
+                    "background_color": [0, 0, 0],
+                    "color_maps": [
+                        [
+                            { "query": 0.0, "rgb_raw": [255, 0, 0] },
+                            { "query": 1.0, "rgb_raw": [0, 0, 0] }
+                        ],
+                        [
+                            { "query": 0.0, "rgb_raw": [0, 255, 0] },
+                            { "query": 1.0, "rgb_raw": [0, 0, 0] }
+                        ]
+                    ]
+                },
+                "lookup_table_count": 16,
+                "histogram_bin_count": 16
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn bob_released_near_a_magnet_settles_on_it() {
+        let params = three_magnet_params();
+        for (index, magnet) in params.magnets.iter().enumerate() {
+            let start = [magnet.position[0] * 1.1, magnet.position[1] * 1.1];
+            let (time, captured) = params.simulate(start).unwrap();
+            assert_eq!(captured, index);
+            assert!(time > 0.0 && time < params.max_time, "{time}");
+        }
+
+        // Already at rest over a magnet: captured immediately.
+        assert_eq!(params.simulate([1.0, 0.0]), Some((0.0, 0)));
+    }
+
+    #[test]
+    fn basins_share_the_symmetry_of_the_magnets() {
+        let params = three_magnet_params();
+        let rotate = |p: [f64; 2]| {
+            let (sin, cos) = (2.0 * std::f64::consts::PI / 3.0).sin_cos();
+            [cos * p[0] - sin * p[1], sin * p[0] + cos * p[1]]
+        };
+        for start in [[2.0, 0.5], [-1.7, 1.9], [0.3, -2.4]] {
+            let (time, index) = params.simulate(start).unwrap();
+            let (rotated_time, rotated_index) = params.simulate(rotate(start)).unwrap();
+            assert_eq!(rotated_index, (index + 1) % 3);
+            assert!(
+                (time - rotated_time).abs() < 0.1,
+                "{time} vs {rotated_time}"
+            );
+        }
+    }
+
+    #[test]
+    fn non_positive_or_non_finite_time_step_and_max_time_are_rejected() {
+        let json = serde_json::to_string(&three_magnet_params()).unwrap();
+        for (field, bad_value) in [
+            ("time_step", "0.0"),
+            ("time_step", "-0.02"),
+            ("max_time", "0.0"),
+            ("max_time", "-60.0"),
+        ] {
+            let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            value[field] = serde_json::from_str(bad_value).unwrap();
+            let error = serde_json::from_value::<MagneticPendulumParams>(value)
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains(field), "{field} = {bad_value}: {error}");
+        }
+    }
+
+    #[test]
+    fn evaluate_wraps_magnet_index_onto_the_color_maps() {
+        let params = three_magnet_params();
+        let third = params.magnets[2].position;
+        assert_eq!(params.evaluate(third), Some((0.0, 0)));
+    }
+}
@@ -1,7 +1,9 @@
 pub mod barnsley_fern;
+pub mod buddhabrot;
 pub mod common;
 pub mod driven_damped_pendulum;
 pub mod julia;
+pub mod magnetic_pendulum;
 pub mod mandelbrot;
 pub mod newtons_method;
 pub mod quadratic_map;
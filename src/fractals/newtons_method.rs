@@ -3,18 +3,21 @@ use serde::{Deserialize, Serialize};
 use std::{f64::consts::PI, fmt::Debug};
 
 use crate::core::{
-    color_map::ColorPalette,
-    field_iteration::FieldKernel,
+    color_map::{ColorPalette, OVERLAY_COLOR_MAP_INDEX},
+    complex_expression::ComplexExpression,
+    field_iteration::{CellCounts, FieldBuffer, FieldKernel, count_cells},
     file_io::FilePrefix,
     image_utils::{
-        self, ImageSpecification, RenderOptions, Renderable, SpeedOptimizer,
+        self, ImageSpecification, PixelMapper, RenderOptions, Renderable, SpeedOptimizer,
         scale_down_parameter_for_speed, scale_up_parameter_for_speed,
     },
-    interactive,
     interpolation::ClampedLogInterpolator,
 };
 use crate::fractals::common::newton_snapshot_json;
 
+#[cfg(feature = "gui")]
+use crate::core::interactive;
+
 // Its often more efficient to compute both the value of a complex function
 // and its derivative (slope) at the same time.
 pub struct ComplexValueAndSlope {
@@ -22,31 +25,90 @@ pub struct ComplexValueAndSlope {
     slope: Complex64,
 }
 
+/// Below this squared magnitude, the slope is treated as zero: the Newton
+/// step `f(z) / f'(z)` would be meaningless (or NaN).
+const MIN_SLOPE_NORM_SQR: f64 = 1e-24;
+
 // A complex-valued function with its derivative (slope).
 pub trait ComplexFunctionWithSlope: Serialize + Clone + Debug + Sync {
     fn eval(&self, z: Complex64) -> ComplexValueAndSlope;
 
-    fn newton_step_size(&self) -> f64;
+    /// Multiplier for the Newton step, usually 1. A complex multiplier also
+    /// rotates the step, which swirls the basin boundaries.
+    fn newton_step_size(&self) -> Complex64;
 
+    /// The undamped Newton step `f(z) / f'(z)`. Where the slope is (near)
+    /// zero this is zero if `z` is also a root (e.g. a double root such as
+    /// `cosh(z) - 1` at `z = 0`), and NaN otherwise, which
+    /// `newton_rhapson_iteration_sequence` reports as `NonConvergence::FlatSlope`.
     fn value_divided_by_slope(&self, z: Complex64) -> Complex64 {
         let vs = self.eval(z);
+        if vs.slope.norm_sqr() < MIN_SLOPE_NORM_SQR {
+            return if vs.value.norm_sqr() < MIN_SLOPE_NORM_SQR {
+                Complex64::new(0.0, 0.0)
+            } else {
+                Complex64::new(f64::NAN, f64::NAN)
+            };
+        }
         vs.value / vs.slope
     }
 
     fn newton_rhapson_step(&self, z: Complex64) -> Complex64 {
-        z - self
-            .value_divided_by_slope(z)
-            .scale(self.newton_step_size())
+        let step = self.value_divided_by_slope(z);
+        let step_size = self.newton_step_size();
+        // Scale by a real step size directly: the complex product would turn
+        // an overflowed (infinite) step into NaN.
+        if step_size.im == 0.0 {
+            z - step.scale(step_size.re)
+        } else {
+            z - step * step_size
+        }
     }
 
     /// Returns the index of the root that is closest to `z`.
     fn root_index(&self, z: Complex64) -> usize;
+
+    /// Periods of the fractal along the real and imaginary axes, if any.
+    /// The tileable render mode wraps the domain along each periodic axis.
+    fn periods(&self) -> [Option<f64>; 2] {
+        [None, None]
+    }
+}
+
+/// Multiplier for the Newton step: a real number, or `[re, im]` for a
+/// complex multiplier that rotates the step (a "relaxed" Newton's method).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(untagged)]
+pub enum NewtonStepSize {
+    /// Real multiplier; `1.0` is the standard Newton step.
+    Real(f64),
+    /// Complex multiplier `[re, im]`.
+    Complex([f64; 2]),
+}
+
+impl NewtonStepSize {
+    /// The multiplier as a complex number.
+    pub fn value(&self) -> Complex64 {
+        match *self {
+            NewtonStepSize::Real(re) => Complex64::new(re, 0.0),
+            NewtonStepSize::Complex([re, im]) => Complex64::new(re, im),
+        }
+    }
+}
+
+impl From<f64> for NewtonStepSize {
+    fn from(step_size: f64) -> Self {
+        NewtonStepSize::Real(step_size)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RootsOfUnityParams {
+    /// Number of roots, each drawn with its own color map: the palette must
+    /// define exactly this many.
     pub n_roots: i32,
-    pub newton_step_size: f64,
+    /// Multiplier for the Newton step (usually 1.0).
+    pub newton_step_size: NewtonStepSize,
 }
 
 impl ComplexFunctionWithSlope for RootsOfUnityParams {
@@ -59,8 +121,8 @@ impl ComplexFunctionWithSlope for RootsOfUnityParams {
         }
     }
 
-    fn newton_step_size(&self) -> f64 {
-        self.newton_step_size
+    fn newton_step_size(&self) -> Complex64 {
+        self.newton_step_size.value()
     }
 
     fn root_index(&self, z: Complex64) -> usize {
@@ -80,8 +142,8 @@ impl ComplexFunctionWithSlope for RootsOfUnityParams {
 /// Parameters / marker type for f(z) = cosh(z) - 1
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CoshMinusOneParams {
-    /// Scalar multiplier for the Newton step (usually 1.0).
-    pub newton_step_size: f64,
+    /// Multiplier for the Newton step (usually 1.0).
+    pub newton_step_size: NewtonStepSize,
 }
 
 impl ComplexFunctionWithSlope for CoshMinusOneParams {
@@ -94,8 +156,8 @@ impl ComplexFunctionWithSlope for CoshMinusOneParams {
         ComplexValueAndSlope { value, slope }
     }
 
-    fn newton_step_size(&self) -> f64 {
-        self.newton_step_size
+    fn newton_step_size(&self) -> Complex64 {
+        self.newton_step_size.value()
     }
 
     /// Roots of cosh(z) - 1 are at z_k = 2π i k, k ∈ ℤ.
@@ -115,9 +177,67 @@ impl ComplexFunctionWithSlope for CoshMinusOneParams {
             (-2 * k - 1) as usize
         }
     }
+
+    /// Shifting `z` by `2π i` maps each root onto the next one, so the basins
+    /// of attraction repeat with that period along the imaginary axis.
+    fn periods(&self) -> [Option<f64>; 2] {
+        [None, Some(2.0 * PI)]
+    }
+}
+
+/// Parameters for a user-defined f(z), written as an expression string such
+/// as `"z^3 - 1"` or `"sin(z) - z"`. The derivative is computed exactly from
+/// the parsed expression. See `core::complex_expression` for the syntax.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExpressionParams {
+    /// The parsed f(z) whose roots are found, e.g. `"z^3 - 1"`.
+    pub expression: ComplexExpression,
+    /// Multiplier for the Newton step (usually 1.0).
+    pub newton_step_size: NewtonStepSize,
+    /// Known roots of f(z), as `[re, im]`. When given, each root is colored
+    /// with the color map of the same index (modulo the number of maps).
+    /// When empty, roots are told apart by position alone, so the color
+    /// map assigned to each root is arbitrary but stable.
+    #[serde(default)]
+    pub roots: Vec<[f64; 2]>,
+}
+
+impl ComplexFunctionWithSlope for ExpressionParams {
+    fn eval(&self, z: Complex64) -> ComplexValueAndSlope {
+        let (value, slope) = self.expression.value_and_slope(z);
+        ComplexValueAndSlope { value, slope }
+    }
+
+    fn newton_step_size(&self) -> Complex64 {
+        self.newton_step_size.value()
+    }
+
+    fn root_index(&self, z: Complex64) -> usize {
+        if !self.roots.is_empty() {
+            let distance_squared =
+                |root: &[f64; 2]| (z - Complex64::new(root[0], root[1])).norm_sqr();
+            return self
+                .roots
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| distance_squared(a).total_cmp(&distance_squared(b)))
+                .map_or(0, |(index, _)| index);
+        }
+
+        // Quantize the converged point so that every sample converging to the
+        // same root gets the same key, then mix the key so that neighboring
+        // roots do not land on neighboring indices.
+        const ROOT_QUANTUM: f64 = 1e-3;
+        let [re, im] = [z.re, z.im].map(|x| (x / ROOT_QUANTUM).round() as i64 as u64);
+        let mut hash =
+            re.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ im.wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        hash ^= hash >> 31;
+        hash as usize
+    }
 }
 
 /// Result of running a Newton-Rhapson iteration sequence to convergence.
+#[derive(Debug)]
 pub struct NewtonRhapsonResult {
     /// The point to which the Newton-Rhapson iteration sequence converge.
     pub soln: Complex64,
@@ -132,28 +252,128 @@ pub struct NewtonRhapsonResult {
     pub smooth_iteration_count: f32,
 }
 
-/// Returns Some(NewtonRhapsonResult) if the iteration converges within
-/// `max_iteration_count` iterations to within `convergence_tolerance`. Otherwise returns None.
+/// Why a Newton-Rhapson iteration sequence did not converge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonConvergence {
+    /// An iterate left the disk of radius `divergence_radius`.
+    Diverged,
+    /// Still wandering (e.g. trapped by a cyclic attractor) after
+    /// `max_iteration_count` iterations.
+    IterationLimit,
+    /// An iterate landed on a critical point (`f'(z) = 0`) that is not a
+    /// root, where the Newton step is undefined. Such points lie on the
+    /// boundary between basins.
+    FlatSlope,
+}
+
+/// How `convergence_tolerance` measures the size of a Newton step.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToleranceMode {
+    /// The squared length of the step, `|z_next - z_prev|²`.
+    #[default]
+    Absolute,
+    /// The squared length of the step relative to the iterate,
+    /// `|z_next - z_prev|² / |z_next|²`, so that roots far from the origin
+    /// converge to as many significant digits as those near it. Inside the
+    /// unit disk this is the absolute step instead: near a root at the
+    /// origin the step is about as long as `z` itself, so the relative step
+    /// would never shrink.
+    Relative,
+}
+
+impl ToleranceMode {
+    /// Size of the step from `z_prev` to `z_next`, in the units of
+    /// `convergence_tolerance`.
+    pub fn step_error(self, z_prev: Complex64, z_next: Complex64) -> f64 {
+        let step = (z_next - z_prev).norm_sqr();
+        match self {
+            ToleranceMode::Absolute => step,
+            ToleranceMode::Relative => step / z_next.norm_sqr().max(1.0),
+        }
+    }
+}
+
+/// Returns the `NewtonRhapsonResult` if the iteration converges within
+/// `max_iteration_count` iterations to within `convergence_tolerance`, with
+/// each step measured as `tolerance_mode` says.
+/// If `divergence_radius` is set, an iterate with a larger magnitude stops
+/// the sequence early as `NonConvergence::Diverged`.
+///
+/// After convergence, `refinement_count` extra Newton steps are taken. The
+/// smooth iteration count models the error as geometric; with refinement the
+/// rate is averaged over the converging step plus the extra steps instead of
+/// being taken from that single step, which gives smoother shading at the cost
+/// of a few more function evaluations per converged point.
 pub fn newton_rhapson_iteration_sequence<F: ComplexFunctionWithSlope>(
     system: &F,
     z0: Complex64,
     convergence_tolerance: f64,
+    tolerance_mode: ToleranceMode,
     max_iteration_count: u32,
-) -> Option<NewtonRhapsonResult> {
+    refinement_count: u32,
+    divergence_radius: Option<f64>,
+) -> Result<NewtonRhapsonResult, NonConvergence> {
+    let divergence_radius_squared = divergence_radius.map(|r| r * r);
     let mut z_prev = z0;
     let mut prev_err: Option<f64> = None;
 
     for iteration in 0..=max_iteration_count {
         let z_next = system.newton_rhapson_step(z_prev);
-        let error = (z_next - z_prev).norm_sqr();
+        if z_next.is_nan() {
+            return Err(NonConvergence::FlatSlope);
+        }
+        let error = tolerance_mode.step_error(z_prev, z_next);
 
         if error < convergence_tolerance {
             let iteration_count = iteration;
+
+            // Extra steps past convergence: refine the solution and record the
+            // error after the last one, along with how many steps it spans.
+            let mut soln = z_next;
+            let mut last_error = error;
+            let mut rate_steps = 1;
+            for _ in 0..refinement_count {
+                if last_error <= 0.0 {
+                    break;
+                }
+                let z_refined = system.newton_rhapson_step(soln);
+                if z_refined.is_nan() {
+                    break;
+                }
+                let refined_error = tolerance_mode.step_error(soln, z_refined);
+                soln = z_refined;
+                if refined_error <= 0.0 {
+                    break;
+                }
+                last_error = refined_error;
+                rate_steps += 1;
+            }
+
+            // Converging on the very first step leaves no error before
+            // convergence to interpolate from. Extrapolate one backward from
+            // the error ratio past convergence, taking an extra probe step if
+            // there were no refinement steps, so the smooth count stays
+            // continuous with neighbors that converge on the second step.
+            let prev_err = prev_err.or_else(|| {
+                if error <= 0.0 {
+                    return None;
+                }
+                let (next_error, steps) = if rate_steps > 1 {
+                    (last_error, rate_steps - 1)
+                } else {
+                    let z_probe = system.newton_rhapson_step(z_next);
+                    (tolerance_mode.step_error(z_next, z_probe), 1)
+                };
+                let error_ratio = (next_error / error).powf(1.0 / steps as f64);
+                (error_ratio > 0.0).then(|| error / error_ratio)
+            });
+
             let smooth_iteration_count = if let Some(e_prev) = prev_err {
                 // Guard against the case where error actually hits zero, which would cause ln(0).
                 if error > 0.0 {
-                    // model error as geometric between e_prev and err
-                    let error_ratio = error / e_prev;
+                    // Average geometric error ratio per step, from the step
+                    // before convergence through the last refinement step.
+                    let error_ratio = (last_error / e_prev).powf(1.0 / rate_steps as f64);
 
                     // Model the error as geometric between the last two steps:
                     //   e_n ≈ e_prev * error_ratio^(n - (k - 1))
@@ -165,23 +385,29 @@ pub fn newton_rhapson_iteration_sequence<F: ComplexFunctionWithSlope>(
                     iteration as f32
                 }
             } else {
-                // We converged on the first iteration, so we have no data for interpolation.
+                // The first step landed exactly on the root.
                 iteration as f32
             };
 
-            return Some(NewtonRhapsonResult {
-                soln: z_next,
+            return Ok(NewtonRhapsonResult {
+                soln,
                 iteration_count,
                 smooth_iteration_count,
             });
         }
 
+        if let Some(radius_squared) = divergence_radius_squared
+            && (!z_next.is_finite() || z_next.norm_sqr() > radius_squared)
+        {
+            return Err(NonConvergence::Diverged);
+        }
+
         prev_err = Some(error);
         z_prev = z_next;
     }
 
     // Only reach here if we fail to converge.
-    None
+    Err(NonConvergence::IterationLimit)
 }
 
 /// These parameters are common to all Newton's method fractals, and are not
@@ -194,6 +420,25 @@ pub struct CommonParams {
     pub max_iteration_count: u32,
     /// Tolerance used to detect convergence to a root.
     pub convergence_tolerance: f64,
+    /// Whether `convergence_tolerance` bounds the absolute or the relative
+    /// size of the last Newton step. Defaults to absolute.
+    #[serde(default)]
+    pub tolerance_mode: ToleranceMode,
+    /// Extra Newton steps taken after convergence to refine the estimate of
+    /// the smooth iteration count. Each step costs one more function
+    /// evaluation per converged point; 1-2 steps noticeably smooth the
+    /// shading, beyond that the returns are small. Defaults to 0.
+    #[serde(default)]
+    pub refinement_count: u32,
+    /// Points whose iterates leave the disk of this radius are classified as
+    /// divergent right away, instead of running to `max_iteration_count`.
+    /// Unset (the default) disables the test.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub divergence_radius: Option<f64>,
+    /// Flat color of divergent points, distinct from the cyclic-attractor
+    /// background color. Falls back to the background color when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub divergent_color_rgb: Option<[u8; 3]>,
     /// Rendering options (anti-aliasing, downsampling, etc.).
     pub render_options: RenderOptions,
     /// Per-root color maps plus the cyclic-attractor (non-converged) flat color.
@@ -203,6 +448,18 @@ pub struct CommonParams {
     /// Number of bins per per-root histogram. Each root gets its own
     /// histogram and CDF over its own iteration-count distribution.
     pub histogram_bin_count: usize,
+    /// Render a seamlessly tileable texture. The view is snapped to a whole
+    /// number of periods along each periodic axis of the system, and the
+    /// domain is wrapped so that opposite edges line up exactly. Only
+    /// supported for periodic systems (e.g. `CoshMinusOne`, which tiles
+    /// along the imaginary axis).
+    #[serde(default)]
+    pub tileable: bool,
+    /// Overlay isolines of the iteration count on the basin colors: thin
+    /// lines, in the background color, wherever neighboring samples that
+    /// converge to the same root took a different number of iterations.
+    #[serde(default)]
+    pub show_isolines: bool,
 }
 
 // The `NewtonsMethodParams` struct encapsulates all parameters needed to
@@ -210,11 +467,36 @@ pub struct CommonParams {
 // `Box<dyn>` to allow for different types of systems to be specified.
 // It is analgous to `FractalParams`.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(try_from = "NewtonsMethodParamsFile")]
 pub struct NewtonsMethodParams {
     pub params: CommonParams,
     pub system: SystemType,
 }
 
+/// `NewtonsMethodParams` as written in a params file, before the color maps
+/// and `tileable` are checked against the system.
+#[derive(Deserialize)]
+struct NewtonsMethodParamsFile {
+    params: CommonParams,
+    system: SystemType,
+}
+
+impl TryFrom<NewtonsMethodParamsFile> for NewtonsMethodParams {
+    type Error = String;
+
+    fn try_from(file: NewtonsMethodParamsFile) -> Result<Self, Self::Error> {
+        file.system
+            .validate_color_map_count(file.params.color.color_maps.len())?;
+        if file.params.tileable && file.system.periods().iter().all(Option::is_none) {
+            return Err("`tileable` rendering requires a system with a periodic axis".to_owned());
+        }
+        Ok(NewtonsMethodParams {
+            params: file.params,
+            system: file.system,
+        })
+    }
+}
+
 /// Newtype wrapper that carries Newton's-method parameters plus the
 /// concrete root system. Histogram, CDF, and color caches now live in the
 /// rendering pipeline, not here.
@@ -223,33 +505,208 @@ pub struct NewtonsMethodRenderable<F: ComplexFunctionWithSlope> {
     pub params: CommonParams,
     /// Concrete system (e.g. roots-of-unity, cosh-minus-one).
     pub system: F,
+    /// Tallied from the field of the last render; reported by
+    /// `write_diagnostics`.
+    basin_counts: CellCounts,
+    /// `system.periods()`, cached off the per-pixel path.
+    periods: [Option<f64>; 2],
+    /// Where each periodic axis wraps for the current view, when tileable.
+    axis_wraps: [Option<AxisWrap>; 2],
+}
+
+/// The wrap boundary and period of one periodic axis of a tileable render.
+#[derive(Debug, Clone, Copy)]
+struct AxisWrap {
+    boundary: f64,
+    step_sign: f64,
+    period: f64,
+}
+
+/// The wrap of each periodic axis for `spec`. The wrap boundary sits half a
+/// pixel outside the first row / column, so the sample one pixel past the
+/// opposite edge lands back on the first one.
+fn axis_wraps(spec: &ImageSpecification, periods: [Option<f64>; 2]) -> [Option<AxisWrap>; 2] {
+    let mapper = PixelMapper::new(spec);
+    let axes = [&mapper.width, &mapper.height];
+    std::array::from_fn(|dim| {
+        periods[dim].map(|period| {
+            let step = axes[dim].map(1) - axes[dim].map(0);
+            AxisWrap {
+                boundary: axes[dim].map(0) - 0.5 * step,
+                step_sign: step.signum(),
+                period,
+            }
+        })
+    })
+}
+
+/// How many samples of the last render converged to each root, and how
+/// many did not converge. Helps to balance the basins when choosing the
+/// per-root color maps. Derived from the finished field: converged samples
+/// are counted by the color map their root is drawn with (roots that share
+/// a color map share a count), diverged samples are the overlay cells, and
+/// every other sample is empty.
+fn display_basin_stats<W: std::io::Write>(
+    counts: &CellCounts,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    let total = counts.total();
+    let percent = |count: u32| {
+        if total == 0 {
+            0.0
+        } else {
+            100.0 * count as f32 / total as f32
+        }
+    };
+    writeln!(writer, "Basin statistics:")?;
+    writeln!(writer, "  total count: {total}")?;
+    for (index, &count) in counts.by_color_map.iter().enumerate() {
+        writeln!(
+            writer,
+            "  root (color map {index}): {count}  ({:.2}%)",
+            percent(count)
+        )?;
+    }
+    for (label, count) in [
+        ("Diverged", counts.overlay),
+        (
+            "Cycled (hit max_iteration_count) or landed on a flat slope",
+            counts.empty,
+        ),
+    ] {
+        writeln!(writer, "  {label}: {count}  ({:.2}%)", percent(count))?;
+    }
+    writeln!(writer)
 }
 
 impl<F: ComplexFunctionWithSlope> NewtonsMethodRenderable<F> {
     /// Construct a Newton renderer. Asserts there is at least one
     /// color map (the colorize cache assumes `color_maps` is non-empty).
-    pub fn new(params: CommonParams, system: F) -> Self {
+    /// A `tileable` render of a system without a periodic axis is rejected
+    /// when the params are loaded; here it just renders without wrapping.
+    pub fn new(mut params: CommonParams, system: F) -> Self {
         assert!(
             !params.color.color_maps.is_empty(),
             "color.color_maps must define at least one color map"
         );
-        Self { params, system }
+        let periods = system.periods();
+        if params.tileable {
+            params.image_specification = snap_to_periods(&params.image_specification, periods);
+        }
+        let axis_wraps = axis_wraps(&params.image_specification, periods);
+        Self {
+            params,
+            system,
+            basin_counts: CellCounts::default(),
+            periods,
+            axis_wraps,
+        }
     }
 
-    fn newton_rhapson_iteration_sequence(&self, z0: Complex64) -> Option<NewtonRhapsonResult> {
+    /// Map `point` into a single period along each periodic axis, when
+    /// rendering a tileable image.
+    fn wrap_point(&self, mut point: [f64; 2]) -> [f64; 2] {
+        if !self.params.tileable {
+            return point;
+        }
+        for (coordinate, wrap) in point.iter_mut().zip(&self.axis_wraps) {
+            if let Some(wrap) = wrap {
+                let offset = (*coordinate - wrap.boundary) * wrap.step_sign;
+                *coordinate = wrap.boundary + offset.rem_euclid(wrap.period) * wrap.step_sign;
+            }
+        }
+        point
+    }
+
+    fn newton_rhapson_iteration_sequence(
+        &self,
+        z0: Complex64,
+    ) -> Result<NewtonRhapsonResult, NonConvergence> {
         newton_rhapson_iteration_sequence(
             &self.system,
             z0,
             self.params.convergence_tolerance,
+            self.params.tolerance_mode,
             self.params.max_iteration_count,
+            self.params.refinement_count,
+            self.params.divergence_radius,
         )
     }
 }
 
+/// Resize the view (keeping its center) so that each periodic axis spans a
+/// whole number of periods, measured from the first pixel to one pixel past
+/// the last. If only one axis is periodic the other is scaled to keep the
+/// pixel aspect ratio; if both are, the pixel aspect ratio absorbs the change.
+fn snap_to_periods(spec: &ImageSpecification, periods: [Option<f64>; 2]) -> ImageSpecification {
+    // `PixelMapper` puts the first and last pixel on the view edges, so `n`
+    // pixels span `extent * n / (n - 1)` once the wrap-around pixel is included.
+    let snap = |extent: f64, n: u32, period: f64| {
+        let n = n.max(2) as f64;
+        let repeats = (extent * n / (n - 1.0) / period).round().max(1.0);
+        repeats * period * (n - 1.0) / n
+    };
+    let mut snapped = *spec;
+    match periods {
+        [Some(x_period), None] => {
+            snapped.width = snap(spec.width, spec.resolution[0], x_period);
+        }
+        [None, Some(y_period)] => {
+            let height = snap(spec.height(), spec.resolution[1], y_period);
+            snapped.width *= height / spec.height();
+        }
+        [Some(x_period), Some(y_period)] => {
+            snapped.width = snap(spec.width, spec.resolution[0], x_period);
+            let height = snap(spec.height(), spec.resolution[1], y_period);
+            snapped.pixel_aspect_ratio = 1.0;
+            snapped.pixel_aspect_ratio = height / snapped.height();
+        }
+        [None, None] => {}
+    }
+    snapped
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum SystemType {
     RootsOfUnity(Box<RootsOfUnityParams>), // f(z) = z^n - 1
     CoshMinusOne(Box<CoshMinusOneParams>), // f(z) cosh(z) - 1
+    Expression(Box<ExpressionParams>),     // f(z) given as a string, e.g. "z^3 - 1"
+}
+
+impl SystemType {
+    /// Periods of the system along the real and imaginary axes, if any.
+    pub fn periods(&self) -> [Option<f64>; 2] {
+        match self {
+            SystemType::RootsOfUnity(system) => system.periods(),
+            SystemType::CoshMinusOne(system) => system.periods(),
+            SystemType::Expression(system) => system.periods(),
+        }
+    }
+
+    /// Each root is drawn with the color map of the same index. `z^n - 1`
+    /// has exactly `n` roots, so it needs exactly `n` color maps. The other
+    /// systems may have any number of roots (infinitely many for
+    /// `cosh(z) - 1`), so their roots cycle through the color maps.
+    pub fn validate_color_map_count(&self, color_map_count: usize) -> Result<(), String> {
+        match self {
+            SystemType::RootsOfUnity(system) => {
+                if system.n_roots < 1 {
+                    return Err(format!(
+                        "`n_roots` ({}) must be at least one",
+                        system.n_roots
+                    ));
+                }
+                if color_map_count != system.n_roots as usize {
+                    return Err(format!(
+                        "`RootsOfUnity` with `n_roots` = {} needs one color map per root, found {color_map_count}",
+                        system.n_roots
+                    ));
+                }
+                Ok(())
+            }
+            SystemType::CoshMinusOne(_) | SystemType::Expression(_) => Ok(()),
+        }
+    }
 }
 
 impl<F> SpeedOptimizer for NewtonsMethodRenderable<F>
@@ -288,11 +745,18 @@ where
 {
     fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)> {
         let n_color_maps = self.params.color.color_maps.len() as u32;
-        self.newton_rhapson_iteration_sequence(Complex64::new(point[0], point[1]))
+        let point = self.wrap_point(point);
+        let result = self
+            .newton_rhapson_iteration_sequence(Complex64::new(point[0], point[1]))
             .map(|res| {
                 let k = (self.system.root_index(res.soln) as u32) % n_color_maps.max(1);
                 (res.smooth_iteration_count, k)
-            })
+            });
+        match result {
+            Ok(cell) => Some(cell),
+            Err(NonConvergence::Diverged) => Some((0.0, OVERLAY_COLOR_MAP_INDEX)),
+            Err(NonConvergence::IterationLimit | NonConvergence::FlatSlope) => None,
+        }
     }
 }
 
@@ -312,10 +776,25 @@ where
 
     fn set_image_specification(&mut self, image_specification: ImageSpecification) {
         self.params.image_specification = image_specification;
+        self.axis_wraps = axis_wraps(&image_specification, self.periods);
+    }
+
+    fn write_diagnostics<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        display_basin_stats(&self.basin_counts, writer)
     }
 
-    fn write_diagnostics<W: std::io::Write>(&self, _writer: &mut W) -> std::io::Result<()> {
-        Ok(())
+    fn record_field_statistics(
+        &mut self,
+        field: &FieldBuffer,
+        n_max_plus_1: usize,
+        sampling_level: i32,
+    ) {
+        self.basin_counts = count_cells(
+            n_max_plus_1,
+            sampling_level,
+            field,
+            self.params.color.color_maps.len(),
+        );
     }
 
     fn params(&self) -> &Self::Params {
@@ -341,6 +820,14 @@ where
     fn lookup_table_count(&self) -> usize {
         self.params.lookup_table_count
     }
+
+    fn overlay_color(&self) -> Option<[u8; 3]> {
+        self.params.divergent_color_rgb
+    }
+
+    fn show_isolines(&self) -> bool {
+        self.params.show_isolines
+    }
 }
 
 // Renders a Newton's method fractal based on the provided parameters.
@@ -355,16 +842,25 @@ pub fn render_newtons_method(
         SystemType::RootsOfUnity(system_params) => image_utils::render(
             NewtonsMethodRenderable::new(params.params.clone(), system_params.as_ref().clone()),
             file_prefix,
+            "NewtonsMethod",
             |p| newton_snapshot_json(&params.system, p),
         ),
         SystemType::CoshMinusOne(system_params) => image_utils::render(
             NewtonsMethodRenderable::new(params.params.clone(), system_params.as_ref().clone()),
             file_prefix,
+            "NewtonsMethod",
+            |p| newton_snapshot_json(&params.system, p),
+        ),
+        SystemType::Expression(system_params) => image_utils::render(
+            NewtonsMethodRenderable::new(params.params.clone(), system_params.as_ref().clone()),
+            file_prefix,
+            "NewtonsMethod",
             |p| newton_snapshot_json(&params.system, p),
         ),
     }
 }
 
+#[cfg(feature = "gui")]
 pub fn explore_fractal(
     params: &NewtonsMethodParams,
     mut file_prefix: FilePrefix,
@@ -390,5 +886,557 @@ pub fn explore_fractal(
                 move |p| newton_snapshot_json(&system, p),
             )
         }
+        SystemType::Expression(system_params) => {
+            file_prefix.create_and_step_into_sub_directory("expression");
+            let system = params.system.clone();
+            interactive::explore(
+                file_prefix,
+                params.params.image_specification,
+                NewtonsMethodRenderable::new(params.params.clone(), system_params.as_ref().clone()),
+                move |p| newton_snapshot_json(&system, p),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tileable_cosh_renderable() -> NewtonsMethodRenderable<CoshMinusOneParams> {
+        let params: CommonParams = serde_json::from_str(
+            r#"{
+                "image_specification": {
+                    "resolution": [7, 12],
+                    "center": [0.3, 1.0],
+                    "width": 4.0
+                },
+                "max_iteration_count": 64,
+                "convergence_tolerance": 1e-8,
+                "render_options": { "sampling_level": 0 },
+                "color": {
+                    "background_color": [0, 0, 0],
+                    "color_maps": [
+                        [
+                            { "query": 0.0, "rgb_raw": [0, 0, 0] },
+                            { "query": 1.0, "rgb_raw": [255, 255, 255] }
+                        ],
+                        [
+                            { "query": 0.0, "rgb_raw": [255, 0, 0] },
+                            { "query": 1.0, "rgb_raw": [0, 0, 255] }
+                        ],
+                        [
+                            { "query": 0.0, "rgb_raw": [0, 255, 0] },
+                            { "query": 1.0, "rgb_raw": [255, 255, 0] }
+                        ]
+                    ]
+                },
+                "lookup_table_count": 16,
+                "histogram_bin_count": 16,
+                "tileable": true
+            }"#,
+        )
+        .unwrap();
+        NewtonsMethodRenderable::new(
+            params,
+            CoshMinusOneParams {
+                newton_step_size: 1.0.into(),
+            },
+        )
+    }
+
+    #[test]
+    fn refinement_steps_refine_the_root_and_keep_the_smooth_count_bracketed() {
+        let system = RootsOfUnityParams {
+            n_roots: 3,
+            newton_step_size: 1.0.into(),
+        };
+        let z0 = Complex64::new(0.7, 0.4);
+        let tolerance = 1e-6;
+        let root = Complex64::new(1.0, 0.0);
+
+        let plain = newton_rhapson_iteration_sequence(
+            &system,
+            z0,
+            tolerance,
+            ToleranceMode::Absolute,
+            64,
+            0,
+            None,
+        )
+        .unwrap();
+        let refined = newton_rhapson_iteration_sequence(
+            &system,
+            z0,
+            tolerance,
+            ToleranceMode::Absolute,
+            64,
+            2,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(plain.iteration_count, refined.iteration_count);
+        assert!((refined.soln - root).norm() <= (plain.soln - root).norm());
+        for result in [&plain, &refined] {
+            let k = result.iteration_count as f32;
+            assert!(
+                (k - 1.0..=k).contains(&result.smooth_iteration_count),
+                "{} not in [{}, {}]",
+                result.smooth_iteration_count,
+                k - 1.0,
+                k
+            );
+        }
+    }
+
+    /// Points converging on the very first step still get a fractional
+    /// smooth count, continuous with neighbors that converge on the second.
+    #[test]
+    fn smooth_count_is_continuous_across_first_step_convergence() {
+        let system = RootsOfUnityParams {
+            n_roots: 3,
+            newton_step_size: 1.0.into(),
+        };
+        // March away from the root at z = 1 across the boundary between
+        // converging on the first and on the second step.
+        let results: Vec<NewtonRhapsonResult> = (0..100)
+            .map(|i| {
+                let offset = 1e-4 * 1.05f64.powi(i);
+                let z0 = Complex64::new(1.0 + offset, 0.3 * offset);
+                newton_rhapson_iteration_sequence(
+                    &system,
+                    z0,
+                    1e-6,
+                    ToleranceMode::Absolute,
+                    64,
+                    0,
+                    None,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let first_step: Vec<f32> = results
+            .iter()
+            .filter(|result| result.iteration_count == 0)
+            .map(|result| result.smooth_iteration_count)
+            .collect();
+        assert!(!first_step.is_empty());
+        assert!(results.iter().any(|result| result.iteration_count == 1));
+        for count in first_step {
+            assert!(count > -1.0 && count < 0.0, "{count}");
+        }
+        for pair in results.windows(2) {
+            let step = pair[1].smooth_iteration_count - pair[0].smooth_iteration_count;
+            assert!(step > 0.0 && step < 0.05, "{step}");
+        }
+    }
+
+    #[test]
+    fn divergence_radius_stops_iterates_thrown_off_by_a_flat_slope() {
+        let system = RootsOfUnityParams {
+            n_roots: 3,
+            newton_step_size: 1.0.into(),
+        };
+        // f'(z) = 3 z^2 is small near the origin, so a start next to it is
+        // thrown far away.
+        for z0 in [Complex64::new(1e-3, 0.0), Complex64::new(0.0, -2e-3)] {
+            assert_eq!(
+                newton_rhapson_iteration_sequence(
+                    &system,
+                    z0,
+                    1e-6,
+                    ToleranceMode::Absolute,
+                    4,
+                    0,
+                    Some(1e3)
+                )
+                .unwrap_err(),
+                NonConvergence::Diverged
+            );
+            assert_eq!(
+                newton_rhapson_iteration_sequence(
+                    &system,
+                    z0,
+                    1e-6,
+                    ToleranceMode::Absolute,
+                    4,
+                    0,
+                    None
+                )
+                .unwrap_err(),
+                NonConvergence::IterationLimit
+            );
+        }
+
+        // Convergent points are unaffected.
+        let z0 = Complex64::new(0.7, 0.4);
+        let bounded = newton_rhapson_iteration_sequence(
+            &system,
+            z0,
+            1e-6,
+            ToleranceMode::Absolute,
+            64,
+            0,
+            Some(1e3),
+        );
+        let unbounded = newton_rhapson_iteration_sequence(
+            &system,
+            z0,
+            1e-6,
+            ToleranceMode::Absolute,
+            64,
+            0,
+            None,
+        );
+        assert_eq!(bounded.unwrap().soln, unbounded.unwrap().soln);
+    }
+
+    #[test]
+    fn flat_slope_is_a_boundary_point_unless_it_is_a_root() {
+        // f'(z) = 3 z^2 vanishes at the origin, which is not a root.
+        let roots_of_unity = RootsOfUnityParams {
+            n_roots: 3,
+            newton_step_size: 1.0.into(),
+        };
+        for z0 in [Complex64::new(0.0, 0.0), Complex64::new(1e-13, 0.0)] {
+            assert_eq!(
+                newton_rhapson_iteration_sequence(
+                    &roots_of_unity,
+                    z0,
+                    1e-6,
+                    ToleranceMode::Absolute,
+                    64,
+                    2,
+                    None
+                )
+                .unwrap_err(),
+                NonConvergence::FlatSlope
+            );
+        }
+
+        // cosh(z) - 1 has a double root at the origin: converged already.
+        let cosh = CoshMinusOneParams {
+            newton_step_size: 1.0.into(),
+        };
+        let result = newton_rhapson_iteration_sequence(
+            &cosh,
+            Complex64::new(0.0, 0.0),
+            1e-6,
+            ToleranceMode::Absolute,
+            64,
+            2,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.iteration_count, 0);
+        assert_eq!(result.soln, Complex64::new(0.0, 0.0));
+    }
+
+    /// A view centered on the critical point of `cosh(z) - 1` at the origin
+    /// (with a pixel exactly on it) renders without any NaN values.
+    #[test]
+    fn critical_point_in_view_produces_no_nan_cells() {
+        let mut renderable = tileable_cosh_renderable();
+        renderable.params.tileable = false;
+        renderable.params.refinement_count = 2;
+        renderable.params.image_specification = ImageSpecification {
+            resolution: [21, 21],
+            center: [0.0, 0.0],
+            width: 1e-6,
+            pixel_aspect_ratio: 1.0,
+            min_width: None,
+            max_width: None,
+        };
+        let pixel_mapper = PixelMapper::new(&renderable.params.image_specification);
+        let mut converged_count = 0;
+        for i in 0..21 {
+            for j in 0..21 {
+                let (x, y) = pixel_mapper.map(&(i, j));
+                if let Some((value, _)) = renderable.evaluate([x, y]) {
+                    assert!(value.is_finite(), "NaN at ({x}, {y})");
+                    converged_count += 1;
+                }
+            }
+        }
+        assert_eq!(converged_count, 21 * 21);
+    }
+
+    /// A system written as the string `"z^3 - 1"` must follow the same Newton
+    /// iterates as the hard-coded roots of unity, and `roots` picks the index.
+    #[test]
+    fn expression_system_matches_hand_coded_roots_of_unity() {
+        let hand_coded = RootsOfUnityParams {
+            n_roots: 3,
+            newton_step_size: 1.0.into(),
+        };
+        let SystemType::Expression(expression) = serde_json::from_str(
+            r#"{ "Expression": {
+                "expression": "z^3 - 1",
+                "newton_step_size": 1.0,
+                "roots": [[1.0, 0.0], [-0.5, 0.8660254037844386], [-0.5, -0.8660254037844386]]
+            } }"#,
+        )
+        .unwrap() else {
+            panic!("expected Expression variant");
+        };
+
+        for z0 in [
+            Complex64::new(0.7, 0.4),
+            Complex64::new(-1.3, 0.2),
+            Complex64::new(-0.2, -0.9),
+        ] {
+            let expected = newton_rhapson_iteration_sequence(
+                &hand_coded,
+                z0,
+                1e-6,
+                ToleranceMode::Absolute,
+                64,
+                0,
+                None,
+            )
+            .unwrap();
+            let actual = newton_rhapson_iteration_sequence(
+                expression.as_ref(),
+                z0,
+                1e-6,
+                ToleranceMode::Absolute,
+                64,
+                0,
+                None,
+            )
+            .unwrap();
+            assert_eq!(actual.iteration_count, expected.iteration_count);
+            assert!((actual.soln - expected.soln).norm() < 1e-12);
+            assert_eq!(
+                expression.root_index(actual.soln),
+                hand_coded.root_index(expected.soln)
+            );
+        }
+    }
+
+    /// With `tileable` set, the row one pixel past the bottom edge must match
+    /// the top row, so stacked copies of the image line up seamlessly.
+    #[test]
+    fn tileable_cosh_minus_one_wraps_top_and_bottom_edges() {
+        let renderable = tileable_cosh_renderable();
+        let spec = renderable.params.image_specification;
+        let n_rows = spec.resolution[1];
+
+        // The snapped view spans a whole number of periods, including the
+        // wrap-around row.
+        let periods = spec.height() * (n_rows as f64) / ((n_rows - 1) as f64) / (2.0 * PI);
+        assert!((periods - periods.round()).abs() < 1e-9, "{periods}");
+        assert_eq!(spec.center, [0.3, 1.0]);
+
+        let mapper = PixelMapper::new(&spec);
+        for col in 0..spec.resolution[0] {
+            let x = mapper.width.map(col);
+            let top = renderable.evaluate([x, mapper.height.map(0)]);
+            let past_bottom = renderable.evaluate([x, mapper.height.map(n_rows)]);
+            match (top, past_bottom) {
+                (Some((top_value, top_index)), Some((wrapped_value, wrapped_index))) => {
+                    assert_eq!(top_index, wrapped_index);
+                    assert!((top_value - wrapped_value).abs() < 1e-3);
+                }
+                (None, None) => {}
+                mismatch => panic!("edge pixels differ in column {col}: {mismatch:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn tileable_rejects_non_periodic_systems_at_load() {
+        let params = serde_json::to_string(&tileable_cosh_renderable().params).unwrap();
+        let parse = |system: &str| {
+            serde_json::from_str::<NewtonsMethodParams>(&format!(
+                r#"{{ "params": {params}, "system": {system} }}"#
+            ))
+        };
+        assert!(parse(r#"{ "CoshMinusOne": { "newton_step_size": 1.0 } }"#).is_ok());
+        let message = parse(r#"{ "RootsOfUnity": { "n_roots": 3, "newton_step_size": 1.0 } }"#)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("periodic axis"), "{message}");
+    }
+
+    #[test]
+    fn basin_stats_tally_each_root_and_each_kind_of_non_convergence() {
+        let mut params = tileable_cosh_renderable().params;
+        params.tileable = false;
+        params.max_iteration_count = 3;
+        let mut renderable = NewtonsMethodRenderable::new(
+            params,
+            RootsOfUnityParams {
+                n_roots: 3,
+                newton_step_size: 1.0.into(),
+            },
+        );
+        let points = [
+            [1.1, 0.0],
+            [0.9, 0.1],
+            [-0.5, 0.9],
+            [-0.5, -0.9],
+            [0.01, 0.0],
+        ];
+        let expected: Vec<Result<u32, NonConvergence>> = points
+            .iter()
+            .map(|point| {
+                renderable
+                    .newton_rhapson_iteration_sequence(Complex64::new(point[0], point[1]))
+                    .map(|res| renderable.system.root_index(res.soln) as u32 % 3)
+            })
+            .collect();
+        assert_eq!(expected[0], expected[1]);
+        assert_ne!(expected[0], expected[2]);
+        assert!(expected[4].is_err());
+
+        let mut field = FieldBuffer::new([points.len(), 1]);
+        for (x, point) in points.into_iter().enumerate() {
+            field[[x, 0]] = renderable.evaluate(point);
+        }
+        renderable.record_field_statistics(&field, 1, 0);
+        for (index, &count) in renderable.basin_counts.by_color_map.iter().enumerate() {
+            let expected_count = expected.iter().filter(|&&e| e == Ok(index as u32)).count();
+            assert_eq!(count as usize, expected_count);
+        }
+        let counts = &renderable.basin_counts;
+        assert_eq!(counts.overlay + counts.empty, 1);
+
+        let mut diagnostics = Vec::new();
+        renderable.write_diagnostics(&mut diagnostics).unwrap();
+        let diagnostics = String::from_utf8(diagnostics).unwrap();
+        assert!(diagnostics.contains("total count: 5"), "{diagnostics}");
+        let first_root = expected[0].unwrap();
+        assert!(
+            diagnostics.contains(&format!("root (color map {first_root}): 2  (40.00%)")),
+            "{diagnostics}"
+        );
+
+        // The next render replaces the tallies rather than adding to them.
+        renderable.record_field_statistics(&field, 1, 0);
+        assert_eq!(renderable.basin_counts.total(), 5);
+    }
+
+    #[test]
+    fn complex_step_size_rotates_the_newton_step() {
+        let parse = |json: &str| -> RootsOfUnityParams { serde_json::from_str(json).unwrap() };
+        let real = parse(r#"{ "n_roots": 3, "newton_step_size": 1.0 }"#);
+        let rotated = parse(r#"{ "n_roots": 3, "newton_step_size": [0.8, 0.3] }"#);
+        assert_eq!(real.newton_step_size, NewtonStepSize::Real(1.0));
+        assert_eq!(
+            rotated.newton_step_size,
+            NewtonStepSize::Complex([0.8, 0.3])
+        );
+        assert_eq!(
+            serde_json::to_value(&rotated).unwrap()["newton_step_size"],
+            serde_json::json!([0.8, 0.3])
+        );
+
+        let z = Complex64::new(0.7, 0.4);
+        let full_step = z - real.newton_rhapson_step(z);
+        let rotated_step = z - rotated.newton_rhapson_step(z);
+        assert!((rotated_step - full_step * Complex64::new(0.8, 0.3)).norm() < 1e-12);
+
+        // The relaxed iteration still settles onto a root.
+        let result = newton_rhapson_iteration_sequence(
+            &rotated,
+            z,
+            1e-12,
+            ToleranceMode::Absolute,
+            256,
+            0,
+            None,
+        )
+        .unwrap();
+        assert!((result.soln.powi(3) - 1.0).norm() < 1e-5);
+    }
+
+    #[test]
+    fn roots_of_unity_need_one_color_map_per_root() {
+        let parse = |system: &str, color_map_count: usize| {
+            let color_maps = vec![
+                r#"[{ "query": 0.0, "rgb_raw": [9, 42, 27] }, { "query": 1.0, "rgb_raw": [0, 0, 0] }]"#;
+                color_map_count
+            ];
+            serde_json::from_str::<NewtonsMethodParams>(&format!(
+                r#"{{
+                    "params": {{
+                        "image_specification": {{ "resolution": [4, 4], "center": [0, 0], "width": 4 }},
+                        "max_iteration_count": 20,
+                        "convergence_tolerance": 1e-6,
+                        "render_options": {{ "sampling_level": 0 }},
+                        "color": {{ "background_color": [0, 0, 0], "color_maps": [{}] }},
+                        "lookup_table_count": 16,
+                        "histogram_bin_count": 16
+                    }},
+                    "system": {system}
+                }}"#,
+                color_maps.join(", ")
+            ))
+        };
+        let roots_of_unity = r#"{ "RootsOfUnity": { "n_roots": 3, "newton_step_size": 1.0 } }"#;
+        assert!(parse(roots_of_unity, 3).is_ok());
+        for color_map_count in [1, 2, 4] {
+            let message = parse(roots_of_unity, color_map_count)
+                .unwrap_err()
+                .to_string();
+            assert!(
+                message.contains("one color map per root"),
+                "{color_map_count}: {message}"
+            );
+        }
+        let no_roots = r#"{ "RootsOfUnity": { "n_roots": 0, "newton_step_size": 1.0 } }"#;
+        assert!(parse(no_roots, 0).is_err());
+
+        // cosh(z) - 1 has a root every 2 pi i, so any number of maps cycles.
+        let cosh = r#"{ "CoshMinusOne": { "newton_step_size": 1.0 } }"#;
+        for color_map_count in [1, 2, 5] {
+            assert!(parse(cosh, color_map_count).is_ok());
+        }
+    }
+
+    /// The roots of `cosh(z) - 1` repeat every `2πi`, so starting the same
+    /// offset from a root near the origin and one far from it takes the
+    /// same Newton steps. An absolute tolerance stops both at the same
+    /// absolute error; a relative one stops both at the same relative error,
+    /// which takes fewer steps far from the origin.
+    #[test]
+    fn relative_tolerance_scales_with_the_magnitude_of_the_root() {
+        let cosh = CoshMinusOneParams {
+            newton_step_size: 1.0.into(),
+        };
+        let solve = |k: f64, tolerance_mode| {
+            let root = Complex64::new(0.0, 2.0 * PI * k);
+            let result = newton_rhapson_iteration_sequence(
+                &cosh,
+                root + Complex64::new(0.3, 0.2),
+                1e-12,
+                tolerance_mode,
+                200,
+                0,
+                None,
+            )
+            .unwrap();
+            let error = (result.soln - root).norm();
+            (result.iteration_count, error, error / root.norm())
+        };
+
+        let (near_count, near_error, _) = solve(1.0, ToleranceMode::Absolute);
+        let (far_count, far_error, _) = solve(1e4, ToleranceMode::Absolute);
+        assert_eq!(near_count, far_count);
+        assert!((far_error / near_error - 1.0).abs() < 0.01);
+
+        let (near_count, _, near_relative_error) = solve(1.0, ToleranceMode::Relative);
+        let (far_count, _, far_relative_error) = solve(1e4, ToleranceMode::Relative);
+        assert!(far_count < near_count, "{far_count} vs {near_count}");
+        let ratio = far_relative_error / near_relative_error;
+        assert!((0.5..2.0).contains(&ratio), "{ratio}");
+        // Within the unit disk the relative test is the absolute one.
+        assert_eq!(
+            ToleranceMode::Relative.step_error(Complex64::new(0.5, 0.0), Complex64::new(0.25, 0.0)),
+            ToleranceMode::Absolute.step_error(Complex64::new(0.5, 0.0), Complex64::new(0.25, 0.0))
+        );
     }
 }
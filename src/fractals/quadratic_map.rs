@@ -1,11 +1,14 @@
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
 use crate::core::{
-    color_map::ColorPalette,
+    color_map::{
+        ChannelCurves, ColorEffect, ColorPalette, OVERLAY_COLOR_MAP_INDEX, ScalarNormalization,
+    },
     field_iteration::FieldKernel,
     image_utils::{
-        ImageSpecification, RenderOptions, Renderable, SpeedOptimizer,
+        ImageSpecification, PixelMapper, RenderOptions, Renderable, SpeedOptimizer,
         scale_down_parameter_for_speed,
     },
     interpolation::ClampedLinearInterpolator,
@@ -23,13 +26,202 @@ pub struct ColorMapParams {
     pub lookup_table_count: usize,
     /// Number of bins used by the histogram that drives color-map normalization.
     pub histogram_bin_count: usize,
+    /// How the smooth log-escape count maps onto the color map. Defaults to
+    /// histogram equalization; `Linear` / `Log` keep colors fixed while
+    /// panning, because they do not depend on what is currently on screen.
+    #[serde(default)]
+    pub normalization: ScalarNormalization,
+    /// Compute the histogram normalization once, from the first view
+    /// rendered at full quality, and keep it fixed afterward. In explore mode
+    /// this stops the colors from shifting as the view pans, at the cost of a
+    /// less even spread of the palette far from the starting view.
+    #[serde(default)]
+    pub fixed_histogram: bool,
+    /// Overlay the field lines of the escape-time potential: equipotential
+    /// curves (where the smooth escape count crosses an integer) and external
+    /// rays (where the argument of the escaped orbit crosses the real axis).
+    /// Together they trace the binary decomposition of the exterior. Best
+    /// with `refinement_count: 0` and a large escape radius.
+    #[serde(default)]
+    pub show_field_lines: bool,
+    /// Color of the field-line overlay.
+    #[serde(default = "default_field_line_color")]
+    pub field_line_color: [u8; 3],
+    /// Split the escaped points into this many sectors by the argument of
+    /// `Z` at escape, and tint each sector with its own hue on top of the
+    /// smooth escape-count shading. Two sectors give the classic binary
+    /// decomposition.
+    #[serde(default)]
+    pub angle_decomposition: Option<u32>,
+    /// How to color the points that never escape.
+    #[serde(default)]
+    pub interior_coloring: InteriorColoring,
+    /// Drive the escaped points' colors by independent red, green, and blue
+    /// curves of the escape value, in place of the first color map of
+    /// `color`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_curves: Option<ChannelCurves>,
+    /// Color effects (invert, solarize, posterize) applied in order to the
+    /// colors of every color map.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<ColorEffect>,
+    /// Quantize the escape value into this many equal bands before the color
+    /// map, for the classic iteration-band look: each band takes the color
+    /// at its start (see `ColorPaletteCache::set_band_count`). At least 1;
+    /// unset keeps the coloring smooth.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_band_count",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub banded: Option<u32>,
+    /// Fraction, in `[0, 1]`, of each band that blends into the next one,
+    /// softening the band edges into short gradients. 0 (the default) gives
+    /// hard bands and 1 the smooth coloring. Only used with `banded`.
+    #[serde(default, deserialize_with = "deserialize_band_softness")]
+    pub band_softness: f32,
+}
+
+fn default_field_line_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+/// Reject a `banded` count of zero when the params file is loaded.
+fn deserialize_band_count<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let band_count = Option::<u32>::deserialize(deserializer)?;
+    if band_count == Some(0) {
+        return Err(serde::de::Error::custom("banded must be at least 1"));
+    }
+    Ok(band_count)
 }
 
+/// Reject a `band_softness` outside `[0, 1]` when the params file is loaded.
+fn deserialize_band_softness<'de, D>(deserializer: D) -> Result<f32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let band_softness = f32::deserialize(deserializer)?;
+    if !(0.0..=1.0).contains(&band_softness) {
+        return Err(serde::de::Error::custom(format!(
+            "band_softness must be in [0, 1], got {band_softness}"
+        )));
+    }
+    Ok(band_softness)
+}
+
+/// Coloring of the points inside the set (the bounded orbits).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InteriorColoring {
+    /// Flat background color.
+    #[default]
+    Background,
+    /// Color by the attracting cycle that the orbit settles onto: the
+    /// period selects one of the color maps after the first (cycling through
+    /// them, or the first map if there is only one), shaded by the magnitude
+    /// of the cycle's multiplier. Orbits that have not settled by
+    /// `max_iter_count` keep the background color.
+    CyclePeriod,
+}
+
+/// Longest attracting cycle that `InteriorColoring::CyclePeriod` looks for.
+const MAX_CYCLE_PERIOD: u32 = 64;
+
+/// An orbit has returned to its starting point, closing the cycle, once it
+/// is within this distance.
+const CYCLE_TOLERANCE: f64 = 1e-6;
+
+/// Half-width of a field line, as a fraction of the spacing between
+/// neighboring lines.
+const FIELD_LINE_HALF_WIDTH: f64 = 0.04;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "ConvergenceParamsFile")]
 pub struct ConvergenceParams {
     pub escape_radius_squared: f64,
     pub max_iter_count: u32,
+    /// Extra iterations after the escape, to smooth the escape count. `0`
+    /// gives integer (banded) counts. A params file may also give `"auto"`,
+    /// which picks a count from the escape radius when the file is loaded;
+    /// see `auto_refinement_count`.
     pub refinement_count: u32,
+    /// Clifford Pickover's "biomorph" mode: the orbit escapes as soon as
+    /// either the real or the imaginary part leaves the escape radius, and
+    /// points whose other component is still inside the radius at that
+    /// moment form the cell-like "body" of the biomorph. The body is colored
+    /// by the final component values (see `biomorph_body_cell`), while the
+    /// rest of the image keeps its escape-time coloring. Larger escape radii
+    /// (e.g. `escape_radius_squared: 100`) give the classic shapes.
+    #[serde(default)]
+    pub biomorph: bool,
+}
+
+/// `ConvergenceParams` as written in a params file, before an `"auto"`
+/// refinement count is resolved.
+#[derive(Deserialize)]
+struct ConvergenceParamsFile {
+    escape_radius_squared: f64,
+    max_iter_count: u32,
+    refinement_count: RefinementCount,
+    #[serde(default)]
+    biomorph: bool,
+}
+
+/// A refinement count given either as a number or as `"auto"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RefinementCount {
+    Count(u32),
+    Auto(AutoKeyword),
+}
+
+#[derive(Deserialize)]
+enum AutoKeyword {
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+impl TryFrom<ConvergenceParamsFile> for ConvergenceParams {
+    type Error = String;
+
+    fn try_from(file: ConvergenceParamsFile) -> Result<Self, Self::Error> {
+        let refinement_count = match file.refinement_count {
+            RefinementCount::Count(count) => count,
+            RefinementCount::Auto(AutoKeyword::Auto) => {
+                auto_refinement_count(file.escape_radius_squared)?
+            }
+        };
+        Ok(ConvergenceParams {
+            escape_radius_squared: file.escape_radius_squared,
+            max_iter_count: file.max_iter_count,
+            refinement_count,
+            biomorph: file.biomorph,
+        })
+    }
+}
+
+/// Radius squared that the refined orbit should reach before the smooth
+/// escape count is read off: `|z| = 1000`.
+const REFINED_RADIUS_SQUARED: f64 = 1e6;
+
+/// The smooth escape count `n - log2(ln |z|)` assumes that `|z|` is so large
+/// that the constant term of `z^2 + c` no longer matters; its error falls
+/// off like `1 / |z|^2`. Each refinement step squares `|z|`, so starting
+/// from the escape radius `R`, `k` steps reach `R^(2^k)`. The heuristic
+/// picks the fewest steps that carry the orbit past `|z| = 1000`, which is
+/// well below one color-map step of error: 4 steps for the classic
+/// `escape_radius_squared: 4`, 2 for `100`. At least one step is always
+/// taken, since `0` turns the smoothing off.
+pub fn auto_refinement_count(escape_radius_squared: f64) -> Result<u32, String> {
+    if !(escape_radius_squared.is_finite() && escape_radius_squared > 1.0) {
+        return Err(format!(
+            "`refinement_count: \"auto\"` needs an `escape_radius_squared` greater than one, found {escape_radius_squared}"
+        ));
+    }
+    let growth = REFINED_RADIUS_SQUARED.ln() / escape_radius_squared.ln();
+    Ok(growth.log2().ceil().max(1.0) as u32)
 }
 
 /**
@@ -50,7 +242,7 @@ impl QuadraticMapSequence {
     /// Implements the equation:  Z := Z*Z + C, where both Z and C are imaginary numbers
     /// point:  initial value for "Z" in the above equation
     /// constant_term:  initial value for "C" in the above equation
-    fn new(point: &[f64; 2], constant_term: &[f64; 2]) -> QuadraticMapSequence {
+    pub fn new(point: &[f64; 2], constant_term: &[f64; 2]) -> QuadraticMapSequence {
         let mut value = QuadraticMapSequence {
             x0: constant_term[0],
             y0: constant_term[1],
@@ -64,7 +256,8 @@ impl QuadraticMapSequence {
         value
     }
 
-    fn radius_squared(&self) -> f64 {
+    /// Squared magnitude of the current point `z`.
+    pub fn radius_squared(&self) -> f64 {
         self.x_sqr + self.y_sqr
     }
 
@@ -77,9 +270,9 @@ impl QuadraticMapSequence {
         (iter_count - 1.0).ln()
     }
 
-    // Z = Z*Z + C
+    /// Advance one iteration of `z = z*z + c`.
     // Note:  This implementation is somewhat faster than the directly writing the above equation with the `Complex` number type.
-    fn step(&mut self) {
+    pub fn step(&mut self) {
         self.y = (self.x + self.x) * self.y + self.y0;
         self.x = self.x_sqr - self.y_sqr + self.x0;
         self.x_sqr = self.x * self.x;
@@ -87,18 +280,81 @@ impl QuadraticMapSequence {
         self.iter_count += 1;
     }
 
+    /// Step once, showing the current point to `observer` first.
+    #[inline]
+    fn step_observed(&mut self, observer: &mut impl OrbitObserver) {
+        observer.observe(self.x, self.y);
+        self.step();
+    }
+
     // @return: true -- escaped! false --> did not escape
     // @return: true if the point escapes, false otherwise.
-    fn step_until_condition(&mut self, max_iter_count: u32, max_radius_squared: f64) -> bool {
+    fn step_until_condition(
+        &mut self,
+        max_iter_count: u32,
+        max_radius_squared: f64,
+        observer: &mut impl OrbitObserver,
+    ) -> bool {
         while self.iter_count < max_iter_count {
             if self.radius_squared() > max_radius_squared {
                 return true;
             }
-            self.step();
+            self.step_observed(observer);
         }
         false
     }
 
+    /// Biomorph variant of `step_until_condition`: the escape test is applied
+    /// to each component separately, rather than to the magnitude of Z.
+    /// @return: true if either component escapes, false otherwise.
+    fn step_until_component_escape(
+        &mut self,
+        max_iter_count: u32,
+        max_radius_squared: f64,
+        observer: &mut impl OrbitObserver,
+    ) -> bool {
+        while self.iter_count < max_iter_count {
+            if self.x_sqr > max_radius_squared || self.y_sqr > max_radius_squared {
+                return true;
+            }
+            self.step_observed(observer);
+        }
+        false
+    }
+
+    /// Look for the attracting cycle that a bounded orbit has settled onto,
+    /// by stepping until the orbit returns to its current point. Returns
+    /// `None` if it does not within `MAX_CYCLE_PERIOD` steps.
+    fn attracting_cycle(&mut self, escape_radius_squared: f64) -> Option<AttractingCycle> {
+        if self.radius_squared() > escape_radius_squared {
+            return None;
+        }
+        let start = [self.x, self.y];
+        let mut multiplier = [1.0, 0.0];
+        for period in 1..=MAX_CYCLE_PERIOD {
+            // The multiplier is the product of d(Z*Z + C)/dZ = 2 Z around the cycle.
+            let [mx, my] = multiplier;
+            multiplier = [
+                2.0 * (self.x * mx - self.y * my),
+                2.0 * (self.x * my + self.y * mx),
+            ];
+            self.step();
+            let [dx, dy] = [self.x - start[0], self.y - start[1]];
+            if dx * dx + dy * dy < CYCLE_TOLERANCE * CYCLE_TOLERANCE {
+                return Some(AttractingCycle {
+                    period,
+                    multiplier: multiplier[0].hypot(multiplier[1]),
+                });
+            }
+        }
+        None
+    }
+
+    /// After a component escape: true if the other component is still bounded.
+    fn in_biomorph_body(&self, max_radius_squared: f64) -> bool {
+        self.x_sqr < max_radius_squared || self.y_sqr < max_radius_squared
+    }
+
     /**
      * @return: natural log of the normalized iteration count (if escaped), or unset optional.
      */
@@ -107,11 +363,12 @@ impl QuadraticMapSequence {
         max_iter_count: u32,
         max_radius_squared: f64,
         refinement_count: u32,
+        observer: &mut impl OrbitObserver,
     ) -> Option<f32> {
         use std::f64;
-        let _ = self.step_until_condition(max_iter_count, max_radius_squared);
+        let _ = self.step_until_condition(max_iter_count, max_radius_squared, observer);
         for _ in 0..refinement_count {
-            self.step();
+            self.step_observed(observer);
         }
         const SCALE: f64 = 1.0 / std::f64::consts::LN_2;
         let normalized_iteration_count =
@@ -124,35 +381,297 @@ impl QuadraticMapSequence {
         }
     }
 
-    /// Test whether a point is in the mandelbrot set.
-    /// @param test_point: a point in the complex plane to test
-    /// @param escape_radius_squared: a point is not in the mandelbrot set if it exceeds this radius squared from the origin during the mandelbrot iteration sequence.
-    /// @param max_iter_count: assume that a point is in the mandelbrot set if this number of iterations is reached without exceeding the escape radius.
-    /// @param refinement_count: normalize the escape count, providing smooth interpolation between integer "escape count" values.
-    /// @return: normalized (smooth) iteration count if the point escapes, otherwise None().
-    pub fn normalized_log_escape_count(
+    /// Iterate the map from `test_point` until the orbit escapes or
+    /// `max_iter_count` is reached, and report how it ended.
+    /// @param test_point: initial value for "Z".
+    /// @param constant_term: value of "C".
+    /// @param convergence_params: escape radius, iteration limit, and the refinement that smooths the escape count.
+    /// @param tracking: which of the optional `EscapeResult` fields to compute, and whether a bounded orbit looks for its attracting cycle; the rest cost nothing.
+    /// @param pixel_term: which of `test_point` and `constant_term` is the pixel, i.e. what the derivative is taken with respect to.
+    /// @return: `Escaped` with the smooth escape count and final state, `Cycle` if the orbit stayed bounded and `tracking.interior_cycle` found its attracting cycle, `Bounded` otherwise.
+    pub fn orbit_result(
         test_point: &[f64; 2],
         constant_term: &[f64; 2],
         convergence_params: &ConvergenceParams,
-    ) -> Option<f32> {
+        tracking: EscapeTracking,
+        pixel_term: PixelTerm,
+    ) -> OrbitResult {
+        let bounded = |sequence: &mut QuadraticMapSequence| {
+            if !tracking.interior_cycle {
+                return OrbitResult::Bounded;
+            }
+            sequence
+                .attracting_cycle(convergence_params.escape_radius_squared)
+                .map_or(OrbitResult::Bounded, OrbitResult::Cycle)
+        };
+
+        let not_escaped = |sequence: &mut QuadraticMapSequence, end| match end {
+            OrbitEnd::BiomorphBody => OrbitResult::BiomorphBody([sequence.x, sequence.y]),
+            OrbitEnd::Escaped | OrbitEnd::Bounded => bounded(sequence),
+        };
+
         let mut escape_sequence = QuadraticMapSequence::new(test_point, constant_term);
+        if !tracking.derivative && tracking.trap_point.is_none() {
+            return match escape_sequence.escape_count(convergence_params, &mut ()) {
+                Ok(smooth_count) => OrbitResult::Escaped(escape_sequence.result(
+                    smooth_count,
+                    [0.0, 0.0],
+                    f64::INFINITY,
+                )),
+                Err(end) => not_escaped(&mut escape_sequence, end),
+            };
+        }
 
-        if convergence_params.refinement_count == 0 {
-            if escape_sequence.step_until_condition(
-                convergence_params.max_iter_count,
-                convergence_params.escape_radius_squared,
-            ) {
-                return Some(Self::log_iter_count(escape_sequence.iter_count as f32));
-            } else {
-                return None;
-            }
+        let mut tracker = OrbitTracker::new(tracking, pixel_term);
+        // `new` takes the first step, so the initial point is observed here.
+        tracker.observe(test_point[0], test_point[1]);
+        let smooth_count = match escape_sequence.escape_count(convergence_params, &mut tracker) {
+            Ok(smooth_count) => smooth_count,
+            Err(end) => return not_escaped(&mut escape_sequence, end),
+        };
+        tracker.update_trap_distance(escape_sequence.x, escape_sequence.y);
+        OrbitResult::Escaped(escape_sequence.result(
+            smooth_count,
+            tracker.derivative,
+            tracker.min_trap_distance_squared.sqrt(),
+        ))
+    }
+
+    fn result(
+        &self,
+        smooth_count: f32,
+        derivative: [f64; 2],
+        min_trap_distance: f64,
+    ) -> EscapeResult {
+        EscapeResult {
+            smooth_count,
+            iter_count: self.iter_count,
+            final_z: [self.x, self.y],
+            derivative,
+            min_trap_distance,
         }
+    }
 
-        escape_sequence.compute_normalized_log_escape(
+    /// Run the escape-time iteration, leaving the sequence at its final
+    /// (escaped and refined) state. Returns the smooth escape count, or how
+    /// the orbit ended if it did not escape.
+    fn escape_count(
+        &mut self,
+        convergence_params: &ConvergenceParams,
+        observer: &mut impl OrbitObserver,
+    ) -> Result<f32, OrbitEnd> {
+        match self.escape(convergence_params, observer) {
+            OrbitEnd::Escaped => {}
+            end => return Err(end),
+        }
+        // A component escape implies `|Z| > radius`, so the refinement below
+        // also picks up right where the biomorph test stopped.
+        if convergence_params.refinement_count == 0 {
+            return Ok(Self::log_iter_count(self.iter_count as f32));
+        }
+        self.compute_normalized_log_escape(
             convergence_params.max_iter_count,
             convergence_params.escape_radius_squared,
             convergence_params.refinement_count,
+            observer,
         )
+        .ok_or(OrbitEnd::Bounded)
+    }
+
+    /// Iterate until the orbit escapes, it lands in the body of a biomorph,
+    /// or `max_iter_count` is reached.
+    fn escape(
+        &mut self,
+        convergence_params: &ConvergenceParams,
+        observer: &mut impl OrbitObserver,
+    ) -> OrbitEnd {
+        let escaped = if convergence_params.biomorph {
+            let escaped = self.step_until_component_escape(
+                convergence_params.max_iter_count,
+                convergence_params.escape_radius_squared,
+                observer,
+            );
+            if escaped && self.in_biomorph_body(convergence_params.escape_radius_squared) {
+                return OrbitEnd::BiomorphBody;
+            }
+            escaped
+        } else {
+            self.step_until_condition(
+                convergence_params.max_iter_count,
+                convergence_params.escape_radius_squared,
+                observer,
+            )
+        };
+        if escaped {
+            OrbitEnd::Escaped
+        } else {
+            OrbitEnd::Bounded
+        }
+    }
+}
+
+/// How `QuadraticMapSequence::escape` stopped iterating.
+enum OrbitEnd {
+    Escaped,
+    BiomorphBody,
+    Bounded,
+}
+
+/// Everything the coloring can learn from one escaping orbit. The
+/// `derivative` and `min_trap_distance` fields cost extra work on every
+/// iteration, so they are only computed when requested through
+/// `EscapeTracking`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EscapeResult {
+    /// Natural log of the normalized (smooth) escape count.
+    pub smooth_count: f32,
+    /// Number of iterations taken to reach `final_z`.
+    pub iter_count: u32,
+    /// Z at the end of the iteration: after escaping, and after any
+    /// refinement steps.
+    pub final_z: [f64; 2],
+    /// Derivative of `final_z` with respect to the pixel coordinate (see
+    /// `PixelTerm`). Zero unless tracked.
+    pub derivative: [f64; 2],
+    /// Smallest distance between the orbit and the trap point. Infinite
+    /// unless tracked.
+    pub min_trap_distance: f64,
+}
+
+impl EscapeResult {
+    /// Argument of `final_z`, in radians.
+    pub fn angle(&self) -> f64 {
+        self.final_z[1].atan2(self.final_z[0])
+    }
+
+    /// The potential is (up to a constant) `n - log2(ln|Z|)`, which crosses
+    /// an integer once per iteration, and the argument of `Z` doubles with
+    /// each iteration, so thresholding both gives a grid of equipotentials
+    /// and external rays. Each refinement step doubles the number of rays, so
+    /// the grid is clearest with a large escape radius and no refinement.
+    fn on_field_line(&self) -> bool {
+        let near_integer = |value: f64| (value - value.round()).abs() < FIELD_LINE_HALF_WIDTH;
+        let [x, y] = self.final_z;
+        let potential = (self.iter_count as f64) - (x * x + y * y).sqrt().ln().log2();
+        // Two rays per turn: the positive and negative real axis.
+        let ray_phase = self.angle() / std::f64::consts::PI;
+        near_integer(potential) || near_integer(ray_phase)
+    }
+}
+
+/// Attracting cycle of a bounded orbit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttractingCycle {
+    /// Number of points on the cycle.
+    pub period: u32,
+    /// Magnitude of the product of `2 Z` around the cycle: below one for an
+    /// attracting cycle, and zero for a superattracting one.
+    pub multiplier: f64,
+}
+
+/// How an orbit ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrbitResult {
+    /// Left the escape radius.
+    Escaped(EscapeResult),
+    /// Stayed bounded and settled onto an attracting cycle. Only reported
+    /// when `EscapeTracking::interior_cycle` is set.
+    Cycle(AttractingCycle),
+    /// One component left the escape radius while the other was still
+    /// inside it, in biomorph mode. Holds Z at that iteration.
+    BiomorphBody([f64; 2]),
+    /// Stayed bounded for `max_iter_count` iterations.
+    Bounded,
+}
+
+impl OrbitResult {
+    /// The escape result, or `None` if the orbit stayed bounded.
+    pub fn escaped(self) -> Option<EscapeResult> {
+        match self {
+            OrbitResult::Escaped(result) => Some(result),
+            OrbitResult::Cycle(_) | OrbitResult::BiomorphBody(_) | OrbitResult::Bounded => None,
+        }
+    }
+}
+
+/// Selects the optional fields of `EscapeResult` to compute.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EscapeTracking {
+    /// Track the derivative of the orbit with respect to the pixel.
+    pub derivative: bool,
+    /// Track the closest approach of the orbit to this point.
+    pub trap_point: Option<[f64; 2]>,
+    /// Look for the attracting cycle of orbits that do not escape.
+    pub interior_cycle: bool,
+}
+
+/// The term of `Z := Z*Z + C` that is set by the pixel coordinate, which is
+/// what `EscapeResult::derivative` is taken with respect to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelTerm {
+    /// Mandelbrot: the pixel is `C`, and `dZ/dC` starts at zero.
+    ConstantTerm,
+    /// Julia: the pixel is the initial `Z`, and `dZ/dZ0` starts at one.
+    InitialPoint,
+}
+
+/// Sees each point of the orbit just before it is stepped.
+trait OrbitObserver {
+    fn observe(&mut self, x: f64, y: f64);
+}
+
+/// The untracked escape count observes nothing.
+impl OrbitObserver for () {
+    #[inline]
+    fn observe(&mut self, _x: f64, _y: f64) {}
+}
+
+/// Accumulates the optional fields of `EscapeResult` along the orbit.
+struct OrbitTracker {
+    tracking: EscapeTracking,
+    derivative: [f64; 2],
+    /// `dC / d(pixel)`: one for the Mandelbrot set, zero for Julia sets.
+    derivative_increment: f64,
+    min_trap_distance_squared: f64,
+}
+
+impl OrbitTracker {
+    fn new(tracking: EscapeTracking, pixel_term: PixelTerm) -> OrbitTracker {
+        let (derivative, derivative_increment) = match pixel_term {
+            PixelTerm::ConstantTerm => ([0.0, 0.0], 1.0),
+            PixelTerm::InitialPoint => ([1.0, 0.0], 0.0),
+        };
+        OrbitTracker {
+            tracking,
+            derivative: if tracking.derivative {
+                derivative
+            } else {
+                [0.0, 0.0]
+            },
+            derivative_increment,
+            min_trap_distance_squared: f64::INFINITY,
+        }
+    }
+
+    fn update_trap_distance(&mut self, x: f64, y: f64) {
+        if let Some([trap_x, trap_y]) = self.tracking.trap_point {
+            let distance_squared = (x - trap_x) * (x - trap_x) + (y - trap_y) * (y - trap_y);
+            self.min_trap_distance_squared = self.min_trap_distance_squared.min(distance_squared);
+        }
+    }
+}
+
+impl OrbitObserver for OrbitTracker {
+    fn observe(&mut self, x: f64, y: f64) {
+        if self.tracking.derivative {
+            // dZ := 2 Z dZ + dC
+            let [dx, dy] = self.derivative;
+            self.derivative = [
+                2.0 * (x * dx - y * dy) + self.derivative_increment,
+                2.0 * (x * dy + y * dx),
+            ];
+        }
+        self.update_trap_distance(x, y);
     }
 }
 
@@ -182,8 +701,14 @@ pub trait QuadraticMapParams: Serialize + Clone + Debug + Sync + Send {
     fn render_options(&self) -> &RenderOptions;
     fn render_options_mut(&mut self) -> &mut RenderOptions;
 
-    /// Evaluate the smooth log-escape count at the given point.
-    fn normalized_log_escape_count(&self, point: &[f64; 2]) -> Option<f32>;
+    /// Iterate the map from the given point, computing the optional fields
+    /// of the result requested by `tracking`.
+    fn orbit_result(&self, point: &[f64; 2], tracking: EscapeTracking) -> OrbitResult;
+
+    /// The result for an escaping orbit, or `None` if it stays bounded.
+    fn escape_result(&self, point: &[f64; 2], tracking: EscapeTracking) -> Option<EscapeResult> {
+        self.orbit_result(point, tracking).escaped()
+    }
 }
 
 /// Reference cache used by `SpeedOptimizer` to interpolate runtime
@@ -219,10 +744,94 @@ impl<T: QuadraticMapParams> SpeedOptimizer for T {
 
 impl<T: QuadraticMapParams> FieldKernel for T {
     fn evaluate(&self, point: [f64; 2]) -> Option<(f32, u32)> {
-        self.normalized_log_escape_count(&point).map(|v| (v, 0))
+        let color_map_params = self.color_map_params();
+        let tracking = EscapeTracking {
+            interior_cycle: color_map_params.interior_coloring == InteriorColoring::CyclePeriod,
+            ..EscapeTracking::default()
+        };
+        let result = match self.orbit_result(&point, tracking) {
+            OrbitResult::Escaped(result) => result,
+            OrbitResult::Cycle(cycle) => return Some(interior_cycle_cell(self, cycle)),
+            OrbitResult::BiomorphBody(final_z) => return Some(biomorph_body_cell(self, final_z)),
+            OrbitResult::Bounded => return None,
+        };
+        let value = result.smooth_count;
+        if color_map_params.show_field_lines && result.on_field_line() {
+            return Some((value, OVERLAY_COLOR_MAP_INDEX));
+        }
+        // Route sector `k` to `(k + 1) * n + 0`, which the color cache draws
+        // with color map 0 and the hue of sector `k`. Interior cells keep
+        // indices below `n`, so they are not tinted.
+        let tint_band = color_map_params
+            .angle_decomposition
+            .map_or(0, |sector_count| {
+                1 + angle_sector(result.angle(), sector_count)
+            });
+        Some((
+            value,
+            tint_band * color_map_params.color.color_maps.len() as u32,
+        ))
     }
 }
 
+/// Field cell for an interior point that settled onto `cycle`; see
+/// `InteriorColoring::CyclePeriod`. The multiplier is scaled onto the range
+/// of the escape counts, so it shares their normalization.
+fn interior_cycle_cell<T: QuadraticMapParams>(params: &T, cycle: AttractingCycle) -> (f32, u32) {
+    let interior_map_count = params.color_map_params().color.color_maps.len() as u32 - 1;
+    let color_map_index = if interior_map_count == 0 {
+        0
+    } else {
+        1 + (cycle.period - 1) % interior_map_count
+    };
+    let value = (cycle.multiplier as f32).min(1.0) * params.histogram_max_value();
+    (value, color_map_index)
+}
+
+/// Field cell for a point in the body of a biomorph, colored by the final
+/// component values: the value is the magnitude of the component that
+/// stayed inside the escape radius, as a fraction of that radius, scaled
+/// onto the range of the escape counts. The body is drawn with the second
+/// color map when there is one, so it gets its own histogram.
+fn biomorph_body_cell<T: QuadraticMapParams>(params: &T, final_z: [f64; 2]) -> (f32, u32) {
+    let radius = params.convergence_params().escape_radius_squared.sqrt();
+    let inside_component = final_z[0].abs().min(final_z[1].abs());
+    let fraction = (inside_component / radius).min(1.0) as f32;
+    let color_map_index = (params.color_map_params().color.color_maps.len() > 1) as u32;
+    (fraction * params.histogram_max_value(), color_map_index)
+}
+
+/// Smooth escape count at the center of every pixel of the image, computed
+/// in parallel by row as `(pixel_coordinate, escape_count)` without
+/// allocating a field buffer. Points that do not escape yield `None`. Lets
+/// downstream code fold its own statistics over the raw scalar field or feed
+/// it into a custom color pipeline; no anti-aliasing or colorization is
+/// applied.
+pub fn par_escape_counts<T: QuadraticMapParams>(
+    params: &T,
+) -> impl ParallelIterator<Item = ([u32; 2], Option<f32>)> + '_ {
+    let spec = QuadraticMapParams::image_specification(params);
+    let pixel_mapper = PixelMapper::new(spec);
+    let [width, height] = spec.resolution;
+    (0..height).into_par_iter().flat_map_iter(move |y| {
+        let pixel_mapper = pixel_mapper.clone();
+        (0..width).map(move |x| {
+            let (re, im) = pixel_mapper.map(&(x, y));
+            let escape_count = params
+                .escape_result(&[re, im], EscapeTracking::default())
+                .map(|result| result.smooth_count);
+            ([x, y], escape_count)
+        })
+    })
+}
+
+/// Index of the sector, out of `sector_count` equal sectors starting at the
+/// positive real axis, that contains `angle`.
+fn angle_sector(angle: f64, sector_count: u32) -> u32 {
+    let turns = (angle / std::f64::consts::TAU).rem_euclid(1.0);
+    ((turns * sector_count as f64) as u32).min(sector_count.saturating_sub(1))
+}
+
 impl<T: QuadraticMapParams> Renderable for T {
     type Params = T;
 
@@ -265,4 +874,480 @@ impl<T: QuadraticMapParams> Renderable for T {
     fn lookup_table_count(&self) -> usize {
         self.color_map_params().lookup_table_count
     }
+
+    fn scalar_normalization(&self) -> ScalarNormalization {
+        self.color_map_params().normalization
+    }
+
+    fn hold_cdfs_fixed(&self) -> bool {
+        self.color_map_params().fixed_histogram
+    }
+
+    fn counts_escapes(&self) -> bool {
+        self.color_map_params().interior_coloring == InteriorColoring::Background
+    }
+
+    fn overlay_color(&self) -> Option<[u8; 3]> {
+        let color_map_params = self.color_map_params();
+        color_map_params
+            .show_field_lines
+            .then_some(color_map_params.field_line_color)
+    }
+
+    fn sector_count(&self) -> Option<u32> {
+        self.color_map_params()
+            .angle_decomposition
+            .filter(|&sector_count| sector_count > 0)
+    }
+
+    fn channel_curves(&self) -> Option<ChannelCurves> {
+        self.color_map_params().channel_curves.clone()
+    }
+
+    fn band_count(&self) -> Option<u32> {
+        self.color_map_params().banded
+    }
+
+    fn band_softness(&self) -> f32 {
+        self.color_map_params().band_softness
+    }
+
+    fn color_effects(&self) -> Vec<ColorEffect> {
+        self.color_map_params().effects.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::color_map::{ColorMap, ColorMapKeyFrame};
+    use crate::fractals::{julia::JuliaParams, mandelbrot::MandelbrotParams};
+
+    fn mandelbrot_escape(
+        c: &[f64; 2],
+        params: &ConvergenceParams,
+        tracking: EscapeTracking,
+    ) -> Option<EscapeResult> {
+        QuadraticMapSequence::orbit_result(
+            &[0.0, 0.0],
+            c,
+            params,
+            tracking,
+            PixelTerm::ConstantTerm,
+        )
+        .escaped()
+    }
+
+    fn julia_escape(
+        z: &[f64; 2],
+        c: &[f64; 2],
+        params: &ConvergenceParams,
+        tracking: EscapeTracking,
+    ) -> Option<EscapeResult> {
+        QuadraticMapSequence::orbit_result(z, c, params, tracking, PixelTerm::InitialPoint)
+            .escaped()
+    }
+
+    /// Small Mandelbrot view, colored by one black-to-white color map, for
+    /// tests to adjust.
+    fn test_mandelbrot_params() -> MandelbrotParams {
+        serde_json::from_str(
+            r#"{
+                "image_specification": {
+                    "resolution": [9, 7],
+                    "center": [-0.5, 0.0],
+                    "width": 3.0
+                },
+                "convergence_params": {
+                    "escape_radius_squared": 16.0,
+                    "max_iter_count": 64,
+                    "refinement_count": 1
+                },
+                "color_map": {
+                    "color": {
+                        "background_color": [0, 0, 0],
+                        "color_maps": [[
+                            { "query": 0.0, "rgb_raw": [0, 0, 0] },
+                            { "query": 1.0, "rgb_raw": [255, 255, 255] }
+                        ]]
+                    },
+                    "lookup_table_count": 16,
+                    "histogram_bin_count": 16
+                },
+                "render_options": { "sampling_level": 0 }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    /// `test_mandelbrot_params`, as the Julia set of `constant_term`.
+    fn test_julia_params(constant_term: [f64; 2]) -> JuliaParams {
+        let MandelbrotParams {
+            image_specification,
+            convergence_params,
+            color_map,
+            render_options,
+        } = test_mandelbrot_params();
+        JuliaParams {
+            image_specification,
+            constant_term,
+            convergence_params,
+            color_map,
+            render_options,
+        }
+    }
+
+    /// Two-keyframe color map from `start` to `end`.
+    fn gradient(start: [u8; 3], end: [u8; 3]) -> ColorMap {
+        [(0.0, start), (1.0, end)]
+            .map(|(query, rgb_raw)| ColorMapKeyFrame { query, rgb_raw })
+            .to_vec()
+    }
+
+    fn convergence_params(biomorph: bool, refinement_count: u32) -> ConvergenceParams {
+        ConvergenceParams {
+            escape_radius_squared: 100.0,
+            max_iter_count: 64,
+            refinement_count,
+            biomorph,
+        }
+    }
+
+    /// Points whose orbit leaves along one axis while the other component
+    /// stays small belong to the biomorph body; points whose components both
+    /// grow keep the usual escape count.
+    #[test]
+    fn biomorph_mode_masks_the_body_and_keeps_the_escape_count_elsewhere() {
+        let constant_term = [0.5, 0.1];
+        let escape_count = |point: &[f64; 2], biomorph, refinement_count| {
+            julia_escape(
+                point,
+                &constant_term,
+                &convergence_params(biomorph, refinement_count),
+                EscapeTracking::default(),
+            )
+            .map(|result| result.smooth_count)
+        };
+
+        // Only the real part leaves the radius; the imaginary part is small.
+        let body_point = [-1.0, -0.3];
+        assert!(escape_count(&body_point, false, 0).is_some());
+        assert_eq!(escape_count(&body_point, true, 0), None);
+        let OrbitResult::BiomorphBody([x, y]) = QuadraticMapSequence::orbit_result(
+            &body_point,
+            &constant_term,
+            &convergence_params(true, 0),
+            EscapeTracking::default(),
+            PixelTerm::InitialPoint,
+        ) else {
+            panic!("expected the body of the biomorph");
+        };
+        assert!(x.abs() > 10.0 && y.abs() < 10.0, "{x}, {y}");
+
+        // Both components are outside the radius when the orbit escapes.
+        let outside_point = [-1.1, -0.1];
+        // A component outside the radius puts `|Z|` outside it too, so the
+        // component test is stricter than the magnitude test and the
+        // biomorph escape can only come at the same step or later.
+        for refinement_count in [0, 2] {
+            let biomorph = escape_count(&outside_point, true, refinement_count).unwrap();
+            let standard = escape_count(&outside_point, false, refinement_count).unwrap();
+            assert!(biomorph >= standard, "{biomorph} < {standard}");
+        }
+
+        // Bounded orbits stay in the set in both modes.
+        let bounded_point = [0.0, 0.0];
+        let constant_term = [-0.1, 0.1];
+        for biomorph in [false, true] {
+            assert_eq!(
+                julia_escape(
+                    &bounded_point,
+                    &constant_term,
+                    &convergence_params(biomorph, 0),
+                    EscapeTracking::default(),
+                ),
+                None
+            );
+        }
+    }
+
+    /// Orbits that stay on the positive real axis lie on an external ray.
+    #[test]
+    fn field_lines_follow_the_real_axis() {
+        let params = ConvergenceParams {
+            escape_radius_squared: 1e4,
+            max_iter_count: 64,
+            refinement_count: 0,
+            biomorph: false,
+        };
+        let query = |c: [f64; 2]| mandelbrot_escape(&c, &params, EscapeTracking::default());
+
+        assert!(query([1.0, 0.0]).unwrap().on_field_line());
+
+        // Off the axis, only a thin fraction of points lie on a line.
+        let on_line_count = (0..100)
+            .map(|i| [0.5 + 0.01 * i as f64, 0.7])
+            .filter(|&c| query(c).unwrap().on_field_line())
+            .count();
+        assert!(on_line_count > 0 && on_line_count < 50, "{on_line_count}");
+
+        assert_eq!(query([0.0, 0.0]), None);
+    }
+
+    /// The tracked derivative matches a finite difference with respect to the
+    /// pixel, for both choices of pixel term, and tracking leaves the rest of
+    /// the result unchanged.
+    #[test]
+    fn tracked_derivative_matches_finite_difference() {
+        let params = convergence_params(false, 0);
+        let tracking = EscapeTracking {
+            derivative: true,
+            trap_point: None,
+            interior_cycle: false,
+        };
+        let step = 1e-7;
+        let check = |escape: &dyn Fn([f64; 2], EscapeTracking) -> Option<EscapeResult>,
+                     pixel: [f64; 2]| {
+            let tracked = escape(pixel, tracking).unwrap();
+            let untracked = escape(pixel, EscapeTracking::default()).unwrap();
+            assert_eq!(tracked.smooth_count, untracked.smooth_count);
+            assert_eq!(tracked.final_z, untracked.final_z);
+            assert_eq!(untracked.derivative, [0.0, 0.0]);
+
+            // Holomorphic: the derivative along the real axis is enough.
+            let shifted = escape([pixel[0] + step, pixel[1]], tracking).unwrap();
+            assert_eq!(shifted.iter_count, tracked.iter_count);
+            for i in 0..2 {
+                let finite_difference = (shifted.final_z[i] - tracked.final_z[i]) / step;
+                let relative_error = (finite_difference - tracked.derivative[i]).abs()
+                    / tracked.derivative[0].hypot(tracked.derivative[1]);
+                assert!(relative_error < 1e-3, "{finite_difference} {tracked:?}");
+            }
+        };
+        check(
+            &|c, tracking| mandelbrot_escape(&c, &params, tracking),
+            [0.3, 0.6],
+        );
+        check(
+            &|z, tracking| julia_escape(&z, &[-0.8, 0.156], &params, tracking),
+            [0.4, 0.5],
+        );
+    }
+
+    #[test]
+    fn trap_distance_is_the_closest_approach_of_the_orbit() {
+        let params = convergence_params(false, 0);
+        let trap = |trap_point| {
+            mandelbrot_escape(
+                &[1.0, 0.0],
+                &params,
+                EscapeTracking {
+                    derivative: false,
+                    trap_point: Some(trap_point),
+                    interior_cycle: false,
+                },
+            )
+            .unwrap()
+            .min_trap_distance
+        };
+        // The orbit of C = 1 is 0, 1, 2, 5, 26, ...
+        assert_eq!(trap([2.0, 0.0]), 0.0);
+        assert_eq!(trap([2.0, 4.0]), 4.0);
+        assert_eq!(trap([0.0, -0.5]), 0.5);
+        assert_eq!(
+            mandelbrot_escape(&[1.0, 0.0], &params, EscapeTracking::default())
+                .unwrap()
+                .min_trap_distance,
+            f64::INFINITY
+        );
+    }
+
+    #[test]
+    fn angle_sectors_start_at_the_positive_real_axis() {
+        use std::f64::consts::PI;
+        assert_eq!(angle_sector(0.0, 2), 0);
+        assert_eq!(angle_sector(0.5 * PI, 2), 0);
+        assert_eq!(angle_sector(-0.5 * PI, 2), 1);
+        assert_eq!(angle_sector(PI, 2), 1);
+        assert_eq!(angle_sector(-PI, 4), 2);
+        assert_eq!(angle_sector(-1e-12, 6), 5);
+        assert_eq!(angle_sector(1.0, 1), 0);
+    }
+
+    #[test]
+    fn par_escape_counts_visits_every_pixel_once_with_its_escape_count() {
+        let params = test_mandelbrot_params();
+
+        let mut counts: Vec<([u32; 2], Option<f32>)> = par_escape_counts(&params).collect();
+        assert_eq!(counts.len(), 9 * 7);
+        counts.sort_by_key(|&([x, y], _)| (y, x));
+        let pixel_mapper = PixelMapper::new(&params.image_specification);
+        for (index, ([x, y], escape_count)) in counts.into_iter().enumerate() {
+            assert_eq!([x, y], [index as u32 % 9, index as u32 / 9]);
+            let (re, im) = pixel_mapper.map(&(x, y));
+            assert_eq!(
+                escape_count,
+                params.evaluate([re, im]).map(|(value, _)| value)
+            );
+        }
+        // The center row crosses both the set and its exterior.
+        let center_row: Vec<Option<f32>> = par_escape_counts(&params)
+            .filter(|([_, y], _)| *y == 3)
+            .map(|(_, escape_count)| escape_count)
+            .collect();
+        assert!(center_row.iter().any(Option::is_none));
+        assert!(center_row.iter().any(Option::is_some));
+    }
+
+    #[test]
+    fn interior_orbits_report_the_period_of_their_attracting_cycle() {
+        let params = convergence_params(false, 0);
+        let tracking = EscapeTracking {
+            interior_cycle: true,
+            ..EscapeTracking::default()
+        };
+        let cycle =
+            |z: &[f64; 2], c: &[f64; 2], pixel_term| match QuadraticMapSequence::orbit_result(
+                z, c, &params, tracking, pixel_term,
+            ) {
+                OrbitResult::Cycle(cycle) => cycle,
+                other => panic!("expected a cycle, got {other:?}"),
+            };
+
+        // Centers of the main cardioid and the period-2 bulb are superattracting.
+        let main_cardioid = cycle(&[0.0, 0.0], &[0.0, 0.0], PixelTerm::ConstantTerm);
+        assert_eq!(main_cardioid.period, 1);
+        assert!(main_cardioid.multiplier < 1e-9);
+        assert_eq!(
+            cycle(&[0.0, 0.0], &[-1.0, 0.0], PixelTerm::ConstantTerm).period,
+            2
+        );
+        let attracting = cycle(&[0.0, 0.0], &[0.2, 0.1], PixelTerm::ConstantTerm);
+        assert_eq!(attracting.period, 1);
+        assert!(attracting.multiplier > 0.0 && attracting.multiplier < 1.0);
+        // Julia set orbits settle onto the same cycle as the critical orbit.
+        assert_eq!(
+            cycle(&[0.1, 0.05], &[-1.0, 0.0], PixelTerm::InitialPoint).period,
+            2
+        );
+
+        // Without the flag, or for an escaping orbit, there is no cycle.
+        assert_eq!(
+            QuadraticMapSequence::orbit_result(
+                &[0.0, 0.0],
+                &[-1.0, 0.0],
+                &params,
+                EscapeTracking::default(),
+                PixelTerm::ConstantTerm,
+            ),
+            OrbitResult::Bounded
+        );
+        assert!(matches!(
+            QuadraticMapSequence::orbit_result(
+                &[0.0, 0.0],
+                &[1.0, 0.0],
+                &params,
+                tracking,
+                PixelTerm::ConstantTerm,
+            ),
+            OrbitResult::Escaped(_)
+        ));
+    }
+
+    #[test]
+    fn cycle_period_interior_coloring_routes_the_set_to_the_later_color_maps() {
+        let mut params = test_mandelbrot_params();
+        params.convergence_params.max_iter_count = 256;
+        params.color_map.color.color_maps.extend([
+            gradient([255, 0, 0], [0, 0, 255]),
+            gradient([0, 255, 0], [0, 0, 255]),
+        ]);
+        assert!(params.counts_escapes());
+        assert_eq!(params.evaluate([0.0, 0.0]), None);
+
+        params.color_map.interior_coloring = InteriorColoring::CyclePeriod;
+        assert!(!params.counts_escapes());
+        assert_eq!(params.evaluate([0.0, 0.0]), Some((0.0, 1)));
+        assert_eq!(params.evaluate([-1.0, 0.0]), Some((0.0, 2)));
+        // Period 3 wraps around to the first interior map.
+        assert_eq!(params.evaluate([-1.754_877_666, 0.0]).unwrap().1, 1);
+        let exterior = params.evaluate([1.0, 0.0]).unwrap();
+        assert_eq!(exterior.1, 0);
+    }
+
+    #[test]
+    fn biomorph_body_is_colored_by_its_final_components_with_the_second_color_map() {
+        let mut params = test_julia_params([0.5, 0.1]);
+        params.convergence_params = convergence_params(true, 0);
+        params
+            .color_map
+            .color
+            .color_maps
+            .push(gradient([255, 0, 0], [0, 0, 255]));
+        let (value, color_map_index) = params.evaluate([-1.0, -0.3]).unwrap();
+        assert_eq!(color_map_index, 1);
+        assert!(
+            value > 0.0 && value < params.histogram_max_value(),
+            "{value}"
+        );
+        assert_eq!(params.evaluate([-1.1, -0.1]).unwrap().1, 0);
+
+        // With a single color map the body shares it.
+        params.color_map.color.color_maps.truncate(1);
+        assert_eq!(params.evaluate([-1.0, -0.3]).unwrap().1, 0);
+    }
+
+    #[test]
+    fn auto_refinement_count_shrinks_as_the_escape_radius_grows() {
+        assert_eq!(auto_refinement_count(4.0), Ok(4));
+        assert_eq!(auto_refinement_count(16.0), Ok(3));
+        assert_eq!(auto_refinement_count(100.0), Ok(2));
+        assert_eq!(auto_refinement_count(1e6), Ok(1));
+        assert_eq!(auto_refinement_count(1e12), Ok(1));
+        assert!(auto_refinement_count(1.0).is_err());
+
+        let parse = |json: &str| serde_json::from_str::<ConvergenceParams>(json);
+        let auto = parse(
+            r#"{ "escape_radius_squared": 100.0, "max_iter_count": 64, "refinement_count": "auto" }"#,
+        )
+        .unwrap();
+        assert_eq!(auto.refinement_count, 2);
+        let manual = parse(
+            r#"{ "escape_radius_squared": 100.0, "max_iter_count": 64, "refinement_count": 7 }"#,
+        )
+        .unwrap();
+        assert_eq!(manual.refinement_count, 7);
+        assert!(
+            parse(r#"{ "escape_radius_squared": 4.0, "max_iter_count": 64, "refinement_count": "many" }"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn banded_coloring_is_off_by_default_and_needs_at_least_one_band() {
+        let parse = |extra: &str| {
+            serde_json::from_str::<ColorMapParams>(&format!(
+                r#"{{
+                    "color": {{
+                        "background_color": [0, 0, 0],
+                        "color_maps": [[
+                            {{ "query": 0.0, "rgb_raw": [0, 0, 0] }},
+                            {{ "query": 1.0, "rgb_raw": [255, 255, 255] }}
+                        ]]
+                    }},
+                    "lookup_table_count": 16,
+                    "histogram_bin_count": 16{extra}
+                }}"#
+            ))
+        };
+        let smooth = parse("").unwrap();
+        assert_eq!(smooth.banded, None);
+        assert!(!serde_json::to_string(&smooth).unwrap().contains("banded"));
+        assert_eq!(parse(r#", "banded": 12"#).unwrap().banded, Some(12));
+        assert!(parse(r#", "banded": 0"#).is_err());
+        assert_eq!(smooth.band_softness, 0.0);
+        let soft = parse(r#", "banded": 12, "band_softness": 0.25"#).unwrap();
+        assert_eq!(soft.band_softness, 0.25);
+        assert!(parse(r#", "banded": 12, "band_softness": 1.5"#).is_err());
+    }
 }
@@ -1,8 +1,13 @@
-use crate::core::chaos_game::{ColoredPoint, chaos_game_render};
+use crate::core::chaos_game::{
+    ChaosGame, ColoredPoint, SeededRng, chaos_game_render, deserialize_time_budget,
+};
 use crate::core::file_io::{FilePrefix, serialize_to_json_or_panic};
-use crate::core::image_utils::{FitImage, ViewRectangle};
+use crate::core::image_utils::{
+    FitImage, ImageSpecification, RenderOptions, SpeedOptimizer, ViewRectangle,
+    scale_down_parameter_for_speed,
+};
+use crate::core::interpolation::ClampedLogInterpolator;
 use rand::distributions::{Distribution, Uniform};
-use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 
@@ -13,13 +18,66 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SierpinskiParams {
     pub fit_image: FitImage,
+    /// Number of chaos-game samples to draw; an upper limit when a
+    /// `time_budget_seconds` is given.
     pub sample_count: u32,
+    /// Stop sampling once this much time has been spent on it, so the render
+    /// takes about as long on any machine. The number of samples actually
+    /// drawn is reported in the diagnostics. Only applies to the `render`
+    /// command. Unset by default.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_time_budget",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub time_budget_seconds: Option<f64>,
+    /// Seed of the random number generator, so a render is reproducible.
     pub rng_seed: u64,
-    pub subpixel_antialiasing: u32,
+    /// Only `sampling_level` applies: each pixel is split into
+    /// `(sampling_level + 1)²` subpixels for anti-aliasing. Defaults to
+    /// `sampling_level: 0`.
+    #[serde(default)]
+    pub render_options: RenderOptions,
+    /// Deprecated and ignored: replaced by `render_options.sampling_level`.
+    /// Older params files that set it still load and it is not written back
+    /// out; see `FractalParams::deprecation_warnings`.
+    #[serde(default, skip_serializing)]
+    pub subpixel_antialiasing: Option<u32>,
     pub background_color_rgb: [u8; 3],
     pub vertex_colors_rgb: Vec<[u8; 3]>,
 }
 
+/// Reference cache used by `SpeedOptimizer` to interpolate runtime
+/// parameters back toward the user's specified values.
+pub struct ParamsReferenceCache {
+    /// User-specified `sample_count`.
+    pub sample_count: u32,
+    /// User-specified render options (including `sampling_level`).
+    pub render_options: RenderOptions,
+}
+
+impl SpeedOptimizer for SierpinskiParams {
+    type ReferenceCache = ParamsReferenceCache;
+
+    fn reference_cache(&self) -> Self::ReferenceCache {
+        ParamsReferenceCache {
+            sample_count: self.sample_count,
+            render_options: self.render_options,
+        }
+    }
+
+    fn set_speed_optimization_level(&mut self, level: f64, cache: &Self::ReferenceCache) {
+        self.sample_count = scale_down_parameter_for_speed(
+            1e5,
+            cache.sample_count as f64,
+            level,
+            ClampedLogInterpolator,
+        ) as u32;
+        self.render_options
+            .set_speed_optimization_level(level, &cache.render_options);
+    }
+}
+
 /**
  * Computes the set of polygon vertices that live on the unit circle for a polygon of `num_vertices` sides.
  */
@@ -52,6 +110,7 @@ fn optimal_contraction_ratio(n: usize) -> f64 {
     1.0 / (1.0 + alpha)
 }
 
+#[derive(Debug, Clone)]
 struct SampleGenerator {
     distribution: Uniform<usize>, // samples the next vertex to jump to
     vertices: Vec<nalgebra::Vector2<f64>>,
@@ -89,6 +148,60 @@ impl SampleGenerator {
     }
 }
 
+impl ChaosGame for SierpinskiParams {
+    fn image_specification(&self) -> ImageSpecification {
+        let vertices = polygon_verticies(self.vertex_colors_rgb.len());
+        let verticies_plain: Vec<[f64; 2]> = vertices.iter().map(|p| [p.x, p.y]).collect();
+        self.fit_image
+            .image_specification(&ViewRectangle::from_vertices(&verticies_plain))
+    }
+
+    fn render_options(&self) -> &RenderOptions {
+        &self.render_options
+    }
+
+    fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    fn background_color_rgb(&self) -> [u8; 3] {
+        self.background_color_rgb
+    }
+
+    fn point_colors_rgb(&self) -> Vec<[u8; 3]> {
+        self.vertex_colors_rgb.clone()
+    }
+
+    type Samples = SierpinskiSamples;
+
+    fn samples(&self) -> SierpinskiSamples {
+        let vertices = polygon_verticies(self.vertex_colors_rgb.len());
+        SierpinskiSamples {
+            rng: SeededRng::seed_from_u64(self.rng_seed),
+            sample_point: vertices[0],
+            generator: SampleGenerator::regular_polygon(&self.vertex_colors_rgb, &vertices),
+        }
+    }
+}
+
+/// Endless chaos-game sequence, jumping toward a random vertex each step.
+#[derive(Debug, Clone)]
+pub struct SierpinskiSamples {
+    rng: SeededRng,
+    sample_point: nalgebra::Vector2<f64>,
+    generator: SampleGenerator,
+}
+
+impl Iterator for SierpinskiSamples {
+    type Item = ColoredPoint;
+
+    fn next(&mut self) -> Option<ColoredPoint> {
+        let next_colored_point = self.generator.next(&mut self.rng, &self.sample_point);
+        self.sample_point = next_colored_point.point.into();
+        Some(next_colored_point)
+    }
+}
+
 /**
  * Called by main, used to render the fractal using the above data structures.
  */
@@ -96,29 +209,15 @@ pub fn render_sierpinski(
     params: &SierpinskiParams,
     file_prefix: FilePrefix,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let vertices = polygon_verticies(params.vertex_colors_rgb.len());
-    let mut sample_point = vertices[0];
-    let mut rng = StdRng::seed_from_u64(params.rng_seed);
-    let generator = SampleGenerator::regular_polygon(&params.vertex_colors_rgb, &vertices);
-
-    let mut distribution = || {
-        let next_colored_point = generator.next(&mut rng, &sample_point);
-        sample_point = next_colored_point.point.into();
-        next_colored_point
-    };
-
     serialize_to_json_or_panic(file_prefix.full_path_with_suffix(".json"), &params);
 
-    let verticies_plain: Vec<[f64; 2]> = vertices.iter().map(|p| [p.x, p.y]).collect();
-
     chaos_game_render(
         image::Rgb(params.background_color_rgb),
-        &mut distribution,
+        params.samples(),
         params.sample_count,
-        params.subpixel_antialiasing,
-        &params
-            .fit_image
-            .image_specification(&ViewRectangle::from_vertices(&verticies_plain)),
+        params.time_budget_seconds,
+        &params.render_options,
+        &params.image_specification(),
         file_prefix,
     )
 }
@@ -127,7 +226,12 @@ pub fn render_sierpinski(
 mod tests {
     use approx::assert_relative_eq;
 
-    use super::optimal_contraction_ratio;
+    use super::*;
+    use crate::core::{
+        chaos_game::SampleAccumulatorRenderable,
+        image_utils::{DEFAULT_MAX_PIXEL_COUNT, Renderable, render_to_image},
+    };
+    use crate::fractals::common::FractalParams;
 
     #[test]
     fn test_optimal_packing_ratio() {
@@ -142,4 +246,138 @@ mod tests {
         assert_relative_eq!(optimal_contraction_ratio(9), 0.742, epsilon = tol);
         assert_relative_eq!(optimal_contraction_ratio(10), 0.764, epsilon = tol);
     }
+
+    #[test]
+    fn deprecated_subpixel_antialiasing_is_accepted_and_ignored() {
+        let params: SierpinskiParams = serde_json::from_str(
+            r#"{
+                "fit_image": { "resolution": [30, 30], "padding_scale": 1.05 },
+                "sample_count": 1000,
+                "rng_seed": 12345,
+                "subpixel_antialiasing": 3,
+                "background_color_rgb": [0, 0, 0],
+                "vertex_colors_rgb": [[255, 0, 0], [0, 255, 0], [0, 0, 255]]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(params.subpixel_antialiasing, Some(3));
+        assert_eq!(params.render_options.sampling_level, 0);
+        let saved = serde_json::to_string(&params).unwrap();
+        assert!(!saved.contains("subpixel_antialiasing"), "{saved}");
+        let fractal_params = FractalParams::Sierpinski(Box::new(params));
+        let warnings = fractal_params.deprecation_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("subpixel_antialiasing"));
+    }
+
+    #[test]
+    fn speed_optimization_scales_samples_and_anti_aliasing_together() {
+        let mut params: SierpinskiParams = serde_json::from_str(
+            r#"{
+                "fit_image": { "resolution": [30, 30], "padding_scale": 1.05 },
+                "sample_count": 10000000,
+                "rng_seed": 12345,
+                "render_options": { "sampling_level": 2 },
+                "background_color_rgb": [0, 0, 0],
+                "vertex_colors_rgb": [[255, 0, 0], [0, 255, 0], [0, 0, 255]]
+            }"#,
+        )
+        .unwrap();
+        let cache = params.reference_cache();
+
+        params.set_speed_optimization_level(1.0, &cache);
+        assert!(params.sample_count < cache.sample_count);
+        assert!(params.render_options.sampling_level < cache.render_options.sampling_level);
+
+        params.set_speed_optimization_level(0.0, &cache);
+        assert_eq!(params.sample_count, cache.sample_count);
+        assert_eq!(
+            params.render_options.sampling_level,
+            cache.render_options.sampling_level
+        );
+    }
+
+    #[test]
+    fn sample_accumulator_renders_the_attractor_and_follows_the_view() {
+        let params: SierpinskiParams = serde_json::from_str(
+            r#"{
+                "fit_image": { "resolution": [40, 40], "padding_scale": 1.05 },
+                "sample_count": 200000,
+                "rng_seed": 12345,
+                "render_options": { "sampling_level": 1 },
+                "background_color_rgb": [0, 0, 0],
+                "vertex_colors_rgb": [[255, 0, 0], [0, 255, 0], [0, 0, 255]]
+            }"#,
+        )
+        .unwrap();
+        let mut renderable = SampleAccumulatorRenderable::new(params);
+        let lit_pixels =
+            |image: &image::RgbImage| image.pixels().filter(|pixel| pixel.0 != [0, 0, 0]).count();
+
+        let full_view = render_to_image(renderable.clone(), DEFAULT_MAX_PIXEL_COUNT).unwrap();
+        // The corners lie outside the triangle; the top vertex is red.
+        assert_eq!(full_view.get_pixel(0, 39).0, [0, 0, 0]);
+        assert!(full_view.get_pixel(19, 4).0[0] > 0);
+        assert!(lit_pixels(&full_view) > 100);
+
+        // Zoom in on the top vertex: only the red sub-triangle is on screen.
+        let mut zoomed = *renderable.image_specification();
+        zoomed.center = [0.0, 0.8];
+        zoomed.width *= 0.25;
+        renderable.set_image_specification(zoomed);
+        let zoomed_view = render_to_image(renderable.clone(), DEFAULT_MAX_PIXEL_COUNT).unwrap();
+        assert!(lit_pixels(&zoomed_view) > 100);
+        assert!(
+            zoomed_view
+                .pixels()
+                .all(|pixel| pixel.0[1] == 0 && pixel.0[2] == 0)
+        );
+
+        // Dropping the quality re-runs the game with fewer samples.
+        let cache = renderable.reference_cache();
+        renderable.set_speed_optimization_level(1.0, &cache);
+        let fast_view = render_to_image(renderable, DEFAULT_MAX_PIXEL_COUNT).unwrap();
+        assert!(lit_pixels(&fast_view) <= lit_pixels(&zoomed_view));
+    }
+
+    #[test]
+    fn sample_accumulator_continues_the_sequence_and_refines_until_converged() {
+        let params: SierpinskiParams = serde_json::from_str(
+            r#"{
+                "fit_image": { "resolution": [24, 24], "padding_scale": 1.05 },
+                "sample_count": 3000,
+                "rng_seed": 7,
+                "render_options": { "sampling_level": 1 },
+                "background_color_rgb": [0, 0, 0],
+                "vertex_colors_rgb": [[255, 0, 0], [0, 255, 0], [0, 0, 255]]
+            }"#,
+        )
+        .unwrap();
+        let fresh = render_to_image(
+            SampleAccumulatorRenderable::new(params.clone()),
+            DEFAULT_MAX_PIXEL_COUNT,
+        )
+        .unwrap();
+
+        // Dropping to the fastest level and back reproduces the
+        // full-quality image.
+        let mut renderable = SampleAccumulatorRenderable::new(params);
+        let cache = renderable.reference_cache();
+        renderable.set_speed_optimization_level(1.0, &cache);
+        renderable.set_speed_optimization_level(0.0, &cache);
+        assert_eq!(
+            render_to_image(renderable.clone(), DEFAULT_MAX_PIXEL_COUNT).unwrap(),
+            fresh
+        );
+
+        let lit_pixels =
+            |image: &image::RgbImage| image.pixels().filter(|pixel| pixel.0 != [0, 0, 0]).count();
+        assert!(renderable.refine());
+        assert!(
+            lit_pixels(&render_to_image(renderable.clone(), DEFAULT_MAX_PIXEL_COUNT).unwrap())
+                > lit_pixels(&fresh)
+        );
+        let passes = (0..1000).take_while(|_| renderable.refine()).count();
+        assert!(passes < 1000, "refinement never converged");
+    }
 }
@@ -4,3 +4,5 @@
 pub mod cli;
 pub mod core;
 pub mod fractals;
+#[cfg(feature = "wasm")]
+pub mod wasm;
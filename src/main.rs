@@ -1,16 +1,19 @@
-use core::file_io::{
-    FilePrefix, build_output_path_with_date_time, extract_base_name, maybe_date_time_string,
+use fractal_renderer::core::file_io::{
+    FilePrefix, build_output_path_with_date_time, maybe_date_time_string,
 };
+use fractal_renderer::core::image_utils::check_pixel_count;
+use fractal_renderer::core::params_merge::load_params_with_base;
 
 use clap::Parser;
-use cli::args::{CommandsEnum, FractalRendererArgs, ParameterFilePath};
-use cli::explore::explore_fractal;
-use cli::render::render_fractal;
-use fractals::common::FractalParams;
-
-mod cli;
-mod core;
-mod fractals;
+use fractal_renderer::cli::args::{CommandsEnum, FractalRendererArgs, ParameterFilePath};
+use fractal_renderer::cli::contact_sheet::{ContactSheetLayout, render_contact_sheet};
+use fractal_renderer::cli::diff::render_difference;
+#[cfg(feature = "gui")]
+use fractal_renderer::cli::explore::explore_fractal;
+use fractal_renderer::cli::phase_space::plot_phase_space;
+use fractal_renderer::cli::render::render_fractal;
+use fractal_renderer::cli::sample_pattern::plot_sample_patterns;
+use fractal_renderer::fractals::common::FractalParams;
 
 fn build_file_prefix(params: &ParameterFilePath, command_name: &str) -> FilePrefix {
     FilePrefix {
@@ -18,34 +21,111 @@ fn build_file_prefix(params: &ParameterFilePath, command_name: &str) -> FilePref
             command_name,
             &maybe_date_time_string(params.date_time_out),
         ),
-        file_base: extract_base_name(&params.params_path).to_owned(),
+        file_base: params.output_base_name().to_owned(),
     }
 }
 
+/// Print `result`'s error and exit with a failure status, or return its value.
+fn exit_on_error<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|error| {
+        eprintln!("ERROR:  {error}");
+        std::process::exit(1);
+    })
+}
+
 fn main() {
     let args: FractalRendererArgs = FractalRendererArgs::parse();
 
-    let fractal_params = |path: &str| -> FractalParams {
-        serde_json::from_str(&std::fs::read_to_string(path).expect("Unable to read param file"))
-            .unwrap()
+    let fractal_params = |params: &ParameterFilePath| -> FractalParams {
+        let params_json = params
+            .read_params_json()
+            .expect("Unable to read param file");
+        let mut fractal_params: FractalParams = serde_json::from_value(
+            load_params_with_base(&params_json, params.params_directory()).unwrap(),
+        )
+        .unwrap();
+        for warning in fractal_params.deprecation_warnings() {
+            println!("WARNING:  {warning}");
+        }
+        if params.has_view_override() {
+            let image_specification = fractal_params
+                .image_specification()
+                .ok_or_else(|| "the view of this fractal cannot be overridden".to_owned())
+                .and_then(|image_specification| params.override_view(image_specification))
+                .unwrap();
+            fractal_params
+                .set_image_specification(image_specification)
+                .unwrap();
+        }
+        if let Some(quality_preset) = params.quality_preset {
+            quality_preset.apply(&mut fractal_params).unwrap();
+        }
+        fractal_params
     };
 
     match &args.command {
         Some(CommandsEnum::Render(params)) => {
-            render_fractal(
-                &fractal_params(&params.params_path),
+            exit_on_error(render_fractal(
+                &fractal_params(params),
                 build_file_prefix(params, "render"),
-            )
-            .unwrap();
+                params.max_pixel_count,
+            ));
         }
 
+        #[cfg(feature = "gui")]
         Some(CommandsEnum::Explore(params)) => {
-            explore_fractal(
-                &fractal_params(&params.params_path),
-                build_file_prefix(params, "explore"),
+            let fractal_params = fractal_params(params);
+            exit_on_error(check_pixel_count(
+                fractal_params.resolution(),
+                params.max_pixel_count,
+            ));
+            explore_fractal(&fractal_params, build_file_prefix(params, "explore")).unwrap();
+        }
+        Some(CommandsEnum::ContactSheet(args)) => {
+            let fractal_params = fractal_params(&args.params);
+            let layout =
+                ContactSheetLayout::new(args.zoom_factor, args.count, args.columns).unwrap();
+            exit_on_error(render_contact_sheet(
+                fractal_params,
+                &layout,
+                build_file_prefix(&args.params, "contact_sheet"),
+                args.params.max_pixel_count,
+            ));
+        }
+        Some(CommandsEnum::PhaseSpace(args)) => {
+            plot_phase_space(
+                &fractal_params(&args.params),
+                [args.point[0], args.point[1]],
+                build_file_prefix(&args.params, "phase_space"),
             )
             .unwrap();
         }
+        Some(CommandsEnum::SamplePattern(args)) => {
+            let image_specification = fractal_params(&args.params)
+                .image_specification()
+                .ok_or_else(|| "this fractal has no fixed view to sample".to_owned())
+                .unwrap();
+            plot_sample_patterns(
+                &image_specification,
+                args.subpixel_count,
+                [args.pixel[0], args.pixel[1]],
+                build_file_prefix(&args.params, "sample_pattern"),
+            )
+            .unwrap();
+        }
+        Some(CommandsEnum::Diff(args)) => {
+            let other_params = ParameterFilePath {
+                params_path: args.other_params_path.clone(),
+                ..args.params.clone()
+            };
+            exit_on_error(render_difference(
+                &fractal_params(&args.params),
+                &fractal_params(&other_params),
+                args.mode,
+                build_file_prefix(&args.params, "diff"),
+                args.params.max_pixel_count,
+            ));
+        }
         None => {
             println!("Default command (nothing specified!)");
         }
@@ -0,0 +1,127 @@
+//! Browser-friendly render entry point, enabled by the `wasm` feature.
+//!
+//! Everything here is pure computation: parameters come in as a JSON
+//! string and pixels go out as a flat RGBA byte vector, with no file I/O
+//! and no windowing. The render pipeline still dispatches through rayon;
+//! on targets without thread support rayon runs every parallel iterator on
+//! the calling thread, so the output is identical, just slower.
+//!
+//! Only the pixel-based fractals (those implementing `Renderable`) are
+//! supported. The chaos-game fractals and the Buddhabrot accumulate samples
+//! into their own buffers and write directly to disk, so they are rejected
+//! with an error.
+
+use crate::core::image_utils::{DEFAULT_MAX_PIXEL_COUNT, Renderable, render_to_image};
+use crate::core::relaxed_json::from_relaxed_json_str;
+use crate::fractals::common::FractalParams;
+use crate::fractals::newtons_method::{NewtonsMethodRenderable, SystemType};
+
+/// Render the fractal described by `params_json` (the same tagged
+/// `FractalParams` JSON accepted by the `render` command) and return its
+/// pixels as row-major RGBA bytes, four per pixel, with alpha fixed at 255.
+/// The image dimensions are the `resolution` from the params, which may have
+/// at most `DEFAULT_MAX_PIXEL_COUNT` pixels.
+pub fn render_rgba_from_json(params_json: &str) -> Result<Vec<u8>, String> {
+    let params: FractalParams = from_relaxed_json_str(params_json).map_err(|e| e.to_string())?;
+    match params {
+        FractalParams::Mandelbrot(inner_params) => render_rgba(*inner_params),
+        FractalParams::Julia(inner_params) => render_rgba(*inner_params),
+        FractalParams::DrivenDampedPendulum(inner_params) => render_rgba(*inner_params),
+        FractalParams::MagneticPendulum(inner_params) => render_rgba(*inner_params),
+        FractalParams::NewtonsMethod(inner_params) => {
+            match inner_params.system {
+                SystemType::RootsOfUnity(system_params) => render_rgba(
+                    NewtonsMethodRenderable::new(inner_params.params, *system_params),
+                ),
+                SystemType::CoshMinusOne(system_params) => render_rgba(
+                    NewtonsMethodRenderable::new(inner_params.params, *system_params),
+                ),
+                SystemType::Expression(system_params) => render_rgba(NewtonsMethodRenderable::new(
+                    inner_params.params,
+                    *system_params,
+                )),
+            }
+        }
+        FractalParams::BarnsleyFern(_) | FractalParams::Sierpinski(_) => {
+            Err("chaos-game fractals are not supported by the wasm renderer".to_owned())
+        }
+        FractalParams::Buddhabrot(_) => {
+            Err("the Buddhabrot is not supported by the wasm renderer".to_owned())
+        }
+    }
+}
+
+fn render_rgba<T: Renderable>(renderable: T) -> Result<Vec<u8>, String> {
+    let image =
+        render_to_image(renderable, DEFAULT_MAX_PIXEL_COUNT).map_err(|error| error.to_string())?;
+    Ok(image
+        .pixels()
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_rgba_with_four_bytes_per_pixel() {
+        let json = r#"{
+            "DrivenDampedPendulum": {
+                "image_specification": {
+                    "resolution": [6, 4],
+                    "center": [0, 0],
+                    "width": 14
+                },
+                "time_phase": 0,
+                "n_max_period": 4,
+                "n_steps_per_period": 8,
+                "periodic_state_error_tolerance": 0.05,
+                "render_options": {
+                    "sampling_level": 0
+                }
+            }
+        }"#;
+        let rgba = render_rgba_from_json(json).unwrap();
+        assert_eq!(rgba.len(), 6 * 4 * 4);
+        assert!(rgba.chunks_exact(4).all(|pixel| pixel[3] == u8::MAX));
+    }
+
+    #[test]
+    fn rejects_images_beyond_the_pixel_limit() {
+        let json = r#"{
+            "DrivenDampedPendulum": {
+                "image_specification": {
+                    "resolution": [20000, 20000],
+                    "center": [0, 0],
+                    "width": 14
+                },
+                "time_phase": 0,
+                "n_max_period": 4,
+                "n_steps_per_period": 8,
+                "periodic_state_error_tolerance": 0.05,
+                "render_options": {
+                    "sampling_level": 0
+                }
+            }
+        }"#;
+        let error = render_rgba_from_json(json).unwrap_err();
+        assert!(error.contains("more than the limit"), "{error}");
+    }
+
+    #[test]
+    fn rejects_chaos_game_fractals() {
+        let json = r#"{
+            "Sierpinski": {
+                "fit_image": { "resolution": [4, 4], "padding_scale": 1.0 },
+                "sample_count": 10,
+                "rng_seed": 0,
+                "render_options": { "sampling_level": 0 },
+                "background_color_rgb": [0, 0, 0],
+                "vertex_colors_rgb": [[255, 0, 0], [0, 255, 0], [0, 0, 255]]
+            }
+        }"#;
+        let error = render_rgba_from_json(json).unwrap_err();
+        assert!(error.contains("not supported"), "{error}");
+    }
+}
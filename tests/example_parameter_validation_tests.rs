@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use fractal_renderer::fractals::{common::FractalParams, mandelbrot::MandelbrotParams};
+    use fractal_renderer::fractals::{
+        common::FractalParams, driven_damped_pendulum::DrivenDampedPendulumParams,
+        mandelbrot::MandelbrotParams,
+    };
     use glob::glob;
     use serde::de::DeserializeOwned;
     use std::{any::type_name, fs, path::PathBuf};
@@ -45,6 +48,9 @@ mod tests {
         parse_all_parameter_files_or_panic::<FractalParams>("tests/param_files", &[]);
 
         // ... and the Benchmarks too...
-        parse_all_parameter_files_or_panic::<MandelbrotParams>("benches", &[]);
+        let ddp_bench_file = "benches/driven_damped_pendulum_default.json";
+        parse_all_parameter_files_or_panic::<MandelbrotParams>("benches", &[ddp_bench_file]);
+        let content = fs::read_to_string(ddp_bench_file).unwrap();
+        serde_json::from_str::<DrivenDampedPendulumParams>(&content).unwrap();
     }
 }
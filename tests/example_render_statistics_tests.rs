@@ -0,0 +1,145 @@
+//! Renders every `render-*` example at thumbnail size and checks a few basic
+//! statistics of the result, rather than a golden image: the image is not a
+//! single flat color, and (from `_summary.json`) the render took a finite
+//! time and some, but not necessarily all, of the samples escaped. Catches
+//! broken example params and gross pipeline regressions that would otherwise
+//! only show up in a full-size render.
+
+#[cfg(test)]
+mod tests {
+    use fractal_renderer::{
+        cli::{args::QualityPreset, render::render_fractal},
+        core::{
+            file_io::FilePrefix,
+            image_utils::{DEFAULT_MAX_PIXEL_COUNT, RenderSummary, thumbnail_dimensions},
+        },
+        fractals::common::FractalParams,
+    };
+    use glob::glob;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    /// Long edge, in pixels, of the reduced-resolution renders.
+    const LONG_EDGE: u32 = 48;
+
+    /// Sample budget for the chaos games and the Buddhabrot, whose examples
+    /// draw millions of samples.
+    const MAX_SAMPLE_COUNT: u32 = 100_000;
+
+    /// Integration limits for the magnetic pendulum, whose example simulates
+    /// thousands of steps per pixel.
+    const MIN_TIME_STEP: f64 = 0.05;
+    const MAX_SIMULATION_TIME: f64 = 10.0;
+
+    /// Shrink `params` to a quick, low-resolution render.
+    fn reduce(params: &mut FractalParams) {
+        let resolution = thumbnail_dimensions(params.resolution(), LONG_EDGE);
+        match params {
+            FractalParams::BarnsleyFern(inner) => {
+                inner.fit_image.resolution = resolution;
+                inner.sample_count = inner.sample_count.min(MAX_SAMPLE_COUNT);
+            }
+            FractalParams::Sierpinski(inner) => {
+                inner.fit_image.resolution = resolution;
+                inner.sample_count = inner.sample_count.min(MAX_SAMPLE_COUNT);
+            }
+            FractalParams::Buddhabrot(inner) => {
+                inner.image_specification.resolution = resolution;
+                inner.sample_count = inner.sample_count.min(MAX_SAMPLE_COUNT);
+            }
+            FractalParams::MagneticPendulum(inner) => {
+                inner.image_specification.resolution = resolution;
+                inner.time_step = inner.time_step.max(MIN_TIME_STEP);
+                inner.max_time = inner.max_time.min(MAX_SIMULATION_TIME);
+                QualityPreset::Draft.apply(params).unwrap();
+            }
+            _ => {
+                let mut image_specification = params.image_specification().unwrap();
+                image_specification.resolution = resolution;
+                params.set_image_specification(image_specification).unwrap();
+                QualityPreset::Draft.apply(params).unwrap();
+            }
+        }
+    }
+
+    /// Find the single file ending in `suffix` under `directory`.
+    fn find_output(directory: &Path, suffix: &str) -> Option<PathBuf> {
+        let pattern = format!("{}/**/*{suffix}", directory.display());
+        glob(&pattern).unwrap().flatten().next()
+    }
+
+    fn check_example(params_path: &Path) -> Result<(), String> {
+        let json = fs::read_to_string(params_path).map_err(|err| err.to_string())?;
+        let mut params: FractalParams =
+            serde_json::from_str(&json).map_err(|err| err.to_string())?;
+        reduce(&mut params);
+
+        let example = params_path.parent().unwrap().file_name().unwrap();
+        let directory = std::env::temp_dir()
+            .join("fractal_renderer_example_statistics")
+            .join(example);
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).map_err(|err| err.to_string())?;
+        render_fractal(
+            &params,
+            FilePrefix {
+                directory_path: directory.clone(),
+                file_base: "params".to_owned(),
+            },
+            DEFAULT_MAX_PIXEL_COUNT,
+        )
+        .map_err(|err| err.to_string())?;
+
+        let image_path = find_output(&directory, "params.png").ok_or("no image written")?;
+        let image = image::open(&image_path)
+            .map_err(|err| err.to_string())?
+            .to_rgb8();
+        let first = image.get_pixel(0, 0);
+        if image.pixels().all(|pixel| pixel == first) {
+            return Err(format!("the image is a single flat color, {first:?}"));
+        }
+
+        // Only the fractals drawn by the shared render pipeline write a
+        // summary; the chaos games and the Buddhabrot have their own.
+        if let Some(summary_path) = find_output(&directory, "_summary.json") {
+            let summary: RenderSummary = serde_json::from_str(
+                &fs::read_to_string(summary_path).map_err(|err| err.to_string())?,
+            )
+            .map_err(|err| err.to_string())?;
+            if !summary.render_seconds.is_finite() {
+                return Err(format!("render_seconds = {}", summary.render_seconds));
+            }
+            // Every sample may escape, e.g. for a Julia set that is a dust
+            // of isolated points, but none escaping means the view is
+            // entirely inside the set.
+            if let Some(fraction) = summary.escaped_fraction
+                && !(fraction > 0.0 && fraction <= 1.0)
+            {
+                return Err(format!("escaped_fraction = {fraction}"));
+            }
+        }
+        let diagnostics = find_output(&directory, "_diagnostics.txt")
+            .map(|path| fs::read_to_string(path).unwrap_or_default())
+            .unwrap_or_default();
+        if diagnostics.contains("NaN") {
+            return Err("the diagnostics report a NaN".to_owned());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn every_render_example_produces_a_plausible_image() {
+        let failures: Vec<String> = glob("examples/render-*/params.json")
+            .unwrap()
+            .flatten()
+            .filter_map(|path| {
+                check_example(&path)
+                    .err()
+                    .map(|err| format!("{}: {err}", path.display()))
+            })
+            .collect();
+        assert!(failures.is_empty(), "\n{}\n", failures.join("\n"));
+    }
+}
@@ -107,6 +107,14 @@ mod tests {
                 "newtons_method/cosh_minus_one_regression_test",
                 "90ed7f68c987263bf3bc3535f72bf8c96062dbce95f87c467245bf4b21aca1df",
             ),
+            (
+                "magnetic_pendulum/default_regression_test",
+                "622807d54a0fb449ee2091e976b351973944817c85bfe584ad3744eea3819ee2",
+            ),
+            (
+                "buddhabrot/nebulabrot_regression_test",
+                "6128b34b11127338dde59b92dfd2a6d58d8f979779471535e8a5140bc4aca364",
+            ),
         ];
 
         let mut ok = true;